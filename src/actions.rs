@@ -0,0 +1,745 @@
+// Import the in-place compression used by --compress
+use crate::compress::{self, Format};
+// Import FileInfo for the matches acted on, and long_path for files past
+// MAX_PATH on Windows
+use list_big_files::{long_path, FileInfo};
+// Import fs and io for removing and moving files
+use std::fs::{self, File};
+use std::io;
+// Import OS strings for appending compressed extensions and for --exec
+// arguments holding paths as they are
+use std::ffi::{OsStr, OsString};
+// Import Path and PathBuf for the files acted on and their destinations
+use std::path::{Path, PathBuf};
+// Import Command and Stdio for running --exec commands
+use std::process::{Command, Stdio};
+// Import Duration for the --older-than safety margin
+use std::time::Duration;
+// Import rayon to run --exec commands in parallel
+use rayon::prelude::*;
+// Import serde for the dry-run plan in JSON reports
+use serde::{Deserialize, Serialize};
+
+/// What a cleanup acted on, reported once it ends.
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+impl Summary {
+    /// Count a file acted on, which freed `size_bytes`.
+    pub fn add(&mut self, size_bytes: u64) {
+        self.removed += 1;
+        self.freed_bytes += size_bytes;
+    }
+}
+
+/// How a match is removed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Removal {
+    Delete,
+    Trash,
+}
+
+impl Removal {
+    /// Verb for prompts and previews.
+    pub fn verb(self) -> &'static str {
+        match self {
+            Removal::Delete => "delete",
+            Removal::Trash => "trash",
+        }
+    }
+
+    /// Past tense for status messages.
+    pub fn done(self) -> &'static str {
+        match self {
+            Removal::Delete => "Deleted",
+            Removal::Trash => "Trashed",
+        }
+    }
+
+    /// Remove the file at `path`, permanently or into the platform trash.
+    pub fn apply(self, path: &Path) -> io::Result<()> {
+        match self {
+            Removal::Delete => fs::remove_file(long_path(path)),
+            Removal::Trash => trash::delete(path).map_err(io::Error::other),
+        }
+    }
+}
+
+/// What happens to every match once the scan is over.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Remove(Removal),
+    /// Move each match from under `root` to the same relative place under `target`.
+    Move {
+        root: PathBuf,
+        target: PathBuf,
+    },
+    /// Replace each match with a compressed copy, leaving alone files
+    /// modified more recently than `older_than`.
+    Compress {
+        format: Format,
+        older_than: Option<Duration>,
+    },
+    /// Run a command built from `template` for each match, `jobs` at a time.
+    Exec {
+        template: Vec<String>,
+        jobs: usize,
+    },
+}
+
+/// What `--dry-run` found an action would do, as included in JSON reports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub action: String,
+    pub steps: Vec<Step>,
+}
+
+/// One match a dry run would act on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    #[serde(serialize_with = "list_big_files::serialize_path")]
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+}
+
+/// Result of acting on one match.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// Done, freeing this many bytes in the scanned tree.
+    Done(u64),
+    /// Left alone, for the given reason.
+    Skipped(&'static str),
+}
+
+impl Action {
+    /// Option that asked for this action.
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Action::Remove(Removal::Delete) => "--delete",
+            Action::Remove(Removal::Trash) => "--trash",
+            Action::Move { .. } => "--move-to",
+            Action::Compress { .. } => "--compress",
+            Action::Exec { .. } => "--exec",
+        }
+    }
+
+    /// Verb for prompts, previews and errors.
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Action::Remove(removal) => removal.verb(),
+            Action::Move { .. } => "move",
+            Action::Compress { .. } => "compress",
+            Action::Exec { .. } => "run the command for",
+        }
+    }
+
+    /// Past tense for progress messages.
+    pub fn done(&self) -> &'static str {
+        match self {
+            Action::Remove(removal) => removal.done(),
+            Action::Move { .. } => "Moved",
+            Action::Compress { .. } => "Compressed",
+            Action::Exec { .. } => "Ran the command for",
+        }
+    }
+
+    /// Where the file at `path` ends up, for actions that keep it.
+    pub fn destination(&self, path: &Path) -> Option<PathBuf> {
+        match self {
+            Action::Remove(_) | Action::Exec { .. } => None,
+            Action::Move { root, target } => {
+                let relative = path
+                    .strip_prefix(root)
+                    .ok()
+                    .or_else(|| path.file_name().map(Path::new))
+                    .unwrap_or(path);
+                Some(target.join(relative))
+            }
+            Action::Compress { format, .. } => {
+                let mut name = OsString::from(path);
+                name.push(".");
+                name.push(format.extension());
+                Some(PathBuf::from(name))
+            }
+        }
+    }
+
+    /// Carry out this action on `file`.
+    pub fn apply(&self, file: &FileInfo) -> io::Result<Outcome> {
+        let path = &file.path;
+        match self {
+            Action::Remove(removal) => {
+                removal.apply(path)?;
+                Ok(Outcome::Done(freed_bytes(file)))
+            }
+            Action::Move { .. } => {
+                move_file(path, &self.destination(path).unwrap())?;
+                Ok(Outcome::Done(freed_bytes(file)))
+            }
+            Action::Compress { format, older_than } => {
+                if let Some(age) = older_than {
+                    let modified = fs::metadata(path)?.modified()?;
+                    if modified.elapsed().unwrap_or_default() < *age {
+                        return Ok(Outcome::Skipped("modified too recently"));
+                    }
+                }
+                if compress::already_compressed(path) {
+                    return Ok(Outcome::Skipped("already compressed"));
+                }
+                let destination = self.destination(path).unwrap();
+                let compressed = compress::compress_file(path, &destination, *format)?;
+                if compressed >= file.size_bytes {
+                    fs::remove_file(&destination)?;
+                    return Ok(Outcome::Skipped("would not get smaller"));
+                }
+                fs::remove_file(path)?;
+                Ok(Outcome::Done(file.size_bytes - compressed))
+            }
+            Action::Exec { template, .. } => {
+                let args = command_line(template, file);
+                let status = Command::new(&args[0])
+                    .args(&args[1..])
+                    .stdin(Stdio::null())
+                    .status()?;
+                if !status.success() {
+                    return Err(io::Error::other(format!(
+                        "{} failed: {}",
+                        args[0].to_string_lossy(),
+                        status
+                    )));
+                }
+                // Whatever the command did, nothing is known to be freed
+                Ok(Outcome::Done(0))
+            }
+        }
+    }
+}
+
+/// Apply `action` to every file in `files`, passing the outcome for each to
+/// `report` in order, and total the files acted on and the bytes freed.
+pub fn apply_all<F>(files: &[FileInfo], action: &Action, mut report: F) -> Summary
+where
+    F: FnMut(&FileInfo, &io::Result<Outcome>),
+{
+    let mut summary = Summary::default();
+    let mut record = |file: &FileInfo, result: io::Result<Outcome>| {
+        if let Ok(Outcome::Done(freed_bytes)) = result {
+            summary.add(freed_bytes);
+        }
+        report(file, &result);
+    };
+
+    // Parallel commands are reported once they have all finished
+    let pool = match action {
+        Action::Exec { jobs, .. } if *jobs > 1 => rayon::ThreadPoolBuilder::new()
+            .num_threads(*jobs)
+            .build()
+            .ok(),
+        _ => None,
+    };
+    match pool {
+        Some(pool) => {
+            let results: Vec<io::Result<Outcome>> =
+                pool.install(|| files.par_iter().map(|file| action.apply(file)).collect());
+            for (file, result) in files.iter().zip(results) {
+                record(file, result);
+            }
+        }
+        None => {
+            for file in files {
+                record(file, action.apply(file));
+            }
+        }
+    }
+    summary
+}
+
+/// What applying `action` to `files` would do, without touching anything.
+pub fn plan(files: &[FileInfo], action: &Action) -> Plan {
+    let steps = files
+        .iter()
+        .map(|file| Step {
+            path: file.path.clone(),
+            size_bytes: file.size_bytes,
+            destination: action.destination(&file.path),
+            command: match action {
+                Action::Exec { template, .. } => Some(
+                    command_line(template, file)
+                        .iter()
+                        .map(|arg| arg.to_string_lossy().into_owned())
+                        .collect(),
+                ),
+                _ => None,
+            },
+        })
+        .collect();
+    Plan {
+        action: action.flag().trim_start_matches('-').to_string(),
+        steps,
+    }
+}
+
+/// Arguments `template` expands to for `file`. `{}` is replaced by the path,
+/// `{size}` by the size in bytes, `{name}` by the file name and `{dir}` by the
+/// directory holding it; without any placeholder the path is appended. Paths
+/// are passed as they are, even when they are not valid Unicode.
+pub fn command_line(template: &[String], file: &FileInfo) -> Vec<OsString> {
+    let size = file.size_bytes.to_string();
+    let values = [
+        ("{}", file.path.as_os_str()),
+        ("{size}", OsStr::new(&size)),
+        ("{name}", file.path.file_name().unwrap_or_default()),
+        (
+            "{dir}",
+            file.path.parent().map_or(OsStr::new(""), Path::as_os_str),
+        ),
+    ];
+
+    let mut args: Vec<OsString> = template.iter().map(|arg| expand(arg, &values)).collect();
+    if !template.iter().any(|arg| {
+        values
+            .iter()
+            .any(|(placeholder, _)| arg.contains(placeholder))
+    }) {
+        args.push(file.path.clone().into_os_string());
+    }
+    args
+}
+
+// `arg` with each placeholder replaced by its value
+fn expand(arg: &str, values: &[(&str, &OsStr)]) -> OsString {
+    let mut expanded = OsString::new();
+    let mut rest = arg;
+    // Take whichever placeholder comes first, until none is left
+    while let Some((at, placeholder, value)) = values
+        .iter()
+        .filter_map(|&(placeholder, value)| {
+            rest.find(placeholder).map(|at| (at, placeholder, value))
+        })
+        .min_by_key(|&(at, _, _)| at)
+    {
+        expanded.push(&rest[..at]);
+        expanded.push(value);
+        rest = &rest[at + placeholder.len()..];
+    }
+    expanded.push(rest);
+    expanded
+}
+
+// Bytes freed in the scanned tree once `file.path` is gone: none while one
+// of its other paths is a hardlink still holding the data. Other paths that
+// reached it through a bind mount went with it
+fn freed_bytes(file: &FileInfo) -> u64 {
+    let linked = file
+        .alternate_paths
+        .iter()
+        .any(|path| fs::symlink_metadata(long_path(path)).is_ok());
+    if linked {
+        0
+    } else {
+        file.size_bytes
+    }
+}
+
+// Move `from` to `to`, creating parent directories and never replacing an
+// existing file, even one created while the move runs. Moves to another
+// filesystem copy the data, then remove `from`
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    let (source, target) = (long_path(from), long_path(to));
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let moved = match rename_no_replace(&source, &target) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            copy_then_remove(&source, &target)
+        }
+        result => result,
+    };
+    moved.map_err(|err| match err.kind() {
+        io::ErrorKind::AlreadyExists => io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ),
+        _ => err,
+    })
+}
+
+// Rename `from` to `to` in one step that fails rather than replacing a file
+// at `to`. Filesystems that cannot refuse to replace get a hardlink instead
+#[cfg(target_os = "linux")]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let from_c = CString::new(from.as_os_str().as_bytes())?;
+    let to_c = CString::new(to.as_os_str().as_bytes())?;
+    // SAFETY: both paths are NUL-terminated and outlive the call
+    let result = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            from_c.as_ptr(),
+            libc::AT_FDCWD,
+            to_c.as_ptr(),
+            libc::RENAME_NOREPLACE,
+        )
+    };
+    if result == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::EINVAL | libc::ENOSYS) => link_then_remove(from, to),
+        _ => Err(err),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let from_c = CString::new(from.as_os_str().as_bytes())?;
+    let to_c = CString::new(to.as_os_str().as_bytes())?;
+    // SAFETY: both paths are NUL-terminated and outlive the call
+    let result = unsafe { libc::renamex_np(from_c.as_ptr(), to_c.as_ptr(), libc::RENAME_EXCL) };
+    if result == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::ENOTSUP | libc::EINVAL) => link_then_remove(from, to),
+        _ => Err(err),
+    }
+}
+
+#[cfg(windows)]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::MoveFileExW;
+
+    let wide =
+        |path: &Path| -> Vec<u16> { path.as_os_str().encode_wide().chain(Some(0)).collect() };
+    let (from_w, to_w) = (wide(from), wide(to));
+    // Without MOVEFILE_REPLACE_EXISTING an existing `to` is an error, and
+    // without MOVEFILE_COPY_ALLOWED so is another volume
+    // SAFETY: both paths are NUL-terminated and outlive the call
+    if unsafe { MoveFileExW(from_w.as_ptr(), to_w.as_ptr(), 0) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    link_then_remove(from, to)
+}
+
+// Give the file at `from` its new name as a hardlink, which is never created
+// over an existing file, then remove the old name
+#[cfg_attr(windows, allow(dead_code))]
+fn link_then_remove(from: &Path, to: &Path) -> io::Result<()> {
+    fs::hard_link(from, to)?;
+    fs::remove_file(from)
+}
+
+// Copy `from` to a new file `to` keeping its permissions and modification
+// time, then remove `from`. An existing `to` is never replaced, and a failed
+// copy leaves `from` untouched and no partial `to`
+fn copy_then_remove(from: &Path, to: &Path) -> io::Result<()> {
+    let mut source = File::open(from)?;
+    let metadata = source.metadata()?;
+    let mut output = File::options().write(true).create_new(true).open(to)?;
+    let copied = io::copy(&mut source, &mut output)
+        .and_then(|_| output.set_permissions(metadata.permissions()))
+        .and_then(|_| output.set_modified(metadata.modified()?));
+    if let Err(err) = copied {
+        drop(output);
+        let _ = fs::remove_file(to);
+        return Err(err);
+    }
+    fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_delete_removes_file() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("big.iso");
+        fs::write(&file, b"data").unwrap();
+        Removal::Delete.apply(&file).unwrap();
+        assert!(!file.exists());
+        assert!(Removal::Delete.apply(&file).is_err());
+    }
+
+    #[test]
+    fn test_apply_all_reports_each_file() {
+        let dir = tempdir().unwrap();
+        let present = dir.path().join("a");
+        fs::write(&present, vec![0; 300]).unwrap();
+        let files = vec![
+            FileInfo::new(&present, 300),
+            FileInfo::new(dir.path().join("missing"), 200),
+        ];
+
+        let mut failed = Vec::new();
+        let summary = apply_all(&files, &Action::Remove(Removal::Delete), |file, result| {
+            if result.is_err() {
+                failed.push(file.path.clone());
+            }
+        });
+        assert_eq!(
+            summary,
+            Summary {
+                removed: 1,
+                freed_bytes: 300
+            }
+        );
+        assert_eq!(failed, vec![files[1].path.clone()]);
+        assert!(!present.exists());
+    }
+
+    #[test]
+    fn test_removing_one_hardlink_frees_nothing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.iso");
+        let link = dir.path().join("b.iso");
+        fs::write(&path, vec![0; 300]).unwrap();
+        fs::hard_link(&path, &link).unwrap();
+        let mut info = FileInfo::new(&path, 300);
+        info.alternate_paths = vec![link.clone()];
+        let action = Action::Remove(Removal::Delete);
+        assert_eq!(action.apply(&info).unwrap(), Outcome::Done(0));
+        assert!(link.exists());
+
+        // Paths gone along with it, as through a bind mount, keep nothing
+        fs::rename(&link, &path).unwrap();
+        info.alternate_paths = vec![dir.path().join("mnt/a.iso")];
+        assert_eq!(action.apply(&info).unwrap(), Outcome::Done(300));
+    }
+
+    #[test]
+    fn test_move_keeps_relative_structure() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("data");
+        fs::create_dir_all(root.join("videos")).unwrap();
+        let file = root.join("videos/big.mkv");
+        fs::write(&file, b"movie").unwrap();
+        let action = Action::Move {
+            root: root.clone(),
+            target: dir.path().join("archive"),
+        };
+
+        let moved = dir.path().join("archive/videos/big.mkv");
+        assert_eq!(action.destination(&file), Some(moved.clone()));
+        let info = FileInfo::new(&file, 5);
+        assert_eq!(action.apply(&info).unwrap(), Outcome::Done(5));
+        assert!(!file.exists());
+        assert_eq!(fs::read(&moved).unwrap(), b"movie");
+
+        // An existing destination is never replaced
+        fs::write(&file, b"newer").unwrap();
+        let err = action.apply(&info).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&moved).unwrap(), b"movie");
+    }
+
+    #[test]
+    fn test_compress_replaces_file() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("app.log");
+        fs::write(&log, "line\n".repeat(100_000)).unwrap();
+        let info = FileInfo::new(&log, 500_000);
+        let action = Action::Compress {
+            format: Format::Zstd,
+            older_than: None,
+        };
+
+        let Outcome::Done(freed) = action.apply(&info).unwrap() else {
+            panic!("not compressed");
+        };
+        let zst = dir.path().join("app.log.zst");
+        assert_eq!(freed, 500_000 - fs::metadata(&zst).unwrap().len());
+        assert!(!log.exists());
+
+        // The compressed copy is recognised and left alone
+        let info = FileInfo::new(zst, 500_000 - freed);
+        assert_eq!(
+            action.apply(&info).unwrap(),
+            Outcome::Skipped("already compressed")
+        );
+    }
+
+    #[test]
+    fn test_compress_skips_recent_and_incompressible_files() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("data");
+        fs::write(&file, b"x").unwrap();
+        let info = FileInfo::new(&file, 1);
+
+        let recent = Action::Compress {
+            format: Format::Gzip,
+            older_than: Some(Duration::from_secs(3600)),
+        };
+        assert_eq!(
+            recent.apply(&info).unwrap(),
+            Outcome::Skipped("modified too recently")
+        );
+        let any = Action::Compress {
+            format: Format::Gzip,
+            older_than: None,
+        };
+        assert_eq!(
+            any.apply(&info).unwrap(),
+            Outcome::Skipped("would not get smaller")
+        );
+        assert!(file.exists());
+        assert!(!dir.path().join("data.gz").exists());
+    }
+
+    #[test]
+    fn test_plan_changes_nothing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("big.log");
+        fs::write(&path, b"data").unwrap();
+        let files = vec![FileInfo::new(&path, 4)];
+
+        let plan = plan(
+            &files,
+            &Action::Compress {
+                format: Format::Gzip,
+                older_than: None,
+            },
+        );
+        assert_eq!(plan.action, "compress");
+        assert_eq!(
+            plan.steps[0].destination,
+            Some(dir.path().join("big.log.gz"))
+        );
+        assert_eq!(plan.steps[0].command, None);
+        assert!(path.exists());
+        assert!(!dir.path().join("big.log.gz").exists());
+    }
+
+    #[test]
+    fn test_command_line_placeholders() {
+        let file = FileInfo::new("/srv/data/big file.iso", 4096);
+        let template: Vec<String> = ["cp", "{}", "/backup/{name}.{size}", "--from={dir}"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(
+            command_line(&template, &file),
+            vec![
+                "cp",
+                "/srv/data/big file.iso",
+                "/backup/big file.iso.4096",
+                "--from=/srv/data"
+            ]
+        );
+        assert_eq!(
+            command_line(&["ls".to_string(), "-l".to_string()], &file),
+            vec!["ls", "-l", "/srv/data/big file.iso"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_runs_command_per_file() {
+        let dir = tempdir().unwrap();
+        let files: Vec<FileInfo> = (0..4)
+            .map(|i| FileInfo::new(dir.path().join(i.to_string()), i))
+            .collect();
+        let action = Action::Exec {
+            template: vec!["touch".to_string(), "{}".to_string()],
+            jobs: 2,
+        };
+
+        let mut reported = Vec::new();
+        let summary = apply_all(&files, &action, |file, result| {
+            assert!(result.is_ok());
+            reported.push(file.path.clone());
+        });
+        assert_eq!(summary.removed, 4);
+        assert_eq!(
+            reported,
+            files
+                .iter()
+                .map(|file| file.path.clone())
+                .collect::<Vec<_>>()
+        );
+        assert!(files.iter().all(|file| file.path.exists()));
+
+        let failing = Action::Exec {
+            template: vec!["false".to_string()],
+            jobs: 1,
+        };
+        assert!(failing.apply(&files[0]).is_err());
+    }
+
+    #[test]
+    fn test_copy_then_remove_keeps_mtime() {
+        let dir = tempdir().unwrap();
+        let from = dir.path().join("a");
+        fs::write(&from, b"data").unwrap();
+        let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        File::options()
+            .write(true)
+            .open(&from)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let to = dir.path().join("b");
+        copy_then_remove(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::metadata(&to).unwrap().modified().unwrap(), old);
+    }
+
+    #[test]
+    fn test_moves_never_replace_files() {
+        let dir = tempdir().unwrap();
+        let from = dir.path().join("a");
+        let to = dir.path().join("b");
+        fs::write(&from, b"new").unwrap();
+        fs::write(&to, b"old").unwrap();
+
+        for err in [
+            rename_no_replace(&from, &to).unwrap_err(),
+            copy_then_remove(&from, &to).unwrap_err(),
+            move_file(&from, &to).unwrap_err(),
+        ] {
+            assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        }
+        assert_eq!(fs::read(&from).unwrap(), b"new");
+        assert_eq!(fs::read(&to).unwrap(), b"old");
+
+        link_then_remove(&from, &dir.path().join("c")).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read(dir.path().join("c")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_summary_add() {
+        let mut summary = Summary::default();
+        summary.add(100);
+        summary.add(50);
+        assert_eq!(
+            summary,
+            Summary {
+                removed: 2,
+                freed_bytes: 150
+            }
+        );
+    }
+}
@@ -0,0 +1,104 @@
+// Import Path for the file whose streams are listed
+use std::path::Path;
+
+/// The named alternate data streams of the file or directory at `path`, with
+/// their sizes. The unnamed stream holding the file's usual contents is left
+/// out. Only NTFS has streams, so elsewhere there are none.
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub(crate) fn streams(path: &Path) -> Vec<(String, u64)> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+        use windows_sys::Win32::Storage::FileSystem::{
+            FindClose, FindFirstStreamW, FindNextStreamW, FindStreamInfoStandard,
+            WIN32_FIND_STREAM_DATA,
+        };
+
+        let wide: Vec<u16> = crate::long_path(path)
+            .as_os_str()
+            .encode_wide()
+            .chain(Some(0))
+            .collect();
+        let mut found = Vec::new();
+        let mut data = WIN32_FIND_STREAM_DATA::default();
+        // SAFETY: the name is NUL-terminated and `data` outlives the search
+        // handle, which is closed before returning
+        unsafe {
+            let handle = FindFirstStreamW(
+                wide.as_ptr(),
+                FindStreamInfoStandard,
+                (&mut data as *mut WIN32_FIND_STREAM_DATA).cast(),
+                0,
+            );
+            if handle == INVALID_HANDLE_VALUE {
+                return found;
+            }
+            loop {
+                let len = data
+                    .cStreamName
+                    .iter()
+                    .position(|&unit| unit == 0)
+                    .unwrap_or(data.cStreamName.len());
+                let raw = String::from_utf16_lossy(&data.cStreamName[..len]);
+                if let Some(name) = stream_name(&raw) {
+                    found.push((name.to_string(), data.StreamSize.max(0) as u64));
+                }
+                if FindNextStreamW(handle, (&mut data as *mut WIN32_FIND_STREAM_DATA).cast()) == 0 {
+                    break;
+                }
+            }
+            FindClose(handle);
+        }
+        found
+    }
+    #[cfg(not(windows))]
+    {
+        Vec::new()
+    }
+}
+
+// The name of a stream as FindFirstStreamW reports it, `:name:$DATA`, or None
+// for the unnamed `::$DATA` stream
+#[cfg(any(windows, test))]
+fn stream_name(raw: &str) -> Option<&str> {
+    let name = raw.strip_prefix(':')?.strip_suffix(":$DATA")?;
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_name() {
+        assert_eq!(
+            stream_name(":Zone.Identifier:$DATA"),
+            Some("Zone.Identifier")
+        );
+        assert_eq!(stream_name(":hidden payload:$DATA"), Some("hidden payload"));
+        assert_eq!(stream_name("::$DATA"), None);
+        assert_eq!(stream_name("garbage"), None);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_no_streams_outside_windows() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(streams(dir.path()).is_empty());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_streams_lists_named_streams() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, b"visible").unwrap();
+        let stream = format!("{}:hidden", file.display());
+        if std::fs::write(&stream, [0u8; 4096]).is_err() {
+            // Not on NTFS
+            return;
+        }
+        assert_eq!(streams(&file), vec![("hidden".to_string(), 4096)]);
+    }
+}
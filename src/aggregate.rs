@@ -0,0 +1,619 @@
+// Import FileInfo for the matches grouped by extension
+use list_big_files::FileInfo;
+// Import serde for JSON output of the totals
+use serde::Serialize;
+// Import Reverse for listing the largest entries first
+use std::cmp::Reverse;
+// Import collections for totals keyed by directory or user, and each user's
+// largest files
+use std::collections::{BinaryHeap, HashMap};
+// Import Path and PathBuf for directory keys
+use std::path::{Path, PathBuf};
+// Import time types for bucketing matches by age
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Size and number of files below one directory.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct Total {
+    pub size_bytes: u64,
+    pub files: u64,
+}
+
+impl Total {
+    // Count one more file of `size_bytes`
+    fn add(&mut self, size_bytes: u64) {
+        self.size_bytes += size_bytes;
+        self.files += 1;
+    }
+}
+
+/// A directory and everything below it, as reported by `--dirs`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DirEntry {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub total: Total,
+}
+
+/// Cumulative sizes of the root and of its directories down to `depth`
+/// levels; deeper files count towards their ancestor at that depth.
+pub struct DirTotals {
+    root: PathBuf,
+    depth: usize,
+    dirs: HashMap<PathBuf, Total>,
+}
+
+impl DirTotals {
+    pub fn new(root: &Path, depth: usize) -> Self {
+        DirTotals {
+            root: root.to_path_buf(),
+            depth,
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Count the file at `path`, which must lie below the root.
+    pub fn add(&mut self, path: &Path, size_bytes: u64) {
+        let Some(parent) = path.strip_prefix(&self.root).ok().and_then(Path::parent) else {
+            return;
+        };
+
+        let mut dir = self.root.clone();
+        self.dirs.entry(dir.clone()).or_default().add(size_bytes);
+        for component in parent.components().take(self.depth) {
+            dir.push(component);
+            self.dirs.entry(dir.clone()).or_default().add(size_bytes);
+        }
+    }
+
+    /// Count a file of `size_bytes` towards `dir` alone, for directories
+    /// picked by something other than their depth, such as `--preset dev`.
+    pub fn add_to(&mut self, dir: &Path, size_bytes: u64) {
+        self.dirs
+            .entry(dir.to_path_buf())
+            .or_default()
+            .add(size_bytes);
+    }
+
+    /// Directories of at least `min_size_bytes`, largest first.
+    pub fn largest(self, min_size_bytes: u64) -> Vec<DirEntry> {
+        let mut dirs: Vec<DirEntry> = self
+            .dirs
+            .into_iter()
+            .filter(|(_, total)| total.size_bytes >= min_size_bytes)
+            .map(|(path, total)| DirEntry { path, total })
+            .collect();
+        dirs.sort_by(|a, b| {
+            Reverse(a.total.size_bytes)
+                .cmp(&Reverse(b.total.size_bytes))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        dirs
+    }
+}
+
+/// Directories below `root` ranked by the combined size of the matches inside
+/// them, at any depth, keeping the largest `limit`.
+pub fn top_dirs(root: &Path, files: &[FileInfo], limit: usize) -> Vec<DirEntry> {
+    let mut totals = DirTotals::new(root, usize::MAX);
+    for file in files {
+        totals.add(&file.path, file.size_bytes);
+    }
+    // The root holds every match, so ranking it would say nothing
+    totals
+        .largest(0)
+        .into_iter()
+        .filter(|dir| dir.path != root)
+        .take(limit)
+        .collect()
+}
+
+// How many of each user's files --by-user lists
+const USER_LARGEST: usize = 5;
+
+/// One user's share of a scan, as reported by `--by-user`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct UserEntry {
+    pub user: String,
+    #[serde(flatten)]
+    pub total: Total,
+    /// The user's largest files, largest first.
+    pub largest: Vec<UserFile>,
+}
+
+/// A file listed under its owner by `--by-user`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct UserFile {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+// Sizes and paths of a user's largest files, the smallest of them on top
+type Largest = BinaryHeap<Reverse<(u64, PathBuf)>>;
+
+/// Every scanned file totalled by the user owning it, keeping each user's
+/// largest files.
+#[derive(Default)]
+pub struct UserTotals {
+    users: HashMap<String, (Total, Largest)>,
+}
+
+impl UserTotals {
+    /// Count the file at `path` towards `user`.
+    pub fn add(&mut self, user: &str, path: &Path, size_bytes: u64) {
+        let (total, largest) = self.users.entry(user.to_string()).or_default();
+        total.add(size_bytes);
+        if largest.len() == USER_LARGEST {
+            match largest.peek() {
+                Some(Reverse((smallest, _))) if *smallest < size_bytes => {
+                    largest.pop();
+                }
+                _ => return,
+            }
+        }
+        largest.push(Reverse((size_bytes, path.to_path_buf())));
+    }
+
+    /// Users owning at least `min_size_bytes`, largest total first.
+    pub fn largest(self, min_size_bytes: u64) -> Vec<UserEntry> {
+        let mut users: Vec<UserEntry> = self
+            .users
+            .into_iter()
+            .filter(|(_, (total, _))| total.size_bytes >= min_size_bytes)
+            .map(|(user, (total, largest))| {
+                // Sorting the reversed entries puts the largest first
+                let mut largest = largest.into_vec();
+                largest.sort();
+                UserEntry {
+                    user,
+                    total,
+                    largest: largest
+                        .into_iter()
+                        .map(|Reverse((size_bytes, path))| UserFile { path, size_bytes })
+                        .collect(),
+                }
+            })
+            .collect();
+        users.sort_by(|a, b| {
+            Reverse(a.total.size_bytes)
+                .cmp(&Reverse(b.total.size_bytes))
+                .then_with(|| a.user.cmp(&b.user))
+        });
+        users
+    }
+}
+
+/// Matches sharing one extension, as reported by `--stats-by-ext`.
+#[derive(Debug, PartialEq)]
+pub struct ExtStats<'f> {
+    /// Lowercased extension without the dot, or empty for files without one.
+    pub extension: String,
+    pub total: Total,
+    pub largest: &'f FileInfo,
+}
+
+/// Group `files` by extension, largest total first.
+pub fn by_extension(files: &[FileInfo]) -> Vec<ExtStats<'_>> {
+    let mut groups: HashMap<String, ExtStats> = HashMap::new();
+    for file in files {
+        let extension = file
+            .path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let group = groups.entry(extension.clone()).or_insert_with(|| ExtStats {
+            extension,
+            total: Total::default(),
+            largest: file,
+        });
+        group.total.add(file.size_bytes);
+        if file.size_bytes > group.largest.size_bytes {
+            group.largest = file;
+        }
+    }
+
+    let mut groups: Vec<ExtStats> = groups.into_values().collect();
+    groups.sort_by(|a, b| {
+        Reverse(a.total.size_bytes)
+            .cmp(&Reverse(b.total.size_bytes))
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+    groups
+}
+
+/// Matches clustered under one heading by `--group-by`.
+#[derive(Debug, PartialEq)]
+pub struct Group<'f> {
+    pub label: String,
+    pub total: Total,
+    /// The group's matches, in the order they were given.
+    pub files: Vec<&'f FileInfo>,
+}
+
+/// Group `files` by their parent directory, largest total first.
+pub fn by_dir(files: &[FileInfo]) -> Vec<Group<'_>> {
+    group_by_key(files, |file| {
+        let parent = file.path.parent().unwrap_or(Path::new(""));
+        // Bare file names, such as relative paths at the scan root
+        if parent.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            parent.to_string_lossy().into_owned()
+        }
+    })
+}
+
+/// Group `files` by the user owning them, largest total first. Matches
+/// whose owner is unknown share an `(unknown)` group.
+pub fn by_owner(files: &[FileInfo]) -> Vec<Group<'_>> {
+    group_by_key(files, |file| {
+        file.owner
+            .clone()
+            .unwrap_or_else(|| "(unknown)".to_string())
+    })
+}
+
+// Age groups, newest first, and the age in days each one ends at; the last
+// two hold everything older and files without a modification time
+const AGE_LABELS: [&str; 5] = ["This week", "This month", "This year", "Older", "(unknown)"];
+const AGE_LIMITS: [u64; 3] = [7, 30, 365];
+
+/// Group `files` by how long before `now` they were last modified: this week,
+/// this month, this year or older, newest first. Matches without a
+/// modification time share an `(unknown)` group at the end.
+pub fn by_age(files: &[FileInfo], now: SystemTime) -> Vec<Group<'_>> {
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let mut groups = group_by_key(files, |file| {
+        let bucket = match file.modified {
+            Some(modified) => {
+                let days = now.saturating_sub(modified) / 86_400;
+                AGE_LIMITS
+                    .iter()
+                    .position(|&limit| days < limit)
+                    .unwrap_or(AGE_LIMITS.len())
+            }
+            None => AGE_LABELS.len() - 1,
+        };
+        AGE_LABELS[bucket].to_string()
+    });
+    groups.sort_by_key(|group| AGE_LABELS.iter().position(|&label| label == group.label));
+    groups
+}
+
+// Group `files` by the label `key` gives each, largest total first
+fn group_by_key<'f>(files: &'f [FileInfo], key: impl Fn(&FileInfo) -> String) -> Vec<Group<'f>> {
+    let mut groups: HashMap<String, Group> = HashMap::new();
+    for file in files {
+        let label = key(file);
+        let group = groups.entry(label.clone()).or_insert_with(|| Group {
+            label,
+            total: Total::default(),
+            files: Vec::new(),
+        });
+        group.total.add(file.size_bytes);
+        group.files.push(file);
+    }
+
+    let mut groups: Vec<Group> = groups.into_values().collect();
+    groups.sort_by(|a, b| {
+        Reverse(a.total.size_bytes)
+            .cmp(&Reverse(b.total.size_bytes))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    groups
+}
+
+// Upper bounds of the histogram buckets; the last bucket is open-ended
+const BUCKET_LIMITS: [u64; 6] = [1 << 20, 10 << 20, 100 << 20, 1 << 30, 10 << 30, 100 << 30];
+const BUCKET_LABELS: [&str; 7] = [
+    "< 1 MiB",
+    "1-10 MiB",
+    "10-100 MiB",
+    "100 MiB-1 GiB",
+    "1-10 GiB",
+    "10-100 GiB",
+    ">= 100 GiB",
+];
+
+/// Every scanned file bucketed by order of magnitude of its size.
+#[derive(Debug, Default)]
+pub struct Histogram {
+    buckets: [Total; BUCKET_LABELS.len()],
+}
+
+impl Histogram {
+    /// Count a file of `size_bytes`.
+    pub fn add(&mut self, size_bytes: u64) {
+        let bucket = BUCKET_LIMITS
+            .iter()
+            .position(|&limit| size_bytes < limit)
+            .unwrap_or(BUCKET_LIMITS.len());
+        self.buckets[bucket].add(size_bytes);
+    }
+
+    /// Label and total of every bucket, smallest sizes first.
+    pub fn rows(&self) -> impl Iterator<Item = (&'static str, Total)> + '_ {
+        BUCKET_LABELS
+            .iter()
+            .copied()
+            .zip(self.buckets.iter().copied())
+    }
+
+    /// Combined total of all buckets.
+    pub fn total(&self) -> Total {
+        self.buckets
+            .iter()
+            .fold(Total::default(), |sum, bucket| Total {
+                size_bytes: sum.size_bytes + bucket.size_bytes,
+                files: sum.files + bucket.files,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size_bytes: u64) -> FileInfo {
+        FileInfo::new(path.to_string(), size_bytes)
+    }
+
+    fn totals(depth: usize) -> DirTotals {
+        let mut totals = DirTotals::new(Path::new("/r"), depth);
+        totals.add(Path::new("/r/top.bin"), 1);
+        totals.add(Path::new("/r/a/one.bin"), 10);
+        totals.add(Path::new("/r/a/b/two.bin"), 100);
+        totals.add(Path::new("/r/c/three.bin"), 1000);
+        totals
+    }
+
+    fn sizes(dirs: &[DirEntry]) -> Vec<(&str, u64, u64)> {
+        dirs.iter()
+            .map(|dir| {
+                (
+                    dir.path.to_str().unwrap(),
+                    dir.total.size_bytes,
+                    dir.total.files,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_depth_one() {
+        let dirs = totals(1).largest(0);
+        assert_eq!(
+            sizes(&dirs),
+            vec![("/r", 1111, 4), ("/r/c", 1000, 1), ("/r/a", 110, 2)]
+        );
+    }
+
+    #[test]
+    fn test_deeper_levels_and_threshold() {
+        let dirs = totals(2).largest(100);
+        assert_eq!(
+            sizes(&dirs),
+            vec![
+                ("/r", 1111, 4),
+                ("/r/c", 1000, 1),
+                ("/r/a", 110, 2),
+                ("/r/a/b", 100, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_depth_zero_is_root_only() {
+        assert_eq!(sizes(&totals(0).largest(0)), vec![("/r", 1111, 4)]);
+    }
+
+    #[test]
+    fn test_ignores_paths_outside_root() {
+        let mut totals = DirTotals::new(Path::new("/r"), 1);
+        totals.add(Path::new("/elsewhere/x.bin"), 5);
+        assert!(totals.largest(0).is_empty());
+    }
+
+    #[test]
+    fn test_by_extension() {
+        let files = [
+            file("/r/a.bak", 300),
+            file("/r/b.BAK", 500),
+            file("/r/c.iso", 700),
+            file("/r/Makefile", 10),
+            file("/r/.hidden", 5),
+        ];
+        let stats = by_extension(&files);
+        let summary: Vec<(&str, u64, u64, &str)> = stats
+            .iter()
+            .map(|s| {
+                (
+                    s.extension.as_str(),
+                    s.total.files,
+                    s.total.size_bytes,
+                    s.largest.path.to_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("bak", 2, 800, "/r/b.BAK"),
+                ("iso", 1, 700, "/r/c.iso"),
+                ("", 2, 15, "/r/Makefile"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_by_extension_empty() {
+        assert!(by_extension(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_by_dir() {
+        let files = [
+            file("/r/a/one.bin", 100),
+            file("/r/c/two.bin", 120),
+            file("/r/a/three.bin", 50),
+            file("top.bin", 10),
+        ];
+        let groups = by_dir(&files);
+        let summary: Vec<(&str, u64, u64, Vec<&str>)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.label.as_str(),
+                    group.total.files,
+                    group.total.size_bytes,
+                    group
+                        .files
+                        .iter()
+                        .map(|file| file.path.to_str().unwrap())
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/r/a", 2, 150, vec!["/r/a/one.bin", "/r/a/three.bin"]),
+                ("/r/c", 1, 120, vec!["/r/c/two.bin"]),
+                (".", 1, 10, vec!["top.bin"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_by_owner() {
+        let owned = |path: &str, size_bytes: u64, owner: Option<&str>| {
+            let mut file = file(path, size_bytes);
+            file.owner = owner.map(str::to_string);
+            file
+        };
+        let files = [
+            owned("/r/a.bin", 100, Some("alice")),
+            owned("/r/b.bin", 300, Some("bob")),
+            owned("/r/c.bin", 50, Some("alice")),
+            owned("/r/d.bin", 1, None),
+        ];
+        let groups = by_owner(&files);
+        let summary: Vec<(&str, u64, u64)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.label.as_str(),
+                    group.total.files,
+                    group.total.size_bytes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("bob", 1, 300), ("alice", 2, 150), ("(unknown)", 1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_by_age() {
+        let now = UNIX_EPOCH + std::time::Duration::from_secs(1_000 * 86_400);
+        let aged = |path: &str, size_bytes: u64, days: Option<u64>| {
+            let mut file = file(path, size_bytes);
+            file.modified = days.map(|days| (1_000 - days) * 86_400);
+            file
+        };
+        let files = [
+            aged("/r/old.bin", 500, Some(400)),
+            aged("/r/new.bin", 1, Some(0)),
+            aged("/r/gone.bin", 7, None),
+            aged("/r/spring.bin", 20, Some(100)),
+            aged("/r/ancient.bin", 30, Some(900)),
+        ];
+        let groups = by_age(&files, now);
+        let summary: Vec<(&str, u64, u64)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.label.as_str(),
+                    group.total.files,
+                    group.total.size_bytes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("This week", 1, 1),
+                ("This year", 1, 20),
+                ("Older", 2, 530),
+                ("(unknown)", 1, 7)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        let mut histogram = Histogram::default();
+        for size in [0, (1 << 20) - 1, 1 << 20, 5 << 20, 1 << 30, 200 << 30] {
+            histogram.add(size);
+        }
+        let files: Vec<(&str, u64)> = histogram
+            .rows()
+            .map(|(label, total)| (label, total.files))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("< 1 MiB", 2),
+                ("1-10 MiB", 2),
+                ("10-100 MiB", 0),
+                ("100 MiB-1 GiB", 0),
+                ("1-10 GiB", 1),
+                ("10-100 GiB", 0),
+                (">= 100 GiB", 1),
+            ]
+        );
+        assert_eq!(histogram.total().files, 6);
+        assert_eq!(
+            histogram.total().size_bytes,
+            (1 << 20) - 1 + (1 << 20) + (5 << 20) + (1 << 30) + (200 << 30)
+        );
+    }
+
+    #[test]
+    fn test_top_dirs() {
+        let files = [
+            file("/r/a/b/one.bin", 100),
+            file("/r/a/two.bin", 50),
+            file("/r/c/three.bin", 120),
+            file("/r/top.bin", 500),
+        ];
+        let dirs = top_dirs(Path::new("/r"), &files, 2);
+        assert_eq!(sizes(&dirs), vec![("/r/a", 150, 2), ("/r/c", 120, 1)]);
+        assert_eq!(top_dirs(Path::new("/r"), &files, 10).len(), 3);
+    }
+
+    #[test]
+    fn test_user_totals() {
+        let mut totals = UserTotals::default();
+        for size in 1..=8 {
+            totals.add(
+                "alice",
+                Path::new(&format!("/home/alice/{}.bin", size)),
+                size,
+            );
+        }
+        totals.add("bob", Path::new("/home/bob/big.iso"), 30);
+        totals.add("carol", Path::new("/home/carol/note.txt"), 2);
+
+        let users = totals.largest(3);
+        let summary: Vec<_> = users
+            .iter()
+            .map(|user| (user.user.as_str(), user.total.size_bytes, user.total.files))
+            .collect();
+        assert_eq!(summary, vec![("alice", 36, 8), ("bob", 30, 1)]);
+        let largest: Vec<u64> = users[0].largest.iter().map(|f| f.size_bytes).collect();
+        assert_eq!(largest, vec![8, 7, 6, 5, 4]);
+        assert_eq!(users[1].largest[0].path, Path::new("/home/bob/big.iso"));
+    }
+}
@@ -0,0 +1,196 @@
+// Import the saved scan format the alerts summarize
+use crate::report::ScanReport;
+// Import the size helpers and match type shared with the scanner
+use list_big_files::{display_size, FileInfo, SizeUnit};
+// Import serde for the webhook payload
+use serde::Serialize;
+// Import Reverse for listing the largest files first
+use std::cmp::Reverse;
+// Import Duration for the request timeout
+use std::time::Duration;
+
+// Largest number of files listed in one alert; chat services reject huge messages
+const MAX_FILES: usize = 50;
+
+// How long a webhook may take before it is abandoned
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// JSON body posted to `--webhook`. `text` is what Slack and Teams incoming
+/// webhooks display; the other fields are for programmatic consumers.
+#[derive(Debug, Serialize)]
+pub struct Payload<'r> {
+    pub text: String,
+    pub directory: String,
+    pub min_size_bytes: u64,
+    pub matches: usize,
+    pub total_bytes: u64,
+    pub partial: bool,
+    pub files: Vec<&'r FileInfo>,
+    pub truncated: bool,
+}
+
+/// Combined size of `files`.
+pub fn total_bytes(files: &[FileInfo]) -> u64 {
+    files.iter().map(|file| file.size_bytes).sum()
+}
+
+/// Whether a scan is worth an alert: any match at all, or with
+/// `--alert-threshold`, matches adding up to at least that size.
+pub fn should_alert(files: &[FileInfo], threshold: Option<u64>) -> bool {
+    match threshold {
+        Some(threshold) => !files.is_empty() && total_bytes(files) >= threshold,
+        None => !files.is_empty(),
+    }
+}
+
+/// Whether `files` add up to more than the `--alert-total` budget.
+pub fn over_budget(files: &[FileInfo], budget: u64) -> bool {
+    total_bytes(files) > budget
+}
+
+/// One-line human summary of a scan, e.g. for a chat message or mail subject.
+pub fn summary(report: &ScanReport, unit: SizeUnit) -> String {
+    format!(
+        "list-big-files: {} files >= {} ({} total) in {}{}",
+        report.files.len(),
+        display_size(report.min_size_bytes, unit),
+        display_size(total_bytes(&report.files), unit),
+        report.directory.display(),
+        if report.partial {
+            " (partial scan)"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Build the webhook payload for `report`, listing the largest files first.
+pub fn payload(report: &ScanReport, unit: SizeUnit) -> Payload<'_> {
+    let mut files: Vec<&FileInfo> = report.files.iter().collect();
+    files.sort_by_key(|file| Reverse(file.size_bytes));
+    let truncated = files.len() > MAX_FILES;
+    files.truncate(MAX_FILES);
+
+    let mut text = summary(report, unit);
+    for file in &files {
+        text.push_str(&format!(
+            "\n{}  {}",
+            display_size(file.size_bytes, unit),
+            file.path.display()
+        ));
+    }
+    if truncated {
+        text.push_str(&format!(
+            "\n... and {} more",
+            report.files.len() - MAX_FILES
+        ));
+    }
+
+    Payload {
+        text,
+        directory: report.directory.display().to_string(),
+        min_size_bytes: report.min_size_bytes,
+        matches: report.files.len(),
+        total_bytes: total_bytes(&report.files),
+        partial: report.partial,
+        files,
+        truncated,
+    }
+}
+
+/// POST `payload` as JSON to `url`.
+pub fn send_webhook(url: &str, payload: &Payload) -> Result<(), String> {
+    let body = serde_json::to_string(payload).expect("payloads always serialize");
+    ureq::post(url)
+        .timeout(TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(&body)
+        .map(|_| ())
+        .map_err(|err| format!("webhook to {} failed: {}", url, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::thread;
+
+    fn report(sizes: &[u64]) -> ScanReport {
+        ScanReport {
+            directory: PathBuf::from("/srv"),
+            min_size_bytes: 1024 * 1024,
+            files_scanned: 100,
+            partial: false,
+            volume: None,
+            files: sizes
+                .iter()
+                .enumerate()
+                .map(|(i, &size_bytes)| FileInfo::new(format!("/srv/{}.bin", i), size_bytes))
+                .collect(),
+            dry_run: None,
+            scan: None,
+        }
+    }
+
+    #[test]
+    fn test_should_alert() {
+        let mb = 1024 * 1024;
+        assert!(!should_alert(&report(&[]).files, None));
+        assert!(should_alert(&report(&[mb]).files, None));
+        assert!(!should_alert(&report(&[mb, mb]).files, Some(3 * mb)));
+        assert!(should_alert(&report(&[mb, 2 * mb]).files, Some(3 * mb)));
+    }
+
+    #[test]
+    fn test_over_budget() {
+        let mb = 1024 * 1024;
+        assert!(!over_budget(&report(&[]).files, 0));
+        assert!(!over_budget(&report(&[mb, 2 * mb]).files, 3 * mb));
+        assert!(over_budget(&report(&[mb, 2 * mb]).files, 3 * mb - 1));
+    }
+
+    #[test]
+    fn test_payload_lists_largest_first_and_truncates() {
+        let sizes: Vec<u64> = (1..=60).map(|i| i * 1024 * 1024).collect();
+        let report = report(&sizes);
+        let payload = payload(&report, SizeUnit::MiB);
+        assert_eq!(payload.matches, 60);
+        assert_eq!(payload.files.len(), MAX_FILES);
+        assert!(payload.truncated);
+        assert_eq!(payload.files[0].size_bytes, 60 * 1024 * 1024);
+        assert!(payload
+            .text
+            .starts_with("list-big-files: 60 files >= 1.00 MiB (1830.00 MiB total) in /srv"));
+        assert!(payload.text.ends_with("... and 10 more"));
+    }
+
+    #[test]
+    fn test_send_webhook_posts_json() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+        let receiver = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let _ = request.respond(tiny_http::Response::from_string("ok"));
+            body
+        });
+
+        let report = report(&[2 * 1024 * 1024]);
+        send_webhook(&url, &payload(&report, SizeUnit::MiB)).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&receiver.join().unwrap()).unwrap();
+        assert_eq!(body["matches"], 1);
+        assert_eq!(body["files"][0]["path"], "/srv/0.bin");
+    }
+
+    #[test]
+    fn test_send_webhook_reports_http_errors() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+        thread::spawn(move || {
+            let request = server.recv().unwrap();
+            let _ = request.respond(tiny_http::Response::empty(500));
+        });
+        assert!(send_webhook(&url, &payload(&report(&[1]), SizeUnit::MiB)).is_err());
+    }
+}
@@ -0,0 +1,695 @@
+// Import rayon to read several archives at once
+use rayon::prelude::*;
+// Import the gzip decoder for .tar.gz archives
+use flate2::read::MultiGzDecoder;
+// Import FileInfo for the members reported as matches
+use list_big_files::FileInfo;
+// Import fs and io for reading archive indexes
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+// Import Path types for archive names
+use std::path::{Path, PathBuf};
+// Import warn for archives that cannot be read
+use tracing::warn;
+
+/// Archive kinds `--scan-archives` can look inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+}
+
+// Names accepted by --scan-archives and the file name endings of each format
+const FORMATS: [(&str, ArchiveFormat, &[&str]); 4] = [
+    ("zip", ArchiveFormat::Zip, &[".zip"]),
+    ("tar", ArchiveFormat::Tar, &[".tar"]),
+    ("tar.gz", ArchiveFormat::TarGz, &[".tar.gz", ".tgz"]),
+    ("tar.zst", ArchiveFormat::TarZst, &[".tar.zst", ".tzst"]),
+];
+
+/// Parse the comma-separated list given to `--scan-archives`.
+pub fn parse(list: &str) -> Result<Vec<ArchiveFormat>, String> {
+    let mut formats = Vec::new();
+    for name in list.split(',').map(str::trim) {
+        let format = match name {
+            "tgz" => ArchiveFormat::TarGz,
+            "tzst" => ArchiveFormat::TarZst,
+            name => FORMATS
+                .iter()
+                .find(|(format, _, _)| *format == name)
+                .map(|&(_, format, _)| format)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown archive format {:?} (expected zip, tar, tar.gz or tar.zst)",
+                        name
+                    )
+                })?,
+        };
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    Ok(formats)
+}
+
+/// The format of the archive at `path` among `formats`, judged by the end
+/// of its name, or None when it is not one to look inside.
+pub fn format_of(path: &Path, formats: &[ArchiveFormat]) -> Option<ArchiveFormat> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    FORMATS
+        .iter()
+        .find(|(_, _, endings)| endings.iter().any(|ending| name.ends_with(ending)))
+        .map(|&(_, format, _)| format)
+        .filter(|format| formats.contains(format))
+}
+
+/// A file stored in an archive, with its uncompressed size.
+#[derive(Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub size_bytes: u64,
+}
+
+/// The members of at least `min_size_bytes` in each of `archives`, as
+/// matches named `archive.zip!inner/path`. Archives that cannot be read are
+/// reported on stderr and skipped.
+pub fn large_members(
+    archives: &[PathBuf],
+    formats: &[ArchiveFormat],
+    min_size_bytes: u64,
+) -> Vec<FileInfo> {
+    archives
+        .par_iter()
+        .flat_map_iter(|archive| {
+            let members = match format_of(archive, formats).map(|format| members(archive, format)) {
+                Some(Ok(members)) => members,
+                Some(Err(err)) => {
+                    warn!("cannot read archive {}: {}", archive.display(), err);
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+            members
+                .into_iter()
+                .filter(|member| member.size_bytes >= min_size_bytes)
+                .map(|member| {
+                    let mut shown = archive.clone().into_os_string();
+                    shown.push("!");
+                    shown.push(&member.name);
+                    FileInfo::new(shown, member.size_bytes)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The files stored in the archive at `path`, leaving out directories.
+pub fn members(path: &Path, format: ArchiveFormat) -> io::Result<Vec<Member>> {
+    match format {
+        ArchiveFormat::Zip => zip_members(&mut File::open(path)?),
+        ArchiveFormat::Tar => tar_members(BufReader::new(File::open(path)?)),
+        ArchiveFormat::TarGz => tar_members(MultiGzDecoder::new(BufReader::new(File::open(path)?))),
+        ArchiveFormat::TarZst => tar_members(zstd::Decoder::new(File::open(path)?)?),
+    }
+}
+
+// Signatures of the zip records read here
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const ZIP64_END_OF_DIRECTORY: u32 = 0x0606_4b50;
+const DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+
+// The end record is 22 bytes followed by a comment of up to 64 KiB
+const END_SEARCH: u64 = 22 + 0xffff;
+
+// Read a zip's central directory, which lists every member and its size at
+// the end of the file, without decompressing anything
+fn zip_members<R: Read + Seek>(zip: &mut R) -> io::Result<Vec<Member>> {
+    let length = zip.seek(SeekFrom::End(0))?;
+    let tail_start = length.saturating_sub(END_SEARCH);
+    let mut tail = Vec::new();
+    zip.seek(SeekFrom::Start(tail_start))?;
+    zip.take(END_SEARCH).read_to_end(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| u32_at(&tail, i) == END_OF_DIRECTORY)
+        .ok_or_else(|| invalid("no end of central directory record"))?;
+
+    let mut entries = u64::from(u16_at(&tail, end + 10));
+    let mut directory_offset = u64::from(u32_at(&tail, end + 16));
+    // Archives too large for the original fields keep them in a Zip64 record
+    if end >= 20 && u32_at(&tail, end - 20) == ZIP64_LOCATOR {
+        let record_offset = u64_at(&tail, end - 20 + 8);
+        let mut record = [0; 56];
+        zip.seek(SeekFrom::Start(record_offset))?;
+        zip.read_exact(&mut record)?;
+        if u32_at(&record, 0) != ZIP64_END_OF_DIRECTORY {
+            return Err(invalid("bad Zip64 end of central directory record"));
+        }
+        entries = u64_at(&record, 32);
+        directory_offset = u64_at(&record, 48);
+    }
+
+    zip.seek(SeekFrom::Start(directory_offset))?;
+    let mut directory = io::BufReader::new(zip);
+    let mut members = Vec::new();
+    for _ in 0..entries {
+        let mut header = [0; 46];
+        directory.read_exact(&mut header)?;
+        if u32_at(&header, 0) != DIRECTORY_ENTRY {
+            return Err(invalid("bad central directory entry"));
+        }
+        let mut size_bytes = u64::from(u32_at(&header, 24));
+        let name_len = usize::from(u16_at(&header, 28));
+        let extra_len = usize::from(u16_at(&header, 30));
+        let comment_len = usize::from(u16_at(&header, 32));
+        let mut rest = vec![0; name_len + extra_len + comment_len];
+        directory.read_exact(&mut rest)?;
+        let name = String::from_utf8_lossy(&rest[..name_len]).into_owned();
+        if size_bytes == u64::from(u32::MAX) {
+            size_bytes = zip64_size(&rest[name_len..name_len + extra_len]).unwrap_or(size_bytes);
+        }
+        if !name.ends_with('/') {
+            members.push(Member { name, size_bytes });
+        }
+    }
+    Ok(members)
+}
+
+// The uncompressed size from a member's Zip64 extra field, which comes first
+// in it whenever the regular field overflowed
+fn zip64_size(mut extra: &[u8]) -> Option<u64> {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let len = usize::from(u16_at(extra, 2));
+        let data = extra.get(4..4 + len)?;
+        if id == 0x0001 && data.len() >= 8 {
+            return Some(u64_at(data, 0));
+        }
+        extra = &extra[4 + len..];
+    }
+    None
+}
+
+// Tar archives are a sequence of 512-byte headers, each followed by the
+// entry's contents padded to a whole block
+const BLOCK: u64 = 512;
+
+/// Readers able to move past an entry's contents. Compressed streams have to
+/// decompress them; plain files seek over them.
+pub trait Skip: Read + Sized {
+    fn skip(&mut self, bytes: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut self.by_ref().take(bytes), &mut io::sink())?;
+        if skipped < bytes {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+}
+
+impl Skip for BufReader<File> {
+    fn skip(&mut self, bytes: u64) -> io::Result<()> {
+        let bytes = i64::try_from(bytes).map_err(|_| invalid("entry too large"))?;
+        self.seek_relative(bytes)
+    }
+}
+
+impl<R: BufRead> Skip for MultiGzDecoder<R> {}
+
+impl<R: BufRead> Skip for zstd::Decoder<'_, R> {}
+
+// An entry's contents, as visit_tar hands them out
+impl Skip for BufReader<&mut dyn Read> {}
+
+#[cfg(test)]
+impl<T: AsRef<[u8]>> Skip for io::Cursor<T> {}
+
+// The regular files in a tar archive
+fn tar_members<R: Skip>(tar: R) -> io::Result<Vec<Member>> {
+    let mut members = Vec::new();
+    visit_tar(tar, |member, _| {
+        members.push(member);
+        Ok(())
+    })?;
+    Ok(members)
+}
+
+/// Read a tar archive's headers one after another, calling `visit` with each
+/// regular file and a reader over its contents; whatever `visit` leaves
+/// unread is skipped. GNU long names and pax path and size records are
+/// applied.
+pub fn visit_tar<R: Skip>(
+    mut tar: R,
+    mut visit: impl FnMut(Member, &mut dyn Read) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut long_name: Option<String> = None;
+    let mut pax = Pax::default();
+    let mut header = [0; BLOCK as usize];
+    for first in std::iter::once(true).chain(std::iter::repeat(false)) {
+        match tar.read_exact(&mut header) {
+            Ok(()) => {}
+            // Some writers leave out the closing blocks
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && !first => break,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(invalid("not a tar archive"))
+            }
+            Err(err) => return Err(err),
+        }
+        // A zeroed block ends the archive
+        if header.iter().all(|&byte| byte == 0) {
+            break;
+        }
+        if !checksum_matches(&header) {
+            return Err(invalid("bad tar header checksum"));
+        }
+        let size = pax
+            .size
+            .take()
+            .map_or_else(|| tar_size(&header[124..136]), Ok)?;
+        let padded = size
+            .div_ceil(BLOCK)
+            .checked_mul(BLOCK)
+            .ok_or_else(|| invalid("entry too large"))?;
+        match header[156] {
+            // GNU long name for the next entry
+            b'L' => {
+                let mut name = Vec::new();
+                tar.by_ref().take(size).read_to_end(&mut name)?;
+                tar.skip(padded - size)?;
+                long_name = Some(text(&name));
+                continue;
+            }
+            // Pax records for the next entry
+            b'x' => {
+                let mut records = Vec::new();
+                tar.by_ref().take(size).read_to_end(&mut records)?;
+                tar.skip(padded - size)?;
+                pax = Pax::parse(&records);
+                continue;
+            }
+            // Regular files and contiguous files
+            b'0' | b'\0' | b'7' => {
+                let name = pax
+                    .path
+                    .take()
+                    .or(long_name.take())
+                    .unwrap_or_else(|| ustar_name(&header));
+                let member = Member {
+                    name,
+                    size_bytes: size,
+                };
+                let mut contents = tar.by_ref().take(size);
+                visit(member, &mut contents)?;
+                let unread = contents.limit();
+                long_name = None;
+                pax = Pax::default();
+                // unread is at most size, so this stays within padded
+                tar.skip(unread + (padded - size))?;
+                continue;
+            }
+            _ => {}
+        }
+        long_name = None;
+        pax = Pax::default();
+        tar.skip(padded)?;
+    }
+    Ok(())
+}
+
+/// Like `visit_tar`, on a stream that may be gzip or zstd compressed, as told
+/// by its first bytes.
+pub fn visit_any_tar(
+    reader: &mut dyn Read,
+    visit: impl FnMut(Member, &mut dyn Read) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        visit_tar(MultiGzDecoder::new(reader), visit)
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        visit_tar(zstd::Decoder::with_buffer(reader)?, visit)
+    } else {
+        visit_tar(reader, visit)
+    }
+}
+
+// The path and size a pax header overrides for the entry after it
+#[derive(Default)]
+struct Pax {
+    path: Option<String>,
+    size: Option<u64>,
+}
+
+impl Pax {
+    // Parse records of the form "<length> <key>=<value>\n"
+    fn parse(mut records: &[u8]) -> Pax {
+        let mut pax = Pax::default();
+        while let Some(space) = records.iter().position(|&byte| byte == b' ') {
+            let Some(length) = std::str::from_utf8(&records[..space])
+                .ok()
+                .and_then(|length| length.parse::<usize>().ok())
+                .filter(|&length| length > space && length <= records.len())
+            else {
+                break;
+            };
+            // A length too short to hold the key ends the records
+            let Some(record) = records.get(space + 1..length - 1) else {
+                break;
+            };
+            if let Some(equals) = record.iter().position(|&byte| byte == b'=') {
+                let value = &record[equals + 1..];
+                match &record[..equals] {
+                    b"path" => pax.path = Some(text(value)),
+                    b"size" => {
+                        pax.size = std::str::from_utf8(value).ok().and_then(|v| v.parse().ok())
+                    }
+                    _ => {}
+                }
+            }
+            records = &records[length..];
+        }
+        pax
+    }
+}
+
+// The name in a header, joined to its ustar prefix when there is one
+fn ustar_name(header: &[u8]) -> String {
+    let name = text(&header[..100]);
+    let prefix = text(&header[345..500]);
+    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+        format!("{}/{}", prefix, name)
+    } else {
+        name
+    }
+}
+
+// A size field: octal digits, or big-endian binary when the top bit of the
+// first byte is set, as GNU tar writes sizes of 8 GiB and more
+fn tar_size(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut size = u64::from(field[0] & 0x7f);
+        for &byte in &field[1..] {
+            size = size
+                .checked_mul(256)
+                .ok_or_else(|| invalid("entry too large"))?
+                | u64::from(byte);
+        }
+        return Ok(size);
+    }
+    let digits = text(field);
+    let digits = digits.trim();
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| invalid("bad tar size field"))
+}
+
+// Whether a header's checksum field matches the sum of its bytes, counting
+// the field itself as spaces
+fn checksum_matches(header: &[u8]) -> bool {
+    let sum: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &byte)| {
+            if (148..156).contains(&i) {
+                u64::from(b' ')
+            } else {
+                u64::from(byte)
+            }
+        })
+        .sum();
+    tar_size(&header[148..156]).is_ok_and(|expected| expected == sum)
+}
+
+// A NUL-terminated header string
+fn text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+// Little-endian integers at `at` in a record
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Cursor;
+    use tempfile::tempdir;
+
+    // A stored (uncompressed) zip holding `files`, as `zip -0` writes it
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut directory = Vec::new();
+        for (name, data) in files {
+            let offset = out.len() as u32;
+            let mut header = Vec::new();
+            header.extend(0x0403_4b50u32.to_le_bytes());
+            header.extend([20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            header.extend(0u32.to_le_bytes());
+            header.extend((data.len() as u32).to_le_bytes());
+            header.extend((data.len() as u32).to_le_bytes());
+            header.extend((name.len() as u16).to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+            out.extend(&header);
+            out.extend(name.as_bytes());
+            out.extend(*data);
+
+            directory.extend(DIRECTORY_ENTRY.to_le_bytes());
+            directory.extend([20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            directory.extend(0u32.to_le_bytes());
+            directory.extend((data.len() as u32).to_le_bytes());
+            directory.extend((data.len() as u32).to_le_bytes());
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let directory_offset = out.len() as u32;
+        out.extend(&directory);
+        out.extend(END_OF_DIRECTORY.to_le_bytes());
+        out.extend([0; 4]);
+        out.extend((files.len() as u16).to_le_bytes());
+        out.extend((files.len() as u16).to_le_bytes());
+        out.extend((directory.len() as u32).to_le_bytes());
+        out.extend(directory_offset.to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_zip_members() {
+        let bytes = zip(&[
+            ("docs/", b""),
+            ("docs/a.txt", b"hello"),
+            ("b.bin", &[0; 300]),
+        ]);
+        let members = zip_members(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(
+            members,
+            vec![
+                Member {
+                    name: "docs/a.txt".to_string(),
+                    size_bytes: 5
+                },
+                Member {
+                    name: "b.bin".to_string(),
+                    size_bytes: 300
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_not_a_zip() {
+        assert!(zip_members(&mut Cursor::new(vec![0u8; 100])).is_err());
+    }
+
+    #[test]
+    fn test_zip64_size() {
+        let mut extra = vec![0x0a, 0x00, 0x02, 0x00, 0xff, 0xff];
+        extra.extend([0x01, 0x00, 0x08, 0x00]);
+        extra.extend((5u64 << 32).to_le_bytes());
+        assert_eq!(zip64_size(&extra), Some(5 << 32));
+        assert_eq!(zip64_size(&[]), None);
+    }
+
+    #[test]
+    fn test_large_members() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("backup.ZIP");
+        fs::write(&path, zip(&[("big.bin", &[0; 300]), ("small.txt", b"x")])).unwrap();
+        let found = large_members(std::slice::from_ref(&path), &[ArchiveFormat::Zip], 100);
+        assert_eq!(
+            found,
+            vec![FileInfo::new(format!("{}!big.bin", path.display()), 300)]
+        );
+        assert!(large_members(&[path], &[], 100).is_empty());
+    }
+
+    // One tar entry: a ustar header of type `kind` and its padded contents
+    pub(crate) fn tar_entry(name: &str, kind: u8, data: &[u8]) -> Vec<u8> {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[136..147].copy_from_slice(b"00000000000");
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[148..156].copy_from_slice(b"        ");
+        let sum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        let mut entry = header.to_vec();
+        entry.extend(data);
+        entry.resize(entry.len().div_ceil(512) * 512, 0);
+        entry
+    }
+
+    pub(crate) fn tar(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut tar = entries.concat();
+        tar.extend([0; 1024]);
+        tar
+    }
+
+    fn sizes(members: &[Member]) -> Vec<(&str, u64)> {
+        members
+            .iter()
+            .map(|member| (member.name.as_str(), member.size_bytes))
+            .collect()
+    }
+
+    #[test]
+    fn test_tar_members() {
+        let long = format!("{}/deep.bin", "d".repeat(120));
+        let pax = "21 path=pax/name.bin\n";
+        let bytes = tar(&[
+            tar_entry("logs/", b'5', b""),
+            tar_entry("logs/app.log", b'0', &[1; 700]),
+            tar_entry("././@LongLink", b'L', format!("{}\0", long).as_bytes()),
+            tar_entry("truncated", b'0', &[2; 10]),
+            tar_entry("PaxHeaders/x", b'x', pax.as_bytes()),
+            tar_entry("short", b'0', &[3; 5]),
+            tar_entry("link", b'2', b""),
+        ]);
+        let members = tar_members(Cursor::new(bytes)).unwrap();
+        assert_eq!(
+            sizes(&members),
+            vec![
+                ("logs/app.log", 700),
+                (long.as_str(), 10),
+                ("pax/name.bin", 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_pax_records() {
+        // A record whose length does not cover its own contents is ignored
+        let bytes = tar(&[
+            tar_entry("PaxHeaders/x", b'x', b"2 "),
+            tar_entry("short", b'0', &[3; 5]),
+        ]);
+        let members = tar_members(Cursor::new(bytes)).unwrap();
+        assert_eq!(sizes(&members), vec![("short", 5)]);
+
+        // A size no archive can hold fails the archive rather than overflowing
+        let pax = "29 size=18446744073709551615\n";
+        let bytes = tar(&[
+            tar_entry("PaxHeaders/x", b'x', pax.as_bytes()),
+            tar_entry("huge", b'0', &[3; 5]),
+        ]);
+        let err = tar_members(Cursor::new(bytes)).unwrap_err();
+        assert_eq!(err.to_string(), "entry too large");
+    }
+
+    #[test]
+    fn test_not_a_tar() {
+        assert!(tar_members(Cursor::new(vec![7u8; 1024])).is_err());
+        assert!(tar_members(Cursor::new(Vec::new())).is_err());
+    }
+
+    #[test]
+    fn test_tar_size() {
+        assert_eq!(tar_size(b"00000001750\0").unwrap(), 1000);
+        let mut binary = [0u8; 12];
+        binary[0] = 0x80;
+        binary[7] = 0x02;
+        assert_eq!(tar_size(&binary).unwrap(), 2 << 32);
+    }
+
+    #[test]
+    fn test_compressed_tars() {
+        let dir = tempdir().unwrap();
+        let bytes = tar(&[tar_entry("big.bin", b'0', &[0; 300])]);
+        let gz = dir.path().join("a.tgz");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(&mut encoder, &bytes).unwrap();
+        fs::write(&gz, encoder.finish().unwrap()).unwrap();
+        let zst = dir.path().join("a.tar.zst");
+        fs::write(&zst, zstd::encode_all(&bytes[..], 1).unwrap()).unwrap();
+        let plain = dir.path().join("a.tar");
+        fs::write(&plain, &bytes).unwrap();
+
+        for path in [&gz, &zst, &plain] {
+            let format = format_of(
+                path,
+                &[
+                    ArchiveFormat::Tar,
+                    ArchiveFormat::TarGz,
+                    ArchiveFormat::TarZst,
+                ],
+            );
+            let members = members(path, format.unwrap()).unwrap();
+            assert_eq!(sizes(&members), vec![("big.bin", 300)]);
+        }
+    }
+
+    #[test]
+    fn test_format_of() {
+        let all = [ArchiveFormat::Zip, ArchiveFormat::TarGz];
+        assert_eq!(
+            format_of(Path::new("/b/x.TAR.GZ"), &all),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            format_of(Path::new("/b/x.zip"), &all),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(format_of(Path::new("/b/x.tar"), &all), None);
+        assert_eq!(format_of(Path::new("/b/x.gz"), &all), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("zip"), Ok(vec![ArchiveFormat::Zip]));
+        assert_eq!(
+            parse("tar,tgz,tar.zst"),
+            Ok(vec![
+                ArchiveFormat::Tar,
+                ArchiveFormat::TarGz,
+                ArchiveFormat::TarZst
+            ])
+        );
+        assert!(parse("rar").is_err());
+    }
+}
@@ -0,0 +1,344 @@
+// Import the HTTP date helpers for request and Last-Modified dates
+use crate::daemon::{http_date, parse_http_date};
+// Import the listing shared by object stores
+use crate::store::{self, Object, Page, Store};
+// Import base64 for the account key and the signature
+use base64::{engine::general_purpose::STANDARD, Engine};
+// Import HMAC and SHA-256 for Shared Key signatures
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+// Import SystemTime for request dates
+use std::time::SystemTime;
+
+// Blob service version the requests and responses follow
+const VERSION: &str = "2021-08-06";
+
+/// Where and as whom to reach Blob Storage, read from the variables the Azure
+/// CLI uses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub account: String,
+    /// The blob endpoint, `https://ACCOUNT.blob.core.windows.net` unless the
+    /// connection string names another, such as Azurite's.
+    pub endpoint: String,
+    /// None for anonymous requests, which only public containers allow.
+    pub credentials: Option<Credentials>,
+}
+
+/// How requests prove access to the account.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Credentials {
+    /// The base64 account key, signing each request.
+    SharedKey(String),
+    /// A shared access signature, appended to each query.
+    Sas(String),
+}
+
+impl Config {
+    /// Read the account, endpoint and credentials from
+    /// `AZURE_STORAGE_CONNECTION_STRING`, or else from `AZURE_STORAGE_ACCOUNT`
+    /// with `AZURE_STORAGE_KEY` or `AZURE_STORAGE_SAS_TOKEN`.
+    pub fn from_env<E>(env: E) -> Result<Config, String>
+    where
+        E: Fn(&str) -> Option<String>,
+    {
+        let settings: Vec<(String, String)> = env("AZURE_STORAGE_CONNECTION_STRING")
+            .map(|connection| {
+                connection
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let setting = |name: &str| {
+            settings
+                .iter()
+                .find(|(setting, _)| setting == name)
+                .map(|(_, value)| value.clone())
+        };
+        let account = setting("AccountName")
+            .or_else(|| env("AZURE_STORAGE_ACCOUNT"))
+            .ok_or("az:// roots need AZURE_STORAGE_ACCOUNT or AZURE_STORAGE_CONNECTION_STRING")?;
+        let endpoint = setting("BlobEndpoint").unwrap_or_else(|| {
+            format!(
+                "{}://{}.blob.{}",
+                setting("DefaultEndpointsProtocol").unwrap_or_else(|| "https".to_string()),
+                account,
+                setting("EndpointSuffix").unwrap_or_else(|| "core.windows.net".to_string())
+            )
+        });
+        let credentials = if let Some(key) = setting("AccountKey") {
+            Some(Credentials::SharedKey(key))
+        } else if let Some(sas) = setting("SharedAccessSignature") {
+            Some(Credentials::Sas(sas))
+        } else if let Some(key) = env("AZURE_STORAGE_KEY") {
+            Some(Credentials::SharedKey(key))
+        } else {
+            env("AZURE_STORAGE_SAS_TOKEN").map(Credentials::Sas)
+        };
+        Ok(Config {
+            account,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            credentials: credentials.map(|credentials| match credentials {
+                Credentials::Sas(sas) => Credentials::Sas(sas.trim_start_matches('?').to_string()),
+                key => key,
+            }),
+        })
+    }
+}
+
+/// The blobs under a name prefix of a container, listed with List Blobs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    pub config: Config,
+    pub name: String,
+    pub prefix: String,
+}
+
+impl Store for Container {
+    fn url(&self) -> String {
+        format!("az://{}", self.name)
+    }
+
+    fn page(&self, token: Option<String>) -> Result<Page, String> {
+        let mut query = vec![
+            ("comp", "list".to_string()),
+            ("restype", "container".to_string()),
+        ];
+        if !self.prefix.is_empty() {
+            query.push(("prefix", self.prefix.clone()));
+        }
+        if let Some(token) = token {
+            query.push(("marker", token));
+        }
+        parse_page(&self.get(&query)?)
+    }
+}
+
+impl Container {
+    // Send a List Blobs request and return the XML it answers with
+    fn get(&self, query: &[(&str, String)]) -> Result<String, String> {
+        let config = &self.config;
+        let url = format!("{}/{}", config.endpoint, store::encode(&self.name));
+        let mut pairs: Vec<String> = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, store::encode(value)))
+            .collect();
+        if let Some(Credentials::Sas(sas)) = &config.credentials {
+            pairs.push(sas.clone());
+        }
+        let date = http_date(SystemTime::now());
+        let mut request = ureq::get(&format!("{}?{}", url, pairs.join("&")))
+            .set("x-ms-date", &date)
+            .set("x-ms-version", VERSION);
+        if let Some(Credentials::SharedKey(key)) = &config.credentials {
+            // The resource is the account and the URL path, which for
+            // path-style endpoints repeats the account
+            let path = url
+                .split_once("://")
+                .and_then(|(_, rest)| rest.find('/').map(|slash| &rest[slash..]))
+                .unwrap_or("/");
+            let signature = sign(key, &config.account, path, query, &date)?;
+            request = request.set(
+                "Authorization",
+                &format!("SharedKey {}:{}", config.account, signature),
+            );
+        }
+        store::call(request, &self.url(), store::xml_error)
+    }
+}
+
+// The Shared Key signature of a GET of `path` with `query`, sent at `date`
+fn sign(
+    key: &str,
+    account: &str,
+    path: &str,
+    query: &[(&str, String)],
+    date: &str,
+) -> Result<String, String> {
+    let key = STANDARD
+        .decode(key)
+        .map_err(|_| "AZURE_STORAGE_KEY is not a base64 account key".to_string())?;
+    let mut query: Vec<String> = query
+        .iter()
+        .map(|(name, value)| format!("\n{}:{}", name.to_lowercase(), value))
+        .collect();
+    query.sort();
+    // The verb, eleven standard headers left empty, then the x-ms- headers
+    // and the resource
+    let to_sign = format!(
+        "GET\n{}x-ms-date:{}\nx-ms-version:{}\n/{}{}{}",
+        "\n".repeat(11),
+        date,
+        VERSION,
+        account,
+        path,
+        query.concat()
+    );
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key).expect("HMAC takes keys of any length");
+    mac.update(to_sign.as_bytes());
+    Ok(STANDARD.encode(mac.finalize().into_bytes()))
+}
+
+// Parse a List Blobs response
+fn parse_page(xml: &str) -> Result<Page, String> {
+    let document =
+        roxmltree::Document::parse(xml).map_err(|err| format!("bad Azure listing: {}", err))?;
+    let root = document.root_element();
+    let child = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|child| child.has_tag_name(name))
+            .and_then(|child| child.text())
+            .map(str::to_string)
+    };
+    let objects = root
+        .descendants()
+        .filter(|node| node.has_tag_name("Blob"))
+        .map(|node| {
+            let properties = node
+                .children()
+                .find(|child| child.has_tag_name("Properties"))
+                .ok_or("Azure listing has a blob without properties")?;
+            Ok(Object {
+                key: child(node, "Name").ok_or("Azure listing has a blob without a name")?,
+                size_bytes: child(properties, "Content-Length")
+                    .and_then(|size| size.parse().ok())
+                    .ok_or("Azure listing has a blob without a size")?,
+                modified: child(properties, "Last-Modified")
+                    .and_then(|time| parse_http_date(&time)),
+            })
+        })
+        .collect::<Result<_, &str>>()?;
+    Ok(Page {
+        objects,
+        next: child(root, "NextMarker"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::thread;
+
+    #[test]
+    fn test_sign() {
+        // Worked out independently from the Shared Key documentation
+        let query = [
+            ("restype", "container".to_string()),
+            ("comp", "list".to_string()),
+            ("prefix", "logs/".to_string()),
+        ];
+        let signature = sign(
+            "c2VjcmV0",
+            "myaccount",
+            "/backups",
+            &query,
+            "Wed, 01 May 2024 12:00:00 GMT",
+        )
+        .unwrap();
+        assert_eq!(signature, "XlVBS/4m8krQStmFkcgFc7JEe4mLCvHDfyOBR9msz1k=");
+        assert!(sign("not base64!", "myaccount", "/", &[], "").is_err());
+    }
+
+    #[test]
+    fn test_config_from_connection_string() {
+        let config = Config::from_env(|name| match name {
+            "AZURE_STORAGE_CONNECTION_STRING" => Some(
+                "DefaultEndpointsProtocol=http;AccountName=devstoreaccount1;\
+                 AccountKey=c2VjcmV0;BlobEndpoint=http://127.0.0.1:10000/devstoreaccount1/;"
+                    .to_string(),
+            ),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.account, "devstoreaccount1");
+        assert_eq!(config.endpoint, "http://127.0.0.1:10000/devstoreaccount1");
+        assert_eq!(
+            config.credentials,
+            Some(Credentials::SharedKey("c2VjcmV0".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let config = Config::from_env(|name| match name {
+            "AZURE_STORAGE_ACCOUNT" => Some("myaccount".to_string()),
+            "AZURE_STORAGE_SAS_TOKEN" => Some("?sv=2022-11-02&sig=abc".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(config.endpoint, "https://myaccount.blob.core.windows.net");
+        assert_eq!(
+            config.credentials,
+            Some(Credentials::Sas("sv=2022-11-02&sig=abc".to_string()))
+        );
+        assert!(Config::from_env(|_| None).is_err());
+    }
+
+    #[test]
+    fn test_list_follows_markers() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let endpoint = format!(
+            "http://{}/devstoreaccount1",
+            server.server_addr().to_ip().unwrap()
+        );
+        let handle = thread::spawn(move || {
+            let pages = [
+                "<EnumerationResults><Blobs>\
+                 <Blob><Name>logs/a.log</Name><Properties>\
+                 <Last-Modified>Wed, 01 May 2024 12:00:00 GMT</Last-Modified>\
+                 <Content-Length>2048</Content-Length></Properties></Blob>\
+                 </Blobs><NextMarker>2!48!bG9ncy9i</NextMarker></EnumerationResults>",
+                "<EnumerationResults><Blobs>\
+                 <Blob><Name>logs/b.log</Name><Properties>\
+                 <Content-Length>10</Content-Length></Properties></Blob>\
+                 </Blobs><NextMarker /></EnumerationResults>",
+            ];
+            let mut requests = Vec::new();
+            for page in pages {
+                let request = server.recv().unwrap();
+                let authorization = request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Authorization"))
+                    .map(|header| header.value.to_string());
+                requests.push((request.url().to_string(), authorization));
+                request
+                    .respond(tiny_http::Response::from_string(page))
+                    .unwrap();
+            }
+            requests
+        });
+        let container = Container {
+            config: Config {
+                account: "devstoreaccount1".to_string(),
+                endpoint,
+                credentials: Some(Credentials::SharedKey("c2VjcmV0".to_string())),
+            },
+            name: "backups".to_string(),
+            prefix: "logs/".to_string(),
+        };
+        let mut files = Vec::new();
+        let listed = store::list(&container, |file| files.push(file)).unwrap();
+        assert_eq!(listed, 2);
+        assert_eq!(files[0].path, Path::new("az://backups/logs/a.log"));
+        assert_eq!(files[0].modified, Some(1_714_564_800));
+        assert_eq!(files[1].size_bytes, 10);
+        let requests = handle.join().unwrap();
+        assert_eq!(
+            requests[0].0,
+            "/devstoreaccount1/backups?comp=list&restype=container&prefix=logs%2F"
+        );
+        assert_eq!(
+            requests[1].0,
+            "/devstoreaccount1/backups?comp=list&restype=container&prefix=logs%2F\
+             &marker=2%2148%21bG9ncy9i"
+        );
+        assert!(requests[0]
+            .1
+            .as_deref()
+            .is_some_and(|value| value.starts_with("SharedKey devstoreaccount1:")));
+    }
+}
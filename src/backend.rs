@@ -0,0 +1,91 @@
+// Import the error reported for unknown backend names
+use crate::ParseError;
+// Import fmt for printing backend names
+use std::fmt;
+// Import FromStr for parsing backend names from the command line
+use std::str::FromStr;
+
+/// How file sizes are read during a scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// Portable `stat` calls through the standard library.
+    #[default]
+    Std,
+    /// Linux only: batch `statx` calls for each directory through io_uring.
+    /// Requires the `io-uring` feature and a kernel that allows io_uring.
+    IoUring,
+    /// Windows only: read the NTFS Master File Table straight from the volume
+    /// instead of walking directories. Needs administrator rights.
+    Mft,
+}
+
+impl Backend {
+    /// Every backend, whether or not it is usable on this system.
+    pub const ALL: [Backend; 3] = [Backend::Std, Backend::IoUring, Backend::Mft];
+
+    /// Name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Std => "std",
+            Backend::IoUring => "io-uring",
+            Backend::Mft => "mft",
+        }
+    }
+
+    /// Whether this build and system can run scans with this backend.
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::Std => true,
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            Backend::IoUring => crate::uring::is_available(),
+            #[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+            Backend::IoUring => false,
+            // Whether the volume is NTFS and readable is only known once the
+            // scan opens it
+            Backend::Mft => cfg!(windows),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = ParseError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Backend::ALL
+            .into_iter()
+            .find(|backend| backend.name() == name.to_lowercase())
+            .ok_or_else(|| ParseError::UnknownBackend(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_from_str() {
+        assert_eq!("std".parse(), Ok(Backend::Std));
+        assert_eq!("io-uring".parse(), Ok(Backend::IoUring));
+        assert_eq!("IO-URING".parse(), Ok(Backend::IoUring));
+        assert_eq!("mft".parse(), Ok(Backend::Mft));
+    }
+
+    #[test]
+    fn test_backend_from_str_unknown() {
+        assert_eq!(
+            "fast".parse::<Backend>(),
+            Err(ParseError::UnknownBackend("fast".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_std_backend_always_available() {
+        assert!(Backend::Std.is_available());
+    }
+}
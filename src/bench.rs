@@ -0,0 +1,140 @@
+// Import the scanner and backends being timed
+use list_big_files::{Backend, ScanError, Scanner};
+// Import Serialize for JSON output of the measurements
+use serde::Serialize;
+// Import Path for the benchmarked tree
+use std::path::Path;
+// Import time types for timing each scan
+use std::time::{Duration, Instant};
+
+/// How fast one backend and thread count scanned the tree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Measurement {
+    pub backend: &'static str,
+    pub threads: usize,
+    pub files_scanned: usize,
+    pub best_secs: f64,
+    pub median_secs: f64,
+    /// Files scanned per second in the median run.
+    pub files_per_sec: f64,
+}
+
+/// Thread counts worth trying on a machine with `cpus` CPUs: the powers of
+/// two below it, and `cpus` itself.
+pub fn default_thread_counts(cpus: usize) -> Vec<usize> {
+    let mut counts: Vec<usize> = (0..)
+        .map(|power| 1 << power)
+        .take_while(|&count| count < cpus)
+        .collect();
+    counts.push(cpus.max(1));
+    counts
+}
+
+/// Scan `directory` `runs` times with every backend in `backends` and every
+/// thread count in `thread_counts`, calling `on_measured` as each combination
+/// finishes. One untimed scan first fills the file system's caches, so every
+/// combination sees the same warm tree.
+pub fn run(
+    directory: &Path,
+    backends: &[Backend],
+    thread_counts: &[usize],
+    runs: usize,
+    mut on_measured: impl FnMut(&Measurement),
+) -> Result<Vec<Measurement>, ScanError> {
+    scan(directory, Backend::Std, None)?;
+    let mut measurements = Vec::new();
+    for &backend in backends {
+        for &threads in thread_counts {
+            let mut times = Vec::with_capacity(runs);
+            let mut files_scanned = 0;
+            for _ in 0..runs {
+                let started = Instant::now();
+                files_scanned = scan(directory, backend, Some(threads))?;
+                times.push(started.elapsed());
+            }
+            times.sort();
+            let median = median(&times);
+            let measurement = Measurement {
+                backend: backend.name(),
+                threads,
+                files_scanned,
+                best_secs: times[0].as_secs_f64(),
+                median_secs: median.as_secs_f64(),
+                files_per_sec: files_scanned as f64 / median.as_secs_f64().max(f64::EPSILON),
+            };
+            on_measured(&measurement);
+            measurements.push(measurement);
+        }
+    }
+    Ok(measurements)
+}
+
+/// The measurement with the highest throughput.
+pub fn fastest(measurements: &[Measurement]) -> Option<&Measurement> {
+    measurements
+        .iter()
+        .max_by(|a, b| a.files_per_sec.total_cmp(&b.files_per_sec))
+}
+
+// Walk the whole tree without reporting anything, returning the files scanned
+fn scan(directory: &Path, backend: Backend, threads: Option<usize>) -> Result<usize, ScanError> {
+    let mut scanner = Scanner::new(directory).min_size(u64::MAX).backend(backend);
+    if let Some(threads) = threads {
+        scanner = scanner.threads(threads);
+    }
+    scanner.scan_with(|_| {})
+}
+
+// The middle of sorted `times`, or the mean of the two middle ones
+fn median(times: &[Duration]) -> Duration {
+    let middle = times.len() / 2;
+    if times.len().is_multiple_of(2) {
+        (times[middle - 1] + times[middle]) / 2
+    } else {
+        times[middle]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_default_thread_counts() {
+        assert_eq!(default_thread_counts(1), [1]);
+        assert_eq!(default_thread_counts(6), [1, 2, 4, 6]);
+        assert_eq!(default_thread_counts(8), [1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn test_median() {
+        let ms = Duration::from_millis;
+        assert_eq!(median(&[ms(3)]), ms(3));
+        assert_eq!(median(&[ms(1), ms(2), ms(9)]), ms(2));
+        assert_eq!(median(&[ms(1), ms(3), ms(5), ms(9)]), ms(4));
+    }
+
+    #[test]
+    fn test_run_measures_every_combination() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b", "c"] {
+            fs::write(dir.path().join(name), [0u8; 10]).unwrap();
+        }
+        let mut seen = 0;
+        let measurements = run(dir.path(), &[Backend::Std], &[1, 2], 2, |_| seen += 1).unwrap();
+        assert_eq!(seen, 2);
+        assert_eq!(measurements.len(), 2);
+        assert_eq!(measurements[1].threads, 2);
+        assert!(measurements.iter().all(|m| m.files_scanned == 3));
+        assert!(fastest(&measurements).is_some());
+        assert!(run(
+            &dir.path().join("missing"),
+            &[Backend::Std],
+            &[1],
+            1,
+            |_| {}
+        )
+        .is_err());
+    }
+}
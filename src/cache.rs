@@ -0,0 +1,176 @@
+// Import serde for the on-disk index format
+use serde::{Deserialize, Serialize};
+// Import HashMap for the per-directory index
+use std::collections::HashMap;
+// Import OsString so file names survive the round trip unchanged
+use std::ffi::OsString;
+// Import fs and io for loading and saving the index
+use std::fs;
+use std::io;
+// Import Path for directory keys and the index location
+use std::path::Path;
+// Import atomics for counting reused directories
+use std::sync::atomic::{AtomicUsize, Ordering};
+// Import Mutex for sharing the index between walker threads
+use std::sync::Mutex;
+// Import SystemTime for directory modification times
+use std::time::SystemTime;
+
+// Bumped whenever the on-disk layout changes; older indexes are discarded
+const FORMAT_VERSION: u32 = 1;
+
+/// What a directory contained when it was last listed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedDir {
+    pub mtime: SystemTime,
+    pub files: Vec<(OsString, u64)>,
+    pub subdirs: Vec<OsString>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    dirs: HashMap<OsString, CachedDir>,
+}
+
+/// Per-directory index of file names, sizes and modification times, used to
+/// skip listing directories that have not changed since the previous scan.
+///
+/// A directory's modification time changes when entries are added, removed or
+/// renamed, but not when an existing file grows in place, so the files of a
+/// reused listing are still statted for their current sizes.
+#[derive(Debug, Default)]
+pub struct ScanCache {
+    previous: Mutex<HashMap<OsString, CachedDir>>,
+    current: Mutex<HashMap<OsString, CachedDir>>,
+    reused: AtomicUsize,
+    listed: AtomicUsize,
+}
+
+impl ScanCache {
+    /// An empty cache; every directory will be listed.
+    pub fn new() -> Self {
+        ScanCache::default()
+    }
+
+    /// Load an index written by [`ScanCache::save`]. A missing file, or one
+    /// written by an incompatible version, gives an empty cache.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(ScanCache::new()),
+            Err(err) => return Err(err),
+        };
+
+        let dirs = match bincode::deserialize::<IndexFile>(&bytes) {
+            Ok(index) if index.version == FORMAT_VERSION => index.dirs,
+            _ => HashMap::new(),
+        };
+        Ok(ScanCache {
+            previous: Mutex::new(dirs),
+            ..ScanCache::default()
+        })
+    }
+
+    /// Write the directories seen by the last scan to `path`. Directories
+    /// that no longer exist are dropped.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let current = self.current.lock().unwrap();
+        let index = IndexFile {
+            version: FORMAT_VERSION,
+            dirs: current.clone(),
+        };
+        let bytes = bincode::serialize(&index).map_err(io::Error::other)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, bytes)
+    }
+
+    /// Number of directories whose listing was reused in the last scan.
+    pub fn reused(&self) -> usize {
+        self.reused.load(Ordering::Relaxed)
+    }
+
+    /// Number of directories that had to be listed in the last scan.
+    pub fn listed(&self) -> usize {
+        self.listed.load(Ordering::Relaxed)
+    }
+
+    // Take the cached listing of `dir` if it has not been modified since
+    pub(crate) fn lookup(&self, dir: &Path, mtime: SystemTime) -> Option<CachedDir> {
+        let cached = self.previous.lock().unwrap().remove(dir.as_os_str())?;
+        (cached.mtime == mtime).then_some(cached)
+    }
+
+    // Record the listing of `dir` for the next scan
+    pub(crate) fn store(&self, dir: &Path, listing: CachedDir, reused: bool) {
+        let counter = if reused { &self.reused } else { &self.listed };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.current
+            .lock()
+            .unwrap()
+            .insert(dir.as_os_str().to_os_string(), listing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn listing(mtime: SystemTime) -> CachedDir {
+        CachedDir {
+            mtime,
+            files: vec![(OsString::from("a.bin"), 42)],
+            subdirs: vec![OsString::from("sub")],
+        }
+    }
+
+    #[test]
+    fn test_lookup_requires_matching_mtime() {
+        let cache = ScanCache::new();
+        let now = SystemTime::now();
+        cache.store(Path::new("/data"), listing(now), false);
+
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("index.bin");
+        cache.save(&file).unwrap();
+
+        let loaded = ScanCache::load(&file).unwrap();
+        assert_eq!(loaded.lookup(Path::new("/data"), now), Some(listing(now)));
+
+        let loaded = ScanCache::load(&file).unwrap();
+        let later = now + Duration::from_secs(1);
+        assert_eq!(loaded.lookup(Path::new("/data"), later), None);
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let dir = tempdir().unwrap();
+        let cache = ScanCache::load(&dir.path().join("missing.bin")).unwrap();
+        assert_eq!(cache.lookup(Path::new("/"), SystemTime::now()), None);
+    }
+
+    #[test]
+    fn test_load_corrupt_file_is_empty() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("index.bin");
+        fs::write(&file, b"not an index").unwrap();
+
+        let cache = ScanCache::load(&file).unwrap();
+        assert_eq!(cache.lookup(Path::new("/"), SystemTime::now()), None);
+    }
+
+    #[test]
+    fn test_store_counts_reuse() {
+        let cache = ScanCache::new();
+        cache.store(Path::new("/a"), listing(SystemTime::now()), true);
+        cache.store(Path::new("/b"), listing(SystemTime::now()), false);
+        cache.store(Path::new("/c"), listing(SystemTime::now()), false);
+        assert_eq!(cache.reused(), 1);
+        assert_eq!(cache.listed(), 2);
+    }
+}
@@ -0,0 +1,118 @@
+// Import rayon to hash the matches in parallel
+use rayon::prelude::*;
+// Import FileInfo for the matches that receive a hash, and long_path for
+// files past MAX_PATH on Windows
+use list_big_files::{long_path, FileInfo};
+// Import Digest for the SHA-256 hasher interface
+use sha2::{Digest, Sha256};
+// Import fs and io for reading file contents
+use std::fs::File;
+use std::io::{self, Read};
+// Import Path for the file being hashed
+use std::path::Path;
+// Import Xxh3 for the fast non-cryptographic digest
+use xxhash_rust::xxh3::Xxh3;
+
+/// Content hash included per file with `--hash`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Sha256,
+    Blake3,
+    Xxh3,
+}
+
+/// Set the hash of every file in `files`. Files that cannot be read are left
+/// without one.
+pub fn add_hashes(files: &mut [FileInfo], algorithm: Algorithm) {
+    files.par_iter_mut().for_each(|file| {
+        file.hash = hash_file(&file.path, algorithm).ok();
+    });
+}
+
+/// Hex digest of the contents of the file at `path`.
+pub fn hash_file(path: &Path, algorithm: Algorithm) -> io::Result<String> {
+    let mut file = File::open(long_path(path))?;
+    match algorithm {
+        Algorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            feed(&mut file, |chunk| hasher.update(chunk))?;
+            Ok(hex(&hasher.finalize()))
+        }
+        Algorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(file)?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+        Algorithm::Xxh3 => {
+            let mut hasher = Xxh3::new();
+            feed(&mut file, |chunk| hasher.update(chunk))?;
+            Ok(format!("{:016x}", hasher.digest()))
+        }
+    }
+}
+
+// Pass the contents of `reader` to `update` one buffer at a time
+fn feed<F>(reader: &mut impl Read, mut update: F) -> io::Result<()>
+where
+    F: FnMut(&[u8]),
+{
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => update(&buffer[..read]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// Lowercase hex encoding of `bytes`
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_hash_file_known_digests() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("abc");
+        fs::write(&file, b"abc").unwrap();
+
+        assert_eq!(
+            hash_file(&file, Algorithm::Sha256).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hash_file(&file, Algorithm::Blake3).unwrap(),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert_eq!(hash_file(&file, Algorithm::Xxh3).unwrap().len(), 16);
+    }
+
+    #[test]
+    fn test_add_hashes_skips_unreadable_files() {
+        let dir = tempdir().unwrap();
+        let present = dir.path().join("present");
+        fs::write(&present, vec![1; 200_000]).unwrap();
+        let mut files = vec![
+            FileInfo::new(present, 200_000),
+            FileInfo::new(dir.path().join("missing"), 10),
+        ];
+
+        add_hashes(&mut files, Algorithm::Xxh3);
+        assert_eq!(
+            files[0].hash,
+            Some(format!(
+                "{:016x}",
+                xxhash_rust::xxh3::xxh3_64(&[1; 200_000])
+            ))
+        );
+        assert_eq!(files[1].hash, None);
+    }
+}
@@ -0,0 +1,340 @@
+// Import the totals shared with --dirs
+use crate::aggregate::Total;
+// Import serde for JSON output of the suggestions
+use serde::Serialize;
+// Import Reverse for listing the largest directories first
+use std::cmp::Reverse;
+// Import HashMap for totals and for directories already recognized
+use std::collections::HashMap;
+// Import Path types for the directories being recognized
+use std::path::{Component, Path, PathBuf};
+
+// A kind of directory that can be removed or emptied and rebuilt on demand
+struct Rule {
+    // The last components of the directory's path, lowercase
+    suffix: &'static [&'static str],
+    // A file that must exist, relative to the directory, before it is trusted
+    marker: Option<&'static str>,
+    kind: &'static str,
+    // How to reclaim the space; {dir} is the directory and {parent} the one
+    // holding it
+    command: &'static str,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        suffix: &["node_modules"],
+        marker: None,
+        kind: "npm dependencies",
+        command: "rm -rf {dir}  # npm install restores it",
+    },
+    Rule {
+        suffix: &["target"],
+        marker: Some("../Cargo.toml"),
+        kind: "Cargo build output",
+        command: "cd {parent} && cargo clean",
+    },
+    Rule {
+        suffix: &[".venv"],
+        marker: Some("pyvenv.cfg"),
+        kind: "Python virtualenv",
+        command: "rm -rf {dir}  # recreate with python -m venv",
+    },
+    Rule {
+        suffix: &["venv"],
+        marker: Some("pyvenv.cfg"),
+        kind: "Python virtualenv",
+        command: "rm -rf {dir}  # recreate with python -m venv",
+    },
+    Rule {
+        suffix: &["__pycache__"],
+        marker: None,
+        kind: "Python bytecode",
+        command: "rm -rf {dir}",
+    },
+    Rule {
+        suffix: &[".gradle", "caches"],
+        marker: None,
+        kind: "Gradle cache",
+        command: "rm -rf {dir}",
+    },
+    Rule {
+        suffix: &[".cache", "pip"],
+        marker: None,
+        kind: "pip cache",
+        command: "pip cache purge",
+    },
+    Rule {
+        suffix: &["library", "caches", "pip"],
+        marker: None,
+        kind: "pip cache",
+        command: "pip cache purge",
+    },
+    Rule {
+        suffix: &["appdata", "local", "pip", "cache"],
+        marker: None,
+        kind: "pip cache",
+        command: "pip cache purge",
+    },
+    Rule {
+        suffix: &[".npm", "_cacache"],
+        marker: None,
+        kind: "npm cache",
+        command: "npm cache clean --force",
+    },
+    Rule {
+        suffix: &["appdata", "local", "npm-cache"],
+        marker: None,
+        kind: "npm cache",
+        command: "npm cache clean --force",
+    },
+    Rule {
+        suffix: &[".cache", "yarn"],
+        marker: None,
+        kind: "Yarn cache",
+        command: "yarn cache clean",
+    },
+    Rule {
+        suffix: &["library", "caches", "yarn"],
+        marker: None,
+        kind: "Yarn cache",
+        command: "yarn cache clean",
+    },
+    Rule {
+        suffix: &[".cache", "go-build"],
+        marker: None,
+        kind: "Go build cache",
+        command: "go clean -cache",
+    },
+    Rule {
+        suffix: &["docker", "overlay2"],
+        marker: None,
+        kind: "Docker layers",
+        command: "docker system prune  # add --all --volumes to remove more",
+    },
+    Rule {
+        suffix: &[".cache", "google-chrome"],
+        marker: None,
+        kind: "Chrome cache",
+        command: "clear the cache in the browser's settings",
+    },
+    Rule {
+        suffix: &[".cache", "chromium"],
+        marker: None,
+        kind: "Chromium cache",
+        command: "clear the cache in the browser's settings",
+    },
+    Rule {
+        suffix: &[".cache", "mozilla"],
+        marker: None,
+        kind: "Firefox cache",
+        command: "clear the cache in the browser's settings",
+    },
+    Rule {
+        suffix: &["library", "caches", "google"],
+        marker: None,
+        kind: "Chrome cache",
+        command: "clear the cache in the browser's settings",
+    },
+    Rule {
+        suffix: &["library", "caches", "firefox"],
+        marker: None,
+        kind: "Firefox cache",
+        command: "clear the cache in the browser's settings",
+    },
+    Rule {
+        suffix: &["user data", "default", "cache"],
+        marker: None,
+        kind: "Chrome cache",
+        command: "clear the cache in the browser's settings",
+    },
+];
+
+/// A directory that can be reclaimed, with what it holds and how.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Suggestion {
+    pub path: PathBuf,
+    pub kind: &'static str,
+    pub command: String,
+    #[serde(flatten)]
+    pub total: Total,
+}
+
+/// Totals of the reclaimable directories below a root. Files count towards
+/// the outermost one holding them, so node_modules inside node_modules is
+/// suggested once.
+pub struct Cleanup {
+    root: PathBuf,
+    // What each directory seen so far was recognized as, if anything
+    known: HashMap<PathBuf, Option<usize>>,
+    totals: HashMap<PathBuf, (usize, Total)>,
+}
+
+impl Cleanup {
+    pub fn new(root: &Path) -> Self {
+        Cleanup {
+            root: root.to_path_buf(),
+            known: HashMap::new(),
+            totals: HashMap::new(),
+        }
+    }
+
+    /// Count the file at `path` towards the reclaimable directory holding it,
+    /// if there is one.
+    pub fn add(&mut self, path: &Path, size_bytes: u64) {
+        let Some(parent) = path.strip_prefix(&self.root).ok().and_then(Path::parent) else {
+            return;
+        };
+        let mut dir = self.root.clone();
+        let mut components = parent.components();
+        loop {
+            let rule = match self.known.get(&dir) {
+                Some(rule) => *rule,
+                None => {
+                    let rule = recognize(&dir);
+                    self.known.insert(dir.clone(), rule);
+                    rule
+                }
+            };
+            if let Some(rule) = rule {
+                let (_, total) = self.totals.entry(dir).or_insert((rule, Total::default()));
+                total.size_bytes += size_bytes;
+                total.files += 1;
+                return;
+            }
+            match components.next() {
+                Some(component) => dir.push(component),
+                None => return,
+            }
+        }
+    }
+
+    /// Reclaimable directories of at least `min_size_bytes`, largest first.
+    pub fn suggestions(self, min_size_bytes: u64) -> Vec<Suggestion> {
+        let mut suggestions: Vec<Suggestion> = self
+            .totals
+            .into_iter()
+            .filter(|(_, (_, total))| total.size_bytes >= min_size_bytes)
+            .map(|(path, (rule, total))| Suggestion {
+                command: command(RULES[rule].command, &path),
+                kind: RULES[rule].kind,
+                path,
+                total,
+            })
+            .collect();
+        suggestions.sort_by(|a, b| {
+            Reverse(a.total.size_bytes)
+                .cmp(&Reverse(b.total.size_bytes))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        suggestions
+    }
+}
+
+// The index of the rule `dir` matches, if any
+fn recognize(dir: &Path) -> Option<usize> {
+    let names: Vec<String> = dir
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_lowercase()),
+            _ => None,
+        })
+        .collect();
+    RULES.iter().position(|rule| {
+        rule.suffix.len() <= names.len()
+            && names
+                .iter()
+                .rev()
+                .zip(rule.suffix.iter().rev())
+                .all(|(name, want)| name == want)
+            && rule.marker.is_none_or(|marker| dir.join(marker).is_file())
+    })
+}
+
+// `template` with the directory and its parent filled in, quoted for a shell
+fn command(template: &str, dir: &Path) -> String {
+    let parent = dir.parent().unwrap_or(dir);
+    template
+        .replace("{dir}", &quote(dir))
+        .replace("{parent}", &quote(parent))
+}
+
+// `path` in single quotes if a shell would otherwise split or expand it
+fn quote(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-~:\\".contains(c))
+    {
+        path.into_owned()
+    } else {
+        format!("'{}'", path.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_recognize_needs_markers() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target");
+        fs::create_dir(&target).unwrap();
+        assert_eq!(recognize(&target), None);
+        fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        assert_eq!(
+            RULES[recognize(&target).unwrap()].kind,
+            "Cargo build output"
+        );
+        assert_eq!(
+            RULES[recognize(Path::new("/home/me/.cache/pip")).unwrap()].kind,
+            "pip cache"
+        );
+        assert_eq!(
+            RULES[recognize(Path::new("/var/lib/docker/overlay2")).unwrap()].kind,
+            "Docker layers"
+        );
+        assert_eq!(recognize(Path::new("/home/me/pip")), None);
+    }
+
+    #[test]
+    fn test_files_count_towards_outermost_match() {
+        let root = Path::new("/src");
+        let mut cleanup = Cleanup::new(root);
+        cleanup.add(Path::new("/src/app/node_modules/a/index.js"), 10);
+        cleanup.add(Path::new("/src/app/node_modules/a/node_modules/b.js"), 5);
+        cleanup.add(Path::new("/src/app/main.js"), 100);
+        cleanup.add(Path::new("/src/web/node_modules/c.js"), 1);
+        let suggestions = cleanup.suggestions(2);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].path, Path::new("/src/app/node_modules"));
+        assert_eq!(
+            suggestions[0].total,
+            Total {
+                size_bytes: 15,
+                files: 2
+            }
+        );
+        assert_eq!(
+            suggestions[0].command,
+            "rm -rf /src/app/node_modules  # npm install restores it"
+        );
+    }
+
+    #[test]
+    fn test_command_quotes_paths() {
+        assert_eq!(
+            command(
+                "cd {parent} && cargo clean",
+                Path::new("/work/my app/target")
+            ),
+            "cd '/work/my app' && cargo clean"
+        );
+        assert_eq!(
+            command("rm -rf {dir}", Path::new("/tmp/it's")),
+            r"rm -rf '/tmp/it'\''s'"
+        );
+    }
+}
@@ -0,0 +1,3498 @@
+// Import the actions --delete, --trash, --move-to, --compress and --exec ask for
+use crate::actions::{Action, Removal};
+// Import the formats --compress writes
+use crate::compress::Format;
+// Import Algorithm for the --hash choices
+use crate::checksum::Algorithm;
+// Import the archive kinds --scan-archives looks inside
+use crate::archive::{self, ArchiveFormat};
+// Import the --color choices and size tiers
+use crate::color::{ColorChoice, Tiers};
+// Import the fields --columns chooses between
+use crate::columns::{self, Column};
+// Import the audits --preset bundles
+use crate::preset::{self, Preset};
+// Import the weights --score ranks matches by
+use crate::score::{self, Weights};
+// Import state for snapshots saved by name
+use crate::state;
+// Import the storage services scan roots can name
+use crate::remote::{self, Remote};
+// Import the line format given with --template
+use crate::template::Template;
+// Import the size parser shared with library users
+use list_big_files::{parse_size, Backend, NumberFormat, SizeUnit};
+// Import env for option defaults taken from the environment
+use std::env;
+// Import PathBuf for the directory argument
+use std::path::{Path, PathBuf};
+// Import Duration for the daemon's rescan interval
+use std::time::Duration;
+// Import LevelFilter for the --log-level choices
+use tracing::level_filters::LevelFilter;
+
+/// What the user asked the binary to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Scan(Box<Options>),
+    Diff(DiffOptions),
+    Show(ShowOptions),
+    Git(GitOptions),
+    Docker(DockerOptions),
+    Bench(BenchOptions),
+    SelfUpdate(UpdateOptions),
+}
+
+/// How scan results are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    /// The same fields as `Json`, written as YAML.
+    Yaml,
+    Treemap,
+    /// One line per match in the format given with `--template`.
+    Template,
+}
+
+impl OutputFormat {
+    /// Name accepted by `--format`.
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Treemap => "treemap",
+            OutputFormat::Template => "template",
+        }
+    }
+}
+
+/// Order of the result table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Size,
+    Path,
+}
+
+/// What `--group-by` clusters the result table by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    /// The directory holding each match.
+    Dir,
+    /// The user owning each match.
+    Owner,
+    /// How long ago each match was last modified.
+    Age,
+}
+
+/// What happens to cloud placeholder files, with `--placeholders`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placeholders {
+    /// Report them at their full size, like any other file.
+    Include,
+    /// Leave them out, since they take no space on disk.
+    Exclude,
+    /// Report them with a cloud column telling them from local files.
+    Mark,
+}
+
+/// How warnings, errors and the daemon's progress are logged, with
+/// `--log-format`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// One readable line per event.
+    Text,
+    /// One JSON object per event, for log collectors.
+    Json,
+}
+
+/// How paths are printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathStyle {
+    /// Under the directory as it was typed.
+    AsGiven,
+    /// Canonicalized, with `--absolute`.
+    Absolute,
+    /// Relative to the scan root, with `--relative`.
+    Relative,
+}
+
+/// Settings for a scan, gathered from positional arguments and flags.
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub directory: PathBuf,
+    /// Set when the directory is a storage service URL such as `s3://`.
+    pub remote: Option<Remote>,
+    pub min_size_bytes: u64,
+    pub display_unit: SizeUnit,
+    /// How sizes and counts are written in the table.
+    pub numbers: NumberFormat,
+    pub live: bool,
+    pub threads: Option<usize>,
+    pub backend: Backend,
+    pub cache: bool,
+    pub format: OutputFormat,
+    pub compare: Option<PathBuf>,
+    pub delta_bytes: u64,
+    pub sort: SortOrder,
+    /// Rank matches by size and age with these weights instead of by size.
+    pub score: Option<Weights>,
+    pub color: ColorChoice,
+    /// Shell command long tables are paged through on a terminal, unless
+    /// `--no-pager` is given.
+    pub pager: Option<String>,
+    pub color_tiers: Tiers,
+    pub columns: Vec<Column>,
+    pub template: Option<Template>,
+    pub paths: PathStyle,
+    /// Escape paths that are not valid Unicode in JSON and template output.
+    pub escape_paths: bool,
+    pub save_snapshot: Option<PathBuf>,
+    /// Directory for caches and named snapshots, replacing the platform's.
+    pub state_dir: Option<PathBuf>,
+    /// Shared libraries with filters and sinks to load.
+    pub plugins: Vec<PathBuf>,
+    /// Rhai script deciding which matches are reported.
+    pub filter_script: Option<PathBuf>,
+    /// Write the report to this file instead of stdout.
+    pub output: Option<PathBuf>,
+    /// Add a line with each scan's totals to this NDJSON log.
+    pub append_history: Option<PathBuf>,
+    pub watch: bool,
+    pub daemon: bool,
+    pub interval: Duration,
+    pub report_dir: Option<PathBuf>,
+    pub keep: usize,
+    pub metrics: Option<String>,
+    pub serve: Option<String>,
+    pub webhook: Option<String>,
+    pub alert_threshold: Option<u64>,
+    /// Budget the matches may add up to before the scan fails and notifies.
+    pub alert_total: Option<u64>,
+    pub email_to: Vec<String>,
+    pub email_from: String,
+    pub smtp_server: Option<String>,
+    pub log_syslog: bool,
+    pub dirs: bool,
+    pub depth: usize,
+    /// List known caches and build output with commands to reclaim them.
+    pub suggest_cleanup: bool,
+    /// Total every scanned file by the user owning it.
+    pub by_user: bool,
+    /// List deleted files that processes still hold open, instead of scanning.
+    pub deleted_open: bool,
+    pub stats_by_ext: bool,
+    pub histogram: bool,
+    pub top_dirs: Option<usize>,
+    pub find_duplicates: bool,
+    pub hash: Option<Algorithm>,
+    pub detect_type: bool,
+    pub mime: Vec<String>,
+    /// Only report the files this audit is after.
+    pub preset: Option<Preset>,
+    pub estimate_compression: bool,
+    pub percent_of_disk: bool,
+    pub mtime: bool,
+    pub owner: bool,
+    pub show_perms: bool,
+    pub inode: bool,
+    /// Also report NTFS alternate data streams, as `file:stream`.
+    pub ads: bool,
+    /// Walk into directories behind symlinks and junctions.
+    pub follow_links: bool,
+    /// Report macOS bundles such as `.app` as single files.
+    pub bundle_as_file: bool,
+    /// Leave out network and FUSE file systems mounted below the directory.
+    pub skip_network: bool,
+    /// What to do with files a sync client only downloads on demand.
+    pub placeholders: Placeholders,
+    /// Why the SIZE argument was ignored under `--lenient`, to warn about.
+    pub ignored_size: Option<String>,
+    /// With `--warn-size`, the size from which matches are critical rather
+    /// than warnings; `min_size_bytes` is then the warning size.
+    pub critical_bytes: Option<u64>,
+    pub group_by: Option<GroupBy>,
+    pub scan_archives: Vec<ArchiveFormat>,
+    pub tui: bool,
+    pub interactive_delete: bool,
+    /// Print only the totals, not the matches.
+    pub summary: bool,
+    /// Print only the number of matches.
+    pub count: bool,
+    /// Print the scan's counters and timings to stderr afterwards.
+    pub stats: bool,
+    /// The most detailed events logged to stderr.
+    pub log_level: LevelFilter,
+    pub log_format: LogFormat,
+    pub delete: bool,
+    pub trash: bool,
+    pub move_to: Option<PathBuf>,
+    pub compress: Option<Format>,
+    pub older_than: Option<Duration>,
+    pub exec: Option<Vec<String>>,
+    pub exec_jobs: Option<usize>,
+    pub yes: bool,
+    pub dry_run: bool,
+}
+
+/// Settings for comparing two saved scans.
+#[derive(Debug, PartialEq)]
+pub struct DiffOptions {
+    pub old: PathBuf,
+    pub new: PathBuf,
+    pub delta_bytes: u64,
+    pub display_unit: SizeUnit,
+}
+
+/// Settings for printing a saved snapshot again.
+#[derive(Debug, PartialEq)]
+pub struct ShowOptions {
+    pub snapshot: PathBuf,
+    pub min_size_bytes: u64,
+    pub display_unit: SizeUnit,
+    pub numbers: NumberFormat,
+    pub format: OutputFormat,
+    pub sort: SortOrder,
+}
+
+/// Settings for listing the largest blobs in a repository's history.
+#[derive(Debug, PartialEq)]
+pub struct GitOptions {
+    pub repo: PathBuf,
+    pub min_size_bytes: u64,
+    pub display_unit: SizeUnit,
+    pub format: OutputFormat,
+}
+
+/// Settings for listing the largest files in each layer of an image.
+#[derive(Debug, PartialEq)]
+pub struct DockerOptions {
+    /// An image name, or a file written by `docker save`.
+    pub image: String,
+    pub min_size_bytes: u64,
+    pub display_unit: SizeUnit,
+    pub format: OutputFormat,
+}
+
+/// Settings for timing scans of a tree with different backends and thread
+/// counts.
+#[derive(Debug, PartialEq)]
+pub struct BenchOptions {
+    pub directory: PathBuf,
+    /// Timed scans for each combination.
+    pub runs: usize,
+    /// Thread counts to try; empty for powers of two up to the number of CPUs.
+    pub thread_counts: Vec<usize>,
+    /// Backends to try; empty for every one this system can run.
+    pub backends: Vec<Backend>,
+    pub format: OutputFormat,
+}
+
+/// Settings for replacing the binary with the latest release.
+#[derive(Debug, PartialEq)]
+pub struct UpdateOptions {
+    /// Only report whether a newer release exists.
+    pub check: bool,
+}
+
+impl Options {
+    /// Whether `--columns` or `--template` shows `column`.
+    pub fn wants(&self, column: Column) -> bool {
+        self.columns.contains(&column)
+            || self
+                .template
+                .as_ref()
+                .is_some_and(|template| template.uses(column))
+    }
+
+    /// What happens to the matches after the scan, if anything.
+    pub fn action(&self) -> Option<Action> {
+        if self.delete {
+            Some(Action::Remove(Removal::Delete))
+        } else if self.trash {
+            Some(Action::Remove(Removal::Trash))
+        } else {
+            self.move_to
+                .as_ref()
+                .map(|target| Action::Move {
+                    root: self.directory.clone(),
+                    target: target.clone(),
+                })
+                .or_else(|| {
+                    self.compress.map(|format| Action::Compress {
+                        format,
+                        older_than: self.older_than,
+                    })
+                })
+                .or_else(|| {
+                    self.exec.as_ref().map(|template| Action::Exec {
+                        template: template.clone(),
+                        jobs: self.exec_jobs.unwrap_or(1),
+                    })
+                })
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            directory: PathBuf::from("."),
+            remote: None,
+            min_size_bytes: 100 * 1024 * 1024,
+            display_unit: SizeUnit::MiB,
+            numbers: NumberFormat::PLAIN,
+            live: false,
+            threads: None,
+            backend: Backend::Std,
+            cache: false,
+            format: OutputFormat::Table,
+            compare: None,
+            delta_bytes: 0,
+            sort: SortOrder::Size,
+            score: None,
+            color: ColorChoice::Auto,
+            pager: Some("less".to_string()),
+            color_tiers: Tiers::default(),
+            columns: Vec::new(),
+            template: None,
+            paths: PathStyle::AsGiven,
+            escape_paths: false,
+            save_snapshot: None,
+            state_dir: None,
+            plugins: Vec::new(),
+            filter_script: None,
+            output: None,
+            append_history: None,
+            watch: false,
+            daemon: false,
+            interval: Duration::from_secs(60 * 60),
+            report_dir: None,
+            keep: 24,
+            metrics: None,
+            serve: None,
+            webhook: None,
+            alert_threshold: None,
+            alert_total: None,
+            email_to: Vec::new(),
+            email_from: "list-big-files@localhost".to_string(),
+            smtp_server: None,
+            log_syslog: false,
+            dirs: false,
+            suggest_cleanup: false,
+            by_user: false,
+            deleted_open: false,
+            depth: 1,
+            stats_by_ext: false,
+            histogram: false,
+            top_dirs: None,
+            find_duplicates: false,
+            hash: None,
+            detect_type: false,
+            mime: Vec::new(),
+            preset: None,
+            estimate_compression: false,
+            percent_of_disk: false,
+            mtime: false,
+            owner: false,
+            show_perms: false,
+            inode: false,
+            ads: false,
+            follow_links: false,
+            bundle_as_file: false,
+            skip_network: false,
+            ignored_size: None,
+            critical_bytes: None,
+            placeholders: Placeholders::Include,
+            group_by: None,
+            scan_archives: Vec::new(),
+            tui: false,
+            interactive_delete: false,
+            summary: false,
+            count: false,
+            stats: false,
+            log_level: LevelFilter::INFO,
+            log_format: LogFormat::Text,
+            delete: false,
+            trash: false,
+            move_to: None,
+            compress: None,
+            older_than: None,
+            exec: None,
+            exec_jobs: None,
+            yes: false,
+            dry_run: false,
+        }
+    }
+}
+
+/// Parse the arguments following the program name.
+pub fn parse_args<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    parse_args_with_env(args, |key| env::var(key).ok())
+}
+
+/// Parse arguments, looking up environment defaults through `env`.
+pub fn parse_args_with_env<I, E>(args: I, env: E) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+    E: Fn(&str) -> Option<String>,
+{
+    let mut options = Options::default();
+    let mut positionals = Vec::new();
+    let mut subcommand = None;
+    let mut delta_unit = None;
+    let mut human = false;
+    let mut bytes = None;
+    let mut numbers = None;
+    let mut depth_given = false;
+    // Daemon-only flags seen, to reject them without --daemon
+    let mut daemon_flag = None;
+    let mut docker = None;
+    let mut lenient = false;
+    // Named forms of the DIRECTORY and SIZE arguments
+    let mut directory_flag = None;
+    let mut min_size_flag = None;
+    let mut warn_bytes = None;
+    let mut tiers_given = false;
+    // bench settings, and the first bench-only flag seen
+    let mut runs = 3;
+    let mut thread_counts = Vec::new();
+    let mut backend_given = false;
+    let mut bench_flag = None;
+    // --score and the weights it ranks with, which may come first
+    let mut scored = false;
+    let mut weights = None;
+    // self-update only reports whether a release is newer
+    let mut check = false;
+    let args: Vec<String> = args.into_iter().collect();
+    // --si changes what every size means, so it applies wherever it appears
+    let si = args.iter().any(|arg| arg == "--si");
+    if si {
+        options.min_size_bytes = 100 * 1_000_000;
+    }
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        // Accept both `--flag value` and `--flag=value`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || flag_value(&flag, inline_value.clone(), &mut args);
+
+        match flag.as_str() {
+            "--help" | "-h" => return Ok(Command::Help),
+            "help" if positionals.is_empty() => return Ok(Command::Help),
+            "diff" | "show" | "git" | "bench" | "self-update"
+                if positionals.is_empty() && subcommand.is_none() =>
+            {
+                subcommand = Some(arg)
+            }
+            "--live" => options.live = true,
+            "--cache" => options.cache = true,
+            "--watch" => options.watch = true,
+            "--daemon" => options.daemon = true,
+            "--log-syslog" => options.log_syslog = true,
+            "--dirs" => options.dirs = true,
+            "--suggest-cleanup" => options.suggest_cleanup = true,
+            "--by-user" => options.by_user = true,
+            "--deleted-open" if cfg!(target_os = "linux") => options.deleted_open = true,
+            "--deleted-open" => return Err("--deleted-open is only available on Linux".to_string()),
+            "--stats-by-ext" => options.stats_by_ext = true,
+            "--histogram" => options.histogram = true,
+            "--find-duplicates" => options.find_duplicates = true,
+            "--detect-type" => options.detect_type = true,
+            "--estimate-compression" => options.estimate_compression = true,
+            "--percent-of-disk" => options.percent_of_disk = true,
+            "--mtime" => options.mtime = true,
+            "--owner" => options.owner = true,
+            "--show-perms" => options.show_perms = true,
+            "--inode" => options.inode = true,
+            "--ads" if cfg!(windows) => options.ads = true,
+            "--ads" => return Err("--ads is only available on Windows".to_string()),
+            "--follow-links" => options.follow_links = true,
+            "--bundle-as-file" if cfg!(target_os = "macos") => options.bundle_as_file = true,
+            "--bundle-as-file" => {
+                return Err("--bundle-as-file is only available on macOS".to_string())
+            }
+            "--skip-network" => options.skip_network = true,
+            "--lenient" => lenient = true,
+            "--tui" => options.tui = true,
+            "--interactive-delete" => options.interactive_delete = true,
+            "--summary" => options.summary = true,
+            "--count" => options.count = true,
+            "--stats" => options.stats = true,
+            "--delete" => options.delete = true,
+            "--trash" => options.trash = true,
+            "--move-to" => options.move_to = Some(PathBuf::from(value()?)),
+            "--compress" => {
+                options.compress = Some(match value()?.as_str() {
+                    "zstd" => Format::Zstd,
+                    "gzip" => Format::Gzip,
+                    other => {
+                        return Err(format!(
+                            "unknown compression {:?} (expected zstd or gzip)",
+                            other
+                        ))
+                    }
+                });
+            }
+            "--older-than" => {
+                options.older_than = Some(parse_interval("--older-than", &value()?)?);
+            }
+            "--exec" => options.exec = Some(split_words(&value()?)?),
+            "--exec-jobs" => {
+                options.exec_jobs = Some(parse_threads("--exec-jobs", &value()?)?);
+            }
+            "--yes" | "-y" => options.yes = true,
+            "--dry-run" => options.dry_run = true,
+            "--mime" => {
+                for pattern in value()?.split(',') {
+                    if pattern.is_empty() || pattern.contains(char::is_whitespace) {
+                        return Err(format!("invalid MIME pattern {:?}", pattern));
+                    }
+                    options.mime.push(pattern.to_string());
+                }
+            }
+            "--preset" => options.preset = Some(preset::parse(&value()?)?),
+            "--hash" => {
+                options.hash = Some(match value()?.as_str() {
+                    "sha256" => Algorithm::Sha256,
+                    "blake3" => Algorithm::Blake3,
+                    "xxh3" => Algorithm::Xxh3,
+                    other => {
+                        return Err(format!(
+                            "unknown hash {:?} (expected sha256, blake3 or xxh3)",
+                            other
+                        ))
+                    }
+                });
+            }
+            "--top-dirs" => {
+                options.top_dirs = Some(parse_threads("--top-dirs", &value()?)?);
+            }
+            "--depth" => {
+                let depth = value()?;
+                options.depth = depth
+                    .parse()
+                    .map_err(|_| format!("--depth must be a number, got {:?}", depth))?;
+                depth_given = true;
+            }
+            "--metrics" => options.metrics = Some(value()?),
+            "--serve" => options.serve = Some(value()?),
+            "--docker" => docker = Some(value()?),
+            "--webhook" => options.webhook = Some(value()?),
+            "--email-to" => options.email_to.extend(
+                value()?
+                    .split(',')
+                    .map(|to| to.trim().to_string())
+                    .filter(|to| !to.is_empty()),
+            ),
+            "--email-from" => options.email_from = value()?,
+            "--smtp-server" => options.smtp_server = Some(value()?),
+            "--color" => {
+                options.color = match value()?.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    other => {
+                        return Err(format!(
+                            "unknown color choice {:?} (expected auto, always or never)",
+                            other
+                        ))
+                    }
+                }
+            }
+            "--color-tiers" => {
+                options.color_tiers = parse_tiers(&value()?, si)?;
+                tiers_given = true;
+            }
+            "--dir" => directory_flag = Some(value()?),
+            "--min-size" => min_size_flag = Some(value()?),
+            "--warn-size" => warn_bytes = Some(parse_size_bytes(&value()?, si)?.0),
+            "--columns" => options.columns = columns::parse(&value()?)?,
+            "--scan-archives" => options.scan_archives = archive::parse(&value()?)?,
+            "--template" => options.template = Some(Template::parse(&value()?, si)?),
+            "--absolute" | "--relative" => {
+                let style = if flag == "--absolute" {
+                    PathStyle::Absolute
+                } else {
+                    PathStyle::Relative
+                };
+                if options.paths != PathStyle::AsGiven && options.paths != style {
+                    return Err("--absolute cannot be combined with --relative".to_string());
+                }
+                options.paths = style;
+            }
+            "--escape-paths" => options.escape_paths = true,
+            "--no-pager" => options.pager = None,
+            "--numbers" => numbers = Some(value()?),
+            "--alert-threshold" => {
+                options.alert_threshold = Some(parse_size_bytes(&value()?, si)?.0)
+            }
+            "--alert-total" => options.alert_total = Some(parse_size_bytes(&value()?, si)?.0),
+            "--interval" => {
+                options.interval = parse_interval("--interval", &value()?)?;
+                daemon_flag = Some("--interval");
+            }
+            "--report-dir" => {
+                options.report_dir = Some(PathBuf::from(value()?));
+                daemon_flag = Some("--report-dir");
+            }
+            "--keep" => {
+                let keep = value()?;
+                options.keep = keep
+                    .parse()
+                    .map_err(|_| format!("--keep must be a number, got {:?}", keep))?;
+                daemon_flag = Some("--keep");
+            }
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "table" => OutputFormat::Table,
+                    "json" => OutputFormat::Json,
+                    "yaml" => OutputFormat::Yaml,
+                    "treemap" => OutputFormat::Treemap,
+                    other => {
+                        return Err(format!(
+                            "unknown format {:?} (expected table, json, yaml or treemap)",
+                            other
+                        ))
+                    }
+                };
+            }
+            "--sort" => {
+                options.sort = match value()?.as_str() {
+                    "size" => SortOrder::Size,
+                    "path" => SortOrder::Path,
+                    other => {
+                        return Err(format!(
+                            "unknown sort order {:?} (expected size or path)",
+                            other
+                        ))
+                    }
+                };
+            }
+            "--score" => scored = true,
+            "--score-weights" => weights = Some(score::parse_weights(&value()?)?),
+            "--log-level" => {
+                options.log_level = match value()?.as_str() {
+                    "off" => LevelFilter::OFF,
+                    "error" => LevelFilter::ERROR,
+                    "warn" => LevelFilter::WARN,
+                    "info" => LevelFilter::INFO,
+                    "debug" => LevelFilter::DEBUG,
+                    "trace" => LevelFilter::TRACE,
+                    other => {
+                        return Err(format!(
+                        "unknown log level {:?} (expected off, error, warn, info, debug or trace)",
+                        other
+                    ))
+                    }
+                };
+            }
+            "--log-format" => {
+                options.log_format = match value()?.as_str() {
+                    "text" => LogFormat::Text,
+                    "json" => LogFormat::Json,
+                    other => {
+                        return Err(format!(
+                            "unknown log format {:?} (expected text or json)",
+                            other
+                        ))
+                    }
+                };
+            }
+            "--placeholders" => {
+                options.placeholders = match value()?.as_str() {
+                    "include" => Placeholders::Include,
+                    "exclude" => Placeholders::Exclude,
+                    "mark" => Placeholders::Mark,
+                    other => {
+                        return Err(format!(
+                            "unknown placeholder handling {:?} (expected include, exclude or mark)",
+                            other
+                        ))
+                    }
+                };
+            }
+            "--group-by" => {
+                options.group_by = match value()?.as_str() {
+                    "dir" => Some(GroupBy::Dir),
+                    "owner" => Some(GroupBy::Owner),
+                    "age" => Some(GroupBy::Age),
+                    other => {
+                        return Err(format!(
+                            "unknown grouping {:?} (expected dir, owner or age)",
+                            other
+                        ))
+                    }
+                };
+            }
+            "--save-snapshot" => options.save_snapshot = Some(PathBuf::from(value()?)),
+            "--state-dir" => options.state_dir = Some(PathBuf::from(value()?)),
+            "--plugin" => options.plugins.push(PathBuf::from(value()?)),
+            "--filter-script" => options.filter_script = Some(PathBuf::from(value()?)),
+            "--output" => options.output = Some(PathBuf::from(value()?)),
+            "--append-history" => options.append_history = Some(PathBuf::from(value()?)),
+            "--compare" => options.compare = Some(PathBuf::from(value()?)),
+            "--delta" => {
+                let (delta_bytes, unit) = parse_size_bytes(&value()?, si)?;
+                options.delta_bytes = delta_bytes;
+                delta_unit = Some(unit);
+            }
+            "--human" => human = true,
+            "--bytes" => {
+                bytes = Some(match inline_value.as_deref() {
+                    None => SizeUnit::B,
+                    Some("grouped") => SizeUnit::GroupedB,
+                    Some(other) => {
+                        return Err(format!(
+                            "invalid --bytes value {:?}: expected --bytes or --bytes=grouped",
+                            other
+                        ))
+                    }
+                })
+            }
+            "--si" => {}
+            "--threads" => options.threads = Some(parse_threads("--threads", &value()?)?),
+            "--backend" => {
+                let name = value()?;
+                options.backend = name
+                    .parse()
+                    .map_err(|err| format!("{} (expected one of: std, io-uring, mft)", err))?;
+                backend_given = true;
+            }
+            "--runs" => {
+                runs = parse_threads("--runs", &value()?)?;
+                bench_flag = Some("--runs");
+            }
+            "--thread-counts" => {
+                thread_counts = value()?
+                    .split(',')
+                    .map(|count| parse_threads("--thread-counts", count))
+                    .collect::<Result<_, _>>()?;
+                bench_flag = Some("--thread-counts");
+            }
+            "--check" => check = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown option {:?}", flag));
+            }
+            _ => positionals.push(arg),
+        }
+    }
+
+    if human && bytes.is_some() {
+        return Err("--bytes cannot be combined with --human".to_string());
+    }
+
+    // --human and --bytes override the unit the threshold was given in, --si its base
+    let unit = |unit| {
+        let unit = if human { SizeUnit::Auto } else { unit };
+        if let Some(bytes) = bytes {
+            bytes
+        } else if si {
+            unit.si()
+        } else {
+            unit
+        }
+    };
+
+    // https://no-color.org: a non-empty NO_COLOR turns colors off unless asked for
+    if options.color == ColorChoice::Auto && env("NO_COLOR").is_some_and(|value| !value.is_empty())
+    {
+        options.color = ColorChoice::Never;
+    }
+
+    if let Some(style) = numbers {
+        options.numbers = match style.as_str() {
+            "plain" => NumberFormat::PLAIN,
+            "grouped" => NumberFormat::GROUPED,
+            // Looked up in the order setlocale uses for LC_NUMERIC
+            "locale" => NumberFormat::for_locale(
+                &["LC_ALL", "LC_NUMERIC", "LANG"]
+                    .into_iter()
+                    .filter_map(&env)
+                    .find(|locale| !locale.is_empty())
+                    .unwrap_or_default(),
+            ),
+            other => {
+                return Err(format!(
+                    "unknown number style {:?} (expected plain, grouped or locale)",
+                    other
+                ))
+            }
+        };
+        // Scripts read the other formats, so their numbers stay plain
+        if options.format != OutputFormat::Table || options.template.is_some() {
+            return Err("--numbers requires --format table".to_string());
+        }
+    }
+
+    // LBF_PAGER, then PAGER, name the pager as GIT_PAGER and PAGER do for git;
+    // an empty one or cat turns paging off
+    if options.pager.is_some() {
+        if let Some(pager) = env("LBF_PAGER").or_else(|| env("PAGER")) {
+            options.pager = Some(pager).filter(|pager| !matches!(pager.trim(), "" | "cat"));
+        }
+    }
+
+    // An explicit --threads is the only count bench tries; LBF_THREADS is not
+    let threads_flag = options.threads;
+    if options.threads.is_none() {
+        if let Some(threads) = env("LBF_THREADS") {
+            options.threads = Some(parse_threads("LBF_THREADS", &threads)?);
+        }
+    }
+
+    if options.state_dir.is_none() {
+        options.state_dir = env("LBF_STATE_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+    }
+    // Snapshots given by bare name are kept in the state directory
+    let snapshot = |path: PathBuf| state::snapshot_file(&path, options.state_dir.as_deref());
+    options.save_snapshot = options.save_snapshot.clone().map(snapshot).transpose()?;
+    options.compare = options.compare.clone().map(snapshot).transpose()?;
+
+    if subcommand.is_some() || docker.is_some() {
+        let mut scan_only = vec![
+            ("--dir", directory_flag.is_some()),
+            ("--min-size", min_size_flag.is_some()),
+            ("--warn-size", warn_bytes.is_some()),
+            ("--stats", options.stats),
+            ("--output", options.output.is_some()),
+            ("--preset", options.preset.is_some()),
+            ("--suggest-cleanup", options.suggest_cleanup),
+            ("--by-user", options.by_user),
+            ("--deleted-open", options.deleted_open),
+            ("--score", scored),
+            ("--append-history", options.append_history.is_some()),
+            ("--alert-total", options.alert_total.is_some()),
+            ("--plugin", !options.plugins.is_empty()),
+            ("--filter-script", options.filter_script.is_some()),
+            ("--dirs", options.dirs),
+        ];
+        // Nothing that reads, acts on or publishes matches applies either;
+        // each subcommand checks --format itself
+        scan_only.extend(
+            match_flags(&options, warn_bytes)
+                .into_iter()
+                .filter(|(flag, _)| !flag.starts_with("--format")),
+        );
+        scan_only.extend([("--yes", options.yes), ("--dry-run", options.dry_run)]);
+        if let Some((flag, _)) = scan_only.iter().find(|(_, set)| *set) {
+            return Err(format!("{} only applies to directory scans", flag));
+        }
+    }
+
+    if subcommand.as_deref() == Some("show") {
+        let mut positionals = positionals.into_iter();
+        let snapshot = positionals
+            .next()
+            .ok_or("show requires a snapshot file written with --save-snapshot")?;
+        // Without a size every file recorded in the snapshot is shown
+        let (min_size_bytes, display_unit) = match positionals.next() {
+            Some(size) => parse_size_bytes(&size, si)?,
+            None => (0, SizeUnit::MiB),
+        };
+        if let Some(extra) = positionals.next() {
+            return Err(format!("unexpected argument {:?}", extra));
+        }
+        return Ok(Command::Show(ShowOptions {
+            snapshot: state::snapshot_file(Path::new(&snapshot), options.state_dir.as_deref())?,
+            min_size_bytes,
+            display_unit: unit(display_unit),
+            numbers: options.numbers,
+            format: options.format,
+            sort: options.sort,
+        }));
+    }
+
+    if let Some(image) = docker {
+        if let Some(subcommand) = subcommand {
+            return Err(format!("--docker cannot be combined with {}", subcommand));
+        }
+        let mut positionals = positionals.into_iter();
+        // Image layers are smaller than disks, so the default is lower
+        let (min_size_bytes, display_unit) = match positionals.next() {
+            Some(size) => parse_size_bytes(&size, si)?,
+            None => (10 * 1024 * 1024, SizeUnit::MiB),
+        };
+        if let Some(extra) = positionals.next() {
+            return Err(format!("unexpected argument {:?}", extra));
+        }
+        if !matches!(options.format, OutputFormat::Table | OutputFormat::Json) {
+            return Err(format!(
+                "--docker cannot be combined with --format {}",
+                options.format.name()
+            ));
+        }
+        return Ok(Command::Docker(DockerOptions {
+            image,
+            min_size_bytes,
+            display_unit: unit(display_unit),
+            format: options.format,
+        }));
+    }
+
+    if subcommand.as_deref() == Some("git") {
+        let mut positionals = positionals.into_iter();
+        let repo = positionals.next().unwrap_or_else(|| ".".to_string());
+        // Repositories rarely hold 100 MB files, so the default is lower
+        let (min_size_bytes, display_unit) = match positionals.next() {
+            Some(size) => parse_size_bytes(&size, si)?,
+            None => (1024 * 1024, SizeUnit::MiB),
+        };
+        if let Some(extra) = positionals.next() {
+            return Err(format!("unexpected argument {:?}", extra));
+        }
+        if !matches!(options.format, OutputFormat::Table | OutputFormat::Json) {
+            return Err(format!(
+                "git cannot be combined with --format {}",
+                options.format.name()
+            ));
+        }
+        return Ok(Command::Git(GitOptions {
+            repo: PathBuf::from(repo),
+            min_size_bytes,
+            display_unit: unit(display_unit),
+            format: options.format,
+        }));
+    }
+
+    if subcommand.as_deref() == Some("bench") {
+        let mut positionals = positionals.into_iter();
+        let directory = match positionals.next() {
+            Some(directory) => expand_home(&directory, &env),
+            None => PathBuf::from("."),
+        };
+        if let Some(extra) = positionals.next() {
+            return Err(format!("unexpected argument {:?}", extra));
+        }
+        if !matches!(options.format, OutputFormat::Table | OutputFormat::Json) {
+            return Err(format!(
+                "bench cannot be combined with --format {}",
+                options.format.name()
+            ));
+        }
+        if threads_flag.is_some() && !thread_counts.is_empty() {
+            return Err("--threads cannot be combined with --thread-counts".to_string());
+        }
+        return Ok(Command::Bench(BenchOptions {
+            directory,
+            runs,
+            thread_counts: threads_flag.map_or(thread_counts, |threads| vec![threads]),
+            backends: if backend_given {
+                vec![options.backend]
+            } else {
+                Vec::new()
+            },
+            format: options.format,
+        }));
+    }
+    if let Some(flag) = bench_flag {
+        return Err(format!("{} only applies to bench", flag));
+    }
+
+    if subcommand.as_deref() == Some("self-update") {
+        if let Some(extra) = positionals.first() {
+            return Err(format!("unexpected argument {:?}", extra));
+        }
+        return Ok(Command::SelfUpdate(UpdateOptions { check }));
+    }
+    if check {
+        return Err("--check only applies to self-update".to_string());
+    }
+
+    if subcommand.as_deref() == Some("diff") {
+        let [old, new] = <[String; 2]>::try_from(positionals)
+            .map_err(|_| "diff requires two scan reports: OLD.json NEW.json".to_string())?;
+        let state_dir = options.state_dir.as_deref();
+        return Ok(Command::Diff(DiffOptions {
+            old: state::snapshot_file(Path::new(&old), state_dir)?,
+            new: state::snapshot_file(Path::new(&new), state_dir)?,
+            delta_bytes: options.delta_bytes,
+            display_unit: unit(delta_unit.unwrap_or(SizeUnit::MiB)),
+        }));
+    }
+
+    if let Some(flag) = daemon_flag.filter(|_| !options.daemon) {
+        return Err(format!("{} requires --daemon", flag));
+    }
+    if options.metrics.is_some() && !options.daemon && !options.watch {
+        return Err("--metrics requires --daemon or --watch".to_string());
+    }
+    if options.email_to.is_empty() != options.smtp_server.is_none() {
+        return Err("--email-to and --smtp-server must be used together".to_string());
+    }
+    if options.template.is_some() {
+        if options.format != OutputFormat::Table {
+            return Err(format!(
+                "--template cannot be combined with --format {}",
+                options.format.name()
+            ));
+        }
+        if !options.columns.is_empty() {
+            return Err("--template cannot be combined with --columns".to_string());
+        }
+        options.format = OutputFormat::Template;
+    }
+    // The table is for reading, so it shows such paths with replacement characters
+    if options.escape_paths && options.format == OutputFormat::Table {
+        return Err(
+            "--escape-paths requires --format json, yaml or treemap, or --template".to_string(),
+        );
+    }
+    // The report goes to the file and the totals to the terminal, so nothing
+    // else may print matches or sections of its own there
+    if options.output.is_some() {
+        let conflict = [
+            ("--dirs", options.dirs),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+            ("--tui", options.tui),
+            ("--interactive-delete", options.interactive_delete),
+            ("--live", options.live),
+            ("--watch", options.watch),
+            ("--compare", options.compare.is_some()),
+            ("--stats-by-ext", options.stats_by_ext),
+            ("--histogram", options.histogram),
+            ("--top-dirs", options.top_dirs.is_some()),
+            ("--find-duplicates", options.find_duplicates),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --output", flag));
+        }
+    }
+    if options.wants(Column::Hash) && options.hash.is_none() {
+        return Err("the hash field needs --hash to pick the algorithm".to_string());
+    }
+    // Only the printed matches are made relative; these show paths elsewhere
+    if options.paths == PathStyle::Relative {
+        let conflict = [
+            ("--dirs", options.dirs),
+            ("--watch", options.watch),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+            ("--tui", options.tui),
+            ("--interactive-delete", options.interactive_delete),
+            ("--format treemap", options.format == OutputFormat::Treemap),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --relative", flag));
+        }
+    }
+    // The summary and count stand in for the list of matches, so nothing else
+    // may show or act on them
+    let totals_only = if options.count {
+        Some("--count")
+    } else if options.summary {
+        Some("--summary")
+    } else {
+        None
+    };
+    if let Some(only) = totals_only {
+        let conflict = [
+            ("--summary", options.count && options.summary),
+            ("--output", options.output.is_some()),
+            (
+                "--format json",
+                options.count && options.format == OutputFormat::Json,
+            ),
+            (
+                "--format yaml",
+                options.count && options.format == OutputFormat::Yaml,
+            ),
+            ("--format treemap", options.format == OutputFormat::Treemap),
+            ("--template", options.template.is_some()),
+            ("--live", options.live),
+            ("--dirs", options.dirs),
+            ("--watch", options.watch),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+            ("--group-by", options.group_by.is_some()),
+            ("--tui", options.tui),
+            ("--interactive-delete", options.interactive_delete),
+            ("--delete", options.delete),
+            ("--trash", options.trash),
+            ("--move-to", options.move_to.is_some()),
+            ("--compress", options.compress.is_some()),
+            ("--exec", options.exec.is_some()),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with {}", flag, only));
+        }
+    }
+    // Groups only make sense in the table
+    if options.group_by.is_some() {
+        let conflict = [
+            ("--format json", options.format == OutputFormat::Json),
+            ("--format yaml", options.format == OutputFormat::Yaml),
+            ("--format treemap", options.format == OutputFormat::Treemap),
+            ("--template", options.template.is_some()),
+            ("--dirs", options.dirs),
+            ("--tui", options.tui),
+            ("--interactive-delete", options.interactive_delete),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --group-by", flag));
+        }
+    }
+    // Archive members are not files of their own, so they can only be listed
+    if !options.scan_archives.is_empty() {
+        let conflict = [
+            ("--dirs", options.dirs),
+            ("--watch", options.watch),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+            ("--tui", options.tui),
+            ("--interactive-delete", options.interactive_delete),
+            ("--delete", options.delete),
+            ("--trash", options.trash),
+            ("--move-to", options.move_to.is_some()),
+            ("--compress", options.compress.is_some()),
+            ("--exec", options.exec.is_some()),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --scan-archives", flag));
+        }
+    }
+    // Streams are found while walking directories, which these modes skip
+    if options.ads {
+        let conflict = [
+            ("--dirs", options.dirs),
+            ("--backend mft", options.backend == Backend::Mft),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --ads", flag));
+        }
+    }
+    if options.bundle_as_file && options.dirs {
+        return Err("--dirs cannot be combined with --bundle-as-file".to_string());
+    }
+    if options.follow_links && options.backend == Backend::Mft {
+        return Err("--backend mft cannot be combined with --follow-links".to_string());
+    }
+    // The MFT is read in one pass with no directories to count, and the other
+    // modes scan more than once
+    if options.stats {
+        let conflict = [
+            ("--backend mft", options.backend == Backend::Mft),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --stats", flag));
+        }
+    }
+    if !options.plugins.is_empty() {
+        for plugin in &options.plugins {
+            if is_wasm(plugin) && cfg!(not(feature = "wasm")) {
+                return Err(
+                    "--plugin with a .wasm module requires a build with the wasm feature"
+                        .to_string(),
+                );
+            }
+            if !is_wasm(plugin) && cfg!(not(feature = "plugins")) {
+                return Err("--plugin requires a build with the plugins feature".to_string());
+            }
+        }
+        // Threshold crossings and server scans do not go through plugins
+        let conflict = [
+            ("--watch", options.watch),
+            ("--serve", options.serve.is_some()),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --plugin", flag));
+        }
+    }
+    if options.filter_script.is_some() {
+        if cfg!(not(feature = "scripting")) {
+            return Err("--filter-script requires a build with the scripting feature".to_string());
+        }
+        // Like plugins, scripts only see the matches of whole scans
+        let conflict = [
+            ("--watch", options.watch),
+            ("--serve", options.serve.is_some()),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --filter-script", flag));
+        }
+    }
+    if depth_given && !options.dirs {
+        return Err("--depth requires --dirs".to_string());
+    }
+    // Only scans that make a report have totals to log
+    if options.append_history.is_some() {
+        let conflict = [
+            ("--dirs", options.dirs),
+            ("--suggest-cleanup", options.suggest_cleanup),
+            ("--by-user", options.by_user),
+            ("--deleted-open", options.deleted_open),
+            ("--serve", options.serve.is_some()),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --append-history", flag));
+        }
+    }
+    if weights.is_some() && !scored {
+        return Err("--score-weights requires --score".to_string());
+    }
+    if scored {
+        options.score = Some(weights.unwrap_or_default());
+        // Scores need each match's age, which is read once the scan is over
+        let conflict = [
+            ("--sort path", options.sort == SortOrder::Path),
+            ("--dirs", options.dirs),
+            ("--suggest-cleanup", options.suggest_cleanup),
+            ("--by-user", options.by_user),
+            ("--deleted-open", options.deleted_open),
+            ("--live", options.live),
+            ("--watch", options.watch),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --score", flag));
+        }
+    }
+    // Cleanup suggestions are directory totals of their own, so nothing that
+    // reads, lists, acts on or publishes matches applies to them
+    if options.suggest_cleanup {
+        let mut conflict = vec![("--dirs", options.dirs)];
+        conflict.extend(match_flags(&options, warn_bytes));
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!(
+                "{} cannot be combined with --suggest-cleanup",
+                flag
+            ));
+        }
+    }
+    // Per-user totals count every file rather than listing matches, as
+    // cleanup suggestions do
+    if options.by_user {
+        let mut conflict = vec![
+            ("--dirs", options.dirs),
+            ("--suggest-cleanup", options.suggest_cleanup),
+        ];
+        conflict.extend(match_flags(&options, warn_bytes));
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --by-user", flag));
+        }
+    }
+    // Deleted files are found through the processes holding them, so
+    // nothing about walking the tree or listing its matches applies
+    if options.deleted_open {
+        let mut conflict = vec![
+            ("--by-user", options.by_user),
+            ("--stats", options.stats),
+            ("--plugin", !options.plugins.is_empty()),
+            ("--filter-script", options.filter_script.is_some()),
+            ("--dirs", options.dirs),
+            ("--suggest-cleanup", options.suggest_cleanup),
+        ];
+        conflict.extend(match_flags(&options, warn_bytes));
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --deleted-open", flag));
+        }
+    }
+    if options.dirs {
+        let conflict = [
+            ("--live", options.live),
+            ("--watch", options.watch),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+            ("--compare", options.compare.is_some()),
+            ("--save-snapshot", options.save_snapshot.is_some()),
+            ("--cache", options.cache),
+            ("--stats-by-ext", options.stats_by_ext),
+            ("--histogram", options.histogram),
+            ("--top-dirs", options.top_dirs.is_some()),
+            ("--find-duplicates", options.find_duplicates),
+            ("--hash", options.hash.is_some()),
+            ("--detect-type", options.detect_type),
+            ("--mime", !options.mime.is_empty()),
+            ("--preset", options.preset.is_some()),
+            ("--estimate-compression", options.estimate_compression),
+            ("--percent-of-disk", options.percent_of_disk),
+            ("--mtime", options.mtime),
+            ("--owner", options.owner),
+            ("--show-perms", options.show_perms),
+            ("--inode", options.inode),
+            (
+                "--placeholders",
+                options.placeholders != Placeholders::Include,
+            ),
+            ("--columns", !options.columns.is_empty()),
+            // Directory totals make no report to notify anyone of
+            ("--webhook", options.webhook.is_some()),
+            ("--email-to", !options.email_to.is_empty()),
+            ("--log-syslog", options.log_syslog),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --dirs", flag));
+        }
+    }
+    if options.alert_threshold.is_some()
+        && options.webhook.is_none()
+        && options.smtp_server.is_none()
+    {
+        return Err("--alert-threshold requires --webhook or --email-to".to_string());
+    }
+    // A budget sets when notifications go out, and fails the scan as well
+    if options.alert_total.is_some() {
+        let conflict = [
+            ("--alert-threshold", options.alert_threshold.is_some()),
+            ("--dirs", options.dirs),
+            ("--suggest-cleanup", options.suggest_cleanup),
+            ("--by-user", options.by_user),
+            ("--deleted-open", options.deleted_open),
+            ("--serve", options.serve.is_some()),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --alert-total", flag));
+        }
+    }
+    if options.serve.is_some() {
+        let conflict = [
+            ("--daemon", options.daemon),
+            ("--live", options.live),
+            ("--watch", options.watch),
+            ("--compare", options.compare.is_some()),
+            ("--save-snapshot", options.save_snapshot.is_some()),
+            ("--webhook", options.webhook.is_some()),
+            ("--email-to", options.smtp_server.is_some()),
+            ("--log-syslog", options.log_syslog),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --serve", flag));
+        }
+    }
+    if options.daemon {
+        let conflict = [
+            ("--live", options.live),
+            ("--watch", options.watch),
+            ("--compare", options.compare.is_some()),
+            ("--save-snapshot", options.save_snapshot.is_some()),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --daemon", flag));
+        }
+    }
+    if let Some(action) = options.action() {
+        let actions = [
+            ("--delete", options.delete),
+            ("--trash", options.trash),
+            ("--move-to", options.move_to.is_some()),
+            ("--compress", options.compress.is_some()),
+            ("--exec", options.exec.is_some()),
+        ];
+        // Only one action can be applied to the matches
+        if let Some((flag, _)) = actions.iter().filter(|(_, set)| *set).nth(1) {
+            return Err(format!(
+                "{} cannot be combined with {}",
+                flag,
+                action.flag()
+            ));
+        }
+        let conflict = [
+            ("--watch", options.watch),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+            ("--dirs", options.dirs),
+            ("--tui", options.tui),
+            ("--interactive-delete", options.interactive_delete),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!(
+                "{} cannot be combined with {}",
+                flag,
+                action.flag()
+            ));
+        }
+    } else {
+        let requires = [("--yes", options.yes), ("--dry-run", options.dry_run)];
+        if let Some((flag, _)) = requires.iter().find(|(_, set)| *set) {
+            return Err(format!(
+                "{} requires --delete, --trash, --move-to, --compress or --exec",
+                flag
+            ));
+        }
+    }
+    if options.older_than.is_some() && options.compress.is_none() {
+        return Err("--older-than requires --compress".to_string());
+    }
+    if options.exec_jobs.is_some() && options.exec.is_none() {
+        return Err("--exec-jobs requires --exec".to_string());
+    }
+    // Both interactive modes replace the table and the sections printed after it
+    if options.tui || options.interactive_delete {
+        let mode = if options.tui {
+            "--tui"
+        } else {
+            "--interactive-delete"
+        };
+        let conflict = [
+            (
+                "--interactive-delete",
+                options.tui && options.interactive_delete,
+            ),
+            ("--live", options.live),
+            ("--watch", options.watch),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+            ("--dirs", options.dirs),
+            ("--template", options.template.is_some()),
+            ("--format", options.format != OutputFormat::Table),
+            ("--compare", options.compare.is_some()),
+            ("--stats-by-ext", options.stats_by_ext),
+            ("--histogram", options.histogram),
+            ("--top-dirs", options.top_dirs.is_some()),
+            ("--find-duplicates", options.find_duplicates),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with {}", flag, mode));
+        }
+    }
+
+    // Matches are only filtered once the scan is over
+    if !options.mime.is_empty() {
+        let conflict = [("--live", options.live), ("--watch", options.watch)];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --mime", flag));
+        }
+    }
+    if options.preset.is_some() {
+        let conflict = [("--live", options.live), ("--watch", options.watch)];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --preset", flag));
+        }
+    }
+    // dev totals build and dependency directories, as --dirs does, so it
+    // has no matches to list or act on
+    if options.preset.is_some_and(|preset| preset.reports_dirs()) {
+        let mut conflict = vec![
+            ("--ads", options.ads),
+            ("--bundle-as-file", options.bundle_as_file),
+            ("--relative", options.paths == PathStyle::Relative),
+            ("--score", options.score.is_some()),
+            ("--append-history", options.append_history.is_some()),
+            ("--alert-total", options.alert_total.is_some()),
+        ];
+        conflict.extend(
+            match_flags(&options, warn_bytes)
+                .into_iter()
+                .filter(|(flag, _)| *flag != "--preset"),
+        );
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!("{} cannot be combined with --preset dev", flag));
+        }
+    }
+    if options.placeholders == Placeholders::Exclude {
+        let conflict = [("--live", options.live), ("--watch", options.watch)];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!(
+                "{} cannot be combined with --placeholders exclude",
+                flag
+            ));
+        }
+    }
+
+    if options.format != OutputFormat::Table {
+        let conflict = [
+            ("--live", options.live),
+            ("--compare", options.compare.is_some()),
+            ("--watch", options.watch),
+            ("--stats-by-ext", options.stats_by_ext),
+            ("--histogram", options.histogram),
+            ("--top-dirs", options.top_dirs.is_some()),
+            ("--find-duplicates", options.find_duplicates),
+            (
+                "--dirs",
+                options.dirs && options.format != OutputFormat::Json,
+            ),
+            (
+                "--hash",
+                options.hash.is_some() && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--detect-type",
+                options.detect_type && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--estimate-compression",
+                options.estimate_compression && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--percent-of-disk",
+                options.percent_of_disk && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--mtime",
+                options.mtime && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--owner",
+                options.owner && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--show-perms",
+                options.show_perms && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--inode",
+                options.inode && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--placeholders mark",
+                options.placeholders == Placeholders::Mark
+                    && options.format == OutputFormat::Treemap,
+            ),
+            (
+                "--columns",
+                !options.columns.is_empty() && options.format == OutputFormat::Treemap,
+            ),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(match options.format {
+                OutputFormat::Template => format!("{} cannot be combined with --template", flag),
+                format => format!(
+                    "{} cannot be combined with --format {}",
+                    flag,
+                    format.name()
+                ),
+            });
+        }
+    }
+
+    let mut positionals = positionals.into_iter();
+
+    // Parse directory argument, default to current directory if not provided.
+    // --dir and --min-size take the place of the arguments they name
+    if let Some(directory) = directory_flag.or_else(|| positionals.next()) {
+        options.remote = remote::parse(&directory)?;
+        options.directory = match options.remote {
+            Some(_) => PathBuf::from(directory),
+            None => expand_home(&directory, &env),
+        };
+    }
+    // Objects are only listed, so nothing can read, watch or act on them
+    if options.remote.is_some() {
+        let detail = options.columns.iter().find(|column| {
+            !matches!(
+                column,
+                Column::Size | Column::Path | Column::Modified | Column::Age
+            )
+        });
+        let conflict = [
+            ("--dirs", options.dirs),
+            ("--watch", options.watch),
+            ("--daemon", options.daemon),
+            ("--serve", options.serve.is_some()),
+            ("--cache", options.cache),
+            ("--absolute", options.paths == PathStyle::Absolute),
+            ("--histogram", options.histogram),
+            ("--find-duplicates", options.find_duplicates),
+            ("--hash", options.hash.is_some()),
+            ("--detect-type", options.detect_type),
+            ("--mime", !options.mime.is_empty()),
+            ("--preset", options.preset.is_some()),
+            ("--estimate-compression", options.estimate_compression),
+            ("--percent-of-disk", options.percent_of_disk),
+            ("--owner", options.owner),
+            ("--show-perms", options.show_perms),
+            ("--inode", options.inode),
+            ("--ads", options.ads),
+            ("--follow-links", options.follow_links),
+            ("--bundle-as-file", options.bundle_as_file),
+            ("--skip-network", options.skip_network),
+            ("--stats", options.stats),
+            ("--suggest-cleanup", options.suggest_cleanup),
+            ("--by-user", options.by_user),
+            ("--deleted-open", options.deleted_open),
+            ("--score", options.score.is_some()),
+            ("--plugin", !options.plugins.is_empty()),
+            ("--filter-script", options.filter_script.is_some()),
+            (
+                "--placeholders",
+                options.placeholders != Placeholders::Include,
+            ),
+            ("--group-by owner", options.group_by == Some(GroupBy::Owner)),
+            ("--columns", detail.is_some()),
+            ("--scan-archives", !options.scan_archives.is_empty()),
+            ("--tui", options.tui),
+            ("--interactive-delete", options.interactive_delete),
+            ("--delete", options.delete),
+            ("--trash", options.trash),
+            ("--move-to", options.move_to.is_some()),
+            ("--compress", options.compress.is_some()),
+            ("--exec", options.exec.is_some()),
+            ("--format treemap", options.format == OutputFormat::Treemap),
+        ];
+        if let Some((flag, _)) = conflict.iter().find(|(_, set)| *set) {
+            return Err(format!(
+                "{} cannot be combined with a remote directory",
+                flag
+            ));
+        }
+    }
+
+    // A preset brings its own threshold, used unless a size is given
+    if let Some(preset) = options.preset {
+        options.min_size_bytes = preset.min_size() * if si { 1_000_000 } else { 1 << 20 };
+    }
+
+    // Parse minimum size argument, default to 100MB if not provided. Older
+    // releases fell back to the default for sizes they could not read, which
+    // --lenient keeps for scripts that relied on it
+    if let Some(size) = min_size_flag.or_else(|| positionals.next()) {
+        match parse_size_bytes(&size, si) {
+            Ok((min_size_bytes, display_unit)) => {
+                options.min_size_bytes = min_size_bytes;
+                options.display_unit = display_unit;
+            }
+            Err(err) if lenient => options.ignored_size = Some(err),
+            Err(err) => return Err(err),
+        }
+    }
+
+    if let Some(extra) = positionals.next() {
+        return Err(format!("unexpected argument {:?}", extra));
+    }
+    options.display_unit = unit(options.display_unit);
+
+    // Matches from the warning size up are listed, and those from the minimum
+    // size up are marked critical
+    if let Some(warn_bytes) = warn_bytes {
+        if warn_bytes >= options.min_size_bytes {
+            return Err("--warn-size must be smaller than --min-size".to_string());
+        }
+        if options.dirs {
+            return Err("--warn-size cannot be combined with --dirs".to_string());
+        }
+        if !tiers_given {
+            options.color_tiers = Tiers {
+                warn_bytes,
+                critical_bytes: options.min_size_bytes,
+            };
+        }
+        options.critical_bytes = Some(options.min_size_bytes);
+        options.min_size_bytes = warn_bytes;
+    }
+
+    Ok(Command::Scan(Box::new(options)))
+}
+
+// Replace a leading `~` with the home directory, as shells do for unquoted
+// arguments, so a quoted "~/Downloads" or one from a script works too.
+// `~user` forms and a `~` when no home is set are left as they are
+fn expand_home<E>(directory: &str, env: &E) -> PathBuf
+where
+    E: Fn(&str) -> Option<String>,
+{
+    let rest = match directory.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => rest,
+        _ => return PathBuf::from(directory),
+    };
+    let home = ["HOME", "USERPROFILE"]
+        .into_iter()
+        .filter_map(env)
+        .find(|home| !home.is_empty());
+    match home {
+        Some(home) if rest.is_empty() => PathBuf::from(home),
+        Some(home) => PathBuf::from(home).join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(directory),
+    }
+}
+
+// Take the value of `flag` from `--flag=value` or the next argument
+fn flag_value<I>(flag: &str, inline_value: Option<String>, args: &mut I) -> Result<String, String>
+where
+    I: Iterator<Item = String>,
+{
+    inline_value
+        .or_else(|| args.next())
+        .ok_or_else(|| format!("{} requires a value", flag))
+}
+
+// Parse --color-tiers WARN,CRITICAL, e.g. 1GB,10GB
+fn parse_tiers(tiers: &str, si: bool) -> Result<Tiers, String> {
+    let (warn, critical) = tiers
+        .split_once(',')
+        .ok_or_else(|| format!("--color-tiers expects WARN,CRITICAL, got {:?}", tiers))?;
+    let parsed = Tiers {
+        warn_bytes: parse_size_bytes(warn.trim(), si)?.0,
+        critical_bytes: parse_size_bytes(critical.trim(), si)?.0,
+    };
+    if parsed.warn_bytes > parsed.critical_bytes {
+        return Err(format!(
+            "--color-tiers: the warning size must not exceed the critical one, got {:?}",
+            tiers
+        ));
+    }
+    Ok(parsed)
+}
+
+// Parse a size argument into bytes and the unit it was given in, reading
+// its unit as decimal with --si (100MB = 100,000,000 bytes)
+fn parse_size_bytes(size: &str, si: bool) -> Result<(u64, SizeUnit), String> {
+    let (bytes, unit) = parse_size(size).map_err(|err| {
+        format!(
+            "invalid size {:?}: {} (expected a number with an optional unit B, KB, MB, GB, \
+             TB or PB, e.g. 500KB, 50MB or 1.5GB)",
+            size, err
+        )
+    })?;
+    if !si {
+        return Ok((bytes, unit));
+    }
+    let count = bytes as f64 / unit.bytes().unwrap_or(1) as f64;
+    let unit = unit.si();
+    Ok((
+        (count * unit.bytes().unwrap_or(1) as f64).round() as u64,
+        unit,
+    ))
+}
+
+// Parse an interval such as 90, 30s, 15m, 1h or 1d; plain numbers are seconds
+fn parse_interval(source: &str, value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, suffix) = value.split_at(split);
+    let multiplier = match suffix {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "unknown interval unit {:?} (use s, m, h or d)",
+                suffix
+            ))
+        }
+    };
+    match number.parse::<u64>() {
+        Ok(number) if number > 0 => Ok(Duration::from_secs(number * multiplier)),
+        _ => Err(format!(
+            "{} must be a positive number, got {:?}",
+            source, value
+        )),
+    }
+}
+
+// Split an --exec command into words the way a shell would, honouring single
+// and double quotes and backslash escapes, without expanding anything else
+fn split_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(format!("unterminated quote in {:?}", command)),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(format!("unterminated quote in {:?}", command)),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(format!("unterminated quote in {:?}", command)),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| format!("trailing backslash in {:?}", command))?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    if words.is_empty() {
+        return Err("--exec needs a command".to_string());
+    }
+    Ok(words)
+}
+
+// The flags that read, list, act on or publish a scan's matches, for the
+// modes that report something else instead
+fn match_flags(options: &Options, warn_bytes: Option<u64>) -> Vec<(&'static str, bool)> {
+    vec![
+        ("--live", options.live),
+        ("--watch", options.watch),
+        ("--daemon", options.daemon),
+        ("--serve", options.serve.is_some()),
+        ("--compare", options.compare.is_some()),
+        ("--save-snapshot", options.save_snapshot.is_some()),
+        ("--output", options.output.is_some()),
+        ("--cache", options.cache),
+        ("--summary", options.summary),
+        ("--count", options.count),
+        ("--stats-by-ext", options.stats_by_ext),
+        ("--histogram", options.histogram),
+        ("--top-dirs", options.top_dirs.is_some()),
+        ("--find-duplicates", options.find_duplicates),
+        ("--hash", options.hash.is_some()),
+        ("--detect-type", options.detect_type),
+        ("--mime", !options.mime.is_empty()),
+        ("--preset", options.preset.is_some()),
+        ("--estimate-compression", options.estimate_compression),
+        ("--percent-of-disk", options.percent_of_disk),
+        ("--mtime", options.mtime),
+        ("--owner", options.owner),
+        ("--show-perms", options.show_perms),
+        ("--inode", options.inode),
+        (
+            "--placeholders",
+            options.placeholders != Placeholders::Include,
+        ),
+        ("--columns", !options.columns.is_empty()),
+        ("--template", options.template.is_some()),
+        ("--group-by", options.group_by.is_some()),
+        ("--scan-archives", !options.scan_archives.is_empty()),
+        ("--tui", options.tui),
+        ("--interactive-delete", options.interactive_delete),
+        ("--delete", options.delete),
+        ("--trash", options.trash),
+        ("--move-to", options.move_to.is_some()),
+        ("--compress", options.compress.is_some()),
+        ("--exec", options.exec.is_some()),
+        ("--warn-size", warn_bytes.is_some()),
+        ("--format yaml", options.format == OutputFormat::Yaml),
+        ("--format treemap", options.format == OutputFormat::Treemap),
+        ("--webhook", options.webhook.is_some()),
+        ("--email-to", !options.email_to.is_empty()),
+        ("--log-syslog", options.log_syslog),
+    ]
+}
+
+// Parse a positive count such as a number of threads
+fn parse_threads(source: &str, value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(threads) if threads > 0 => Ok(threads),
+        _ => Err(format!(
+            "{} must be a positive number, got {:?}",
+            source, value
+        )),
+    }
+}
+
+/// Whether the --plugin at `path` is a WebAssembly module rather than a
+/// shared library.
+pub fn is_wasm(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("wasm"))
+}
+
+// Display help information with usage examples
+pub fn print_help() {
+    println!("list-big-files - Find large files in a directory");
+    println!();
+    println!("USAGE:");
+    println!("    list-big-files [OPTIONS] [DIRECTORY] [SIZE]");
+    println!("    list-big-files diff [--delta SIZE] OLD.json NEW.json");
+    println!("    list-big-files show [--sort ORDER] [--format F] SNAPSHOT [SIZE]");
+    println!("    list-big-files git [--format F] [REPOSITORY] [SIZE]");
+    println!("    list-big-files bench [--runs N] [--thread-counts LIST] [--backend B]");
+    println!("                         [--format F] [DIRECTORY]");
+    println!("    list-big-files self-update [--check]");
+    println!("    list-big-files --docker IMAGE [--format F] [SIZE]");
+    println!("    list-big-files --help");
+    println!("    list-big-files help");
+    println!();
+    println!("ARGUMENTS:");
+    println!("    DIRECTORY    Path to directory to scan (default: current directory),");
+    println!("                 s3://BUCKET/PREFIX, az://CONTAINER/PREFIX or");
+    println!("                 gs://BUCKET/PREFIX to list objects in a build with the s3,");
+    println!("                 azure or gcs feature, sftp://[USER@]HOST[:PORT]/PATH to");
+    println!("                 walk a remote tree in a build with the sftp feature, or");
+    println!("                 dav[s]://[USER[:PASSWORD]@]HOST/PATH for a WebDAV share in a");
+    println!("                 build with the webdav feature");
+    println!("    SIZE         Minimum file size with optional unit");
+    println!("                 - Without unit: interpreted as MB (e.g., 100 = 100MB)");
+    println!("                 - With unit: B, KB, MB, GB, TB or PB (e.g., 500KB, 50MB,");
+    println!("                   1GB, 2G, 1.5TB, 2PB)");
+    println!("                 Default: 100MB");
+    println!("                 A SIZE that cannot be read is an error; with --lenient it");
+    println!("                 is ignored with a warning and the default is used");
+    println!();
+    println!("OPTIONS:");
+    println!("    --dir DIR    The directory to scan, in place of DIRECTORY");
+    println!("    --min-size SIZE");
+    println!("                 The minimum file size, in place of SIZE");
+    println!("    --warn-size SIZE");
+    println!("                 Also list matches from SIZE up, below --min-size, marking");
+    println!("                 them warning and larger ones critical in a Tier column and");
+    println!("                 in yellow and red unless --color-tiers is given");
+    println!("    --live       Print matches as they are found, then the sorted table");
+    println!("    --summary    Print only the number of matches, their total size, the");
+    println!("                 largest one and how long the scan took; a single object");
+    println!("                 with --format json");
+    println!("    --count      Print only the number of matches, e.g. for");
+    println!("                 [ \"$(list-big-files --count /srv 1GB)\" -gt 0 ]");
+    println!("    --stats      After the scan, print its counters as one JSON line on");
+    println!("                 stderr: directories visited, files scanned and statted,");
+    println!("                 bytes considered, entries skipped by reason, wall time");
+    println!("                 and time per walker thread");
+    println!("    --threads N  Use N worker threads (default: all cores, or $LBF_THREADS)");
+    println!("    --human      Show each size in the unit that suits it (KiB, MiB, GiB or");
+    println!("                 TiB) instead of the unit SIZE was given in");
+    println!("    --bytes      Show exact sizes in bytes; --bytes=grouped separates the");
+    println!("                 thousands (1,048,576)");
+    println!("    --numbers STYLE");
+    println!("                 Write sizes and counts in the table as plain digits");
+    println!("                 (default), grouped (1,234,567.50) or as the locale in");
+    println!("                 LC_ALL, LC_NUMERIC or LANG does, e.g. 1.234.567,50 in de_DE");
+    println!("    --si         Read and show sizes in decimal units (1 MB = 1000 KB, as");
+    println!("                 df -H does) instead of binary ones (1 MiB = 1024 KiB)");
+    println!("    --backend B  How file sizes are read: std (default), io-uring (Linux,");
+    println!("                 requires a build with the io-uring feature) or mft");
+    println!("                 (Windows NTFS, requires administrator rights)");
+    println!("    --runs N     bench: timed scans of each setting (default: 3)");
+    println!("    --thread-counts LIST");
+    println!("                 bench: comma-separated thread counts to try (default:");
+    println!("                 powers of two up to the number of cores)");
+    println!("    --check      self-update: only report whether a newer release exists");
+    println!("    --follow-links");
+    println!("                 Walk into directories behind symlinks, junctions and mount");
+    println!("                 points, which are skipped by default. Each directory is");
+    println!("                 walked once, so links cannot loop or count files twice");
+    println!("    --bundle-as-file");
+    println!("                 Report bundles and packages such as .app and");
+    println!("                 .photoslibrary as one file of their total size, as Finder");
+    println!("                 shows them (macOS only)");
+    println!("    --plugin FILE");
+    println!("                 Load a shared library that filters matches or receives");
+    println!("                 each scan's results (builds with the plugins feature; may");
+    println!("                 be repeated). A .wasm module is run sandboxed as a filter");
+    println!("                 (builds with the wasm feature)");
+    println!("    --filter-script FILE");
+    println!("                 Report only matches the Rhai script in FILE evaluates to");
+    println!("                 true for; it sees path, size, mtime, owner and now");
+    println!("                 (builds with the scripting feature)");
+    println!("    --skip-network");
+    println!("                 Leave out NFS, SMB and FUSE file systems mounted inside");
+    println!("                 the directory, which are otherwise scanned with a warning");
+    println!("    --cache      Reuse listings of directories unchanged since the last");
+    println!("                 --cache scan of DIRECTORY instead of reading them again");
+    println!("    --format F   Output format: table (default), json, yaml (the same");
+    println!("                 fields as json), or treemap for nested JSON that");
+    println!("                 d3-style treemap viewers can load");
+    println!("    --dirs       List directories whose contents add up to SIZE or more,");
+    println!("                 instead of individual files");
+    println!("    --suggest-cleanup");
+    println!("                 List caches and build output that can be rebuilt, such as");
+    println!("                 node_modules, Cargo target folders and pip, npm, Docker and");
+    println!("                 browser caches, holding SIZE or more, with a command to");
+    println!("                 reclaim each");
+    println!("    --by-user    Total every scanned file by the user owning it, listing");
+    println!("                 users holding SIZE or more with their five largest files");
+    println!("    --deleted-open");
+    println!("                 List deleted files of SIZE or more that were below the");
+    println!("                 directory and are still held open, with the processes");
+    println!("                 holding them (Linux only)");
+    println!("    --stats-by-ext");
+    println!("                 After the table, total the matches by file extension");
+    println!("    --top-dirs N After the table, rank the N directories holding the most");
+    println!("                 matching bytes, to find whole folders worth cleaning up");
+    println!("    --detect-type");
+    println!("                 Add each match's real format, read from its first bytes");
+    println!("                 (e.g. \"zip archive\"), whatever its extension says");
+    println!("    --mime PATTERN[,PATTERN...]");
+    println!("                 Only report matches whose MIME type, read from their first");
+    println!("                 bytes, matches a pattern such as video/* or application/zip,");
+    println!("                 and add a MIME column (use * to show the column only)");
+    println!("    --preset NAME");
+    println!("                 Only report what a common audit is after, from 50MB unless");
+    println!("                 SIZE is given for media (videos, music, photos), or 10MB for");
+    println!("                 logs (*.log, rotated logs, log folders), or 100MB for");
+    println!("                 backups (.bak, dumps, disk images, archives). dev lists the");
+    println!("                 total of each node_modules, target, .venv and other build");
+    println!("                 folder from 10MB, like --dirs");
+    println!("    --estimate-compression");
+    println!("                 Compress samples of each match with zstd in memory and show");
+    println!("                 the estimated ratio and the space compressing would save");
+    println!("    --percent-of-disk");
+    println!("                 Add each match's share of the capacity of the volume it is");
+    println!("                 on, so big files on small disks stand out");
+    println!("    --mtime      Add each match's last modification time (ISO 8601, UTC);");
+    println!("                 use --columns or --template with age for \"3 months ago\"");
+    println!("    --owner      Add the user and group owning each match, by name where");
+    println!("                 one is known and by uid, gid or SID otherwise");
+    println!("    --inode      Add each match's device and inode numbers (volume serial");
+    println!("                 and file index on Windows), to spot hardlinks and files");
+    println!("                 reached twice through bind mounts");
+    println!("    --ads        Also report NTFS alternate data streams of at least SIZE,");
+    println!("                 as file:stream, since their space is invisible to normal");
+    println!("                 listings (Windows only)");
+    println!("    --placeholders MODE");
+    println!("                 What to do with cloud placeholders, files OneDrive, iCloud");
+    println!("                 Drive or Dropbox list at full size but only download when");
+    println!("                 opened: include (default), exclude, or mark them in a");
+    println!("                 cloud column (Windows and macOS)");
+    println!("    --show-perms Add each match's permissions: mode bits such as -rw-r--r--");
+    println!("                 on Unix, read-only, hidden, system and archive flags on");
+    println!("                 Windows");
+    println!("    --hash ALGORITHM");
+    println!("                 Add a content hash of each match: sha256, blake3 or xxh3");
+    println!("                 (JSON output gains a \"hash\" field per file)");
+    println!("    --tui        Browse the matches in an interactive terminal UI: scroll,");
+    println!("                 filter (/), group by directory (g), open a file's location");
+    println!("                 (o), delete (d), trash (t) or ignore (i) it");
+    println!("    --interactive-delete");
+    println!("                 Go through the matches one at a time, showing size, mtime");
+    println!("                 and type, and choose to keep, delete or trash each one");
+    println!("    --delete     After the scan, delete every match and report the space");
+    println!("                 freed; asks for confirmation unless --yes is given");
+    println!("    --trash      Like --delete, but move the matches to the system trash or");
+    println!("                 recycle bin so they can be restored");
+    println!("    --move-to DIR");
+    println!("                 Like --delete, but move the matches into DIR, keeping their");
+    println!("                 paths relative to DIRECTORY (works across filesystems)");
+    println!("    --compress zstd|gzip");
+    println!("                 Replace each match with a compressed copy (FILE.zst or");
+    println!("                 FILE.gz) and report the space saved; files that are");
+    println!("                 already compressed or would not shrink are skipped");
+    println!("    --older-than AGE");
+    println!("                 With --compress, skip files modified within AGE, e.g. 30d");
+    println!("    --exec CMD   Run CMD for each match, e.g. \"gzip -9 {{}}\", where {{}} is");
+    println!("                 the path, {{size}} its size in bytes, {{name}} its file name");
+    println!("                 and {{dir}} its directory (the path is appended otherwise)");
+    println!("    --exec-jobs N");
+    println!("                 With --exec, run up to N commands at once (default: 1)");
+    println!("    -y, --yes    Act without asking, for unattended cleanup jobs");
+    println!("    --dry-run    With --delete, --trash, --move-to, --compress or --exec, list");
+    println!("                 what would be done, each line marked [DRY RUN], and change");
+    println!("                 nothing (JSON reports gain a \"dry_run\" plan)");
+    println!("    --find-duplicates");
+    println!("                 After the table, list matches with identical contents and");
+    println!("                 the space removing the extra copies would free");
+    println!("    --histogram  After the table, show how all scanned files are spread");
+    println!("                 across size ranges, to help pick a threshold");
+    println!("    --depth N    How many levels below DIRECTORY --dirs reports (default: 1)");
+    println!("    --watch      After the scan, keep watching DIRECTORY and report files");
+    println!("                 that cross SIZE, until Ctrl+C");
+    println!("    --daemon     Keep running and rescan every --interval (default: 1h),");
+    println!("                 printing each report, or writing it to --report-dir");
+    println!("                 where only the newest --keep reports (default: 24) are kept");
+    println!("    --metrics ADDR");
+    println!("                 With --daemon or --watch, serve Prometheus metrics at");
+    println!("                 http://ADDR/metrics (e.g. 127.0.0.1:9184)");
+    println!("    --serve ADDR Answer POST /scan requests on ADDR (e.g. 127.0.0.1:8080)");
+    println!("                 instead of scanning once; DIRECTORY and SIZE are the");
+    println!("                 defaults for requests");
+    println!("    --webhook URL");
+    println!("                 POST a JSON summary to URL (e.g. a Slack or Teams incoming");
+    println!("                 webhook) when a scan finds matches, or --watch sees a");
+    println!("                 file cross SIZE");
+    println!("    --email-to ADDR --smtp-server HOST");
+    println!("                 Mail the report to ADDR (comma-separated or repeated) when");
+    println!("                 a scan finds matches. HOST uses STARTTLS on port 587, or");
+    println!("                 give an smtp:// or smtps:// URL; credentials are read from");
+    println!("                 $LBF_SMTP_USERNAME and $LBF_SMTP_PASSWORD");
+    println!("    --email-from ADDR");
+    println!("                 Sender address (default: list-big-files@localhost)");
+    println!("    --log-syslog Log each match and a summary of every scan to the local");
+    println!("                 syslog daemon or journald (Unix only)");
+    println!("    --log-level LEVEL");
+    println!("                 Log warnings, errors and the daemon's progress to stderr");
+    println!("                 down to LEVEL: off, error, warn, info (default), debug or");
+    println!("                 trace");
+    println!("    --log-format F");
+    println!("                 Write each log event as a text line (default) or, with");
+    println!("                 json, as one JSON object per line");
+    println!("    --alert-threshold SIZE");
+    println!("                 Only notify when the matches add up to at least SIZE");
+    println!("    --alert-total SIZE");
+    println!("                 Treat SIZE as a budget: when the matches add up to more,");
+    println!("                 notify the --webhook and --email-to recipients, if any,");
+    println!("                 and exit with status 3 once the report is printed");
+    println!("    --sort ORDER Order results by size (default, largest first) or path");
+    println!("    --score      Rank matches by size times days since modified instead, so");
+    println!("                 big files nobody has touched in years come first");
+    println!("    --score-weights SIZE,AGE");
+    println!("                 Exponents for the two factors of --score (default: 1,1);");
+    println!("                 e.g. 1,2 favours age, 1,0 is size alone");
+    println!("    --scan-archives FORMATS");
+    println!("                 Also list large members inside archives of these formats");
+    println!("                 (zip, tar, tar.gz, tar.zst), as archive.zip!inner/path, by");
+    println!("                 uncompressed size");
+    println!("    --docker IMAGE");
+    println!("                 List the largest files in each layer of IMAGE, a name the");
+    println!("                 local Docker daemon knows or a `docker save` tarball, from");
+    println!("                 the base layer up (default SIZE: 10MB)");
+    println!("    --group-by KEY");
+    println!("                 Cluster the table's matches under a heading per group with");
+    println!("                 its subtotal: dir (the directory holding each match),");
+    println!("                 owner (the user owning it, as with --owner) or age (this");
+    println!("                 week, this month, this year or older, by mtime)");
+    println!("    --columns LIST");
+    println!("                 Show only these fields, in this order, in the table and in");
+    println!("                 JSON: size, mtime, age, score, type, mime, ratio, disk,");
+    println!("                 hash, perms, owner, group, device, inode, cloud, tier and");
+    println!("                 path, e.g. size,age,path (hash needs --hash)");
+    println!("    --template T Print one line per match in format T instead of the table,");
+    println!("                 e.g. \"{{size_gb}}\\t{{path}}\". Fields: size (in the display");
+    println!("                 unit), size_bytes, size_kb, size_mb, size_gb, size_tb,");
+    println!("                 size_pb, path, name, dir, mtime, age, score, type, mime,");
+    println!("                 ratio, disk, hash, perms, owner, group, device, inode,");
+    println!("                 cloud and tier;");
+    println!("                 \\t and \\n are a tab and a newline, {{{{ and }}}} literal braces");
+    println!("    --absolute   Print paths canonicalized and absolute, however the");
+    println!("                 directory was typed");
+    println!("    --relative   Print the paths of matches relative to the directory");
+    println!("    --escape-paths");
+    println!("                 In JSON, YAML, treemap and --template output, write paths");
+    println!("                 that are not valid Unicode with \\xNN escapes for their");
+    println!("                 invalid bytes (and \\\\ for backslashes) instead of");
+    println!("                 replacement characters, so scripts can recover the exact");
+    println!("                 names");
+    println!("    --color WHEN Color large matches in the table: auto (default, when");
+    println!("                 stdout is a terminal and NO_COLOR is unset), always or never");
+    println!("    --no-pager   Print long tables straight to the terminal instead of");
+    println!("                 through LBF_PAGER, PAGER or less");
+    println!("    --color-tiers WARN,CRITICAL");
+    println!("                 Show matches of at least WARN in yellow and CRITICAL in red");
+    println!("                 (default: 1GB,10GB)");
+    println!("    --save-snapshot FILE");
+    println!("                 Also save the results to FILE, to be shown again later");
+    println!("                 with the show subcommand without re-scanning. A bare name");
+    println!("                 such as weekly, without a directory or extension, is kept");
+    println!("                 in the state directory, and show, diff and --compare find");
+    println!("                 it by that name");
+    println!("    --state-dir DIR");
+    println!("                 Keep caches and named snapshots in DIR instead of the");
+    println!("                 platform's cache and state directories (default:");
+    println!("                 $LBF_STATE_DIR)");
+    println!("    --output FILE");
+    println!("                 Write the table, JSON, YAML, treemap or --template output");
+    println!("                 to FILE, creating its directory, and print only the scan's");
+    println!("                 totals. FILE is replaced in one step, so readers never");
+    println!("                 see a partial report");
+    println!("    --append-history FILE");
+    println!("                 Add a line to FILE after each scan with its start time,");
+    println!("                 directory, number of matches and their total size, as");
+    println!("                 NDJSON, to chart the totals over time");
+    println!("    --compare R  After scanning, compare the matches with report R, a file");
+    println!("                 written earlier with --format json");
+    println!("    --delta SIZE Only report files that grew or shrank by at least SIZE");
+    println!("                 (--compare and diff; default: any change)");
+    println!("    -h, --help   Show this help");
+    println!();
+    println!("EXAMPLES:");
+    println!("    list-big-files /home/user/documents");
+    println!("        Scan documents for files >= 100MB (default)");
+    println!();
+    println!("    list-big-files . 50MB");
+    println!("        Scan current directory for files >= 50MB");
+    println!();
+    println!("    list-big-files /path 1GB");
+    println!("        Scan /path for files >= 1GB");
+    println!();
+    println!("    list-big-files ~/Downloads 200M");
+    println!("        Scan Downloads for files >= 200MB");
+    println!();
+    println!("    list-big-files --live /mnt/nas 10GB");
+    println!("        Show matches on a slow volume as soon as they are found");
+    println!();
+    println!("    list-big-files --format json /srv 1GB > monday.json");
+    println!("    list-big-files diff --delta 100MB monday.json tuesday.json");
+    println!("        Save scans and list files that appeared, disappeared or changed");
+    println!();
+    println!("    list-big-files --dirs --depth 2 ~ 1GB");
+    println!("        Find directories holding 1GB or more, two levels deep");
+    println!();
+    println!("    list-big-files --watch /var/log 500MB");
+    println!("        Catch runaway log files as they grow");
+    println!();
+    println!("    list-big-files --daemon --interval 6h --report-dir /var/lib/lbf /srv 1GB");
+    println!("        Rescan /srv every six hours, keeping the last 24 reports");
+    println!();
+    println!("    list-big-files --save-snapshot home.lbf ~ 10MB");
+    println!("    list-big-files show --sort path home.lbf 1GB");
+    println!("        Scan once, then list the snapshot's files >= 1GB by path");
+    println!();
+    println!("    list-big-files bench /mnt/nas");
+    println!("        Time scans of /mnt/nas with each backend and thread count");
+    println!();
+    println!("    list-big-files self-update");
+    println!("        Replace a standalone binary with the latest release, once its");
+    println!("        published checksum matches");
+    println!();
+    println!("OUTPUT:");
+    println!("    Files are sorted by size (largest first) with scan timing information");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args_with_env(args.iter().map(|arg| arg.to_string()), |_| None)
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match parse(args).unwrap() {
+            Command::Scan(options) => *options,
+            other => panic!("expected scan, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_args_defaults() {
+        assert_eq!(options(&[]), Options::default());
+    }
+
+    #[test]
+    fn test_parse_args_help() {
+        assert_eq!(parse(&["--help"]), Ok(Command::Help));
+        assert_eq!(parse(&["-h"]), Ok(Command::Help));
+        assert_eq!(parse(&["help"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn test_parse_args_directory_and_size() {
+        let options = options(&["/tmp", "1GB"]);
+        assert_eq!(options.directory, PathBuf::from("/tmp"));
+        assert_eq!(options.min_size_bytes, 1024 * 1024 * 1024);
+        assert_eq!(options.display_unit, SizeUnit::GiB);
+    }
+
+    #[test]
+    fn test_parse_args_live_anywhere() {
+        assert!(options(&["--live", "/tmp"]).live);
+        assert!(options(&["/tmp", "10MB", "--live"]).live);
+    }
+
+    #[test]
+    fn test_parse_args_cache() {
+        assert!(!options(&["/tmp"]).cache);
+        assert!(options(&["--cache", "/tmp", "1GB"]).cache);
+    }
+
+    #[test]
+    fn test_parse_args_watch() {
+        assert!(options(&["--watch", "/var/log"]).watch);
+        assert!(parse(&["--watch", "--format", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_rejects_unknown_flag() {
+        assert!(parse(&["--bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_rejects_bad_size() {
+        let err = parse(&[".", "10XB"]).unwrap_err();
+        assert!(err.contains("10XB"));
+        assert!(err.contains("e.g. 500KB, 50MB or 1.5GB"));
+        assert!(parse(&[".", "abc"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_named_directory_and_size() {
+        let named = options(&["--min-size", "1GB", "--dir", "/tmp"]);
+        assert_eq!(named.directory, PathBuf::from("/tmp"));
+        assert_eq!(named.min_size_bytes, 1024 * 1024 * 1024);
+        assert_eq!(named.display_unit, SizeUnit::GiB);
+        assert_eq!(options(&["--dir", "/tmp", "1GB"]).min_size_bytes, 1 << 30);
+        assert_eq!(
+            options(&["--min-size", "1GB", "/tmp"]).directory,
+            PathBuf::from("/tmp")
+        );
+        assert!(parse(&["--dir", "/tmp", "--min-size", "1GB", "extra"]).is_err());
+        assert!(parse(&["show", "scan.lbf", "--min-size", "1GB"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_warn_size() {
+        let tiered = options(&["--min-size", "1GB", "--warn-size", "500MB"]);
+        assert_eq!(tiered.min_size_bytes, 500 * 1024 * 1024);
+        assert_eq!(tiered.critical_bytes, Some(1 << 30));
+        assert_eq!(tiered.display_unit, SizeUnit::GiB);
+        assert_eq!(
+            tiered.color_tiers,
+            Tiers {
+                warn_bytes: 500 * 1024 * 1024,
+                critical_bytes: 1 << 30,
+            }
+        );
+        let colored = options(&["/tmp", "1GB", "--warn-size=500MB", "--color-tiers=2GB,5GB"]);
+        assert_eq!(colored.color_tiers.warn_bytes, 2 << 30);
+        assert_eq!(options(&[]).critical_bytes, None);
+        assert!(parse(&["--min-size", "1GB", "--warn-size", "2GB"]).is_err());
+        assert!(parse(&["--warn-size", "10MB", "--dirs"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_summary() {
+        assert!(!options(&[]).summary);
+        assert!(options(&["--summary"]).summary);
+        assert!(options(&["--summary", "--format", "json"]).summary);
+        assert!(parse(&["--summary", "--format", "treemap"]).is_err());
+        assert!(parse(&["--summary", "--live"]).is_err());
+        assert!(parse(&["--summary", "--delete"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_count() {
+        assert!(!options(&[]).count);
+        assert!(options(&["--count", "/tmp", "1GB"]).count);
+        assert!(parse(&["--count", "--summary"]).is_err());
+        assert!(parse(&["--count", "--format", "json"]).is_err());
+        assert!(parse(&["--count", "--format", "yaml"]).is_err());
+        assert!(parse(&["--count", "--template", "{path}"]).is_err());
+        assert!(parse(&["--count", "--watch"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_stats() {
+        assert!(!options(&[]).stats);
+        assert!(options(&["--stats", "/tmp", "1GB"]).stats);
+        assert!(options(&["--stats", "--dirs"]).stats);
+        assert!(parse(&["--stats", "--daemon"]).is_err());
+        assert!(parse(&["--stats", "s3://backups"]).is_err());
+        assert!(parse(&["git", "--stats"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_log_level_and_format() {
+        let defaults = options(&[]);
+        assert_eq!(defaults.log_level, LevelFilter::INFO);
+        assert_eq!(defaults.log_format, LogFormat::Text);
+        let options = options(&["--log-level", "debug", "--log-format", "json"]);
+        assert_eq!(options.log_level, LevelFilter::DEBUG);
+        assert_eq!(options.log_format, LogFormat::Json);
+        assert!(parse(&["--log-level", "verbose"]).is_err());
+        assert!(parse(&["--log-format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_lenient() {
+        let ignored = options(&["--lenient", ".", "10XB"]);
+        assert_eq!(ignored.min_size_bytes, 100 * 1024 * 1024);
+        assert!(ignored.ignored_size.is_some_and(|err| err.contains("10XB")));
+        let read = options(&["--lenient", ".", "1GB"]);
+        assert_eq!(read.min_size_bytes, 1024 * 1024 * 1024);
+        assert_eq!(read.ignored_size, None);
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_parse_args_remote() {
+        let options = options(&["s3://backups/db", "1GB", "--columns", "size,age,path"]);
+        assert_eq!(
+            options.remote,
+            Some(Remote::S3 {
+                bucket: "backups".to_string(),
+                prefix: "db".to_string(),
+            })
+        );
+        assert!(parse(&["s3://backups", "--delete"]).is_err());
+        assert!(parse(&["s3://backups", "--columns", "owner"]).is_err());
+        assert!(parse(&["s3://backups", "--format", "treemap"]).is_err());
+        assert!(parse(&["s3://backups", "--placeholders", "exclude"]).is_err());
+        assert!(parse(&["s3://backups", "--skip-network"]).is_err());
+    }
+
+    #[cfg(all(feature = "azure", feature = "gcs"))]
+    #[test]
+    fn test_parse_args_azure_and_gcs() {
+        let azure = options(&["az://backups/db", "--sort", "path"]);
+        assert!(matches!(azure.remote, Some(Remote::Azure { .. })));
+        let gcs = options(&["gs://media", "--format", "json"]);
+        assert!(matches!(gcs.remote, Some(Remote::Gcs { .. })));
+        assert!(parse(&["gs://media", "--find-duplicates"]).is_err());
+    }
+
+    #[cfg(feature = "sftp")]
+    #[test]
+    fn test_parse_args_sftp() {
+        let options = options(&["sftp://admin@files.example.com/srv", "500MB"]);
+        assert!(matches!(options.remote, Some(Remote::Sftp { .. })));
+        assert_eq!(options.min_size_bytes, 500 * 1024 * 1024);
+        assert!(parse(&["sftp://files.example.com/srv", "--trash"]).is_err());
+    }
+
+    #[cfg(feature = "webdav")]
+    #[test]
+    fn test_parse_args_webdav() {
+        let options = options(&["davs://alice@cloud.example.com/files", "--sort", "path"]);
+        assert!(matches!(
+            options.remote,
+            Some(Remote::WebDav { secure: true, .. })
+        ));
+        assert!(parse(&["dav://nas/share", "--hash", "sha256"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_rejects_extra_positional() {
+        assert!(parse(&[".", "10MB", "extra"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_threads() {
+        assert_eq!(options(&["--threads", "2"]).threads, Some(2));
+        assert_eq!(options(&["--threads=4", "/tmp"]).threads, Some(4));
+    }
+
+    #[test]
+    fn test_parse_args_threads_requires_positive_number() {
+        assert!(parse(&["--threads", "0"]).is_err());
+        assert!(parse(&["--threads", "many"]).is_err());
+        assert!(parse(&["--threads"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_threads_from_env() {
+        let env = |key: &str| (key == "LBF_THREADS").then(|| "3".to_string());
+        let command = parse_args_with_env(Vec::new(), env).unwrap();
+        assert!(matches!(command, Command::Scan(options) if options.threads == Some(3)));
+    }
+
+    #[test]
+    fn test_parse_args_threads_flag_overrides_env() {
+        let env = |_: &str| Some("3".to_string());
+        let args = vec!["--threads".to_string(), "1".to_string()];
+        let command = parse_args_with_env(args, env).unwrap();
+        assert!(matches!(command, Command::Scan(options) if options.threads == Some(1)));
+    }
+
+    #[test]
+    fn test_parse_args_color() {
+        assert_eq!(options(&[]).color, ColorChoice::Auto);
+        assert_eq!(options(&["--color", "always"]).color, ColorChoice::Always);
+        assert_eq!(
+            options(&["--color-tiers", "500MB, 2GB"]).color_tiers,
+            Tiers {
+                warn_bytes: 500 * 1024 * 1024,
+                critical_bytes: 2 * 1024 * 1024 * 1024,
+            }
+        );
+        assert!(parse(&["--color", "sometimes"]).is_err());
+        assert!(parse(&["--color-tiers", "1GB"]).is_err());
+        assert!(parse(&["--color-tiers", "10GB,1GB"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_pager() {
+        assert_eq!(options(&[]).pager.as_deref(), Some("less"));
+        assert_eq!(options(&["--no-pager"]).pager, None);
+        let pager = |vars: &'static [(&str, &str)], args: &[&str]| {
+            let env = |key: &str| {
+                vars.iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.to_string())
+            };
+            let args = args.iter().map(|arg| arg.to_string());
+            match parse_args_with_env(args, env).unwrap() {
+                Command::Scan(options) => options.pager,
+                other => panic!("expected scan, got {:?}", other),
+            }
+        };
+        assert_eq!(pager(&[("PAGER", "most")], &[]).as_deref(), Some("most"));
+        assert_eq!(
+            pager(&[("PAGER", "most"), ("LBF_PAGER", "less -S")], &[]).as_deref(),
+            Some("less -S")
+        );
+        assert_eq!(pager(&[("PAGER", "cat")], &[]), None);
+        assert_eq!(pager(&[("LBF_PAGER", "")], &[]), None);
+        assert_eq!(pager(&[("PAGER", "most")], &["--no-pager"]), None);
+    }
+
+    #[test]
+    fn test_parse_args_columns() {
+        let chosen = options(&["--columns", "path,size,type"]);
+        assert_eq!(chosen.columns, [Column::Path, Column::Size, Column::Type]);
+        assert!(chosen.wants(Column::Type));
+        assert!(!chosen.wants(Column::Mime));
+        assert!(options(&["--columns=size,hash", "--hash", "sha256"]).wants(Column::Hash));
+        assert!(parse(&["--columns", "size,hash"]).is_err());
+        assert!(parse(&["--columns", "size,uid"]).is_err());
+        assert!(parse(&["--columns", "size", "--dirs"]).is_err());
+        assert!(parse(&["--columns", "size", "--format", "treemap"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_template() {
+        let templated = options(&["--template", "{size_gb}\\t{type}\\t{path}"]);
+        assert_eq!(templated.format, OutputFormat::Template);
+        assert!(templated.wants(Column::Type));
+        assert!(parse(&["--template", "{uid}"]).is_err());
+        assert!(parse(&["--template", "{path}", "--format", "json"]).is_err());
+        assert!(parse(&["--template", "{path}", "--columns", "path"]).is_err());
+        assert!(parse(&["--template", "{path}", "--live"]).is_err());
+        assert!(parse(&["--template", "{hash}"]).is_err());
+        assert!(parse(&["--template", "{path}", "--dirs"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_paths() {
+        assert_eq!(options(&[]).paths, PathStyle::AsGiven);
+        assert_eq!(options(&["--absolute"]).paths, PathStyle::Absolute);
+        assert_eq!(
+            options(&["--relative", "--format", "json"]).paths,
+            PathStyle::Relative
+        );
+        assert!(parse(&["--absolute", "--relative"]).is_err());
+        assert!(parse(&["--relative", "--watch"]).is_err());
+        assert!(parse(&["--relative", "--format", "treemap"]).is_err());
+        assert!(parse(&["--absolute", "--watch"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_args_escape_paths() {
+        assert!(!options(&[]).escape_paths);
+        assert!(options(&["--escape-paths", "--format", "json"]).escape_paths);
+        assert!(options(&["--escape-paths", "--template", "{path}"]).escape_paths);
+        assert!(parse(&["--escape-paths"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_numbers() {
+        assert_eq!(options(&[]).numbers, NumberFormat::PLAIN);
+        assert_eq!(
+            options(&["--numbers", "grouped"]).numbers,
+            NumberFormat::GROUPED
+        );
+        let env = |key: &str| match key {
+            "LC_ALL" => Some(String::new()),
+            "LC_NUMERIC" => Some("de_DE.UTF-8".to_string()),
+            "LANG" => Some("en_US.UTF-8".to_string()),
+            _ => None,
+        };
+        let args = vec!["--numbers=locale".to_string()];
+        let command = parse_args_with_env(args, env).unwrap();
+        assert!(matches!(
+            command,
+            Command::Scan(options) if options.numbers == NumberFormat::for_locale("de_DE")
+        ));
+        assert!(parse(&["--numbers", "roman"]).is_err());
+        assert!(parse(&["--numbers", "grouped", "--format", "json"]).is_err());
+        assert!(parse(&["--numbers", "grouped", "--template", "{size}"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_home_directory() {
+        let env = |key: &str| (key == "HOME").then(|| "/home/dev".to_string());
+        let directory = |arg: &str| match parse_args_with_env(vec![arg.to_string()], env) {
+            Ok(Command::Scan(options)) => options.directory,
+            other => panic!("expected a scan, got {:?}", other),
+        };
+        assert_eq!(directory("~"), PathBuf::from("/home/dev"));
+        assert_eq!(
+            directory("~/Downloads"),
+            PathBuf::from("/home/dev/Downloads")
+        );
+        assert_eq!(directory("~dev/Downloads"), PathBuf::from("~dev/Downloads"));
+        assert_eq!(directory("a/~"), PathBuf::from("a/~"));
+        assert_eq!(expand_home("~/x", &|_: &str| None), PathBuf::from("~/x"));
+    }
+
+    #[test]
+    fn test_parse_args_no_color_env() {
+        let env = |key: &str| (key == "NO_COLOR").then(|| "1".to_string());
+        let command = parse_args_with_env(Vec::new(), env).unwrap();
+        assert!(matches!(command, Command::Scan(options) if options.color == ColorChoice::Never));
+        let args = vec!["--color=always".to_string()];
+        let command = parse_args_with_env(args, env).unwrap();
+        assert!(matches!(command, Command::Scan(options) if options.color == ColorChoice::Always));
+    }
+
+    #[test]
+    fn test_parse_args_backend() {
+        assert_eq!(options(&[]).backend, Backend::Std);
+        assert_eq!(
+            options(&["--backend", "io-uring"]).backend,
+            Backend::IoUring
+        );
+        assert!(parse(&["--backend", "turbo"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_format() {
+        assert_eq!(options(&[]).format, OutputFormat::Table);
+        assert_eq!(options(&["--format", "json"]).format, OutputFormat::Json);
+        assert_eq!(options(&["--format", "yaml"]).format, OutputFormat::Yaml);
+        assert_eq!(
+            options(&["--format", "treemap"]).format,
+            OutputFormat::Treemap
+        );
+        assert!(parse(&["--format", "xml"]).is_err());
+        assert!(parse(&["--format=treemap", "--dirs"]).is_err());
+        assert!(parse(&["--format=json", "--live"]).is_err());
+        assert!(parse(&["--format=yaml", "--dirs"]).is_err());
+        assert!(parse(&["--format=yaml", "--group-by", "ext"]).is_err());
+        assert!(parse(&["git", "--format", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_diff() {
+        assert_eq!(
+            parse(&["diff", "--delta", "1GB", "old.json", "new.json"]),
+            Ok(Command::Diff(DiffOptions {
+                old: PathBuf::from("old.json"),
+                new: PathBuf::from("new.json"),
+                delta_bytes: 1024 * 1024 * 1024,
+                display_unit: SizeUnit::GiB,
+            }))
+        );
+        assert!(parse(&["diff", "old.json"]).is_err());
+        assert!(parse(&["diff", "a.json", "b.json", "c.json"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_compare() {
+        let options = options(&["--compare", "old.json", "/tmp", "--delta=10MB"]);
+        assert_eq!(options.compare, Some(PathBuf::from("old.json")));
+        assert_eq!(options.delta_bytes, 10 * 1024 * 1024);
+        assert_eq!(options.directory, PathBuf::from("/tmp"));
+    }
+
+    #[test]
+    fn test_parse_args_show() {
+        assert_eq!(
+            parse(&["show", "scan.lbf", "1GB", "--sort", "path"]),
+            Ok(Command::Show(ShowOptions {
+                snapshot: PathBuf::from("scan.lbf"),
+                min_size_bytes: 1024 * 1024 * 1024,
+                display_unit: SizeUnit::GiB,
+                numbers: NumberFormat::PLAIN,
+                format: OutputFormat::Table,
+                sort: SortOrder::Path,
+            }))
+        );
+        let Ok(Command::Show(show)) = parse(&["show", "--format=json", "scan.lbf"]) else {
+            panic!("expected show");
+        };
+        assert_eq!(show.min_size_bytes, 0);
+        assert_eq!(show.format, OutputFormat::Json);
+        assert!(parse(&["show"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_git() {
+        assert_eq!(
+            parse(&["git", "/src/app", "10MB"]),
+            Ok(Command::Git(GitOptions {
+                repo: PathBuf::from("/src/app"),
+                min_size_bytes: 10 * 1024 * 1024,
+                display_unit: SizeUnit::MiB,
+                format: OutputFormat::Table,
+            }))
+        );
+        let Ok(Command::Git(git)) = parse(&["git", "--format", "json"]) else {
+            panic!("expected git");
+        };
+        assert_eq!(git.repo, PathBuf::from("."));
+        assert_eq!(git.min_size_bytes, 1024 * 1024);
+        assert_eq!(git.format, OutputFormat::Json);
+        assert!(parse(&["git", "--format", "treemap"]).is_err());
+        assert!(parse(&["git", ".", "1MB", "extra"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_subcommands_reject_scan_flags() {
+        assert_eq!(
+            parse(&["git", "--delete", "--yes", "."]).unwrap_err(),
+            "--delete only applies to directory scans"
+        );
+        assert!(parse(&["git", "--yes"]).is_err());
+        assert!(parse(&["git", "--cache", "--hash", "sha256", "."]).is_err());
+        assert!(parse(&["git", "--columns", "owner,path"]).is_err());
+        assert!(parse(&["bench", "--webhook", "https://hooks.example.com/x", "/tmp"]).is_err());
+        assert!(parse(&["show", "old.json", "--trash"]).is_err());
+        assert!(parse(&["--docker", "app:latest", "--exec", "echo {}"]).is_err());
+        assert!(parse(&["show", "old.json", "--format", "json"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_args_bench() {
+        assert_eq!(
+            parse(&["bench", "/srv"]),
+            Ok(Command::Bench(BenchOptions {
+                directory: PathBuf::from("/srv"),
+                runs: 3,
+                thread_counts: Vec::new(),
+                backends: Vec::new(),
+                format: OutputFormat::Table,
+            }))
+        );
+        let Ok(Command::Bench(bench)) = parse(&[
+            "bench",
+            "--runs",
+            "5",
+            "--thread-counts",
+            "1,4,16",
+            "--backend",
+            "std",
+            "--format",
+            "json",
+        ]) else {
+            panic!("expected bench");
+        };
+        assert_eq!(bench.directory, PathBuf::from("."));
+        assert_eq!(bench.runs, 5);
+        assert_eq!(bench.thread_counts, [1, 4, 16]);
+        assert_eq!(bench.backends, [Backend::Std]);
+        assert_eq!(bench.format, OutputFormat::Json);
+        let Ok(Command::Bench(bench)) = parse(&["bench", "--threads", "2"]) else {
+            panic!("expected bench");
+        };
+        assert_eq!(bench.thread_counts, [2]);
+        assert!(parse(&["bench", "--thread-counts", "1,0"]).is_err());
+        assert!(parse(&["bench", "--runs", "0"]).is_err());
+        assert!(parse(&["bench", "--format", "yaml"]).is_err());
+        assert!(parse(&["bench", "/srv", "1GB"]).is_err());
+        assert!(parse(&["--runs", "2", "/srv"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_self_update() {
+        assert_eq!(
+            parse(&["self-update"]),
+            Ok(Command::SelfUpdate(UpdateOptions { check: false }))
+        );
+        assert_eq!(
+            parse(&["self-update", "--check"]),
+            Ok(Command::SelfUpdate(UpdateOptions { check: true }))
+        );
+        assert!(parse(&["self-update", "latest"]).is_err());
+        assert!(parse(&["self-update", "--stats"]).is_err());
+        assert!(parse(&["--check", "/srv"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_docker() {
+        assert_eq!(
+            parse(&["--docker", "nginx:latest", "50MB"]),
+            Ok(Command::Docker(DockerOptions {
+                image: "nginx:latest".to_string(),
+                min_size_bytes: 50 * 1024 * 1024,
+                display_unit: SizeUnit::MiB,
+                format: OutputFormat::Table,
+            }))
+        );
+        let Ok(Command::Docker(docker)) = parse(&["--format", "json", "--docker=image.tar"]) else {
+            panic!("expected docker");
+        };
+        assert_eq!(docker.image, "image.tar");
+        assert_eq!(docker.min_size_bytes, 10 * 1024 * 1024);
+        assert_eq!(docker.format, OutputFormat::Json);
+        assert!(parse(&["--docker", "nginx", "--format", "treemap"]).is_err());
+        assert!(parse(&["--docker", "nginx", "1MB", "extra"]).is_err());
+        assert!(parse(&["git", "--docker", "nginx"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_human() {
+        assert_eq!(
+            options(&["--human", "/tmp", "1GB"]).display_unit,
+            SizeUnit::Auto
+        );
+        let Ok(Command::Show(show)) = parse(&["show", "scan.lbf", "--human"]) else {
+            panic!("expected show");
+        };
+        assert_eq!(show.display_unit, SizeUnit::Auto);
+        let Ok(Command::Diff(diff)) = parse(&["diff", "--human", "a.json", "b.json"]) else {
+            panic!("expected diff");
+        };
+        assert_eq!(diff.display_unit, SizeUnit::Auto);
+    }
+
+    #[test]
+    fn test_parse_args_bytes() {
+        assert_eq!(
+            options(&["--bytes", "/tmp", "1GB"]).display_unit,
+            SizeUnit::B
+        );
+        assert_eq!(
+            options(&["--bytes=grouped", "--si"]).display_unit,
+            SizeUnit::GroupedB
+        );
+        let Ok(Command::Show(show)) = parse(&["show", "scan.lbf", "--bytes"]) else {
+            panic!("expected show");
+        };
+        assert_eq!(show.display_unit, SizeUnit::B);
+        assert!(parse(&["--bytes=exact"]).is_err());
+        assert!(parse(&["--bytes", "--human"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_si() {
+        let si = options(&["/tmp", "1GB", "--si"]);
+        assert_eq!(si.min_size_bytes, 1_000_000_000);
+        assert_eq!(si.display_unit, SizeUnit::GB);
+        assert_eq!(options(&["--si"]).min_size_bytes, 100_000_000);
+        assert_eq!(
+            options(&["--si", "--human", "--delta", "2MB"]).delta_bytes,
+            2_000_000
+        );
+        assert_eq!(options(&["--human", "--si"]).display_unit, SizeUnit::AutoSi);
+    }
+
+    #[test]
+    fn test_parse_args_output() {
+        let json = options(&["--format", "json", "--output", "out/scan.json", "/tmp"]);
+        assert_eq!(json.output, Some(PathBuf::from("out/scan.json")));
+        assert!(parse(&["--template", "{path}", "--output", "paths.txt"]).is_ok());
+        // The default table can go to a file too
+        let table = options(&["--output", "scan.txt", "/tmp"]);
+        assert_eq!(table.format, OutputFormat::Table);
+        assert_eq!(table.output, Some(PathBuf::from("scan.txt")));
+        assert!(parse(&["--output", "scan.txt", "--live"]).is_err());
+        assert!(parse(&["--output", "scan.txt", "--top-dirs", "5"]).is_err());
+        assert!(parse(&["--format", "json", "--output", "a", "--dirs"]).is_err());
+        assert!(parse(&["--format", "json", "--output", "a", "--summary"]).is_err());
+        assert!(parse(&["show", "--output", "a", "scan.lbf"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_save_snapshot_and_sort() {
+        let options = options(&["--save-snapshot", "scan.lbf", "/tmp"]);
+        assert_eq!(options.save_snapshot, Some(PathBuf::from("scan.lbf")));
+        assert_eq!(options.sort, SortOrder::Size);
+        assert!(parse(&["--sort", "age"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_plugin() {
+        let plugins = parse(&["--plugin", "hold.so", "--plugin=inventory.so"]);
+        if cfg!(feature = "plugins") {
+            let Ok(Command::Scan(options)) = plugins else {
+                panic!("expected a scan");
+            };
+            assert_eq!(
+                options.plugins,
+                [PathBuf::from("hold.so"), PathBuf::from("inventory.so")]
+            );
+            assert!(parse(&["--plugin", "hold.so", "--watch"]).is_err());
+        } else {
+            assert!(plugins.is_err());
+        }
+        assert!(parse(&["git", "--plugin", "hold.so"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_wasm_plugin() {
+        let plugins = parse(&["--plugin", "hold.WASM"]);
+        if cfg!(feature = "wasm") {
+            let Ok(Command::Scan(options)) = plugins else {
+                panic!("expected a scan");
+            };
+            assert_eq!(options.plugins, [PathBuf::from("hold.WASM")]);
+        } else {
+            assert!(plugins.is_err());
+        }
+        assert!(is_wasm(Path::new("filters/hold.wasm")));
+        assert!(!is_wasm(Path::new("libhold.so")));
+    }
+
+    #[test]
+    fn test_parse_args_filter_script() {
+        let script = parse(&["--filter-script", "old.rhai"]);
+        if cfg!(feature = "scripting") {
+            let Ok(Command::Scan(options)) = script else {
+                panic!("expected a scan");
+            };
+            assert_eq!(options.filter_script, Some(PathBuf::from("old.rhai")));
+            assert!(parse(&["--filter-script", "old.rhai", "--serve", ":8080"]).is_err());
+        } else {
+            assert!(script.is_err());
+        }
+        assert!(parse(&["git", "--filter-script", "old.rhai"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_state_dir() {
+        let options = options(&["--state-dir", "/var/lib/lbf", "--save-snapshot", "weekly"]);
+        assert_eq!(options.state_dir, Some(PathBuf::from("/var/lib/lbf")));
+        assert_eq!(
+            options.save_snapshot,
+            Some(PathBuf::from("/var/lib/lbf/snapshots/weekly.lbf"))
+        );
+        let env = |key: &str| (key == "LBF_STATE_DIR").then(|| "/srv/lbf".to_string());
+        let Ok(Command::Diff(diff)) =
+            parse_args_with_env(["diff", "monday", "new.json"].map(String::from), env)
+        else {
+            panic!("expected diff");
+        };
+        assert_eq!(diff.old, PathBuf::from("/srv/lbf/snapshots/monday.lbf"));
+        assert_eq!(diff.new, PathBuf::from("new.json"));
+        let Ok(Command::Show(show)) = parse(&["show", "--state-dir", "/tmp/s", "home"]) else {
+            panic!("expected show");
+        };
+        assert_eq!(show.snapshot, PathBuf::from("/tmp/s/snapshots/home.lbf"));
+    }
+
+    #[test]
+    fn test_parse_args_daemon() {
+        let options = options(&[
+            "--daemon",
+            "--interval",
+            "15m",
+            "--report-dir=/var/lib/lbf",
+            "--keep",
+            "3",
+        ]);
+        assert!(options.daemon);
+        assert_eq!(options.interval, Duration::from_secs(15 * 60));
+        assert_eq!(options.report_dir, Some(PathBuf::from("/var/lib/lbf")));
+        assert_eq!(options.keep, 3);
+    }
+
+    #[test]
+    fn test_parse_args_daemon_flags_require_daemon() {
+        assert!(parse(&["--interval", "1h"]).is_err());
+        assert!(parse(&["--daemon", "--watch"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_metrics() {
+        let options = options(&["--daemon", "--metrics", "127.0.0.1:9184"]);
+        assert_eq!(options.metrics.as_deref(), Some("127.0.0.1:9184"));
+        assert!(parse(&["--watch", "--metrics=:9184"]).is_ok());
+        assert!(parse(&["--metrics", "127.0.0.1:9184"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_serve() {
+        let options = options(&["--serve", "127.0.0.1:8080", "/srv"]);
+        assert_eq!(options.serve.as_deref(), Some("127.0.0.1:8080"));
+        assert!(parse(&["--serve=127.0.0.1:8080", "--daemon"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_webhook() {
+        let options = options(&[
+            "--webhook",
+            "https://hooks.example.com/x",
+            "--alert-threshold=2GB",
+        ]);
+        assert_eq!(
+            options.webhook.as_deref(),
+            Some("https://hooks.example.com/x")
+        );
+        assert_eq!(options.alert_threshold, Some(2 * 1024 * 1024 * 1024));
+        assert!(parse(&["--alert-threshold", "1GB"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_alert_total() {
+        assert_eq!(
+            options(&["--alert-total", "500GB"]).alert_total,
+            Some(500 * 1024 * 1024 * 1024)
+        );
+        assert_eq!(
+            options(&["--alert-total=2TB", "--si", "--daemon"]).alert_total,
+            Some(2_000_000_000_000)
+        );
+        assert!(parse(&["--alert-total", "lots"]).is_err());
+        assert!(parse(&["--alert-total", "1GB", "--dirs"]).is_err());
+        assert!(parse(&[
+            "--alert-total",
+            "1GB",
+            "--alert-threshold",
+            "1GB",
+            "--webhook",
+            "https://hooks.example.com/x"
+        ])
+        .is_err());
+        assert!(parse(&["git", "--alert-total", "1GB"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_email() {
+        let options = options(&[
+            "--email-to",
+            "a@example.com, b@example.com",
+            "--email-to=c@example.com",
+            "--smtp-server",
+            "smtp.example.com",
+        ]);
+        assert_eq!(
+            options.email_to,
+            vec!["a@example.com", "b@example.com", "c@example.com"]
+        );
+        assert_eq!(options.smtp_server.as_deref(), Some("smtp.example.com"));
+        assert!(parse(&["--email-to", "a@example.com"]).is_err());
+        assert!(parse(&["--smtp-server", "smtp.example.com"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_log_syslog() {
+        assert!(options(&["--log-syslog", "/srv"]).log_syslog);
+        assert!(parse(&["--serve", "127.0.0.1:8080", "--log-syslog"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_dirs() {
+        let options = options(&["--dirs", "--depth", "3", "/srv"]);
+        assert!(options.dirs);
+        assert_eq!(options.depth, 3);
+        assert_eq!(self::options(&["--dirs"]).depth, 1);
+        assert!(parse(&["--depth", "2"]).is_err());
+        assert!(parse(&["--dirs", "--live"]).is_err());
+        assert!(parse(&["--dirs", "--webhook", "https://hooks.example.com/x"]).is_err());
+        assert!(parse(&["--dirs", "--log-syslog"]).is_err());
+        assert!(parse(&[
+            "--dirs",
+            "--email-to",
+            "a@example.com",
+            "--smtp-server",
+            "smtp.example.com"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_args_stats_by_ext() {
+        assert!(options(&["--stats-by-ext"]).stats_by_ext);
+        assert!(parse(&["--stats-by-ext", "--format", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_histogram() {
+        assert!(options(&["--histogram", "/srv"]).histogram);
+        assert!(parse(&["--histogram", "--dirs"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_top_dirs() {
+        assert_eq!(options(&["--top-dirs", "5"]).top_dirs, Some(5));
+        assert!(parse(&["--top-dirs", "0"]).is_err());
+        assert!(parse(&["--top-dirs=5", "--format", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_detect_type() {
+        assert!(options(&["--detect-type"]).detect_type);
+        assert!(options(&["--detect-type", "--format", "json"]).detect_type);
+        assert!(parse(&["--detect-type", "--dirs"]).is_err());
+        assert!(parse(&["--detect-type", "--format", "treemap"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_append_history() {
+        assert_eq!(options(&[]).append_history, None);
+        assert_eq!(
+            options(&["--append-history", "/var/log/lbf.ndjson", "--daemon"]).append_history,
+            Some(PathBuf::from("/var/log/lbf.ndjson"))
+        );
+        #[cfg(feature = "s3")]
+        assert!(options(&["--append-history=h.ndjson", "s3://bucket"])
+            .append_history
+            .is_some());
+        assert!(parse(&["--append-history", "h.ndjson", "--dirs"]).is_err());
+        assert!(parse(&["git", "--append-history", "h.ndjson"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_score() {
+        assert_eq!(options(&["--score"]).score, Some(Weights::default()));
+        assert_eq!(
+            options(&["--score-weights", "1,2", "--score"]).score,
+            Some(Weights {
+                size: 1.0,
+                age: 2.0
+            })
+        );
+        assert_eq!(options(&[]).score, None);
+        assert!(parse(&["--score-weights", "1,2"]).is_err());
+        assert!(parse(&["--score", "--score-weights", "2"]).is_err());
+        assert!(parse(&["--score", "--sort", "path"]).is_err());
+        assert!(parse(&["--score", "--dirs"]).is_err());
+        assert!(parse(&["--score", "s3://bucket"]).is_err());
+        assert!(parse(&["show", "--score", "scan.lbf"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_by_user() {
+        let by_user = options(&["--by-user", "/home", "10GB"]);
+        assert!(by_user.by_user);
+        assert_eq!(by_user.min_size_bytes, 10 * 1024 * 1024 * 1024);
+        assert!(options(&["--by-user", "--format", "json"]).by_user);
+        assert!(parse(&["--by-user", "--dirs"]).is_err());
+        assert!(parse(&["--by-user", "--suggest-cleanup"]).is_err());
+        assert!(parse(&["--by-user", "--owner"]).is_err());
+        assert!(parse(&["--by-user", "--log-syslog"]).is_err());
+        assert!(parse(&["--by-user", "--format", "yaml"]).is_err());
+        assert!(parse(&["--by-user", "s3://bucket"]).is_err());
+        assert!(parse(&["git", "--by-user"]).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_args_deleted_open() {
+        let deleted = options(&["--deleted-open", "/var", "100MB"]);
+        assert!(deleted.deleted_open);
+        assert_eq!(deleted.min_size_bytes, 100 * 1024 * 1024);
+        assert!(options(&["--deleted-open", "--format", "json"]).deleted_open);
+        assert!(parse(&["--deleted-open", "--by-user"]).is_err());
+        assert!(parse(&["--deleted-open", "--delete"]).is_err());
+        assert!(parse(&["--deleted-open", "--webhook", "https://hooks.example.com/x"]).is_err());
+        assert!(parse(&["--deleted-open", "--format", "yaml"]).is_err());
+        assert!(parse(&["--deleted-open", "s3://bucket"]).is_err());
+        assert!(parse(&["git", "--deleted-open"]).is_err());
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    #[test]
+    fn test_parse_args_deleted_open_needs_linux() {
+        assert!(parse(&["--deleted-open"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_suggest_cleanup() {
+        let cleanup = options(&["--suggest-cleanup", "~", "1GB"]);
+        assert!(cleanup.suggest_cleanup);
+        assert_eq!(cleanup.min_size_bytes, 1024 * 1024 * 1024);
+        assert!(options(&["--suggest-cleanup", "--format", "json"]).suggest_cleanup);
+        assert!(parse(&["--suggest-cleanup", "--dirs"]).is_err());
+        assert!(parse(&["--suggest-cleanup", "--delete"]).is_err());
+        assert!(parse(&["--suggest-cleanup", "--log-syslog"]).is_err());
+        assert!(parse(&[
+            "--suggest-cleanup",
+            "--webhook",
+            "https://hooks.example.com/x"
+        ])
+        .is_err());
+        assert!(parse(&["--suggest-cleanup", "--format", "yaml"]).is_err());
+        assert!(parse(&["--suggest-cleanup", "s3://bucket"]).is_err());
+        assert!(parse(&["git", "--suggest-cleanup"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_preset() {
+        let dev = options(&["--preset", "dev"]);
+        assert_eq!(dev.preset, Some(Preset::Dev));
+        assert_eq!(dev.min_size_bytes, 10 * 1024 * 1024);
+        let media = options(&["--preset=media", "/srv", "1GB"]);
+        assert_eq!(media.preset, Some(Preset::Media));
+        assert_eq!(media.min_size_bytes, 1024 * 1024 * 1024);
+        assert_eq!(
+            options(&["--preset", "backups", "--si"]).min_size_bytes,
+            100_000_000
+        );
+        assert!(parse(&["--preset", "videos"]).is_err());
+        assert!(parse(&["--preset", "logs", "--watch"]).is_err());
+        assert!(parse(&["--preset", "logs", "--dirs"]).is_err());
+        assert!(parse(&["--preset", "logs", "s3://bucket/logs"]).is_err());
+        assert!(parse(&["git", "--preset", "dev"]).is_err());
+        // dev lists directory totals, so file listings and actions are refused
+        assert!(parse(&["--preset", "dev", "--format", "json"]).is_ok());
+        for flag in ["--delete", "--summary", "--mtime", "--relative"] {
+            let err = parse(&["--preset", "dev", flag]).unwrap_err();
+            assert_eq!(
+                err,
+                format!("{} cannot be combined with --preset dev", flag)
+            );
+        }
+        assert!(parse(&["--preset", "media", "--delete", "--yes"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_args_mime() {
+        assert_eq!(
+            options(&["--mime", "video/*,audio/*"]).mime,
+            vec!["video/*", "audio/*"]
+        );
+        assert_eq!(options(&["--mime=*"]).mime, vec!["*"]);
+        assert!(parse(&["--mime", "video/*,"]).is_err());
+        assert!(parse(&["--mime", "video/*", "--watch"]).is_err());
+        assert!(parse(&["--mime", "video/*", "--dirs"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_percent_of_disk() {
+        assert!(options(&["--percent-of-disk"]).percent_of_disk);
+        assert!(parse(&["--percent-of-disk", "--dirs"]).is_err());
+        assert!(parse(&["--percent-of-disk", "--format", "treemap"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_scan_archives() {
+        assert_eq!(
+            options(&["--scan-archives", "zip"]).scan_archives,
+            vec![ArchiveFormat::Zip]
+        );
+        assert_eq!(
+            options(&["--scan-archives", "zip,tgz"]).scan_archives,
+            vec![ArchiveFormat::Zip, ArchiveFormat::TarGz]
+        );
+        assert!(parse(&["--scan-archives", "rar"]).is_err());
+        assert!(parse(&["--scan-archives", "zip", "--delete"]).is_err());
+        assert!(parse(&["--scan-archives", "zip", "--dirs"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_group_by() {
+        assert_eq!(options(&["--group-by", "dir"]).group_by, Some(GroupBy::Dir));
+        assert_eq!(
+            options(&["--group-by", "owner"]).group_by,
+            Some(GroupBy::Owner)
+        );
+        assert_eq!(options(&["--group-by", "age"]).group_by, Some(GroupBy::Age));
+        assert_eq!(options(&[]).group_by, None);
+        assert!(parse(&["--group-by", "size"]).is_err());
+        assert!(parse(&["--group-by", "dir", "--format", "json"]).is_err());
+        assert!(parse(&["--group-by", "dir", "--template", "{path}"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_show_perms() {
+        assert!(options(&["--show-perms"]).show_perms);
+        assert!(options(&["--columns", "perms,path"]).wants(Column::Perms));
+        assert!(parse(&["--show-perms", "--dirs"]).is_err());
+        assert!(parse(&["--show-perms", "--format", "treemap"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_inode() {
+        assert!(options(&["--inode"]).inode);
+        assert!(options(&["--template", "{device}:{inode}"]).wants(Column::Inode));
+        assert!(parse(&["--inode", "--dirs"]).is_err());
+        assert!(parse(&["--inode", "--format", "treemap"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_skip_network() {
+        assert!(!options(&[]).skip_network);
+        assert!(options(&["--skip-network", "/home"]).skip_network);
+    }
+
+    #[test]
+    fn test_parse_args_placeholders() {
+        assert_eq!(options(&[]).placeholders, Placeholders::Include);
+        assert_eq!(
+            options(&["--placeholders", "exclude"]).placeholders,
+            Placeholders::Exclude
+        );
+        assert_eq!(
+            options(&["--placeholders=mark"]).placeholders,
+            Placeholders::Mark
+        );
+        assert!(options(&["--columns", "size,cloud,path"]).wants(Column::Cloud));
+        assert!(parse(&["--placeholders", "skip"]).is_err());
+        assert!(parse(&["--placeholders", "mark", "--dirs"]).is_err());
+        assert!(parse(&["--placeholders", "exclude", "--watch"]).is_err());
+        assert!(parse(&["--placeholders", "mark", "--format", "treemap"]).is_err());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_parse_args_ads() {
+        assert!(options(&["--ads"]).ads);
+        assert!(parse(&["--ads", "--dirs"]).is_err());
+        assert!(parse(&["--ads", "--backend", "mft"]).is_err());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_parse_args_ads_needs_windows() {
+        assert_eq!(
+            parse(&["--ads"]).unwrap_err(),
+            "--ads is only available on Windows"
+        );
+    }
+
+    #[test]
+    fn test_parse_args_follow_links() {
+        assert!(!options(&[]).follow_links);
+        assert!(options(&["--follow-links", "C:\\Users"]).follow_links);
+        assert!(parse(&["--follow-links", "--backend", "mft"]).is_err());
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_parse_args_bundle_as_file() {
+        assert!(options(&["--bundle-as-file", "/Applications"]).bundle_as_file);
+        assert!(parse(&["--bundle-as-file", "--dirs"]).is_err());
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_parse_args_bundle_as_file_needs_macos() {
+        assert!(parse(&["--bundle-as-file"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_owner() {
+        assert!(options(&["--owner"]).owner);
+        assert!(options(&["--columns", "size,owner,path"]).wants(Column::Owner));
+        assert!(options(&["--template", "{group} {path}"]).wants(Column::Group));
+        assert!(parse(&["--owner", "--dirs"]).is_err());
+        assert!(parse(&["--owner", "--format", "treemap"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_mtime() {
+        assert!(options(&["--mtime"]).mtime);
+        assert!(options(&["--columns", "size,age,path"]).wants(Column::Age));
+        assert!(parse(&["--mtime", "--dirs"]).is_err());
+        assert!(parse(&["--mtime", "--format", "treemap"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_estimate_compression() {
+        assert!(options(&["--estimate-compression"]).estimate_compression);
+        assert!(parse(&["--estimate-compression", "--dirs"]).is_err());
+        assert!(parse(&["--estimate-compression", "--format", "treemap"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_tui() {
+        assert!(options(&["--tui"]).tui);
+        assert!(parse(&["--tui", "--format", "json"]).is_err());
+        assert!(parse(&["--tui", "--watch"]).is_err());
+        assert!(parse(&["--tui", "--histogram"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_interactive_delete() {
+        assert!(options(&["--interactive-delete"]).interactive_delete);
+        assert!(parse(&["--interactive-delete", "--tui"]).is_err());
+        assert!(parse(&["--interactive-delete", "--format", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_delete() {
+        let options = options(&["--delete", "--yes", "--dry-run"]);
+        assert!(options.delete && options.yes && options.dry_run);
+        assert!(parse(&["--delete", "-y", "--format", "json"]).is_ok());
+        assert!(parse(&["--yes"]).is_err());
+        assert!(parse(&["--dry-run"]).is_err());
+        assert!(parse(&["--delete", "--watch"]).is_err());
+        assert!(parse(&["--delete", "--tui"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_trash() {
+        let options = options(&["--trash", "--yes"]);
+        assert_eq!(options.action(), Some(Action::Remove(Removal::Trash)));
+        assert!(parse(&["--trash", "--dry-run"]).is_ok());
+        assert!(parse(&["--trash", "--delete"]).is_err());
+        assert!(parse(&["--trash", "--daemon"]).is_err());
+        assert_eq!(Options::default().action(), None);
+    }
+
+    #[test]
+    fn test_parse_args_move_to() {
+        assert_eq!(
+            options(&["--move-to", "/archive", "/data"]).action(),
+            Some(Action::Move {
+                root: PathBuf::from("/data"),
+                target: PathBuf::from("/archive"),
+            })
+        );
+        assert!(parse(&["--move-to=/archive", "--dry-run"]).is_ok());
+        assert!(parse(&["--move-to", "/archive", "--delete"]).is_err());
+        assert!(parse(&["--move-to"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_compress() {
+        assert_eq!(
+            options(&["--compress", "gzip", "--older-than", "30d"]).action(),
+            Some(Action::Compress {
+                format: Format::Gzip,
+                older_than: Some(Duration::from_secs(30 * 86_400)),
+            })
+        );
+        assert!(parse(&["--compress", "lz4"]).is_err());
+        assert!(parse(&["--compress", "zstd", "--trash"]).is_err());
+        assert!(parse(&["--older-than", "30d"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_exec() {
+        assert_eq!(
+            options(&["--exec", "cp -- {} '/backup/my files/'", "--exec-jobs=4"]).action(),
+            Some(Action::Exec {
+                template: vec![
+                    "cp".to_string(),
+                    "--".to_string(),
+                    "{}".to_string(),
+                    "/backup/my files/".to_string()
+                ],
+                jobs: 4,
+            })
+        );
+        assert!(parse(&["--exec", "rm {}", "--delete"]).is_err());
+        assert!(parse(&["--exec", "rm {}", "--tui"]).is_err());
+        assert!(parse(&["--exec-jobs", "2"]).is_err());
+        assert!(parse(&["--exec", "  "]).is_err());
+        assert!(parse(&["--exec", "echo 'unterminated"]).is_err());
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words(r#"sh -c "echo \"\$1\"" x\ y ''"#).unwrap(),
+            vec!["sh", "-c", "echo \"\\$1\"", "x y", ""]
+        );
+    }
+
+    #[test]
+    fn test_parse_args_hash() {
+        assert_eq!(options(&["--hash", "sha256"]).hash, Some(Algorithm::Sha256));
+        assert_eq!(options(&["--hash=xxh3"]).hash, Some(Algorithm::Xxh3));
+        assert!(parse(&["--hash", "md5"]).is_err());
+        assert!(parse(&["--hash", "blake3", "--format", "treemap"]).is_err());
+        assert_eq!(
+            options(&["--hash", "blake3", "--format", "json"]).hash,
+            Some(Algorithm::Blake3)
+        );
+    }
+
+    #[test]
+    fn test_parse_args_find_duplicates() {
+        assert!(options(&["--find-duplicates"]).find_duplicates);
+        assert!(parse(&["--find-duplicates", "--dirs"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(
+            parse_interval("--interval", "90"),
+            Ok(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_interval("--interval", "2h"),
+            Ok(Duration::from_secs(7200))
+        );
+        assert_eq!(
+            parse_interval("--interval", "1d"),
+            Ok(Duration::from_secs(86_400))
+        );
+        assert!(parse_interval("--interval", "0s").is_err());
+        assert!(parse_interval("--interval", "1w").is_err());
+        assert!(parse_interval("--interval", "soon").is_err());
+    }
+}
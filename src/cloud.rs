@@ -0,0 +1,108 @@
+// Import rayon to read the matches' metadata in parallel
+use rayon::prelude::*;
+// Import FileInfo for the matches that are checked, and long_path for files
+// past MAX_PATH on Windows
+use list_big_files::{long_path, FileInfo};
+// Import fs for reading metadata
+use std::fs;
+
+// Attributes the Windows cloud files API sets on files whose data is not on
+// disk: OneDrive and Dropbox use RECALL_ON_DATA_ACCESS, older clients OFFLINE
+#[cfg(any(windows, test))]
+const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+#[cfg(any(windows, test))]
+const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+#[cfg(any(windows, test))]
+const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+
+// The flag macOS sets on files iCloud Drive and File Provider clients have
+// evicted, leaving only their metadata
+#[cfg(any(target_os = "macos", test))]
+const SF_DATALESS: u32 = 0x40000000;
+
+/// Record for every file in `files` whether it is a cloud placeholder: a file
+/// a sync client such as OneDrive, iCloud Drive or Dropbox lists at its full
+/// size but only downloads when it is opened. Files whose metadata cannot be
+/// read are left unmarked.
+pub fn add_placeholders(files: &mut [FileInfo]) {
+    files.par_iter_mut().for_each(|file| {
+        // Reading the metadata does not open the file, so nothing is downloaded
+        file.placeholder = fs::symlink_metadata(long_path(&file.path))
+            .ok()
+            .map(|meta| is_placeholder(&meta));
+    });
+}
+
+// Whether a file's metadata marks its data as held only in the cloud
+#[cfg(windows)]
+fn is_placeholder(meta: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    offline_attributes(meta.file_attributes())
+}
+
+#[cfg(target_os = "macos")]
+fn is_placeholder(meta: &fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    dataless_flags(meta.st_flags())
+}
+
+// Other systems have no common marker for files that are not on disk
+#[cfg(not(any(windows, target_os = "macos")))]
+fn is_placeholder(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+// Whether Windows file attributes mark the data as not on disk
+#[cfg(any(windows, test))]
+fn offline_attributes(attributes: u32) -> bool {
+    attributes
+        & (FILE_ATTRIBUTE_OFFLINE
+            | FILE_ATTRIBUTE_RECALL_ON_OPEN
+            | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+// Whether BSD file flags mark the file as dataless
+#[cfg(any(target_os = "macos", test))]
+fn dataless_flags(flags: u32) -> bool {
+    flags & SF_DATALESS != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_attributes() {
+        // Archive, and archive with the pinned bit OneDrive sets on local files
+        assert!(!offline_attributes(0x20));
+        assert!(!offline_attributes(0x20 | 0x80000));
+        // An online-only OneDrive file: archive, unpinned, recall on data access
+        assert!(offline_attributes(0x20 | 0x100000 | 0x400000));
+        assert!(offline_attributes(FILE_ATTRIBUTE_RECALL_ON_OPEN));
+        assert!(offline_attributes(FILE_ATTRIBUTE_OFFLINE));
+    }
+
+    #[test]
+    fn test_dataless_flags() {
+        assert!(!dataless_flags(0));
+        // UF_HIDDEN alone
+        assert!(!dataless_flags(0x8000));
+        assert!(dataless_flags(SF_DATALESS | 0x20));
+    }
+
+    #[test]
+    fn test_local_files_are_not_placeholders() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("local.bin");
+        fs::write(&path, [0u8; 1024]).unwrap();
+        let missing = dir.path().join("missing.bin");
+        let mut files = vec![
+            FileInfo::new(path.display().to_string(), 1024),
+            FileInfo::new(missing.display().to_string(), 1024),
+        ];
+        add_placeholders(&mut files);
+        assert_eq!(files[0].placeholder, Some(false));
+        assert_eq!(files[1].placeholder, None);
+    }
+}
@@ -0,0 +1,79 @@
+// Import IsTerminal to color only output a person is reading
+use std::io::{self, IsTerminal};
+
+// ANSI sequences for the tiers and for returning to the terminal's colors
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// When `--color` highlights large matches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    /// Only when stdout is a terminal and NO_COLOR is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+/// Sizes from which matches are shown in yellow and in red.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tiers {
+    pub warn_bytes: u64,
+    pub critical_bytes: u64,
+}
+
+impl Default for Tiers {
+    fn default() -> Self {
+        Tiers {
+            warn_bytes: 1 << 30,
+            critical_bytes: 10 << 30,
+        }
+    }
+}
+
+impl Tiers {
+    /// Wrap `text` in the color of the tier `size_bytes` falls in, leaving
+    /// sizes below every tier alone.
+    pub fn paint(&self, size_bytes: u64, text: &str) -> String {
+        let color = if size_bytes >= self.critical_bytes {
+            RED
+        } else if size_bytes >= self.warn_bytes {
+            YELLOW
+        } else {
+            return text.to_string();
+        };
+        format!("{}{}{}", color, text, RESET)
+    }
+}
+
+/// The tiers to color stdout with, or None when it stays plain.
+pub fn for_stdout(choice: ColorChoice, tiers: Tiers) -> Option<Tiers> {
+    match choice {
+        ColorChoice::Always => Some(tiers),
+        ColorChoice::Never => None,
+        ColorChoice::Auto => io::stdout().is_terminal().then_some(tiers),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paint_tiers() {
+        let tiers = Tiers {
+            warn_bytes: 100,
+            critical_bytes: 1000,
+        };
+        assert_eq!(tiers.paint(99, "small"), "small");
+        assert_eq!(tiers.paint(100, "big"), "\x1b[33mbig\x1b[0m");
+        assert_eq!(tiers.paint(5000, "huge"), "\x1b[31mhuge\x1b[0m");
+    }
+
+    #[test]
+    fn test_for_stdout() {
+        let tiers = Tiers::default();
+        assert_eq!(for_stdout(ColorChoice::Always, tiers), Some(tiers));
+        assert_eq!(for_stdout(ColorChoice::Never, tiers), None);
+    }
+}
@@ -0,0 +1,285 @@
+// Import the size column's header and cells shared with the other tables
+use crate::{size_cell, size_header};
+// Import the modification time formats
+use crate::stat;
+// Import FileInfo for the matches whose fields are shown
+use list_big_files::{FileInfo, SizeUnit};
+// Import SystemTime for how long ago matches were modified
+use std::time::SystemTime;
+// Import serde to write only the chosen fields of each match
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// A field of a match that `--columns` can show.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Size,
+    Modified,
+    /// The modification time relative to now, e.g. `3 months ago`; only
+    /// shown when asked for by name.
+    Age,
+    /// The size and age score given by `--score`.
+    Score,
+    Type,
+    Mime,
+    Ratio,
+    Disk,
+    Hash,
+    Perms,
+    Owner,
+    Group,
+    Device,
+    Inode,
+    /// Whether the match is a cloud placeholder, `online-only`, or `local`.
+    Cloud,
+    /// `warning` or `critical`, when `--warn-size` is given.
+    Tier,
+    Path,
+}
+
+/// Columns shown when `--columns` is not given: the size, each detail some
+/// match carries, then the path.
+pub const DEFAULT: [Column; 16] = [
+    Column::Size,
+    Column::Modified,
+    Column::Score,
+    Column::Type,
+    Column::Mime,
+    Column::Ratio,
+    Column::Disk,
+    Column::Hash,
+    Column::Perms,
+    Column::Owner,
+    Column::Group,
+    Column::Device,
+    Column::Inode,
+    Column::Cloud,
+    Column::Tier,
+    Column::Path,
+];
+
+// Every column, in the order --help lists them
+const ALL: [Column; 17] = [
+    Column::Size,
+    Column::Modified,
+    Column::Age,
+    Column::Score,
+    Column::Type,
+    Column::Mime,
+    Column::Ratio,
+    Column::Disk,
+    Column::Hash,
+    Column::Perms,
+    Column::Owner,
+    Column::Group,
+    Column::Device,
+    Column::Inode,
+    Column::Cloud,
+    Column::Tier,
+    Column::Path,
+];
+
+impl Column {
+    /// Name accepted by `--columns`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Size => "size",
+            Column::Modified => "mtime",
+            Column::Age => "age",
+            Column::Score => "score",
+            Column::Type => "type",
+            Column::Mime => "mime",
+            Column::Ratio => "ratio",
+            Column::Disk => "disk",
+            Column::Hash => "hash",
+            Column::Perms => "perms",
+            Column::Owner => "owner",
+            Column::Group => "group",
+            Column::Device => "device",
+            Column::Inode => "inode",
+            Column::Cloud => "cloud",
+            Column::Tier => "tier",
+            Column::Path => "path",
+        }
+    }
+
+    /// Field holding this column in JSON reports.
+    pub fn json_key(self) -> &'static str {
+        match self {
+            Column::Size => "size_bytes",
+            Column::Modified => "modified",
+            Column::Age => "age",
+            Column::Score => "score",
+            Column::Type => "file_type",
+            Column::Mime => "mime",
+            Column::Ratio => "compression_ratio",
+            Column::Disk => "percent_of_disk",
+            Column::Hash => "hash",
+            Column::Perms => "permissions",
+            Column::Owner => "owner",
+            Column::Group => "group",
+            Column::Device => "device",
+            Column::Inode => "inode",
+            Column::Cloud => "placeholder",
+            Column::Tier => "tier",
+            Column::Path => "path",
+        }
+    }
+
+    /// Title of this column in the table.
+    pub fn header(self, unit: SizeUnit) -> String {
+        match self {
+            Column::Size => size_header("Size", unit),
+            Column::Modified => "Modified".to_string(),
+            Column::Age => "Age".to_string(),
+            Column::Score => "Score".to_string(),
+            Column::Type => "Type".to_string(),
+            Column::Mime => "MIME".to_string(),
+            Column::Ratio => "Ratio".to_string(),
+            Column::Disk => "% Disk".to_string(),
+            Column::Hash => "Hash".to_string(),
+            Column::Perms => "Perms".to_string(),
+            Column::Owner => "Owner".to_string(),
+            Column::Group => "Group".to_string(),
+            Column::Device => "Device".to_string(),
+            Column::Inode => "Inode".to_string(),
+            Column::Cloud => "Cloud".to_string(),
+            Column::Tier => "Tier".to_string(),
+            Column::Path => "Path".to_string(),
+        }
+    }
+
+    /// This column's cell for `file`, or None when the detail was not gathered.
+    pub fn cell(self, file: &FileInfo, unit: SizeUnit) -> Option<String> {
+        match self {
+            Column::Size => Some(size_cell(file.size_bytes, unit)),
+            Column::Modified => file.modified.map(stat::iso),
+            Column::Age => file
+                .modified
+                .map(|modified| stat::ago(modified, SystemTime::now())),
+            Column::Score => file.score.map(|score| format!("{:.1}", score)),
+            Column::Type => file.file_type.clone(),
+            Column::Mime => file.mime.clone(),
+            Column::Ratio => file.compression_ratio.map(|ratio| format!("{:.2}x", ratio)),
+            Column::Disk => file
+                .percent_of_disk
+                .map(|percent| format!("{:.1}%", percent)),
+            Column::Hash => file.hash.clone(),
+            Column::Perms => file.permissions.clone(),
+            Column::Owner => file.owner.clone(),
+            Column::Group => file.group.clone(),
+            Column::Device => file.device.map(|device| device.to_string()),
+            Column::Inode => file.inode.map(|inode| inode.to_string()),
+            Column::Cloud => file
+                .placeholder
+                .map(|placeholder| if placeholder { "online-only" } else { "local" }.to_string()),
+            Column::Tier => file.tier.map(|tier| tier.name().to_string()),
+            Column::Path => Some(file.path.display().to_string()),
+        }
+    }
+}
+
+/// Parse the comma-separated list given to `--columns`.
+pub fn parse(list: &str) -> Result<Vec<Column>, String> {
+    let mut columns = Vec::new();
+    for name in list.split(',').map(str::trim) {
+        let column = ALL
+            .into_iter()
+            .find(|column| column.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = ALL.iter().map(|column| column.name()).collect();
+                format!("unknown column {:?} (expected {})", name, names.join(", "))
+            })?;
+        if columns.contains(&column) {
+            return Err(format!("column {:?} is listed twice", name));
+        }
+        columns.push(column);
+    }
+    Ok(columns)
+}
+
+/// A match written with only the chosen fields, in the order they were listed.
+pub struct Row<'a> {
+    pub file: &'a FileInfo,
+    pub columns: &'a [Column],
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let file = self.file;
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for &column in self.columns {
+            let key = column.json_key();
+            match column {
+                Column::Size => map.serialize_entry(key, &file.size_bytes)?,
+                Column::Modified => map.serialize_entry(key, &file.modified)?,
+                // The age has no raw form, so it is written as shown
+                Column::Age => map.serialize_entry(key, &column.cell(file, SizeUnit::B))?,
+                Column::Score => map.serialize_entry(key, &file.score)?,
+                Column::Type => map.serialize_entry(key, &file.file_type)?,
+                Column::Mime => map.serialize_entry(key, &file.mime)?,
+                Column::Ratio => map.serialize_entry(key, &file.compression_ratio)?,
+                Column::Disk => map.serialize_entry(key, &file.percent_of_disk)?,
+                Column::Hash => map.serialize_entry(key, &file.hash)?,
+                Column::Perms => map.serialize_entry(key, &file.permissions)?,
+                Column::Owner => map.serialize_entry(key, &file.owner)?,
+                Column::Group => map.serialize_entry(key, &file.group)?,
+                Column::Device => map.serialize_entry(key, &file.device)?,
+                Column::Inode => map.serialize_entry(key, &file.inode)?,
+                Column::Cloud => map.serialize_entry(key, &file.placeholder)?,
+                Column::Tier => map.serialize_entry(key, &file.tier)?,
+                Column::Path => map.serialize_entry(key, &file.path)?,
+            }
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("hash, size,path"),
+            Ok(vec![Column::Hash, Column::Size, Column::Path])
+        );
+        assert!(parse("size,uid").is_err());
+        assert!(parse("size,size").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn test_cells() {
+        let mut file = FileInfo::new("/data/a.bin", 3 * 1024 * 1024);
+        file.compression_ratio = Some(2.5);
+        assert_eq!(
+            Column::Size.cell(&file, SizeUnit::MiB).as_deref(),
+            Some("3.00")
+        );
+        assert_eq!(
+            Column::Size.cell(&file, SizeUnit::Auto).as_deref(),
+            Some("3.00 MiB")
+        );
+        assert_eq!(
+            Column::Ratio.cell(&file, SizeUnit::MiB).as_deref(),
+            Some("2.50x")
+        );
+        assert_eq!(Column::Hash.cell(&file, SizeUnit::MiB), None);
+        assert_eq!(Column::Size.header(SizeUnit::GiB), "Size (GiB)");
+    }
+
+    #[test]
+    fn test_row_keeps_listed_order() {
+        let file = FileInfo::new("/data/a.bin", 42);
+        let columns = [Column::Path, Column::Size, Column::Hash];
+        let row = Row {
+            file: &file,
+            columns: &columns,
+        };
+        assert_eq!(
+            serde_json::to_string(&row).unwrap(),
+            r#"{"path":"/data/a.bin","size_bytes":42,"hash":null}"#
+        );
+    }
+}
@@ -0,0 +1,229 @@
+// Import rayon to sample the matches in parallel
+use rayon::prelude::*;
+// Import FileInfo for the matches that receive an estimate
+use list_big_files::FileInfo;
+// Import GzEncoder for --compress gzip
+use flate2::write::GzEncoder;
+// Import MatcherType to recognise formats that are compressed already
+use infer::MatcherType;
+// Import fs and io for reading samples and writing compressed copies
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+// Import Path for the file being sampled or compressed
+use std::path::Path;
+
+// Bytes read from each sampled region of a file
+const SAMPLE_BYTES: u64 = 1024 * 1024;
+// zstd's default level, a fair stand-in for compressing the file in place
+const LEVEL: i32 = 3;
+
+/// Format used by `--compress`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Zstd,
+    Gzip,
+}
+
+impl Format {
+    /// Extension appended to compressed files.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Zstd => "zst",
+            Format::Gzip => "gz",
+        }
+    }
+}
+
+/// Compress the file at `from` into a new file at `to`, which must not exist
+/// yet, copying its permissions and modification time. Returns the compressed
+/// size; on failure no partial `to` is left behind. `from` is kept.
+pub fn compress_file(from: &Path, to: &Path, format: Format) -> io::Result<u64> {
+    let metadata = fs::metadata(from)?;
+    let output = File::options().write(true).create_new(true).open(to)?;
+    let result = write_compressed(from, output, format).and_then(|output| {
+        output.set_permissions(metadata.permissions())?;
+        output.set_modified(metadata.modified()?)?;
+        output.sync_all()?;
+        Ok(output.metadata()?.len())
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(to);
+    }
+    result
+}
+
+// Stream the contents of `from` through the encoder into `output`
+fn write_compressed(from: &Path, output: File, format: Format) -> io::Result<File> {
+    let mut input = BufReader::new(File::open(from)?);
+    match format {
+        Format::Zstd => {
+            let mut encoder = zstd::Encoder::new(output, LEVEL)?;
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()
+        }
+        Format::Gzip => {
+            let mut encoder = GzEncoder::new(output, flate2::Compression::default());
+            io::copy(&mut input, &mut encoder)?;
+            let mut output = encoder.finish()?;
+            output.flush()?;
+            Ok(output)
+        }
+    }
+}
+
+/// Whether the file at `path` holds data that is compressed already, such as
+/// archives, video or most image and audio formats, going by its leading bytes.
+pub fn already_compressed(path: &Path) -> bool {
+    match infer::get_from_path(path) {
+        Ok(Some(kind)) => match kind.matcher_type() {
+            MatcherType::Archive => kind.extension() != "tar",
+            MatcherType::Video => true,
+            MatcherType::Audio => !matches!(kind.extension(), "wav" | "aiff"),
+            MatcherType::Image => !matches!(kind.extension(), "bmp" | "tif"),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Set the estimated compression ratio of every file in `files`. Files that
+/// cannot be read are left without one.
+pub fn add_estimates(files: &mut [FileInfo]) {
+    files.par_iter_mut().for_each(|file| {
+        file.compression_ratio = estimate(&file.path, file.size_bytes).ok();
+    });
+}
+
+/// Estimate how many times smaller zstd would make the file at `path` by
+/// compressing samples from its start, middle and end in memory.
+pub fn estimate(path: &Path, size_bytes: u64) -> io::Result<f64> {
+    let mut file = File::open(path)?;
+    let mut original = 0;
+    let mut compressed = 0;
+    for offset in sample_offsets(size_bytes) {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut sample = Vec::new();
+        (&mut file).take(SAMPLE_BYTES).read_to_end(&mut sample)?;
+        original += sample.len();
+        compressed += zstd::bulk::compress(&sample, LEVEL)?.len();
+    }
+    if original == 0 {
+        return Ok(1.0);
+    }
+    Ok(original as f64 / compressed as f64)
+}
+
+/// Bytes compression would save across `files`, from their estimates.
+pub fn estimated_savings(files: &[FileInfo]) -> u64 {
+    files
+        .iter()
+        .filter_map(|file| {
+            let ratio = file.compression_ratio?;
+            Some(file.size_bytes - (file.size_bytes as f64 / ratio.max(1.0)) as u64)
+        })
+        .sum()
+}
+
+// Where samples start; small files are read whole
+fn sample_offsets(size_bytes: u64) -> Vec<u64> {
+    if size_bytes <= 3 * SAMPLE_BYTES {
+        return vec![0];
+    }
+    vec![
+        0,
+        size_bytes / 2 - SAMPLE_BYTES / 2,
+        size_bytes - SAMPLE_BYTES,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_estimate_tells_text_from_random_data() {
+        let dir = tempdir().unwrap();
+        let text = dir.path().join("log");
+        fs::write(&text, "GET /index.html 200\n".repeat(500_000)).unwrap();
+        let noise = dir.path().join("noise");
+        let mut state = 0x2545f4914f6cdd1du64;
+        let random: Vec<u8> = (0..4_000_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        fs::write(&noise, random).unwrap();
+
+        assert!(estimate(&text, 10_000_000).unwrap() > 10.0);
+        assert!(estimate(&noise, 4_000_000).unwrap() < 1.1);
+    }
+
+    #[test]
+    fn test_sample_offsets() {
+        assert_eq!(sample_offsets(100), vec![0]);
+        let size = 10 * SAMPLE_BYTES;
+        assert_eq!(
+            sample_offsets(size),
+            vec![0, 4 * SAMPLE_BYTES + SAMPLE_BYTES / 2, 9 * SAMPLE_BYTES]
+        );
+    }
+
+    #[test]
+    fn test_compress_file_round_trip() {
+        let dir = tempdir().unwrap();
+        let original = dir.path().join("app.log");
+        let text = "GET /index.html 200\n".repeat(10_000);
+        fs::write(&original, &text).unwrap();
+
+        let zst = dir.path().join("app.log.zst");
+        let size = compress_file(&original, &zst, Format::Zstd).unwrap();
+        assert_eq!(size, fs::metadata(&zst).unwrap().len());
+        assert!(size < text.len() as u64 / 10);
+        assert_eq!(
+            zstd::decode_all(File::open(&zst).unwrap()).unwrap(),
+            text.as_bytes()
+        );
+
+        let gz = dir.path().join("app.log.gz");
+        compress_file(&original, &gz, Format::Gzip).unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(File::open(&gz).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, text);
+        assert!(original.exists());
+
+        // Existing files are never overwritten
+        assert!(compress_file(&original, &gz, Format::Gzip).is_err());
+        assert!(gz.exists());
+    }
+
+    #[test]
+    fn test_already_compressed() {
+        let dir = tempdir().unwrap();
+        let gz = dir.path().join("a");
+        fs::write(&gz, b"\x1f\x8b\x08\x00\x00\x00\x00\x00").unwrap();
+        assert!(already_compressed(&gz));
+
+        let text = dir.path().join("b");
+        fs::write(&text, "plain text").unwrap();
+        assert!(!already_compressed(&text));
+    }
+
+    #[test]
+    fn test_estimated_savings() {
+        let mut text = FileInfo::new("/a", 1000);
+        text.compression_ratio = Some(4.0);
+        let mut random = FileInfo::new("/b", 1000);
+        random.compression_ratio = Some(0.99);
+        assert_eq!(
+            estimated_savings(&[text, random, FileInfo::new("/c", 1000)]),
+            750
+        );
+    }
+}
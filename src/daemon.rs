@@ -0,0 +1,270 @@
+// Import fs for writing and pruning report files
+use std::fs;
+// Import io for report file errors
+use std::io;
+// Import Path and PathBuf for the report directory
+use std::path::{Path, PathBuf};
+// Import atomics for stopping on Ctrl+C
+use std::sync::atomic::{AtomicBool, Ordering};
+// Import thread for sleeping between scans
+use std::thread;
+// Import time types for the schedule and report names
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Longest single sleep, so Ctrl+C is noticed promptly between scans
+const SLEEP_STEP: Duration = Duration::from_millis(200);
+
+// Prefix and suffix of report files, so rotation never touches other files
+const REPORT_PREFIX: &str = "scan-";
+const REPORT_SUFFIX: &str = ".json";
+
+// Month abbreviations of HTTP dates
+#[cfg(any(feature = "azure", feature = "webdav"))]
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Call `cycle` immediately and then every `interval` until `stop` is set.
+/// Scans that overrun the interval are followed by the next one right away.
+pub fn run<F>(interval: Duration, stop: &AtomicBool, mut cycle: F)
+where
+    F: FnMut(),
+{
+    while !stop.load(Ordering::SeqCst) {
+        let started = Instant::now();
+        cycle();
+
+        let next = started + interval;
+        while !stop.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            if now >= next {
+                break;
+            }
+            thread::sleep((next - now).min(SLEEP_STEP));
+        }
+    }
+}
+
+/// UTC time formatted as ISO 8601, e.g. `2024-05-01T12:00:00Z`.
+pub fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+// Convert days since 1970-01-01 to a (year, month, day) date, using Howard
+// Hinnant's algorithm for the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date, the inverse of
+/// `civil_from_days`.
+#[cfg(any(feature = "s3", feature = "azure", feature = "gcs", feature = "webdav"))]
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Seconds since the Unix epoch of a UTC RFC 3339 time such as
+/// `2024-05-01T12:00:00.000Z`.
+#[cfg(any(feature = "s3", feature = "gcs"))]
+pub fn parse_rfc3339(time: &str) -> Option<u64> {
+    let number = |range: std::ops::Range<usize>| time.get(range)?.parse::<u32>().ok();
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    let days = days_from_civil(i64::from(year), month, day);
+    let secs = days * 86_400 + i64::from(hour * 3600 + minute * 60 + second);
+    u64::try_from(secs).ok()
+}
+
+/// Seconds since the Unix epoch of an HTTP date, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`.
+#[cfg(any(feature = "azure", feature = "webdav"))]
+pub fn parse_http_date(date: &str) -> Option<u64> {
+    let mut fields = date.split_whitespace().skip(1);
+    let day: u32 = fields.next()?.parse().ok()?;
+    let month_name = fields.next()?;
+    let month = MONTHS.iter().position(|month| *month == month_name)? as u32 + 1;
+    let year: i64 = fields.next()?.parse().ok()?;
+    let mut time = fields
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(secs).ok()
+}
+
+/// UTC time formatted as an HTTP date, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+#[cfg(feature = "azure")]
+pub fn http_date(time: SystemTime) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let days = (secs / 86_400) as i64;
+    let (year, month, day) = civil_from_days(days);
+    let rem = secs % 86_400;
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[days.rem_euclid(7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Path of the report written at `time` in `dir`. Names sort chronologically.
+pub fn report_path(dir: &Path, time: SystemTime) -> PathBuf {
+    // Colons are not allowed in Windows file names
+    let stamp: String = timestamp(time)
+        .chars()
+        .filter(|c| *c != '-' && *c != ':')
+        .collect();
+    dir.join(format!("{}{}{}", REPORT_PREFIX, stamp, REPORT_SUFFIX))
+}
+
+/// Delete all but the newest `keep` reports in `dir`, returning the removed paths.
+pub fn rotate(dir: &Path, keep: usize) -> io::Result<Vec<PathBuf>> {
+    let mut reports: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(REPORT_PREFIX) && name.ends_with(REPORT_SUFFIX)
+                })
+        })
+        .collect();
+    reports.sort();
+
+    let excess = reports.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = reports.drain(..excess).collect();
+    for path in &removed {
+        fs::remove_file(path)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(timestamp(time), "2024-02-29T12:34:56Z");
+    }
+
+    #[cfg(any(feature = "s3", feature = "azure", feature = "gcs", feature = "webdav"))]
+    #[test]
+    fn test_days_from_civil() {
+        for days in [-719_468, -1, 0, 19_782, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[cfg(any(feature = "s3", feature = "gcs"))]
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00.000Z"), Some(0));
+        assert_eq!(
+            parse_rfc3339("2024-02-29T12:30:15.000Z"),
+            Some(1_709_209_815)
+        );
+        assert_eq!(parse_rfc3339("yesterday"), None);
+    }
+
+    #[cfg(any(feature = "azure", feature = "webdav"))]
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777)
+        );
+        assert_eq!(parse_http_date("yesterday"), None);
+    }
+
+    #[cfg(feature = "azure")]
+    #[test]
+    fn test_http_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        assert_eq!(http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+    }
+
+    #[test]
+    fn test_report_path() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(
+            report_path(Path::new("reports"), time),
+            Path::new("reports").join("scan-20240229T123456Z.json")
+        );
+    }
+
+    #[test]
+    fn test_rotate_keeps_newest_reports() {
+        let dir = tempdir().unwrap();
+        for name in [
+            "scan-20240101T000000Z.json",
+            "scan-20240103T000000Z.json",
+            "scan-20240102T000000Z.json",
+            "notes.txt",
+        ] {
+            fs::write(dir.path().join(name), "{}").unwrap();
+        }
+
+        let removed = rotate(dir.path(), 2).unwrap();
+        assert_eq!(removed, vec![dir.path().join("scan-20240101T000000Z.json")]);
+        assert!(dir.path().join("notes.txt").exists());
+        assert!(dir.path().join("scan-20240103T000000Z.json").exists());
+    }
+
+    #[test]
+    fn test_run_stops_when_flag_is_set() {
+        let stop = AtomicBool::new(false);
+        let mut cycles = 0;
+        run(Duration::from_millis(1), &stop, || {
+            cycles += 1;
+            if cycles == 3 {
+                stop.store(true, Ordering::SeqCst);
+            }
+        });
+        assert_eq!(cycles, 3);
+    }
+}
@@ -0,0 +1,173 @@
+// Only Linux lists open files under /proc
+#![cfg_attr(not(target_os = "linux"), allow(dead_code, unused_imports))]
+
+// Import serialize_path so paths that are not valid Unicode still serialize
+use list_big_files::serialize_path;
+// Import serde for JSON output of the files
+use serde::Serialize;
+// Import Reverse for listing the largest files first
+use std::cmp::Reverse;
+// Import HashMap for files held open more than once
+use std::collections::HashMap;
+// Import fs and io for reading /proc
+use std::fs;
+use std::io;
+// Import Path types for where the files used to be
+use std::path::{Path, PathBuf};
+
+/// A file that was deleted while processes still hold it open, so its space
+/// is only freed once they close it or exit.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DeletedFile {
+    /// Where the file was before it was deleted.
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub holders: Vec<Holder>,
+}
+
+/// A process holding a deleted file open, and the descriptor it holds it by.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Holder {
+    pub pid: u32,
+    pub command: String,
+    pub fd: u32,
+}
+
+/// Deleted files of at least `min_size_bytes` that were below `root` and
+/// are still open, largest first, and how many processes could not be
+/// looked at, usually because they belong to other users.
+#[cfg(target_os = "linux")]
+pub fn find(root: &Path, min_size_bytes: u64) -> io::Result<(Vec<DeletedFile>, usize)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let root = fs::canonicalize(root)?;
+    let mut files: HashMap<(u64, u64), DeletedFile> = HashMap::new();
+    let mut unreadable = 0;
+    for entry in fs::read_dir("/proc")? {
+        let entry = entry?;
+        let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+            continue;
+        };
+        // Processes that exited since /proc was listed are simply gone
+        let fds = match fs::read_dir(entry.path().join("fd")) {
+            Ok(fds) => fds,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(_) => {
+                unreadable += 1;
+                continue;
+            }
+        };
+        let command = fs::read_to_string(entry.path().join("comm"))
+            .map(|command| command.trim_end().to_string())
+            .unwrap_or_default();
+        for fd in fds.flatten() {
+            let Some(number) = fd.file_name().to_str().and_then(|fd| fd.parse().ok()) else {
+                continue;
+            };
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(path) = deleted_path(&target) else {
+                continue;
+            };
+            // The descriptor still reaches the file; no links left means it
+            // really is deleted rather than named "... (deleted)"
+            let Ok(meta) = fs::metadata(fd.path()) else {
+                continue;
+            };
+            if !meta.is_file()
+                || meta.nlink() != 0
+                || meta.len() < min_size_bytes
+                || !path.starts_with(&root)
+            {
+                continue;
+            }
+            files
+                .entry((meta.dev(), meta.ino()))
+                .or_insert_with(|| DeletedFile {
+                    path,
+                    size_bytes: meta.len(),
+                    holders: Vec::new(),
+                })
+                .holders
+                .push(Holder {
+                    pid,
+                    command: command.clone(),
+                    fd: number,
+                });
+        }
+    }
+    let mut files: Vec<DeletedFile> = files.into_values().collect();
+    for file in &mut files {
+        file.holders.sort_by_key(|holder| (holder.pid, holder.fd));
+    }
+    files.sort_by(|a, b| {
+        Reverse(a.size_bytes)
+            .cmp(&Reverse(b.size_bytes))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok((files, unreadable))
+}
+
+/// Other systems have no /proc to look in; --deleted-open is refused there.
+#[cfg(not(target_os = "linux"))]
+pub fn find(_root: &Path, _min_size_bytes: u64) -> io::Result<(Vec<DeletedFile>, usize)> {
+    Ok((Vec::new(), 0))
+}
+
+// Where a descriptor's file was, if its link says it has been deleted.
+// memfd files are memory rather than disk space, so they are left out
+#[cfg(target_os = "linux")]
+fn deleted_path(target: &Path) -> Option<PathBuf> {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = target.as_os_str().as_bytes().strip_suffix(b" (deleted)")?;
+    (path.starts_with(b"/") && !path.starts_with(b"/memfd:"))
+        .then(|| PathBuf::from(OsStr::from_bytes(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_deleted_path() {
+        assert_eq!(
+            deleted_path(Path::new("/var/log/app.log (deleted)")),
+            Some(PathBuf::from("/var/log/app.log"))
+        );
+        assert_eq!(deleted_path(Path::new("/var/log/app.log")), None);
+        assert_eq!(deleted_path(Path::new("/memfd:buffer (deleted)")), None);
+        assert_eq!(deleted_path(Path::new("socket:[1234]")), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_find_open_deleted_file() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("held.log");
+        let mut held = fs::File::create(&path).unwrap();
+        held.write_all(&[0; 4096]).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (files, _) = find(dir.path(), 1024).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].path,
+            fs::canonicalize(dir.path()).unwrap().join("held.log")
+        );
+        assert_eq!(files[0].size_bytes, 4096);
+        assert!(files[0]
+            .holders
+            .iter()
+            .any(|holder| holder.pid == std::process::id()));
+        assert!(find(dir.path(), 8192).unwrap().0.is_empty());
+        drop(held);
+        assert!(find(dir.path(), 1024).unwrap().0.is_empty());
+    }
+}
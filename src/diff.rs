@@ -0,0 +1,194 @@
+// Import the size column helpers shared with the scan table
+use crate::{size_cell, size_header};
+// Import the size unit and match type shared with the scanner
+use list_big_files::{FileInfo, SizeUnit};
+// Import Reverse for listing the biggest differences first
+use std::cmp::Reverse;
+// Import HashMap for matching files between the two scans by path
+use std::collections::HashMap;
+// Import Path types for the files compared
+use std::path::{Path, PathBuf};
+
+/// A file present in both scans whose size changed.
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub path: PathBuf,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+impl Change {
+    // Absolute size difference
+    fn delta(&self) -> u64 {
+        self.old_size.abs_diff(self.new_size)
+    }
+}
+
+/// Differences between two scans.
+#[derive(Debug, Default, PartialEq)]
+pub struct Diff {
+    pub added: Vec<FileInfo>,
+    pub removed: Vec<FileInfo>,
+    pub grown: Vec<Change>,
+    pub shrunk: Vec<Change>,
+}
+
+impl Diff {
+    /// Whether the scans found the same files with similar sizes.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.grown.is_empty()
+            && self.shrunk.is_empty()
+    }
+}
+
+/// Compare the matches of two scans. Files whose size changed by less than
+/// `delta_bytes` are not reported as grown or shrunk.
+pub fn compare(old: &[FileInfo], new: &[FileInfo], delta_bytes: u64) -> Diff {
+    let mut old_sizes: HashMap<&Path, u64> = old
+        .iter()
+        .map(|file| (file.path.as_path(), file.size_bytes))
+        .collect();
+    let mut diff = Diff::default();
+
+    for file in new {
+        let Some(old_size) = old_sizes.remove(file.path.as_path()) else {
+            diff.added.push(file.clone());
+            continue;
+        };
+        let change = Change {
+            path: file.path.clone(),
+            old_size,
+            new_size: file.size_bytes,
+        };
+        if change.delta() == 0 || change.delta() < delta_bytes {
+            continue;
+        }
+        if change.new_size > change.old_size {
+            diff.grown.push(change);
+        } else {
+            diff.shrunk.push(change);
+        }
+    }
+
+    diff.removed = old
+        .iter()
+        .filter(|file| old_sizes.contains_key(file.path.as_path()))
+        .cloned()
+        .collect();
+
+    diff.added.sort_by_key(|f| Reverse(f.size_bytes));
+    diff.removed.sort_by_key(|f| Reverse(f.size_bytes));
+    diff.grown.sort_by_key(|c| Reverse(c.delta()));
+    diff.shrunk.sort_by_key(|c| Reverse(c.delta()));
+    diff
+}
+
+/// Print each non-empty section of `diff` followed by a one-line summary.
+pub fn print(diff: &Diff, unit: SizeUnit) {
+    if diff.is_empty() {
+        println!("No differences");
+        return;
+    }
+
+    if !diff.added.is_empty() {
+        println!("{}:", size_header("New files", unit));
+        for file in &diff.added {
+            println!(
+                "  + {:>14}  {}",
+                size_cell(file.size_bytes, unit),
+                file.path.display()
+            );
+        }
+        println!();
+    }
+    if !diff.removed.is_empty() {
+        println!("{}:", size_header("Removed files", unit));
+        for file in &diff.removed {
+            println!(
+                "  - {:>14}  {}",
+                size_cell(file.size_bytes, unit),
+                file.path.display()
+            );
+        }
+        println!();
+    }
+    for (title, sign, changes) in [("Grown", '+', &diff.grown), ("Shrunk", '-', &diff.shrunk)] {
+        if changes.is_empty() {
+            continue;
+        }
+        println!("{}:", size_header(&format!("{} files", title), unit));
+        for change in changes {
+            println!(
+                "  {} {:>14}  {}  ({} -> {})",
+                sign,
+                size_cell(change.delta(), unit),
+                change.path.display(),
+                size_cell(change.old_size, unit),
+                size_cell(change.new_size, unit)
+            );
+        }
+        println!();
+    }
+
+    println!(
+        "Diff: {} new, {} removed, {} grown, {} shrunk",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.grown.len(),
+        diff.shrunk.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size_bytes: u64) -> FileInfo {
+        FileInfo::new(path.to_string(), size_bytes)
+    }
+
+    #[test]
+    fn test_compare_added_and_removed() {
+        let old = [file("a", 100), file("b", 200)];
+        let new = [file("b", 200), file("c", 300)];
+        let diff = compare(&old, &new, 0);
+        assert_eq!(diff.added, vec![file("c", 300)]);
+        assert_eq!(diff.removed, vec![file("a", 100)]);
+        assert!(diff.grown.is_empty());
+        assert!(diff.shrunk.is_empty());
+    }
+
+    #[test]
+    fn test_compare_size_changes() {
+        let old = [file("a", 100), file("b", 200), file("c", 300)];
+        let new = [file("a", 150), file("b", 100), file("c", 300)];
+        let diff = compare(&old, &new, 0);
+        assert_eq!(
+            diff.grown,
+            vec![Change {
+                path: PathBuf::from("a"),
+                old_size: 100,
+                new_size: 150
+            }]
+        );
+        assert_eq!(diff.shrunk.len(), 1);
+        assert_eq!(diff.shrunk[0].path, Path::new("b"));
+    }
+
+    #[test]
+    fn test_compare_ignores_changes_below_delta() {
+        let old = [file("a", 100), file("b", 100)];
+        let new = [file("a", 120), file("b", 200)];
+        let diff = compare(&old, &new, 50);
+        assert_eq!(diff.grown.len(), 1);
+        assert_eq!(diff.grown[0].path, Path::new("b"));
+    }
+
+    #[test]
+    fn test_compare_identical_scans_is_empty() {
+        let files = [file("a", 100), file("b", 200)];
+        assert!(compare(&files, &files, 0).is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+// Import serde for the volume details in JSON reports
+use serde::{Deserialize, Serialize};
+// Import FileInfo for the matches given a share of their volume
+use list_big_files::FileInfo;
+// Import HashMap to query each volume once
+use std::collections::HashMap;
+// Import io for errors querying a volume
+use std::io;
+// Import Path for the directory whose volume is queried
+use std::path::Path;
+
+/// Capacity and free space of the volume a scan ran on.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Volume {
+    pub total_bytes: u64,
+    /// Space available to this user, which may be less than the space free
+    /// on volumes that keep some back for root.
+    pub available_bytes: u64,
+}
+
+impl Volume {
+    /// Share of the volume's capacity `size_bytes` takes up, in percent.
+    pub fn percent_of_total(&self, size_bytes: u64) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        size_bytes as f64 * 100.0 / self.total_bytes as f64
+    }
+}
+
+/// Capacity and free space of the volume holding `path`, read with statvfs on
+/// Unix and GetDiskFreeSpaceEx on Windows.
+pub fn volume(path: &Path) -> io::Result<Volume> {
+    let stats = fs4::statvfs(path)?;
+    Ok(Volume {
+        total_bytes: stats.total_space(),
+        available_bytes: stats.available_space(),
+    })
+}
+
+/// Set each file's share of the capacity of the volume it is on, which
+/// need not be the scanned directory's when other volumes are mounted below it.
+pub fn add_disk_shares(files: &mut [FileInfo]) {
+    let mut volumes: HashMap<String, Option<Volume>> = HashMap::new();
+    for file in files {
+        let path = &file.path;
+        let Some(key) = volume_key(path) else {
+            continue;
+        };
+        let volume = *volumes.entry(key).or_insert_with(|| volume(path).ok());
+        file.percent_of_disk = volume.map(|volume| volume.percent_of_total(file.size_bytes));
+    }
+}
+
+// Identify the volume holding `path`: its device number on Unix
+#[cfg(unix)]
+fn volume_key(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path)
+        .ok()
+        .map(|meta| meta.dev().to_string())
+}
+
+// Identify the volume holding `path`: its drive or share elsewhere
+#[cfg(not(unix))]
+fn volume_key(path: &Path) -> Option<String> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let root = path.components().next()?;
+    Some(root.as_os_str().to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_volume_of_temp_dir() {
+        let dir = tempdir().unwrap();
+        let volume = volume(dir.path()).unwrap();
+        assert!(volume.total_bytes > 0);
+        assert!(volume.available_bytes <= volume.total_bytes);
+    }
+
+    #[test]
+    fn test_percent_of_total() {
+        let volume = Volume {
+            total_bytes: 64,
+            available_bytes: 8,
+        };
+        assert_eq!(volume.percent_of_total(16), 25.0);
+        let empty = Volume {
+            total_bytes: 0,
+            available_bytes: 0,
+        };
+        assert_eq!(empty.percent_of_total(16), 0.0);
+    }
+
+    #[test]
+    fn test_add_disk_shares() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("big.bin");
+        std::fs::write(&path, vec![0; 4096]).unwrap();
+        let mut files = vec![
+            FileInfo::new(path, 4096),
+            FileInfo::new(dir.path().join("gone.bin"), 4096),
+        ];
+        add_disk_shares(&mut files);
+        let total = volume(dir.path()).unwrap().total_bytes;
+        assert_eq!(
+            files[0].percent_of_disk,
+            Some(4096.0 * 100.0 / total as f64)
+        );
+        assert_eq!(files[1].percent_of_disk, None);
+    }
+
+    #[test]
+    fn test_volume_of_missing_path() {
+        let dir = tempdir().unwrap();
+        assert!(volume(&dir.path().join("missing")).is_err());
+    }
+}
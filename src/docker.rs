@@ -0,0 +1,321 @@
+// Import the tar reader shared with --scan-archives
+use crate::archive;
+// Import FileInfo for the files found in each layer
+use list_big_files::FileInfo;
+// Import serde for the image manifest and JSON output of the layers
+use serde::{Deserialize, Serialize};
+// Import Reverse for listing the largest files first
+use std::cmp::Reverse;
+// Import HashMap for layers keyed by their name in the archive
+use std::collections::HashMap;
+// Import env for DOCKER_HOST
+use std::env;
+// Import fs and io for reading saved images and the daemon's responses
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+// Import Path for telling saved images from image names
+use std::path::Path;
+
+// Where the Docker daemon listens unless DOCKER_HOST says otherwise
+#[cfg(unix)]
+const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+/// A layer of an image and the largest files it adds or changes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Layer {
+    /// Position in the image, from the base layer up, starting at 1.
+    pub index: usize,
+    pub id: String,
+    /// Combined size of every file in the layer.
+    pub size_bytes: u64,
+    pub file_count: u64,
+    /// The layer's files of at least the threshold, largest first.
+    pub files: Vec<FileInfo>,
+}
+
+// The part of a saved image's manifest.json naming its layers, base first
+#[derive(Deserialize)]
+struct Manifest {
+    #[serde(rename = "Layers")]
+    layers: Vec<String>,
+}
+
+/// The layers of `image` with their files of at least `min_size_bytes`.
+/// `image` is either a file written by `docker save`, possibly compressed, or
+/// the name of an image the local Docker daemon has.
+pub fn layers(image: &str, min_size_bytes: u64) -> Result<Vec<Layer>, String> {
+    if Path::new(image).is_file() {
+        let mut file =
+            File::open(image).map_err(|err| format!("cannot open {}: {}", image, err))?;
+        return read_saved(&mut file, min_size_bytes)
+            .map_err(|err| format!("cannot read {}: {}", image, err));
+    }
+    let mut stream = export(image)?;
+    read_saved(&mut stream, min_size_bytes)
+        .map_err(|err| format!("cannot read image {}: {}", image, err))
+}
+
+// Read an image in the `docker save` format: a tar holding each layer as a
+// tar of its own, and a manifest.json listing them in order
+fn read_saved(saved: &mut dyn Read, min_size_bytes: u64) -> io::Result<Vec<Layer>> {
+    let mut manifest = None;
+    let mut found: HashMap<String, Layer> = HashMap::new();
+    archive::visit_any_tar(saved, |entry, contents| {
+        if entry.name == "manifest.json" {
+            let mut text = String::new();
+            contents.read_to_string(&mut text)?;
+            manifest = Some(text);
+        } else if entry.name.ends_with("/layer.tar") || entry.name.starts_with("blobs/") {
+            // Image configs are stored among the layers in newer archives;
+            // they are not tars, so they fail to read and are passed over
+            if let Ok(layer) = read_layer(contents, min_size_bytes) {
+                found.insert(entry.name, layer);
+            }
+        }
+        Ok(())
+    })?;
+
+    let manifest =
+        manifest.ok_or_else(|| invalid("no manifest.json, so not a docker save archive"))?;
+    let manifests: Vec<Manifest> = serde_json::from_str(&manifest)
+        .map_err(|err| invalid(&format!("bad manifest.json: {}", err)))?;
+    let manifest = manifests
+        .into_iter()
+        .next()
+        .ok_or_else(|| invalid("manifest.json lists no images"))?;
+    manifest
+        .layers
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let mut layer = found
+                .get(name)
+                .cloned()
+                .ok_or_else(|| invalid(&format!("layer {} is missing", name)))?;
+            layer.index = i + 1;
+            layer.id = layer_id(name);
+            Ok(layer)
+        })
+        .collect()
+}
+
+// Total the files of one layer, keeping those of at least `min_size_bytes`.
+// Whiteouts, which mark files deleted from lower layers, are left out.
+fn read_layer(contents: &mut dyn Read, min_size_bytes: u64) -> io::Result<Layer> {
+    let mut layer = Layer {
+        index: 0,
+        id: String::new(),
+        size_bytes: 0,
+        file_count: 0,
+        files: Vec::new(),
+    };
+    archive::visit_any_tar(contents, |member, _| {
+        let whiteout = Path::new(&member.name)
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with(".wh."));
+        if whiteout {
+            return Ok(());
+        }
+        layer.size_bytes += member.size_bytes;
+        layer.file_count += 1;
+        if member.size_bytes >= min_size_bytes {
+            let path = format!("/{}", member.name.trim_start_matches("./"));
+            layer.files.push(FileInfo::new(path, member.size_bytes));
+        }
+        Ok(())
+    })?;
+    layer.files.sort_by_key(|file| Reverse(file.size_bytes));
+    Ok(layer)
+}
+
+// A layer's id from its name in the archive: the digest of an OCI blob, or
+// the directory of an older layer.tar
+fn layer_id(name: &str) -> String {
+    match name.strip_prefix("blobs/") {
+        Some(blob) => blob.replacen('/', ":", 1),
+        None => name.trim_end_matches("/layer.tar").to_string(),
+    }
+}
+
+// Stream `image` from the Docker daemon as `docker save` would write it, over
+// the Unix socket or the TCP address in DOCKER_HOST
+fn export(image: &str) -> Result<Box<dyn Read>, String> {
+    let host = env::var("DOCKER_HOST").unwrap_or_default();
+    let path = format!("/images/{}/get", image);
+    if let Some(address) = host.strip_prefix("tcp://") {
+        let response = ureq::get(&format!("http://{}{}", address, path))
+            .call()
+            .map_err(|err| format!("cannot export {} from Docker: {}", image, err))?;
+        return Ok(Box::new(response.into_reader()));
+    }
+    export_from_socket(&host, image, &path)
+}
+
+#[cfg(unix)]
+fn export_from_socket(host: &str, image: &str, path: &str) -> Result<Box<dyn Read>, String> {
+    use std::io::{BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let socket = host.strip_prefix("unix://").unwrap_or(DEFAULT_SOCKET);
+    let mut stream = UnixStream::connect(socket)
+        .map_err(|err| format!("cannot connect to Docker at {}: {}", socket, err))?;
+    // HTTP/1.0 makes the daemon send the body as is and close the connection
+    write!(stream, "GET {} HTTP/1.0\r\nHost: docker\r\n\r\n", path)
+        .map_err(|err| format!("cannot talk to Docker: {}", err))?;
+    let mut response = BufReader::new(stream);
+    read_response_head(&mut response)
+        .map_err(|err| format!("cannot export {} from Docker: {}", image, err))?;
+    Ok(Box::new(response))
+}
+
+#[cfg(not(unix))]
+fn export_from_socket(_host: &str, image: &str, _path: &str) -> Result<Box<dyn Read>, String> {
+    Err(format!(
+        "cannot reach Docker here; save the image with `docker save -o image.tar {}` \
+         and pass image.tar, or set DOCKER_HOST to a tcp:// address",
+        image
+    ))
+}
+
+// Read an HTTP response's status line and headers, returning the daemon's
+// message when it did not succeed
+fn read_response_head(response: &mut impl BufRead) -> Result<(), String> {
+    let mut status = String::new();
+    response
+        .read_line(&mut status)
+        .map_err(|err| err.to_string())?;
+    let code = status.split_whitespace().nth(1).unwrap_or_default();
+    loop {
+        let mut line = String::new();
+        let read = response
+            .read_line(&mut line)
+            .map_err(|err| err.to_string())?;
+        if read == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+    if code == "200" {
+        return Ok(());
+    }
+    #[derive(Deserialize)]
+    struct Error {
+        message: String,
+    }
+    let mut body = String::new();
+    let _ = response.read_to_string(&mut body);
+    Err(serde_json::from_str::<Error>(&body)
+        .map(|error| error.message)
+        .unwrap_or_else(|_| format!("unexpected response {:?}", status.trim())))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::tests::{tar, tar_entry};
+    use std::io::Cursor;
+
+    fn saved_image(layer_names: [&str; 2], manifest: &str) -> Vec<u8> {
+        let base = tar(&[
+            tar_entry("usr/lib/big.so", b'0', &[0; 3000]),
+            tar_entry("etc/hosts", b'0', &[0; 10]),
+        ]);
+        let top = tar(&[
+            tar_entry("./app/model.bin", b'0', &[0; 5000]),
+            tar_entry("etc/.wh.hosts", b'0', b""),
+        ]);
+        tar(&[
+            tar_entry(layer_names[1], b'0', &top),
+            tar_entry(
+                "blobs/sha256/config",
+                b'0',
+                b"{\"architecture\": \"amd64\"}",
+            ),
+            tar_entry(layer_names[0], b'0', &base),
+            tar_entry("manifest.json", b'0', manifest.as_bytes()),
+        ])
+    }
+
+    fn summary(layers: &[Layer]) -> Vec<(usize, &str, u64, u64, Vec<&str>)> {
+        layers
+            .iter()
+            .map(|layer| {
+                (
+                    layer.index,
+                    layer.id.as_str(),
+                    layer.size_bytes,
+                    layer.file_count,
+                    layer
+                        .files
+                        .iter()
+                        .map(|file| file.path.to_str().unwrap())
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_saved_oci_layout() {
+        let bytes = saved_image(
+            ["blobs/sha256/aaa", "blobs/sha256/bbb"],
+            r#"[{"Config": "blobs/sha256/config", "Layers": ["blobs/sha256/aaa", "blobs/sha256/bbb"]}]"#,
+        );
+        let layers = read_saved(&mut Cursor::new(bytes), 1000).unwrap();
+        assert_eq!(
+            summary(&layers),
+            vec![
+                (1, "sha256:aaa", 3010, 2, vec!["/usr/lib/big.so"]),
+                (2, "sha256:bbb", 5000, 1, vec!["/app/model.bin"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_saved_legacy_layout() {
+        let bytes = saved_image(
+            ["111/layer.tar", "222/layer.tar"],
+            r#"[{"Config": "c.json", "Layers": ["111/layer.tar", "222/layer.tar"]}]"#,
+        );
+        let layers = read_saved(&mut Cursor::new(bytes), 4000).unwrap();
+        assert_eq!(
+            summary(&layers),
+            vec![
+                (1, "111", 3010, 2, vec![]),
+                (2, "222", 5000, 1, vec!["/app/model.bin"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_saved_errors() {
+        let no_manifest = tar(&[tar_entry("a.txt", b'0', b"hi")]);
+        assert!(read_saved(&mut Cursor::new(no_manifest), 0).is_err());
+        let missing = saved_image(
+            ["blobs/sha256/aaa", "blobs/sha256/bbb"],
+            r#"[{"Layers": ["blobs/sha256/ccc"]}]"#,
+        );
+        assert!(read_saved(&mut Cursor::new(missing), 0).is_err());
+    }
+
+    #[test]
+    fn test_read_response_head() {
+        let mut ok =
+            Cursor::new(b"HTTP/1.0 200 OK\r\nContent-Type: application/x-tar\r\n\r\nDATA".to_vec());
+        assert_eq!(read_response_head(&mut ok), Ok(()));
+        let mut rest = String::new();
+        ok.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "DATA");
+
+        let mut missing = Cursor::new(
+            b"HTTP/1.0 404 Not Found\r\n\r\n{\"message\":\"reference does not exist\"}".to_vec(),
+        );
+        assert_eq!(
+            read_response_head(&mut missing),
+            Err("reference does not exist".to_string())
+        );
+    }
+}
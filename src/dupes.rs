@@ -0,0 +1,242 @@
+// Import FileInfo for the matches checked for duplicates, and long_path for
+// files past MAX_PATH on Windows
+use list_big_files::{long_path, FileInfo};
+// Import rayon to hash candidates in parallel
+use rayon::prelude::*;
+// Import Reverse for listing the most wasteful sets first
+use std::cmp::Reverse;
+// Import HashMap for grouping by size and content
+use std::collections::HashMap;
+// Import fs and io for reading file contents
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+// Import Path types for opening the candidates and listing the copies
+use std::path::{Path, PathBuf};
+
+/// Files with identical contents.
+#[derive(Debug, PartialEq)]
+pub struct DuplicateSet {
+    pub size_bytes: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    /// Bytes freed by keeping a single copy.
+    pub fn reclaimable(&self) -> u64 {
+        self.size_bytes * (self.paths.len() as u64 - 1)
+    }
+}
+
+// Bytes read from each end of a file for the cheap first comparison
+const EDGE_BYTES: u64 = 64 * 1024;
+
+/// Group `files` into sets with identical contents, most reclaimable bytes
+/// first. Hard links to the same data count once since removing them frees
+/// nothing.
+///
+/// Candidates are narrowed in stages: only files of equal size have their
+/// first and last 64 KB hashed, and only files that still match are hashed
+/// in full, so most big files are never read end to end.
+pub fn find(files: &[FileInfo]) -> Vec<DuplicateSet> {
+    let mut by_size: HashMap<u64, Vec<&FileInfo>> = HashMap::new();
+    for file in files {
+        by_size.entry(file.size_bytes).or_default().push(file);
+    }
+    let groups: Vec<Vec<&FileInfo>> = by_size
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(distinct_inodes)
+        .collect();
+
+    let groups = refine(groups, hash_edges);
+    // Files no larger than both edges were already hashed in full
+    let groups = refine(groups, |file| {
+        if file.size_bytes <= 2 * EDGE_BYTES {
+            Ok(blake3::Hash::from([0; 32]))
+        } else {
+            hash_file(&file.path)
+        }
+    });
+
+    let mut sets: Vec<DuplicateSet> = groups
+        .into_iter()
+        .map(|group| {
+            let mut paths: Vec<PathBuf> = group.iter().map(|file| file.path.clone()).collect();
+            paths.sort();
+            DuplicateSet {
+                size_bytes: group[0].size_bytes,
+                paths,
+            }
+        })
+        .collect();
+    sets.sort_by(|a, b| {
+        Reverse(a.reclaimable())
+            .cmp(&Reverse(b.reclaimable()))
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    sets
+}
+
+// Split each group by `hash`, keeping only the parts with more than one file.
+// Unreadable files cannot be compared and are left out
+fn refine<F>(groups: Vec<Vec<&FileInfo>>, hash: F) -> Vec<Vec<&FileInfo>>
+where
+    F: Fn(&FileInfo) -> io::Result<blake3::Hash> + Sync,
+{
+    groups
+        .into_par_iter()
+        .flat_map_iter(|group| {
+            let mut by_hash: HashMap<blake3::Hash, Vec<&FileInfo>> = HashMap::new();
+            for file in group {
+                if let Ok(key) = hash(file) {
+                    by_hash.entry(key).or_default().push(file);
+                }
+            }
+            by_hash.into_values().filter(|group| group.len() > 1)
+        })
+        .collect()
+}
+
+// Keep one file per inode, so hard links are not reported as copies
+#[cfg(unix)]
+fn distinct_inodes(group: Vec<&FileInfo>) -> Vec<&FileInfo> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut seen = std::collections::HashSet::new();
+    group
+        .into_iter()
+        .filter(|file| match fs::metadata(&file.path) {
+            Ok(metadata) => seen.insert((metadata.dev(), metadata.ino())),
+            Err(_) => false,
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn distinct_inodes(group: Vec<&FileInfo>) -> Vec<&FileInfo> {
+    group
+}
+
+// Hash the first and last EDGE_BYTES of a file, or all of it if smaller
+fn hash_edges(file: &FileInfo) -> io::Result<blake3::Hash> {
+    let mut reader = File::open(long_path(&file.path))?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader((&mut reader).take(EDGE_BYTES))?;
+    if file.size_bytes > EDGE_BYTES {
+        let tail = EDGE_BYTES.min(file.size_bytes - EDGE_BYTES);
+        reader.seek(SeekFrom::End(-(tail as i64)))?;
+        hasher.update_reader(reader.take(tail))?;
+    }
+    Ok(hasher.finalize())
+}
+
+// Hash the full contents of the file at `path`
+fn hash_file(path: &Path) -> io::Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(long_path(path))?)?;
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(dir: &Path, name: &str, contents: &[u8]) -> FileInfo {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        FileInfo::new(path, contents.len() as u64)
+    }
+
+    #[test]
+    fn test_find_groups_identical_files() {
+        let dir = tempdir().unwrap();
+        let files = vec![
+            write(dir.path(), "a.iso", &[1; 1000]),
+            write(dir.path(), "b.iso", &[1; 1000]),
+            write(dir.path(), "c.iso", &[2; 1000]),
+            write(dir.path(), "d.iso", &[1; 1000]),
+            write(dir.path(), "e.bin", &[3; 10]),
+            write(dir.path(), "f.bin", &[3; 10]),
+        ];
+
+        let sets = find(&files);
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].size_bytes, 1000);
+        assert_eq!(sets[0].reclaimable(), 2000);
+        assert_eq!(
+            sets[0].paths,
+            vec![
+                files[0].path.clone(),
+                files[1].path.clone(),
+                files[3].path.clone()
+            ]
+        );
+        assert_eq!(sets[1].paths.len(), 2);
+        assert_eq!(sets[1].reclaimable(), 10);
+    }
+
+    #[test]
+    fn test_find_without_duplicates() {
+        let dir = tempdir().unwrap();
+        let files = vec![
+            write(dir.path(), "a", &[1; 100]),
+            write(dir.path(), "b", &[2; 100]),
+            write(dir.path(), "c", &[1; 50]),
+        ];
+        assert!(find(&files).is_empty());
+    }
+
+    #[test]
+    fn test_find_compares_middle_of_large_files() {
+        let dir = tempdir().unwrap();
+        let size = 4 * EDGE_BYTES as usize;
+        let mut changed = vec![7; size];
+        changed[size / 2] = 8;
+        let files = vec![
+            write(dir.path(), "a", &vec![7; size]),
+            write(dir.path(), "b", &vec![7; size]),
+            write(dir.path(), "c", &changed),
+        ];
+
+        let sets = find(&files);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(
+            sets[0].paths,
+            vec![files[0].path.clone(), files[1].path.clone()]
+        );
+    }
+
+    #[test]
+    fn test_hash_edges_reads_both_ends() {
+        let dir = tempdir().unwrap();
+        let size = 3 * EDGE_BYTES as usize;
+        let mut start = vec![0; size];
+        start[0] = 1;
+        let mut end = vec![0; size];
+        end[size - 1] = 1;
+        let mut middle = vec![0; size];
+        middle[size / 2] = 1;
+
+        let plain = hash_edges(&write(dir.path(), "plain", &vec![0; size])).unwrap();
+        assert_ne!(
+            hash_edges(&write(dir.path(), "start", &start)).unwrap(),
+            plain
+        );
+        assert_ne!(hash_edges(&write(dir.path(), "end", &end)).unwrap(), plain);
+        assert_eq!(
+            hash_edges(&write(dir.path(), "middle", &middle)).unwrap(),
+            plain
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_ignores_hard_links() {
+        let dir = tempdir().unwrap();
+        let original = write(dir.path(), "a", &[1; 100]);
+        fs::hard_link(&original.path, dir.path().join("b")).unwrap();
+        let link = FileInfo::new(dir.path().join("b"), 100);
+        assert!(find(&[original, link]).is_empty());
+    }
+}
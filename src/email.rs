@@ -0,0 +1,165 @@
+// Import the alert summary used as the subject line
+use crate::alert;
+// Import the saved scan format being mailed
+use crate::report::ScanReport;
+// Import lettre for building and delivering the message
+use lettre::message::{header::ContentType, Mailbox, Message};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{SmtpTransport, Transport};
+// Import SizeUnit for formatting sizes in the body
+use list_big_files::{display_size, NumberFormat, SizeUnit};
+// Import env for SMTP credentials, kept off the command line
+use std::env;
+
+/// Where and how a report is mailed.
+pub struct Mail<'o> {
+    /// `host` for STARTTLS on port 587, or an `smtp://` / `smtps://` URL.
+    pub server: &'o str,
+    pub from: &'o str,
+    pub to: &'o [String],
+}
+
+/// Build the message for `report`: a one-line summary as the subject and the
+/// result table as a plain-text body.
+pub fn message(mail: &Mail, report: &ScanReport, unit: SizeUnit) -> Result<Message, String> {
+    let from: Mailbox = mail
+        .from
+        .parse()
+        .map_err(|err| format!("invalid sender {:?}: {}", mail.from, err))?;
+    let mut builder = Message::builder()
+        .from(from)
+        .subject(alert::summary(report, unit));
+    for to in mail.to {
+        let to: Mailbox = to
+            .parse()
+            .map_err(|err| format!("invalid recipient {:?}: {}", to, err))?;
+        builder = builder.to(to);
+    }
+
+    let body = format!(
+        "{}\n\n{}\nTotal: {} files, {} (scanned {} files)\n",
+        alert::summary(report, unit),
+        crate::render_table(&report.files, unit, NumberFormat::PLAIN, &[], None),
+        report.files.len(),
+        display_size(report.total_bytes(), unit),
+        report.files_scanned
+    );
+    builder
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(|err| format!("cannot build email: {}", err))
+}
+
+/// Mail `report` through the configured SMTP server. Credentials, if needed,
+/// come from `LBF_SMTP_USERNAME` and `LBF_SMTP_PASSWORD`.
+pub fn send(mail: &Mail, report: &ScanReport, unit: SizeUnit) -> Result<(), String> {
+    let message = message(mail, report, unit)?;
+
+    let mut transport = if mail.server.contains("://") {
+        SmtpTransport::from_url(mail.server)
+    } else {
+        SmtpTransport::starttls_relay(mail.server)
+    }
+    .map_err(|err| format!("invalid SMTP server {:?}: {}", mail.server, err))?;
+    if let (Ok(username), Ok(password)) =
+        (env::var("LBF_SMTP_USERNAME"), env::var("LBF_SMTP_PASSWORD"))
+    {
+        transport = transport.credentials(Credentials::new(username, password));
+    }
+
+    transport
+        .build()
+        .send(&message)
+        .map(|_| ())
+        .map_err(|err| format!("cannot send email via {}: {}", mail.server, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use list_big_files::FileInfo;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::thread;
+
+    fn report() -> ScanReport {
+        ScanReport {
+            directory: PathBuf::from("/srv"),
+            min_size_bytes: 1024 * 1024,
+            files_scanned: 3,
+            partial: false,
+            volume: None,
+            files: vec![FileInfo::new("/srv/big.iso".to_string(), 2 * 1024 * 1024)],
+            dry_run: None,
+            scan: None,
+        }
+    }
+
+    fn mail<'o>(server: &'o str, to: &'o [String]) -> Mail<'o> {
+        Mail {
+            server,
+            from: "lbf@example.com",
+            to,
+        }
+    }
+
+    #[test]
+    fn test_message_contains_summary_and_table() {
+        let to = [
+            "admin@example.com".to_string(),
+            "ops@example.com".to_string(),
+        ];
+        let message = message(&mail("localhost", &to), &report(), SizeUnit::MiB).unwrap();
+        let text = String::from_utf8(message.formatted()).unwrap();
+        assert!(text.contains("To: admin@example.com, ops@example.com"));
+        assert!(text.contains("Subject: list-big-files: 1 files >= 1.00 MiB"));
+        assert!(text.contains("2.00  /srv/big.iso"));
+    }
+
+    #[test]
+    fn test_message_rejects_bad_address() {
+        let to = ["not an address".to_string()];
+        assert!(message(&mail("localhost", &to), &report(), SizeUnit::MiB).is_err());
+    }
+
+    #[test]
+    fn test_send_over_smtp() {
+        // A minimal SMTP server that accepts one message and returns its data
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("smtp://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            writer.write_all(b"220 test ESMTP\r\n").unwrap();
+            let mut data = String::new();
+            let mut in_data = false;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                if in_data {
+                    if line == ".\r\n" {
+                        in_data = false;
+                        writer.write_all(b"250 queued\r\n").unwrap();
+                    } else {
+                        data.push_str(&line);
+                    }
+                } else if line.starts_with("DATA") {
+                    in_data = true;
+                    writer.write_all(b"354 go ahead\r\n").unwrap();
+                } else if line.starts_with("QUIT") {
+                    writer.write_all(b"221 bye\r\n").unwrap();
+                    break;
+                } else {
+                    writer.write_all(b"250 ok\r\n").unwrap();
+                }
+                line.clear();
+            }
+            data
+        });
+
+        let to = ["admin@example.com".to_string()];
+        send(&mail(&url, &to), &report(), SizeUnit::MiB).unwrap();
+        assert!(server.join().unwrap().contains("/srv/big.iso"));
+    }
+}
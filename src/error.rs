@@ -0,0 +1,45 @@
+// Import Backend to name unavailable backends
+use crate::Backend;
+// Import io::Error so I/O failures keep their original cause
+use std::io;
+// Import PathBuf so errors can name the offending path
+use std::path::PathBuf;
+// Import thiserror to derive Display and Error implementations
+use thiserror::Error;
+
+/// Why a size string such as `"50MB"`, or another option value, could not be
+/// understood.
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseError {
+    #[error("size is empty")]
+    Empty,
+    #[error("invalid number {0:?}")]
+    InvalidNumber(String),
+    #[error("unknown size unit {0:?}")]
+    UnknownUnit(String),
+    #[error("size {0:?} is too large")]
+    TooLarge(String),
+    #[error("unknown backend {0:?}")]
+    UnknownBackend(String),
+}
+
+/// Why a scan could not be started.
+#[derive(Debug, Error)]
+pub enum ScanError {
+    #[error("directory not found: {}", .0.display())]
+    NotFound(PathBuf),
+    #[error("not a directory: {}", .0.display())]
+    NotADirectory(PathBuf),
+    #[error("the {0} backend is not available on this system or build")]
+    BackendUnavailable(Backend),
+    #[error("cannot start worker threads: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+    #[error("cannot access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("cannot hand off results: {0}")]
+    Sink(#[source] crate::SinkError),
+}
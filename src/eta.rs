@@ -0,0 +1,192 @@
+// Import collections for the totals cache and visited top-level entries
+use std::collections::{HashMap, HashSet};
+// Import OsString for top-level directory names
+use std::ffi::OsString;
+// Import fs and io for reading and writing the totals cache
+use std::fs;
+use std::io;
+// Import Path types for scan roots and cache files
+use std::path::{Path, PathBuf};
+// Import Duration and Instant for the time estimate
+use std::time::{Duration, Instant};
+
+// Never claim a scan is more than this far along; totals drift between runs
+const MAX_FRACTION: f64 = 0.99;
+
+// Wait until this much of the scan is done before trusting an estimate
+const MIN_FRACTION: f64 = 0.01;
+
+/// Estimates time remaining for a scan in progress.
+///
+/// Uses the file count of the previous scan of the same root when one was
+/// cached, and otherwise how many of the root's top-level directories the
+/// walk has reached.
+pub struct EtaEstimator {
+    root: PathBuf,
+    start: Instant,
+    expected_files: Option<usize>,
+    top_level_dirs: usize,
+    seen_top_level: HashSet<OsString>,
+}
+
+impl EtaEstimator {
+    pub fn new(root: &Path, expected_files: Option<usize>) -> Self {
+        let top_level_dirs = fs::read_dir(root)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                    .count()
+            })
+            .unwrap_or(0);
+
+        EtaEstimator {
+            root: root.to_path_buf(),
+            start: Instant::now(),
+            expected_files,
+            top_level_dirs,
+            seen_top_level: HashSet::new(),
+        }
+    }
+
+    /// Record progress and return the estimated time remaining, if known.
+    pub fn update(&mut self, files_scanned: usize, current_dir: &Path) -> Option<Duration> {
+        if let Some(first) = current_dir
+            .strip_prefix(&self.root)
+            .ok()
+            .and_then(|relative| relative.components().next())
+        {
+            self.seen_top_level.insert(first.as_os_str().to_os_string());
+        }
+
+        let fraction = self.fraction_done(files_scanned)?;
+        Some(remaining(self.start.elapsed(), fraction))
+    }
+
+    // Best guess at how much of the tree has been walked, from 0 to 1
+    fn fraction_done(&self, files_scanned: usize) -> Option<f64> {
+        let fraction = match self.expected_files {
+            Some(expected) if expected > 0 => files_scanned as f64 / expected as f64,
+            _ if self.top_level_dirs > 0 => {
+                // The directory currently being walked is not finished yet
+                self.seen_top_level.len().saturating_sub(1) as f64 / self.top_level_dirs as f64
+            }
+            _ => return None,
+        };
+
+        (fraction >= MIN_FRACTION).then_some(fraction.min(MAX_FRACTION))
+    }
+}
+
+// Extrapolate the remaining time from the elapsed time and fraction done
+fn remaining(elapsed: Duration, fraction: f64) -> Duration {
+    elapsed.mul_f64((1.0 - fraction) / fraction)
+}
+
+/// Location of the cached file totals, if a cache directory is available.
+pub fn totals_file(state_dir: Option<&Path>) -> Option<PathBuf> {
+    crate::state::cache_dir(state_dir).map(|dir| dir.join("totals.tsv"))
+}
+
+// Read the cache as a map of canonical root to file count
+fn read_totals(cache_file: &Path) -> HashMap<PathBuf, usize> {
+    let Ok(contents) = fs::read_to_string(cache_file) else {
+        return HashMap::new();
+    };
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (count, root) = line.split_once('\t')?;
+            Some((PathBuf::from(root), count.parse().ok()?))
+        })
+        .collect()
+}
+
+/// File count recorded by the last complete scan of `root`.
+pub fn load_total(cache_file: &Path, root: &Path) -> Option<usize> {
+    let root = root.canonicalize().ok()?;
+    read_totals(cache_file).get(&root).copied()
+}
+
+/// Remember `files` as the size of `root` for the next scan's estimate.
+pub fn save_total(cache_file: &Path, root: &Path, files: usize) -> io::Result<()> {
+    let root = root.canonicalize()?;
+    let mut totals = read_totals(cache_file);
+    totals.insert(root, files);
+
+    if let Some(parent) = cache_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents: String = totals
+        .iter()
+        .map(|(root, count)| format!("{}\t{}\n", count, root.display()))
+        .collect();
+    fs::write(cache_file, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_remaining_halfway() {
+        let eta = remaining(Duration::from_secs(10), 0.5);
+        assert_eq!(eta, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_remaining_quarter() {
+        let eta = remaining(Duration::from_secs(10), 0.25);
+        assert_eq!(eta, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_fraction_uses_cached_total() {
+        let dir = tempdir().unwrap();
+        let estimator = EtaEstimator::new(dir.path(), Some(200));
+        assert_eq!(estimator.fraction_done(50), Some(0.25));
+    }
+
+    #[test]
+    fn test_fraction_capped_when_total_exceeded() {
+        let dir = tempdir().unwrap();
+        let estimator = EtaEstimator::new(dir.path(), Some(100));
+        assert_eq!(estimator.fraction_done(150), Some(MAX_FRACTION));
+    }
+
+    #[test]
+    fn test_fraction_from_top_level_directories() {
+        let dir = tempdir().unwrap();
+        for name in ["a", "b", "c", "d"] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+        }
+
+        let mut estimator = EtaEstimator::new(dir.path(), None);
+        assert!(estimator.update(0, &dir.path().join("a")).is_none());
+        estimator.update(0, &dir.path().join("b").join("nested"));
+        estimator.update(0, &dir.path().join("c"));
+        assert_eq!(estimator.fraction_done(0), Some(0.5));
+    }
+
+    #[test]
+    fn test_no_estimate_without_history_or_directories() {
+        let dir = tempdir().unwrap();
+        let mut estimator = EtaEstimator::new(dir.path(), None);
+        assert!(estimator.update(10, dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_totals_round_trip() {
+        let cache = tempdir().unwrap();
+        let root = tempdir().unwrap();
+        let cache_file = cache.path().join("nested").join("totals.tsv");
+
+        assert_eq!(load_total(&cache_file, root.path()), None);
+        save_total(&cache_file, root.path(), 1234).unwrap();
+        assert_eq!(load_total(&cache_file, root.path()), Some(1234));
+        save_total(&cache_file, root.path(), 99).unwrap();
+        assert_eq!(load_total(&cache_file, root.path()), Some(99));
+    }
+}
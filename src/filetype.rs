@@ -0,0 +1,134 @@
+// Import rayon to inspect the matches in parallel
+use rayon::prelude::*;
+// Import FileInfo for the matches that receive a type
+use list_big_files::FileInfo;
+// Import MatcherType for the broad category of a detected format
+use infer::MatcherType;
+// Import io for read errors
+use std::io;
+// Import Path for the file being inspected
+use std::path::Path;
+
+/// Set the detected type of every file in `files`. Files that cannot be read
+/// are left without one.
+pub fn add_types(files: &mut [FileInfo]) {
+    files.par_iter_mut().for_each(|file| {
+        file.file_type = describe(&file.path).ok();
+    });
+}
+
+/// Set the detected MIME type of every file in `files`. Unrecognised formats
+/// get `application/octet-stream`; files that cannot be read get none.
+pub fn add_mime_types(files: &mut [FileInfo]) {
+    files.par_iter_mut().for_each(|file| {
+        file.mime = mime_type(&file.path).ok();
+    });
+}
+
+/// Whether `mime` is matched by any of `patterns`, each either a full type
+/// such as `video/mp4`, a family such as `video/*`, or `*` for anything.
+pub fn mime_matches(patterns: &[String], mime: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some("") => true,
+            Some(prefix) if prefix.ends_with('/') => mime.starts_with(prefix),
+            _ => pattern == mime,
+        })
+}
+
+/// MIME type of the file at `path`, detected from its leading bytes.
+pub fn mime_type(path: &Path) -> io::Result<String> {
+    Ok(match infer::get_from_path(path)? {
+        Some(kind) => kind.mime_type().to_string(),
+        None => "application/octet-stream".to_string(),
+    })
+}
+
+/// Describe the format of the file at `path` from its leading bytes, such as
+/// "zip archive" or "mp4 video", regardless of its extension. Formats that
+/// are not recognised are described as "unknown".
+pub fn describe(path: &Path) -> io::Result<String> {
+    Ok(match infer::get_from_path(path)? {
+        Some(kind) => format!("{} {}", kind.extension(), category(kind.matcher_type())),
+        None => "unknown".to_string(),
+    })
+}
+
+// Word used for each broad category of format
+fn category(matcher: MatcherType) -> &'static str {
+    match matcher {
+        MatcherType::App => "executable",
+        MatcherType::Archive => "archive",
+        MatcherType::Audio => "audio",
+        MatcherType::Book => "ebook",
+        MatcherType::Doc => "document",
+        MatcherType::Font => "font",
+        MatcherType::Image => "image",
+        MatcherType::Text => "text",
+        MatcherType::Video => "video",
+        MatcherType::Custom => "file",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_describe_ignores_extension() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("movie.mkv");
+        fs::write(
+            &file,
+            b"PK\x03\x04\x14\x00\x00\x00\x08\x00rest of the archive",
+        )
+        .unwrap();
+        assert_eq!(describe(&file).unwrap(), "zip archive");
+
+        let file = dir.path().join("disk.iso");
+        fs::write(&file, vec![0; 4096]).unwrap();
+        assert_eq!(describe(&file).unwrap(), "unknown");
+    }
+
+    #[test]
+    fn test_mime_matches() {
+        let patterns = vec!["video/*".to_string(), "application/zip".to_string()];
+        assert!(mime_matches(&patterns, "video/mp4"));
+        assert!(mime_matches(&patterns, "application/zip"));
+        assert!(!mime_matches(&patterns, "application/gzip"));
+        assert!(!mime_matches(&patterns, "videos/mp4"));
+        assert!(mime_matches(&["*".to_string()], "text/plain"));
+    }
+
+    #[test]
+    fn test_add_mime_types() {
+        let dir = tempdir().unwrap();
+        let gzip = dir.path().join("backup.iso");
+        fs::write(&gzip, b"\x1f\x8b\x08\x00\x00\x00\x00\x00").unwrap();
+        let zeros = dir.path().join("zeros");
+        fs::write(&zeros, vec![0; 64]).unwrap();
+        let mut files = vec![FileInfo::new(gzip, 8), FileInfo::new(zeros, 64)];
+
+        add_mime_types(&mut files);
+        assert_eq!(files[0].mime.as_deref(), Some("application/gzip"));
+        assert_eq!(files[1].mime.as_deref(), Some("application/octet-stream"));
+    }
+
+    #[test]
+    fn test_add_types_skips_unreadable_files() {
+        let dir = tempdir().unwrap();
+        let gzip = dir.path().join("backup");
+        fs::write(&gzip, b"\x1f\x8b\x08\x00\x00\x00\x00\x00").unwrap();
+        let mut files = vec![
+            FileInfo::new(gzip, 8),
+            FileInfo::new(dir.path().join("missing"), 8),
+        ];
+
+        add_types(&mut files);
+        assert_eq!(files[0].file_type.as_deref(), Some("gz archive"));
+        assert_eq!(files[1].file_type, None);
+    }
+}
@@ -0,0 +1,197 @@
+// Import the parser for update times
+use crate::daemon::parse_rfc3339;
+// Import the listing shared by object stores
+use crate::store::{self, Object, Page, Store};
+// Import serde_json for the JSON API's responses
+use serde_json::Value;
+
+// Only the fields the listing needs
+const FIELDS: &str = "items(name,size,updated),nextPageToken";
+
+/// Where and as whom to reach Cloud Storage, read from the variables the
+/// Google Cloud CLI and client libraries use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// `https://storage.googleapis.com`, or an emulator's address.
+    pub endpoint: String,
+    /// An OAuth access token, or None for anonymous requests, which only
+    /// public buckets allow.
+    pub token: Option<String>,
+}
+
+impl Config {
+    /// Read the endpoint from `STORAGE_EMULATOR_HOST` and the access token
+    /// from `GOOGLE_OAUTH_ACCESS_TOKEN` or `CLOUDSDK_AUTH_ACCESS_TOKEN`, as
+    /// printed by `gcloud auth print-access-token`.
+    pub fn from_env<E>(env: E) -> Config
+    where
+        E: Fn(&str) -> Option<String>,
+    {
+        let endpoint = match env("STORAGE_EMULATOR_HOST") {
+            Some(host) if host.contains("://") => host,
+            Some(host) => format!("http://{}", host),
+            None => "https://storage.googleapis.com".to_string(),
+        };
+        Config {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            token: env("GOOGLE_OAUTH_ACCESS_TOKEN").or_else(|| env("CLOUDSDK_AUTH_ACCESS_TOKEN")),
+        }
+    }
+}
+
+/// The objects under a name prefix of a bucket, listed with the JSON API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub config: Config,
+    pub name: String,
+    pub prefix: String,
+}
+
+impl Store for Bucket {
+    fn url(&self) -> String {
+        format!("gs://{}", self.name)
+    }
+
+    fn page(&self, token: Option<String>) -> Result<Page, String> {
+        let mut query = format!("fields={}", store::encode(FIELDS));
+        if !self.prefix.is_empty() {
+            query.push_str(&format!("&prefix={}", store::encode(&self.prefix)));
+        }
+        if let Some(token) = token {
+            query.push_str(&format!("&pageToken={}", store::encode(&token)));
+        }
+        let mut request = ureq::get(&format!(
+            "{}/storage/v1/b/{}/o?{}",
+            self.config.endpoint,
+            store::encode(&self.name),
+            query
+        ));
+        if let Some(token) = &self.config.token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        parse_page(&store::call(request, &self.url(), error_message)?)
+    }
+}
+
+// Parse an objects list response
+fn parse_page(json: &str) -> Result<Page, String> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|err| format!("bad Cloud Storage listing: {}", err))?;
+    let items = value["items"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    let objects = items
+        .iter()
+        .map(|item| {
+            Ok(Object {
+                key: item["name"]
+                    .as_str()
+                    .ok_or("Cloud Storage listing has an object without a name")?
+                    .to_string(),
+                // Sizes are 64-bit, so the API sends them as strings
+                size_bytes: item["size"]
+                    .as_str()
+                    .and_then(|size| size.parse().ok())
+                    .ok_or("Cloud Storage listing has an object without a size")?,
+                modified: item["updated"].as_str().and_then(parse_rfc3339),
+            })
+        })
+        .collect::<Result<_, &str>>()?;
+    Ok(Page {
+        objects,
+        next: value["nextPageToken"].as_str().map(str::to_string),
+    })
+}
+
+// The message of a JSON API error response
+fn error_message(json: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    value["error"]["message"].as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::thread;
+
+    #[test]
+    fn test_config_from_env() {
+        let config = Config::from_env(|name| match name {
+            "STORAGE_EMULATOR_HOST" => Some("localhost:4443".to_string()),
+            "CLOUDSDK_AUTH_ACCESS_TOKEN" => Some("ya29.token".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.endpoint, "http://localhost:4443");
+        assert_eq!(config.token.as_deref(), Some("ya29.token"));
+        let config = Config::from_env(|_| None);
+        assert_eq!(config.endpoint, "https://storage.googleapis.com");
+        assert_eq!(config.token, None);
+    }
+
+    #[test]
+    fn test_error_message() {
+        let body = r#"{"error":{"code":404,"message":"The specified bucket does not exist."}}"#;
+        assert_eq!(
+            error_message(body).as_deref(),
+            Some("The specified bucket does not exist.")
+        );
+        assert_eq!(error_message("Not Found"), None);
+    }
+
+    #[test]
+    fn test_list_follows_page_tokens() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let handle = thread::spawn(move || {
+            let pages = [
+                r#"{"items":[{"name":"video/a.mp4","size":"2048",
+                   "updated":"2024-05-01T12:00:00.000Z"}],"nextPageToken":"CgV2/2"}"#,
+                r#"{"items":[{"name":"video/b.mp4","size":"10"}]}"#,
+            ];
+            let mut requests = Vec::new();
+            for page in pages {
+                let request = server.recv().unwrap();
+                let authorization = request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Authorization"))
+                    .map(|header| header.value.to_string());
+                requests.push((request.url().to_string(), authorization));
+                request
+                    .respond(tiny_http::Response::from_string(page))
+                    .unwrap();
+            }
+            requests
+        });
+        let bucket = Bucket {
+            config: Config {
+                endpoint,
+                token: Some("ya29.token".to_string()),
+            },
+            name: "media".to_string(),
+            prefix: "video/".to_string(),
+        };
+        let mut files = Vec::new();
+        let listed = store::list(&bucket, |file| files.push(file)).unwrap();
+        assert_eq!(listed, 2);
+        assert_eq!(files[0].path, Path::new("gs://media/video/a.mp4"));
+        assert_eq!(files[0].modified, Some(1_714_564_800));
+        assert_eq!(files[1].size_bytes, 10);
+        let fields = "fields=items%28name%2Csize%2Cupdated%29%2CnextPageToken";
+        assert_eq!(
+            handle.join().unwrap(),
+            vec![
+                (
+                    format!("/storage/v1/b/media/o?{}&prefix=video%2F", fields),
+                    Some("Bearer ya29.token".to_string())
+                ),
+                (
+                    format!(
+                        "/storage/v1/b/media/o?{}&prefix=video%2F&pageToken=CgV2%2F2",
+                        fields
+                    ),
+                    Some("Bearer ya29.token".to_string())
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+// Import serde for JSON output of the blobs
+use serde::Serialize;
+// Import HashSet for the blobs still in HEAD
+use std::collections::HashSet;
+// Import io for feeding object ids from one git command to the next
+use std::io::{self, BufRead, BufReader, Write};
+// Import Path for the repository
+use std::path::Path;
+// Import process to run git
+use std::process::{Command, Stdio};
+// Import thread to write object ids while reading the sizes back
+use std::thread;
+
+/// A file version stored in a repository's history.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Blob {
+    pub id: String,
+    pub size_bytes: u64,
+    /// A path the blob was committed at.
+    pub path: String,
+    /// The commit that first added the blob, abbreviated.
+    pub commit: Option<String>,
+    /// Whether the blob is still part of HEAD, rather than only in history.
+    pub in_head: bool,
+}
+
+/// The blobs of at least `min_size_bytes` anywhere in the history of the
+/// repository at `repo`, largest first. Sizes are those of the uncompressed
+/// contents, as a checkout would write them.
+pub fn large_blobs(repo: &Path, min_size_bytes: u64) -> Result<Vec<Blob>, String> {
+    let objects = output(git(repo).args(["rev-list", "--objects", "--all"]))?;
+
+    // cat-file reads the object ids and paths on stdin and prints each
+    // object's type and size in turn
+    let mut check = git(repo)
+        .args([
+            "cat-file",
+            "--batch-check=%(objecttype) %(objectname) %(objectsize) %(rest)",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(cannot_run)?;
+    let mut stdin = check.stdin.take().unwrap();
+    let writer = thread::spawn(move || stdin.write_all(objects.as_bytes()));
+    let mut blobs = Vec::new();
+    for line in BufReader::new(check.stdout.take().unwrap()).lines() {
+        let line = line.map_err(|err| format!("cannot read from git: {}", err))?;
+        if let Some(blob) = parse_check(&line).filter(|blob| blob.size_bytes >= min_size_bytes) {
+            blobs.push(blob);
+        }
+    }
+    let _ = writer.join();
+    if !check.wait().map_err(cannot_run)?.success() {
+        return Err("git cat-file failed".to_string());
+    }
+
+    // Repositories without commits have no HEAD, and nothing in it
+    let head: HashSet<String> = output(git(repo).args(["ls-tree", "-r", "HEAD"]))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(2).map(str::to_string))
+        .collect();
+    for blob in &mut blobs {
+        blob.in_head = head.contains(&blob.id);
+        blob.commit = output(git(repo).args([
+            "log",
+            "--all",
+            "--reverse",
+            "--format=%h",
+            &format!("--find-object={}", blob.id),
+        ]))
+        .ok()
+        .and_then(|commits| commits.lines().next().map(str::to_string));
+    }
+    blobs.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then(a.path.cmp(&b.path)));
+    Ok(blobs)
+}
+
+// A blob from a line of cat-file's batch check, or None for other objects
+fn parse_check(line: &str) -> Option<Blob> {
+    let mut fields = line.splitn(4, ' ');
+    if fields.next()? != "blob" {
+        return None;
+    }
+    let id = fields.next()?.to_string();
+    let size_bytes = fields.next()?.parse().ok()?;
+    Some(Blob {
+        id,
+        size_bytes,
+        path: fields.next().unwrap_or_default().to_string(),
+        commit: None,
+        in_head: false,
+    })
+}
+
+// A git command run in `repo`
+fn git(repo: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repo);
+    command
+}
+
+// The stdout of a git command, or its error message when it fails
+fn output(command: &mut Command) -> Result<String, String> {
+    let output = command.output().map_err(cannot_run)?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(message.trim().trim_start_matches("fatal: ").to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn cannot_run(err: io::Error) -> String {
+    format!("cannot run git: {}", err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn run(repo: &Path, args: &[&str]) {
+        let status = git(repo)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn test_parse_check() {
+        assert_eq!(
+            parse_check("blob 0123abcd 2048 assets/video.mp4"),
+            Some(Blob {
+                id: "0123abcd".to_string(),
+                size_bytes: 2048,
+                path: "assets/video.mp4".to_string(),
+                commit: None,
+                in_head: false,
+            })
+        );
+        assert_eq!(parse_check("tree 0123abcd 96 assets"), None);
+        assert_eq!(parse_check("commit 0123abcd 240 "), None);
+    }
+
+    #[test]
+    fn test_large_blobs_include_deleted_files() {
+        let dir = tempdir().unwrap();
+        let repo = dir.path();
+        run(repo, &["init", "-q"]);
+        fs::write(repo.join("big.bin"), vec![1u8; 4096]).unwrap();
+        fs::write(repo.join("kept.bin"), vec![2u8; 2048]).unwrap();
+        fs::write(repo.join("small.txt"), "hi").unwrap();
+        run(repo, &["add", "."]);
+        run(repo, &["commit", "-q", "-m", "add files"]);
+        run(repo, &["rm", "-q", "big.bin"]);
+        run(repo, &["commit", "-q", "-m", "remove big file"]);
+
+        let blobs = large_blobs(repo, 1024).unwrap();
+        let summary: Vec<(&str, u64, bool)> = blobs
+            .iter()
+            .map(|blob| (blob.path.as_str(), blob.size_bytes, blob.in_head))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("big.bin", 4096, false), ("kept.bin", 2048, true)]
+        );
+        assert!(blobs.iter().all(|blob| blob.commit.is_some()));
+    }
+
+    #[test]
+    fn test_not_a_repository() {
+        let dir = tempdir().unwrap();
+        assert!(large_blobs(dir.path(), 0).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+// Import the action taken on each match and the running total
+use crate::actions::{Removal, Summary};
+// Import timestamp for showing modification times
+use crate::daemon::timestamp;
+// Import describe for showing each match's real format
+use crate::filetype::describe;
+// Import the size helpers used in prompts
+use list_big_files::{display_size, FileInfo, SizeUnit};
+// Import fs and io for metadata and the prompt streams
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+/// Show each of `files` in turn and ask whether to keep, delete or trash it,
+/// reading answers from `input`. Stops at the end of the list, on `q`, or when
+/// `input` runs out.
+pub fn run<R, W>(
+    files: &[FileInfo],
+    unit: SizeUnit,
+    mut input: R,
+    mut output: W,
+) -> io::Result<Summary>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut summary = Summary::default();
+
+    for (i, file) in files.iter().enumerate() {
+        let path = &file.path;
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map(timestamp)
+            .unwrap_or_else(|_| "unknown".to_string());
+        let kind = describe(path).unwrap_or_else(|_| "unknown".to_string());
+        writeln!(
+            output,
+            "\n[{}/{}] {}",
+            i + 1,
+            files.len(),
+            file.path.display()
+        )?;
+        writeln!(
+            output,
+            "    {}, modified {}, {}",
+            display_size(file.size_bytes, unit),
+            modified,
+            kind
+        )?;
+
+        let removal = loop {
+            write!(output, "[k]eep, [d]elete, [t]rash or [q]uit? [k] ")?;
+            output.flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                writeln!(output)?;
+                return finish(summary, unit, output);
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "" | "k" | "keep" => break None,
+                "d" | "delete" => break Some(Removal::Delete),
+                "t" | "trash" => break Some(Removal::Trash),
+                "q" | "quit" => return finish(summary, unit, output),
+                _ => writeln!(output, "Please answer k, d, t or q.")?,
+            }
+        };
+
+        if let Some(removal) = removal {
+            match removal.apply(path) {
+                Ok(()) => {
+                    summary.add(file.size_bytes);
+                    writeln!(
+                        output,
+                        "{}, {} freed so far",
+                        removal.done(),
+                        display_size(summary.freed_bytes, unit)
+                    )?;
+                }
+                Err(err) => writeln!(
+                    output,
+                    "error: cannot remove {}: {}",
+                    file.path.display(),
+                    err
+                )?,
+            }
+        }
+    }
+    finish(summary, unit, output)
+}
+
+// Print the final tally
+fn finish<W: Write>(summary: Summary, unit: SizeUnit, mut output: W) -> io::Result<Summary> {
+    writeln!(
+        output,
+        "\nRemoved {} files, freed {}",
+        summary.removed,
+        display_size(summary.freed_bytes, unit)
+    )?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn files(dir: &Path, names: &[&str]) -> Vec<FileInfo> {
+        names
+            .iter()
+            .map(|name| {
+                let path = dir.join(name);
+                fs::write(&path, vec![0; 1024 * 1024]).unwrap();
+                FileInfo::new(path, 1024 * 1024)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_run_follows_answers() {
+        let dir = tempdir().unwrap();
+        let files = files(dir.path(), &["a", "b", "c"]);
+        let mut output = Vec::new();
+
+        let summary = run(&files, SizeUnit::MiB, &b"\nwhat\nd\nk\n"[..], &mut output).unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                removed: 1,
+                freed_bytes: 1024 * 1024
+            }
+        );
+        assert!(dir.path().join("a").exists());
+        assert!(!dir.path().join("b").exists());
+        assert!(dir.path().join("c").exists());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("[2/3]"));
+        assert!(output.contains("Please answer k, d, t or q."));
+        assert!(output.contains("Deleted, 1.00 MiB freed so far"));
+        assert!(output.contains("Removed 1 files, freed 1.00 MiB"));
+    }
+
+    #[test]
+    fn test_run_stops_on_quit_or_end_of_input() {
+        let dir = tempdir().unwrap();
+        let files = files(dir.path(), &["a", "b"]);
+
+        let summary = run(&files, SizeUnit::MiB, &b"q\nd\n"[..], Vec::new()).unwrap();
+        assert_eq!(summary, Summary::default());
+        let summary = run(&files, SizeUnit::MiB, &b""[..], Vec::new()).unwrap();
+        assert_eq!(summary, Summary::default());
+        assert!(dir.path().join("b").exists());
+    }
+}
@@ -0,0 +1,1014 @@
+//! Core scanning logic behind the `list-big-files` CLI.
+//!
+//! Library users can either collect every match with [`list_big_files`] or
+//! react to each match as it is found with [`scan_with`]. [`Scanner`] exposes
+//! further options such as progress reporting, [`FileFilter`]s that decide
+//! which matches are reported, and [`ResultSink`]s that receive them. With the
+//! `async` feature enabled, `scan_async` yields matches as a Tokio-compatible
+//! stream, with `plugins`, `Plugin` loads filters and sinks from shared
+//! libraries, and with `wasm`, `WasmFilter` runs filters compiled to
+//! WebAssembly in a sandbox.
+
+mod ads;
+mod backend;
+mod cache;
+mod error;
+mod longpath;
+mod mft;
+mod plugin;
+mod scanner;
+mod stats;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use backend::Backend;
+pub use cache::ScanCache;
+pub use error::{ParseError, ScanError};
+pub use longpath::{long_path, plain_path};
+#[cfg(feature = "plugins")]
+pub use plugin::Plugin;
+pub use plugin::{FileFilter, ResultSink, SinkError};
+pub use scanner::{Progress, Scanner};
+pub use stats::{ScanStats, Skipped};
+#[cfg(feature = "async")]
+pub use stream::scan_async;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmFilter;
+
+// Import serde so matches can be saved and compared between scans
+use serde::{Deserialize, Serialize, Serializer};
+// Import io for classifying errors on the scan root
+use std::io;
+// Import Cow for paths that need no escaping
+use std::borrow::Cow;
+// Import Path types for handling file system paths
+use std::path::{Path, PathBuf};
+
+/// Unit used when displaying sizes: binary units (1 MiB = 1024 KiB) by
+/// default, or decimal SI units (1 MB = 1000 KB) as `df -H` uses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeUnit {
+    /// Plain bytes, in either system.
+    B,
+    /// Plain bytes with thousands separators, e.g. 1,048,576.
+    GroupedB,
+    KiB,
+    MiB,
+    GiB,
+    TiB,
+    PiB,
+    KB,
+    MB,
+    GB,
+    TB,
+    PB,
+    /// Whichever binary unit reads best for each size on its own.
+    Auto,
+    /// Whichever SI unit reads best for each size on its own.
+    AutoSi,
+}
+
+impl SizeUnit {
+    /// The SI counterpart of this unit, e.g. MB for MiB.
+    pub fn si(self) -> SizeUnit {
+        match self {
+            SizeUnit::KiB => SizeUnit::KB,
+            SizeUnit::MiB => SizeUnit::MB,
+            SizeUnit::GiB => SizeUnit::GB,
+            SizeUnit::TiB => SizeUnit::TB,
+            SizeUnit::PiB => SizeUnit::PB,
+            SizeUnit::Auto => SizeUnit::AutoSi,
+            unit => unit,
+        }
+    }
+
+    /// Bytes in one of this unit; automatic units have no fixed size.
+    pub fn bytes(self) -> Option<u64> {
+        match self {
+            SizeUnit::B | SizeUnit::GroupedB => Some(1),
+            SizeUnit::KiB => Some(1 << 10),
+            SizeUnit::MiB => Some(1 << 20),
+            SizeUnit::GiB => Some(1 << 30),
+            SizeUnit::TiB => Some(1 << 40),
+            SizeUnit::PiB => Some(1 << 50),
+            SizeUnit::KB => Some(1_000),
+            SizeUnit::MB => Some(1_000_000),
+            SizeUnit::GB => Some(1_000_000_000),
+            SizeUnit::TB => Some(1_000_000_000_000),
+            SizeUnit::PB => Some(1_000_000_000_000_000),
+            SizeUnit::Auto | SizeUnit::AutoSi => None,
+        }
+    }
+}
+
+/// A file that matched the size threshold.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileInfo {
+    /// Where the file is, kept as the OS gave it so names that are not valid
+    /// Unicode survive. JSON holds it as text, with U+FFFD for invalid bytes.
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    /// Hex digest of the contents, when checksums were requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Format detected from the leading bytes, when type detection was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_type: Option<String>,
+    /// MIME type detected from the leading bytes, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime: Option<String>,
+    /// Estimated zstd compression ratio from sampled contents, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression_ratio: Option<f64>,
+    /// Share of the capacity of the volume holding the file, in percent, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent_of_disk: Option<f64>,
+    /// Last modification time in seconds since the Unix epoch, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// How stale the file is, weighing its size against its age, when
+    /// requested. Larger scores are files more worth reviewing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// User owning the file, by name where it resolves, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Group owning the file, by name where it resolves, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Mode bits as `ls -l` shows them on Unix, or attribute flags on Windows,
+    /// when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<String>,
+    /// Device holding the file: `st_dev` on Unix, the volume serial number on
+    /// Windows, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<u64>,
+    /// Inode number on Unix, file index on Windows, when requested. Together
+    /// with the device it identifies hardlinks to the same data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+    /// Other paths the same file was found at, e.g. through another bind
+    /// mount of its directory or as a hardlink, when matches were merged by
+    /// device and inode.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_paths"
+    )]
+    pub alternate_paths: Vec<PathBuf>,
+    /// Whether the file is a cloud placeholder whose contents are not stored
+    /// locally, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<bool>,
+    /// Which size tier the file falls in, when tiers were requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<Tier>,
+}
+
+/// How serious a match's size is when a scan lists sizes below its main
+/// threshold too.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    /// At least the lower threshold, but below the main one.
+    Warning,
+    /// At least the main threshold.
+    Critical,
+}
+
+impl Tier {
+    /// Name of the tier in the table and in JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            Tier::Warning => "warning",
+            Tier::Critical => "critical",
+        }
+    }
+}
+
+impl FileInfo {
+    /// A match at `path` holding `size_bytes` bytes.
+    pub fn new(path: impl Into<PathBuf>, size_bytes: u64) -> Self {
+        FileInfo {
+            path: path.into(),
+            size_bytes,
+            hash: None,
+            file_type: None,
+            mime: None,
+            compression_ratio: None,
+            percent_of_disk: None,
+            modified: None,
+            score: None,
+            owner: None,
+            group: None,
+            permissions: None,
+            device: None,
+            inode: None,
+            alternate_paths: Vec::new(),
+            placeholder: None,
+            tier: None,
+        }
+    }
+}
+
+/// Write a path as text, for serde's `serialize_with`. Bytes that are not
+/// valid Unicode become U+FFFD, so such files are still listed instead of
+/// failing the whole report.
+pub fn serialize_path<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&path.to_string_lossy())
+}
+
+// Write paths as text, as serialize_path does
+fn serialize_paths<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(paths.iter().map(|path| path.to_string_lossy()))
+}
+
+/// `path` as text that can be turned back into the same path, for scripts
+/// reading machine-readable output. Paths that are valid Unicode are returned
+/// as they are. In others, backslashes are doubled and each byte that is not
+/// valid UTF-8 is written as `\xNN` on Unix, or each unpaired surrogate as
+/// `\u{XXXX}` on Windows.
+pub fn escape_path(path: &Path) -> Cow<'_, str> {
+    if let Some(text) = path.to_str() {
+        return Cow::Borrowed(text);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Cow::Owned(escape_bytes(path.as_os_str().as_bytes()))
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        Cow::Owned(escape_wide(path.as_os_str().encode_wide()))
+    }
+    #[cfg(not(any(unix, windows)))]
+    path.to_string_lossy()
+}
+
+// Bytes with the invalid UTF-8 sequences escaped
+#[cfg(any(unix, test))]
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        escaped.push_str(&chunk.valid().replace('\\', r"\\"));
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!(r"\x{:02x}", byte));
+        }
+    }
+    escaped
+}
+
+// UTF-16 with the unpaired surrogates escaped
+#[cfg(any(windows, test))]
+fn escape_wide(units: impl Iterator<Item = u16>) -> String {
+    char::decode_utf16(units)
+        .map(|decoded| match decoded {
+            Ok('\\') => r"\\".to_string(),
+            Ok(c) => c.to_string(),
+            Err(err) => format!(r"\u{{{:04x}}}", err.unpaired_surrogate()),
+        })
+        .collect()
+}
+
+/// Parse a size string with an optional binary unit suffix (b, k/kb/kib,
+/// m/mb/mib, g/gb/gib, t/tb/tib or p/pb/pib; MB when omitted) and return
+/// the size in bytes and the unit it was given in.
+pub fn parse_size(size_str: &str) -> Result<(u64, SizeUnit), ParseError> {
+    let size_str = size_str.trim().to_lowercase();
+    if size_str.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    // Split into the numeric part and the trailing alphabetic unit
+    let split = size_str
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(size_str.len());
+    let (num, suffix) = size_str.split_at(split);
+
+    let unit = match suffix {
+        "b" => SizeUnit::B,
+        "k" | "kb" | "kib" => SizeUnit::KiB,
+        "" | "m" | "mb" | "mib" => SizeUnit::MiB,
+        "g" | "gb" | "gib" => SizeUnit::GiB,
+        "t" | "tb" | "tib" => SizeUnit::TiB,
+        "p" | "pb" | "pib" => SizeUnit::PiB,
+        _ => return Err(ParseError::UnknownUnit(suffix.to_string())),
+    };
+
+    let value = num
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .ok_or_else(|| ParseError::InvalidNumber(num.to_string()))?;
+
+    let bytes = value * unit.bytes().unwrap_or(1) as f64;
+    if bytes >= u64::MAX as f64 {
+        return Err(ParseError::TooLarge(size_str));
+    }
+    Ok((bytes as u64, unit))
+}
+
+/// Make sure a scan root exists and is a directory before walking it.
+pub fn check_root(directory: &Path) -> Result<(), ScanError> {
+    match long_path(directory).metadata() {
+        Ok(metadata) if metadata.is_dir() => Ok(()),
+        Ok(_) => Err(ScanError::NotADirectory(directory.to_path_buf())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            Err(ScanError::NotFound(directory.to_path_buf()))
+        }
+        Err(source) => Err(ScanError::Io {
+            path: directory.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Walk `directory` and invoke `callback` for every file of at least
+/// `min_size_bytes`, returning the number of files scanned.
+///
+/// The callback runs on rayon worker threads as matches are found, in no
+/// particular order. Files are checked as their directory is read, so memory
+/// use does not grow with the size of the tree.
+/// Entries that cannot be read during the walk are skipped; only a missing
+/// or unreadable root is reported as an error.
+pub fn scan_with<F>(directory: &Path, min_size_bytes: u64, callback: F) -> Result<usize, ScanError>
+where
+    F: Fn(FileInfo) + Send + Sync,
+{
+    Scanner::new(directory)
+        .min_size(min_size_bytes)
+        .scan_with(callback)
+}
+
+/// Collect every file of at least `min_size_bytes` under `directory`,
+/// returning the matches (unsorted) and the number of files scanned.
+pub fn list_big_files(
+    directory: &Path,
+    min_size_bytes: u64,
+) -> Result<(Vec<FileInfo>, usize), ScanError> {
+    Scanner::new(directory).min_size(min_size_bytes).collect()
+}
+
+/// The unit `size_bytes` is shown in: `unit` itself, or for the automatic
+/// units the largest unit in which the size is at least 1.
+pub fn resolve_unit(size_bytes: u64, unit: SizeUnit) -> SizeUnit {
+    let candidates = match unit {
+        SizeUnit::Auto => [
+            SizeUnit::PiB,
+            SizeUnit::TiB,
+            SizeUnit::GiB,
+            SizeUnit::MiB,
+            SizeUnit::KiB,
+        ],
+        SizeUnit::AutoSi => [
+            SizeUnit::PB,
+            SizeUnit::TB,
+            SizeUnit::GB,
+            SizeUnit::MB,
+            SizeUnit::KB,
+        ],
+        unit => return unit,
+    };
+    candidates
+        .into_iter()
+        .find(|&unit| format_size(size_bytes, unit) >= 1.0)
+        .unwrap_or(candidates[4])
+}
+
+/// Convert a byte count into the given display unit
+pub fn format_size(size_bytes: u64, unit: SizeUnit) -> f64 {
+    let unit = resolve_unit(size_bytes, unit);
+    size_bytes as f64 / unit.bytes().unwrap_or(1) as f64
+}
+
+/// Label printed next to sizes in the given unit. Sizes in the automatic
+/// units each have their own; use [`display_size`] for those.
+pub fn get_unit_label(unit: SizeUnit) -> &'static str {
+    match unit {
+        SizeUnit::B | SizeUnit::GroupedB => "B",
+        SizeUnit::KiB => "KiB",
+        SizeUnit::MiB => "MiB",
+        SizeUnit::GiB => "GiB",
+        SizeUnit::TiB => "TiB",
+        SizeUnit::PiB => "PiB",
+        SizeUnit::KB => "KB",
+        SizeUnit::MB => "MB",
+        SizeUnit::GB => "GB",
+        SizeUnit::TB => "TB",
+        SizeUnit::PB => "PB",
+        SizeUnit::Auto | SizeUnit::AutoSi => "",
+    }
+}
+
+/// A byte count with two decimals and its unit label, e.g. "2.00 TB".
+pub fn display_size(size_bytes: u64, unit: SizeUnit) -> String {
+    NumberFormat::PLAIN.size(size_bytes, unit)
+}
+
+/// A size as a number in the given unit, without its label: exact for bytes,
+/// otherwise rounded to two decimal places.
+pub fn display_number(size_bytes: u64, unit: SizeUnit) -> String {
+    NumberFormat::PLAIN.number(size_bytes, unit)
+}
+
+/// How numbers are written for people to read: the separator between groups
+/// of three digits, if any, and the decimal mark.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    pub thousands: Option<char>,
+    pub decimal: char,
+}
+
+impl NumberFormat {
+    /// Digits only, e.g. 1234567.50, as scripts expect.
+    pub const PLAIN: NumberFormat = NumberFormat {
+        thousands: None,
+        decimal: '.',
+    };
+
+    /// Commas between thousands, e.g. 1,234,567.50.
+    pub const GROUPED: NumberFormat = NumberFormat {
+        thousands: Some(','),
+        decimal: '.',
+    };
+
+    /// The convention of a POSIX locale name such as `de_DE.UTF-8`, e.g.
+    /// 1.234.567,50 in German or 1 234 567,50 in French. Locales not known to
+    /// differ, including C and POSIX, get [`NumberFormat::GROUPED`].
+    pub fn for_locale(locale: &str) -> NumberFormat {
+        let name = locale.split(['.', '@']).next().unwrap_or_default();
+        let language = name.split(['_', '-']).next().unwrap_or_default();
+        let (thousands, decimal) = match (language, name) {
+            (_, "de_CH" | "de_LI" | "it_CH" | "fr_CH") => ('\'', '.'),
+            (
+                "de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+                | "sr",
+                _,
+            ) => ('.', ','),
+            // Their narrow no-break space is shown as a plain one, which keeps
+            // the table's columns lined up in any terminal
+            (
+                "fr" | "ru" | "uk" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "hu"
+                | "bg" | "et" | "lt" | "lv",
+                _,
+            ) => (' ', ','),
+            _ => return NumberFormat::GROUPED,
+        };
+        NumberFormat {
+            thousands: Some(thousands),
+            decimal,
+        }
+    }
+
+    /// A count such as a number of files, e.g. 1,234,567.
+    pub fn count(self, n: u64) -> String {
+        match self.thousands {
+            Some(separator) => group_digits(&n.to_string(), separator),
+            None => n.to_string(),
+        }
+    }
+
+    /// A size as a number in the given unit, without its label: exact for
+    /// bytes, otherwise rounded to two decimal places. Plain byte counts in
+    /// [`SizeUnit::GroupedB`] are grouped even when this format is not.
+    pub fn number(self, size_bytes: u64, unit: SizeUnit) -> String {
+        match resolve_unit(size_bytes, unit) {
+            SizeUnit::B => self.count(size_bytes),
+            SizeUnit::GroupedB => {
+                group_digits(&size_bytes.to_string(), self.thousands.unwrap_or(','))
+            }
+            unit => {
+                let rounded = format!("{:.2}", format_size(size_bytes, unit));
+                let (whole, fraction) = rounded.split_once('.').unwrap_or((&rounded, ""));
+                let whole = match self.thousands {
+                    Some(separator) => group_digits(whole, separator),
+                    None => whole.to_string(),
+                };
+                format!("{}{}{}", whole, self.decimal, fraction)
+            }
+        }
+    }
+
+    /// A size with its unit label, e.g. "2.00 TB".
+    pub fn size(self, size_bytes: u64, unit: SizeUnit) -> String {
+        format!(
+            "{} {}",
+            self.number(size_bytes, unit),
+            get_unit_label(resolve_unit(size_bytes, unit))
+        )
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat::PLAIN
+    }
+}
+
+// Put `separator` between every three of `digits`, e.g. 1234567 as 1,234,567
+fn group_digits(digits: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Reverse;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    fn create_test_file(dir: &Path, name: &str, size_bytes: usize) -> std::io::Result<()> {
+        let file_path = dir.join(name);
+        let mut file = File::create(&file_path)?;
+        file.write_all(&vec![0u8; size_bytes])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_size_mb() {
+        let (size, unit) = parse_size("100MB").unwrap();
+        assert_eq!(size, 100 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::MiB);
+    }
+
+    #[test]
+    fn test_parse_size_m() {
+        let (size, unit) = parse_size("50M").unwrap();
+        assert_eq!(size, 50 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::MiB);
+    }
+
+    #[test]
+    fn test_parse_size_mb_lowercase() {
+        let (size, unit) = parse_size("100mb").unwrap();
+        assert_eq!(size, 100 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::MiB);
+    }
+
+    #[test]
+    fn test_parse_size_m_lowercase() {
+        let (size, unit) = parse_size("50m").unwrap();
+        assert_eq!(size, 50 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::MiB);
+    }
+
+    #[test]
+    fn test_parse_size_gb() {
+        let (size, unit) = parse_size("1GB").unwrap();
+        assert_eq!(size, 1024 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::GiB);
+    }
+
+    #[test]
+    fn test_parse_size_g() {
+        let (size, unit) = parse_size("2G").unwrap();
+        assert_eq!(size, 2 * 1024 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::GiB);
+    }
+
+    #[test]
+    fn test_parse_size_gb_lowercase() {
+        let (size, unit) = parse_size("1gb").unwrap();
+        assert_eq!(size, 1024 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::GiB);
+    }
+
+    #[test]
+    fn test_parse_size_g_lowercase() {
+        let (size, unit) = parse_size("2g").unwrap();
+        assert_eq!(size, 2 * 1024 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::GiB);
+    }
+
+    #[test]
+    fn test_parse_size_no_unit() {
+        let (size, unit) = parse_size("100").unwrap();
+        assert_eq!(size, 100 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::MiB);
+    }
+
+    #[test]
+    fn test_parse_size_other_units() {
+        assert_eq!(parse_size("500B"), Ok((500, SizeUnit::B)));
+        assert_eq!(parse_size("500KB"), Ok((500 * 1024, SizeUnit::KiB)));
+        assert_eq!(parse_size("4k"), Ok((4096, SizeUnit::KiB)));
+        assert_eq!(parse_size("1.5TB"), Ok((3 << 39, SizeUnit::TiB)));
+        assert_eq!(parse_size("2PB"), Ok((2 << 50, SizeUnit::PiB)));
+        assert_eq!(parse_size("8GiB"), Ok((8 << 30, SizeUnit::GiB)));
+    }
+
+    #[test]
+    fn test_parse_size_too_large() {
+        assert_eq!(
+            parse_size("100000PB"),
+            Err(ParseError::TooLarge("100000pb".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_size_invalid() {
+        assert_eq!(
+            parse_size("invalid"),
+            Err(ParseError::UnknownUnit("invalid".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_size_unknown_unit() {
+        assert_eq!(
+            parse_size("10XB"),
+            Err(ParseError::UnknownUnit("xb".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_size_empty() {
+        assert_eq!(parse_size(""), Err(ParseError::Empty));
+        assert_eq!(parse_size("   "), Err(ParseError::Empty));
+    }
+
+    #[test]
+    fn test_parse_size_missing_number() {
+        assert_eq!(
+            parse_size("GB"),
+            Err(ParseError::InvalidNumber(String::new()))
+        );
+    }
+
+    #[test]
+    fn test_parse_size_negative() {
+        assert_eq!(
+            parse_size("-5MB"),
+            Err(ParseError::InvalidNumber("-5".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_size_fractional() {
+        let (size, unit) = parse_size("0.5GB").unwrap();
+        assert_eq!(size, 512 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::GiB);
+    }
+
+    #[test]
+    fn test_parse_size_zero() {
+        let (size, unit) = parse_size("0").unwrap();
+        assert_eq!(size, 0);
+        assert_eq!(unit, SizeUnit::MiB);
+    }
+
+    #[test]
+    fn test_parse_size_large_value() {
+        let (size, unit) = parse_size("1000GB").unwrap();
+        assert_eq!(size, 1000 * 1024 * 1024 * 1024);
+        assert_eq!(unit, SizeUnit::GiB);
+    }
+
+    #[test]
+    fn test_format_size_mb_1mb() {
+        let mb_bytes = 1024 * 1024;
+        let size = format_size(mb_bytes, SizeUnit::MiB);
+        assert!((size - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_format_size_mb_100mb() {
+        let mb_bytes = 100 * 1024 * 1024;
+        let size = format_size(mb_bytes, SizeUnit::MiB);
+        assert!((size - 100.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_format_size_mb_fractional() {
+        let mb_bytes = (1024 * 1024) / 2;
+        let size = format_size(mb_bytes, SizeUnit::MiB);
+        assert!((size - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_format_size_gb_1gb() {
+        let gb_bytes = 1024 * 1024 * 1024;
+        let size = format_size(gb_bytes, SizeUnit::GiB);
+        assert!((size - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_format_size_gb_10gb() {
+        let gb_bytes = 10 * 1024 * 1024 * 1024;
+        let size = format_size(gb_bytes, SizeUnit::GiB);
+        assert!((size - 10.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_format_size_gb_fractional() {
+        let gb_bytes = (1024 * 1024 * 1024) / 2;
+        let size = format_size(gb_bytes, SizeUnit::GiB);
+        assert!((size - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_format_size_zero() {
+        let size = format_size(0, SizeUnit::MiB);
+        assert_eq!(size, 0.0);
+    }
+
+    #[test]
+    fn test_format_size_large_value() {
+        let large_bytes = 1000 * 1024 * 1024 * 1024;
+        let size = format_size(large_bytes, SizeUnit::GiB);
+        assert!((size - 1000.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_get_unit_label_mb() {
+        assert_eq!(get_unit_label(SizeUnit::MiB), "MiB");
+    }
+
+    #[test]
+    fn test_get_unit_label_gb() {
+        assert_eq!(get_unit_label(SizeUnit::GiB), "GiB");
+    }
+
+    #[test]
+    fn test_resolve_unit_auto() {
+        assert_eq!(resolve_unit(512, SizeUnit::Auto), SizeUnit::KiB);
+        assert_eq!(resolve_unit(3 * 1024 * 1024, SizeUnit::Auto), SizeUnit::MiB);
+        assert_eq!(resolve_unit(1 << 30, SizeUnit::Auto), SizeUnit::GiB);
+        assert_eq!(resolve_unit(2 << 40, SizeUnit::Auto), SizeUnit::TiB);
+        assert_eq!(resolve_unit(2 << 40, SizeUnit::MiB), SizeUnit::MiB);
+    }
+
+    #[test]
+    fn test_display_size() {
+        assert_eq!(display_size(2 << 40, SizeUnit::Auto), "2.00 TiB");
+        assert_eq!(display_size(1536, SizeUnit::Auto), "1.50 KiB");
+        assert_eq!(display_size(2 << 40, SizeUnit::MiB), "2097152.00 MiB");
+    }
+
+    #[test]
+    fn test_display_bytes() {
+        assert_eq!(display_size(1536, SizeUnit::B), "1536 B");
+        assert_eq!(display_number(1_234_567, SizeUnit::B), "1234567");
+        assert_eq!(display_number(1_234_567, SizeUnit::GroupedB), "1,234,567");
+        assert_eq!(display_size(123_456, SizeUnit::GroupedB), "123,456 B");
+        assert_eq!(display_number(999, SizeUnit::GroupedB), "999");
+        assert_eq!(display_number(0, SizeUnit::GroupedB), "0");
+    }
+
+    #[test]
+    fn test_number_formats() {
+        let german = NumberFormat::for_locale("de_DE.UTF-8");
+        let french = NumberFormat::for_locale("fr_FR");
+        assert_eq!(NumberFormat::GROUPED.count(1_234_567), "1,234,567");
+        assert_eq!(german.count(1_234_567), "1.234.567");
+        assert_eq!(french.count(999), "999");
+        assert_eq!(NumberFormat::PLAIN.count(1_234_567), "1234567");
+        assert_eq!(
+            NumberFormat::GROUPED.size(2 << 40, SizeUnit::MiB),
+            "2,097,152.00 MiB"
+        );
+        assert_eq!(german.size(1536, SizeUnit::Auto), "1,50 KiB");
+        assert_eq!(french.number(1_234_567_000, SizeUnit::KB), "1 234 567,00");
+        assert_eq!(german.number(1_234_567, SizeUnit::GroupedB), "1.234.567");
+        assert_eq!(
+            NumberFormat::PLAIN.number(1_234_567, SizeUnit::B),
+            "1234567"
+        );
+    }
+
+    #[test]
+    fn test_number_format_for_locale() {
+        assert_eq!(NumberFormat::for_locale("C"), NumberFormat::GROUPED);
+        assert_eq!(
+            NumberFormat::for_locale("en_US.UTF-8"),
+            NumberFormat::GROUPED
+        );
+        assert_eq!(NumberFormat::for_locale("pt_BR.UTF-8").decimal, ',');
+        assert_eq!(
+            NumberFormat::for_locale("de_CH.UTF-8").thousands,
+            Some('\'')
+        );
+        assert_eq!(NumberFormat::for_locale("sv_SE@euro").thousands, Some(' '));
+    }
+
+    #[test]
+    fn test_si_units() {
+        assert_eq!(SizeUnit::GiB.si(), SizeUnit::GB);
+        assert_eq!(SizeUnit::Auto.si(), SizeUnit::AutoSi);
+        assert_eq!(format_size(1_500_000, SizeUnit::MB), 1.5);
+        assert_eq!(display_size(2_500_000_000, SizeUnit::AutoSi), "2.50 GB");
+        assert_eq!(display_size(1_500, SizeUnit::AutoSi), "1.50 KB");
+    }
+
+    #[test]
+    fn test_list_big_files_empty_directory() {
+        let dir = tempdir().unwrap();
+        let (files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024).unwrap();
+        assert_eq!(files.len(), 0);
+        assert_eq!(scanned_count, 0);
+    }
+
+    #[test]
+    fn test_list_big_files_all_small_files() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "small1.txt", 1024).unwrap();
+        create_test_file(dir.path(), "small2.txt", 2048).unwrap();
+        create_test_file(dir.path(), "small3.txt", 4096).unwrap();
+
+        let (files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024).unwrap();
+        assert_eq!(files.len(), 0);
+        assert_eq!(scanned_count, 3);
+    }
+
+    #[test]
+    fn test_list_big_files_all_large_files() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "large1.txt", 150 * 1024 * 1024).unwrap();
+        create_test_file(dir.path(), "large2.txt", 200 * 1024 * 1024).unwrap();
+
+        let (mut files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024).unwrap();
+        files.sort_by_key(|f| Reverse(f.size_bytes));
+        assert_eq!(files.len(), 2);
+        assert_eq!(scanned_count, 2);
+        assert!(files[0].size_bytes > files[1].size_bytes);
+    }
+
+    #[test]
+    fn test_list_big_files_mixed_sizes() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "small.txt", 1024).unwrap();
+        create_test_file(dir.path(), "large.txt", 150 * 1024 * 1024).unwrap();
+        create_test_file(dir.path(), "medium.txt", 50 * 1024 * 1024).unwrap();
+        create_test_file(dir.path(), "huge.txt", 500 * 1024 * 1024).unwrap();
+
+        let (mut files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024).unwrap();
+        files.sort_by_key(|f| Reverse(f.size_bytes));
+        assert_eq!(files.len(), 2);
+        assert_eq!(scanned_count, 4);
+        assert!(files[0].size_bytes > files[1].size_bytes);
+    }
+
+    #[test]
+    fn test_list_big_files_nested_directories() {
+        let dir = tempdir().unwrap();
+        let subdir = dir.path().join("subdir");
+        fs::create_dir(&subdir).unwrap();
+        let nested = subdir.join("nested");
+        fs::create_dir(&nested).unwrap();
+
+        create_test_file(dir.path(), "root_file.txt", 150 * 1024 * 1024).unwrap();
+        create_test_file(&subdir, "sub_file.txt", 200 * 1024 * 1024).unwrap();
+        create_test_file(&nested, "nested_file.txt", 100 * 1024 * 1024).unwrap();
+
+        let (files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(scanned_count, 3);
+    }
+
+    #[test]
+    fn test_list_big_files_threshold_boundary() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "exactly_100mb.txt", 100 * 1024 * 1024).unwrap();
+        create_test_file(dir.path(), "just_under_100mb.txt", 100 * 1024 * 1024 - 1).unwrap();
+
+        let (files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(scanned_count, 2);
+        assert_eq!(files[0].size_bytes, 100 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_list_big_files_size_threshold_bytes() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "1mb.txt", 1024 * 1024).unwrap();
+        create_test_file(dir.path(), "2mb.txt", 2 * 1024 * 1024).unwrap();
+
+        let (files, scanned_count) = list_big_files(dir.path(), 1024 * 1024).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(scanned_count, 2);
+    }
+
+    #[test]
+    fn test_list_big_files_zero_threshold() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "tiny.txt", 1).unwrap();
+
+        let (files, scanned_count) = list_big_files(dir.path(), 0).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(scanned_count, 1);
+    }
+
+    #[test]
+    fn test_file_info_contains_correct_data() {
+        let dir = tempdir().unwrap();
+        let test_size = 150 * 1024 * 1024;
+        create_test_file(dir.path(), "test.txt", test_size).unwrap();
+
+        let (files, _) = list_big_files(dir.path(), 100 * 1024 * 1024).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].size_bytes, test_size as u64);
+        assert!(files[0].path.ends_with("test.txt"));
+    }
+
+    #[test]
+    fn test_escape_bytes() {
+        assert_eq!(escape_bytes(b"/data/caf\xe9.txt"), r"/data/caf\xe9.txt");
+        assert_eq!(escape_bytes(b"/data/a\\b\xff"), r"/data/a\\b\xff");
+        assert_eq!(escape_bytes("/data/café".as_bytes()), "/data/café");
+    }
+
+    #[test]
+    fn test_escape_wide() {
+        let units = [b'C' as u16, b':' as u16, b'\\' as u16, 0xd800, b'x' as u16];
+        assert_eq!(escape_wide(units.into_iter()), r"C:\\\u{d800}x");
+    }
+
+    // Names need not be UTF-8 on Unix; they are kept as they are and only
+    // replaced when written as text
+    #[cfg(unix)]
+    #[test]
+    fn test_paths_that_are_not_utf8_are_kept() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        let name = OsStr::from_bytes(b"caf\xe9.bin");
+        if fs::write(dir.path().join(name), [0u8; 10]).is_err() {
+            // The file system only takes UTF-8 names, e.g. on macOS
+            return;
+        }
+        let (files, _) = list_big_files(dir.path(), 0).unwrap();
+        assert_eq!(files[0].path, dir.path().join(name));
+        assert!(escape_path(&files[0].path).ends_with(r"caf\xe9.bin"));
+        let json = serde_json::to_value(&files[0]).unwrap();
+        assert!(json["path"].as_str().unwrap().ends_with("caf\u{fffd}.bin"));
+    }
+
+    #[test]
+    fn test_scan_with_invokes_callback_per_match() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "small.txt", 1024).unwrap();
+        create_test_file(dir.path(), "big1.txt", 2 * 1024 * 1024).unwrap();
+        create_test_file(dir.path(), "big2.txt", 3 * 1024 * 1024).unwrap();
+
+        let seen = Mutex::new(Vec::new());
+        let scanned_count = scan_with(dir.path(), 1024 * 1024, |file| {
+            seen.lock().unwrap().push(file.size_bytes);
+        })
+        .unwrap();
+
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort();
+        assert_eq!(scanned_count, 3);
+        assert_eq!(seen, vec![2 * 1024 * 1024, 3 * 1024 * 1024]);
+    }
+
+    #[test]
+    fn test_scan_with_no_matches_never_calls_back() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "small.txt", 1024).unwrap();
+
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let scanned_count = scan_with(dir.path(), 1024 * 1024, |_| {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        })
+        .unwrap();
+
+        assert_eq!(scanned_count, 1);
+        assert_eq!(calls.into_inner(), 0);
+    }
+
+    #[test]
+    fn test_list_big_files_missing_directory() {
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("does-not-exist");
+
+        let err = list_big_files(&missing, 0).unwrap_err();
+        assert!(matches!(err, ScanError::NotFound(path) if path == missing));
+    }
+
+    #[test]
+    fn test_list_big_files_not_a_directory() {
+        let dir = tempdir().unwrap();
+        create_test_file(dir.path(), "file.txt", 10).unwrap();
+
+        let err = list_big_files(&dir.path().join("file.txt"), 0).unwrap_err();
+        assert!(matches!(err, ScanError::NotADirectory(_)));
+    }
+}
@@ -0,0 +1,21 @@
+// Import the formats --log-format chooses between
+use crate::cli::LogFormat;
+// Import io for writing log lines to stderr
+use std::io;
+// Import LevelFilter for the most detailed level --log-level lets through
+use tracing::level_filters::LevelFilter;
+
+/// Send warnings, errors and the daemon's progress to stderr, as plain lines
+/// or as one JSON object per line for log collectors. Only events at `level`
+/// or more severe are written.
+pub fn init(level: LevelFilter, format: LogFormat) {
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_target(false);
+    // A subscriber can only be set once; tests and repeated calls keep the first
+    let _ = match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().flatten_event(true).try_init(),
+    };
+}
@@ -0,0 +1,125 @@
+// Import Cow so paths that need no prefix are passed through without copying
+use std::borrow::Cow;
+// Import Path types for the paths being converted
+use std::path::Path;
+#[cfg(windows)]
+use std::path::PathBuf;
+
+// Prefix of Win32 paths that bypass the MAX_PATH limit, and its form for
+// network shares
+#[cfg(any(windows, test))]
+const VERBATIM: &str = r"\\?\";
+#[cfg(any(windows, test))]
+const VERBATIM_UNC: &str = r"\\?\UNC\";
+
+/// `path` in a form the file system accepts past the 260-character MAX_PATH
+/// limit. On Windows this is the absolute path with the `\\?\` prefix (or
+/// `\\?\UNC\` for network shares); elsewhere, and for paths that already
+/// carry a prefix, it is `path` itself.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        // Verbatim paths skip normalization, so `.`, `..` and forward slashes
+        // are resolved first
+        let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        match absolute.to_str().and_then(extend) {
+            Some(extended) => Cow::Owned(PathBuf::from(extended)),
+            None => Cow::Borrowed(path),
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        Cow::Borrowed(path)
+    }
+}
+
+/// `path` without the prefix [`long_path`] adds, as users would type it.
+/// Only Windows paths carry one.
+pub fn plain_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    if let Some(plain) = path.to_str().and_then(strip) {
+        return Cow::Owned(PathBuf::from(plain));
+    }
+    Cow::Borrowed(path)
+}
+
+// The verbatim form of an absolute Windows path: C:\dir becomes \\?\C:\dir
+// and \\server\share becomes \\?\UNC\server\share. None for relative paths,
+// which cannot carry the prefix, and for paths already in a device namespace
+#[cfg(any(windows, test))]
+fn extend(path: &str) -> Option<String> {
+    if path.starts_with(VERBATIM) || path.starts_with(r"\\.\") {
+        return None;
+    }
+    if let Some(share) = path.strip_prefix(r"\\") {
+        return Some(format!("{}{}", VERBATIM_UNC, share));
+    }
+    let bytes = path.as_bytes();
+    let drive =
+        bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\';
+    drive.then(|| format!("{}{}", VERBATIM, path))
+}
+
+// The plain form of a verbatim path, or None when `path` has no prefix
+#[cfg(any(windows, test))]
+fn strip(path: &str) -> Option<String> {
+    if let Some(share) = path.strip_prefix(VERBATIM_UNC) {
+        return Some(format!(r"\\{}", share));
+    }
+    path.strip_prefix(VERBATIM).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extend_drive_and_share_paths() {
+        assert_eq!(
+            extend(r"C:\Users\dev\node_modules").as_deref(),
+            Some(r"\\?\C:\Users\dev\node_modules")
+        );
+        assert_eq!(
+            extend(r"\\fileserver\builds\app").as_deref(),
+            Some(r"\\?\UNC\fileserver\builds\app")
+        );
+    }
+
+    #[test]
+    fn test_extend_leaves_other_paths() {
+        assert_eq!(extend(r"\\?\C:\already"), None);
+        assert_eq!(extend(r"\\.\PhysicalDrive0"), None);
+        assert_eq!(extend(r"relative\dir"), None);
+        assert_eq!(extend("C:"), None);
+    }
+
+    #[test]
+    fn test_strip_undoes_extend() {
+        let deep = format!(r"C:\src{}", r"\node_modules\some-package".repeat(20));
+        assert!(deep.len() > 260);
+        for path in [deep.as_str(), r"\\fileserver\builds\app"] {
+            assert_eq!(strip(&extend(path).unwrap()).as_deref(), Some(path));
+        }
+        assert_eq!(strip(r"C:\plain"), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_long_path_round_trips() {
+        let path = Path::new("some/dir");
+        let long = long_path(path);
+        assert!(long.to_str().unwrap().starts_with(VERBATIM));
+        assert_eq!(plain_path(&long), std::path::absolute(path).unwrap());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_long_path_leaves_other_platforms_alone() {
+        let path = Path::new(r"\\?\C:\dir");
+        assert_eq!(
+            long_path(Path::new("some/dir")).as_ref(),
+            Path::new("some/dir")
+        );
+        assert_eq!(plain_path(path).as_ref(), path);
+    }
+}
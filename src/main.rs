@@ -1,465 +1,2426 @@
-// Import rayon for parallel iteration capabilities
-use rayon::prelude::*;
+mod actions;
+mod aggregate;
+mod alert;
+mod archive;
+#[cfg(feature = "azure")]
+mod azure;
+mod bench;
+mod checksum;
+mod cleanup;
+mod cli;
+mod cloud;
+mod color;
+mod columns;
+mod compress;
+mod daemon;
+mod deleted;
+mod diff;
+mod disk;
+mod docker;
+mod dupes;
+mod email;
+mod eta;
+mod filetype;
+#[cfg(feature = "gcs")]
+mod gcs;
+mod git;
+mod interactive;
+mod logging;
+mod metrics;
+mod netfs;
+mod pager;
+mod preset;
+mod remote;
+mod report;
+#[cfg(feature = "s3")]
+mod s3;
+mod score;
+#[cfg(feature = "scripting")]
+mod script;
+mod server;
+#[cfg(feature = "sftp")]
+mod sftp;
+mod stat;
+mod state;
+#[cfg(any(feature = "s3", feature = "azure", feature = "gcs"))]
+mod store;
+mod syslog;
+mod template;
+mod treemap;
+mod tui;
+mod update;
+mod watch;
+#[cfg(feature = "webdav")]
+mod webdav;
+
+// Import the actions applied to matches after the scan
+use actions::{Action, Outcome, Removal};
+// Import the argument parser and help text
+use cli::{
+    BenchOptions, Command, DiffOptions, DockerOptions, GitOptions, GroupBy, LogFormat, Options,
+    OutputFormat, PathStyle, Placeholders, ShowOptions, SortOrder, UpdateOptions,
+};
+// Import the scanning core shared with library users
+use list_big_files::{
+    check_root, display_size, escape_path, format_size, get_unit_label, plain_path, resolve_unit,
+    Backend, FileInfo, NumberFormat, ScanCache, ScanError, ScanStats, Scanner, SizeUnit, Tier,
+};
+// Import the gauges served by --metrics
+use metrics::Metrics;
+// Import the saved scan format used by --format json, --compare and diff
+use report::{DirsReport, ScanInfo, ScanMetrics, ScanReport};
+// Import Remote for roots listed through a storage service
+use remote::Remote;
+// Import Volume for the free space shown after the table
+use disk::Volume;
+// Import the size tiers rows are colored by
+use color::Tiers;
+// Import the fields --columns chooses between
+use columns::Column;
+// Import the threshold crossings reported by --watch
+use watch::Crossing;
+// Import the time-remaining estimator and its totals cache
+use eta::EtaEstimator;
+// Import indicatif for the interactive progress spinner
+use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+// Import Cow for reports printed with or without --relative paths
+use std::borrow::Cow;
+// Import Reverse for sorting largest first
+use std::cmp::Reverse;
 // Import env for accessing command line arguments
 use std::env;
-// Import Path for handling file system paths
-use std::path::Path;
-// Import Instant for timing the scan operation
-use std::time::Instant;
-// Import WalkDir for recursively walking directory trees
-use walkdir::WalkDir;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum SizeUnit {
-    MB,
-    GB,
-}
-
-// Struct to hold file path and size information
-#[derive(Debug)]
-struct FileInfo {
-    path: String,
-    size_bytes: u64,
-}
-
-// Parse size string with optional unit suffix (g, gb, m, mb) and return size in MB and display unit
-fn parse_size(size_str: &str) -> (f64, SizeUnit) {
-    let size_str = size_str.to_lowercase();
-    let (num, multiplier, unit) = if size_str.ends_with("gb") {
-        (&size_str[..size_str.len() - 2], 1024.0, SizeUnit::GB)
-    } else if size_str.ends_with("g") {
-        (&size_str[..size_str.len() - 1], 1024.0, SizeUnit::GB)
-    } else if size_str.ends_with("mb") {
-        (&size_str[..size_str.len() - 2], 1.0, SizeUnit::MB)
-    } else if size_str.ends_with("m") {
-        (&size_str[..size_str.len() - 1], 1.0, SizeUnit::MB)
-    } else {
-        (size_str.as_str(), 1.0, SizeUnit::MB)
+// Import fs for creating the daemon's report directory
+use std::fs;
+// Import IsTerminal to only draw progress on an interactive stderr, and Write
+// for action output that goes to stdout or stderr
+use std::io::{self, IsTerminal, Write};
+// Import Path for report files named on the command line, and PathBuf for
+// paths as printed
+use std::path::{Path, PathBuf};
+// Import process for exiting with a failure status
+use std::process;
+// Import atomics for the Ctrl+C cancellation flag
+use std::sync::atomic::{AtomicBool, Ordering};
+// Import Arc and Mutex for sharing the flag and collecting matches
+use std::sync::{Arc, Mutex};
+// Import Duration and Instant for timing the scan operation
+use std::time::{Duration, Instant, SystemTime};
+// Import the tracing macros diagnostics are logged with
+use tracing::{debug, error, info, warn};
+// Import LevelFilter for the log level of subcommands
+use tracing::level_filters::LevelFilter;
+
+// Build a stderr spinner showing files scanned, current directory and
+// elapsed time, or None when stderr is not a terminal
+fn progress_bar() -> Option<ProgressBar> {
+    if !io::stderr().is_terminal() {
+        return None;
+    }
+
+    let bar = ProgressBar::new_spinner();
+    bar.set_style(
+        ProgressStyle::with_template(
+            "{spinner} [{elapsed_precise}] {pos} files scanned  {wide_msg}",
+        )
+        .unwrap(),
+    );
+    bar.enable_steady_tick(Duration::from_millis(100));
+    Some(bar)
+}
+
+// Format a single result row of the table
+fn format_row(file: &FileInfo, unit: SizeUnit, numbers: NumberFormat) -> String {
+    format!(
+        "{:>14}  {}",
+        size_cell_in(file.size_bytes, unit, numbers),
+        file.path.display()
+    )
+}
+
+// Title of a size column, with the unit unless each row names its own
+fn size_header(title: &str, unit: SizeUnit) -> String {
+    match unit {
+        SizeUnit::Auto | SizeUnit::AutoSi => title.to_string(),
+        unit => format!("{} ({})", title, get_unit_label(unit)),
+    }
+}
+
+// A size in a column titled by size_header
+fn size_cell(size_bytes: u64, unit: SizeUnit) -> String {
+    size_cell_in(size_bytes, unit, NumberFormat::PLAIN)
+}
+
+// A size in a column titled by size_header, written in the given style
+fn size_cell_in(size_bytes: u64, unit: SizeUnit, numbers: NumberFormat) -> String {
+    match unit {
+        SizeUnit::Auto | SizeUnit::AutoSi => numbers.size(size_bytes, unit),
+        unit => numbers.number(size_bytes, unit),
+    }
+}
+
+// A size threshold as given on the command line, e.g. "100 MB"
+fn threshold(size_bytes: u64, unit: SizeUnit) -> String {
+    match resolve_unit(size_bytes, unit) {
+        SizeUnit::B | SizeUnit::GroupedB => display_size(size_bytes, unit),
+        unit => format!("{} {}", format_size(size_bytes, unit), get_unit_label(unit)),
+    }
+}
+
+// Exit status used when a scan is interrupted, matching shells' 128 + SIGINT
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+// Exit status used when the matches exceed the --alert-total budget
+const OVER_BUDGET_EXIT_CODE: i32 = 3;
+
+// Set once a scan's matches exceed --alert-total, so the process can fail
+// after its report is out
+static OVER_BUDGET: AtomicBool = AtomicBool::new(false);
+
+// Plugins loaded with --plugin, shared by every scan of the run
+#[cfg(feature = "plugins")]
+static PLUGINS: std::sync::OnceLock<Vec<list_big_files::Plugin>> = std::sync::OnceLock::new();
+
+// WebAssembly filters loaded with --plugin, shared like PLUGINS
+#[cfg(feature = "wasm")]
+static WASM_FILTERS: std::sync::OnceLock<Vec<list_big_files::WasmFilter>> =
+    std::sync::OnceLock::new();
+
+// The --filter-script, shared like PLUGINS
+#[cfg(feature = "scripting")]
+static FILTER_SCRIPT: std::sync::OnceLock<script::FilterScript> = std::sync::OnceLock::new();
+
+// Label on every line --dry-run prints, whichever format the report is in
+const DRY_RUN: &str = "[DRY RUN] ";
+
+// Set a flag on the first Ctrl+C so the scan can stop and report what it has;
+// a second Ctrl+C exits immediately
+fn install_interrupt_handler() -> Arc<AtomicBool> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = interrupted.clone();
+    let result = ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::SeqCst) {
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+    });
+    if let Err(err) = result {
+        warn!("cannot handle Ctrl+C: {}", err);
+    }
+    interrupted
+}
+
+fn main() {
+    let command = cli::parse_args(env::args().skip(1));
+    // Scans choose how diagnostics are logged; subcommands log at the defaults
+    match &command {
+        Ok(Command::Scan(options)) => logging::init(options.log_level, options.log_format),
+        _ => logging::init(LevelFilter::INFO, LogFormat::Text),
+    }
+    let mut options = match command {
+        Ok(Command::Help) => {
+            cli::print_help();
+            return;
+        }
+        Ok(Command::Scan(options)) => options,
+        Ok(Command::Show(options)) => {
+            run_show(&options);
+            return;
+        }
+        Ok(Command::Diff(options)) => {
+            run_diff(&options);
+            return;
+        }
+        Ok(Command::Git(options)) => {
+            run_git(&options);
+            return;
+        }
+        Ok(Command::Docker(options)) => {
+            run_docker(&options);
+            return;
+        }
+        Ok(Command::Bench(options)) => {
+            run_bench(&options);
+            return;
+        }
+        Ok(Command::SelfUpdate(options)) => {
+            run_self_update(&options);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            eprintln!("Run 'list-big-files --help' for usage.");
+            process::exit(2);
+        }
     };
 
-    (num.parse::<f64>().unwrap_or(100.0) * multiplier, unit)
-}
-
-// Display help information with usage examples
-fn print_help() {
-    println!("list-big-files - Find large files in a directory");
-    println!();
-    println!("USAGE:");
-    println!("    list-big-files [DIRECTORY] [SIZE]");
-    println!("    list-big-files --help");
-    println!("    list-big-files help");
-    println!();
-    println!("ARGUMENTS:");
-    println!("    DIRECTORY    Path to directory to scan (default: current directory)");
-    println!("    SIZE         Minimum file size with optional unit");
-    println!("                 - Without unit: interpreted as MB (e.g., 100 = 100MB)");
-    println!("                 - With unit: MB or GB (e.g., 50MB, 1GB, 2G, 500M)");
-    println!("                 Default: 100MB");
-    println!();
-    println!("EXAMPLES:");
-    println!("    list-big-files /home/user/documents");
-    println!("        Scan documents for files >= 100MB (default)");
-    println!();
-    println!("    list-big-files . 50MB");
-    println!("        Scan current directory for files >= 50MB");
-    println!();
-    println!("    list-big-files /path 1GB");
-    println!("        Scan /path for files >= 1GB");
-    println!();
-    println!("    list-big-files ~/Downloads 200M");
-    println!("        Scan Downloads for files >= 200MB");
-    println!();
-    println!("OUTPUT:");
-    println!("    Files are sorted by size (largest first) with scan timing information");
-}
-
-fn list_big_files(directory: &Path, min_size_bytes: u64) -> (Vec<FileInfo>, usize) {
-    let start = Instant::now();
+    if let Some(err) = &options.ignored_size {
+        warn!(
+            "{}; using the default of {}",
+            err,
+            threshold(options.min_size_bytes, options.display_unit)
+        );
+    }
 
-    let all_files: Vec<_> = WalkDir::new(directory)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.file_type().is_file())
-        .collect();
+    load_plugins(&options);
 
-    let scanned_count = all_files.len();
+    if let Some(remote) = &options.remote {
+        run_remote(&options, remote);
+        exit_if_over_budget();
+        return;
+    }
 
-    let files: Vec<FileInfo> = all_files
-        .into_par_iter()
-        .filter_map(|entry| {
-            let path = entry.path();
-            let metadata = path.metadata().ok()?;
-            let size_bytes = metadata.len();
+    // Report a missing or mistyped directory before anything else is printed
+    if let Err(err) = check_root(&options.directory) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
 
-            if size_bytes >= min_size_bytes {
-                Some(FileInfo {
-                    path: path.display().to_string(),
-                    size_bytes,
-                })
-            } else {
-                None
+    // Every path is built from the root, so resolving it makes them all
+    // absolute. Windows resolves to a \\?\ path, which is shown without the prefix
+    if options.paths == PathStyle::Absolute {
+        options.directory = match fs::canonicalize(&options.directory) {
+            Ok(directory) => plain_path(&directory).into_owned(),
+            Err(err) => {
+                eprintln!("error: cannot resolve {:?}: {}", options.directory, err);
+                process::exit(1);
             }
-        })
-        .collect();
+        };
+    }
 
-    let duration = start.elapsed();
-    println!("Scanned in: {:.2}s", duration.as_secs_f64());
+    if let Some(addr) = &options.serve {
+        run_server(addr, &options);
+        return;
+    }
 
-    (files, scanned_count)
+    // Start the metrics endpoint before scanning so it is reachable right away
+    let metrics = options.metrics.as_deref().map(|addr| {
+        let metrics = Arc::new(Metrics::new(&options.directory));
+        if let Err(err) = metrics::serve(addr, metrics.clone()) {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+        metrics
+    });
+
+    // Nothing is walked, so network mounts do not matter
+    if options.deleted_open {
+        run_deleted_open(&options);
+        return;
+    }
+    warn_network_mounts(&options);
+    if options.dirs || options.preset.is_some_and(|preset| preset.reports_dirs()) {
+        run_dirs(&options);
+    } else if options.suggest_cleanup {
+        run_cleanup(&options);
+    } else if options.by_user {
+        run_by_user(&options);
+    } else if options.daemon {
+        run_daemon(&options, metrics.as_deref());
+    } else {
+        run_scan(&options, metrics.as_deref());
+        exit_if_over_budget();
+    }
 }
 
-fn format_size(size_bytes: u64, unit: SizeUnit) -> f64 {
-    match unit {
-        SizeUnit::MB => size_bytes as f64 / (1024.0 * 1024.0),
-        SizeUnit::GB => size_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+// Fail the run if a scan's matches went over the --alert-total budget
+fn exit_if_over_budget() {
+    if OVER_BUDGET.load(Ordering::SeqCst) {
+        process::exit(OVER_BUDGET_EXIT_CODE);
     }
 }
 
-fn get_unit_label(unit: SizeUnit) -> &'static str {
-    match unit {
-        SizeUnit::MB => "MB",
-        SizeUnit::GB => "GB",
+// Warn about network and FUSE file systems in the tree, whose files are slow
+// to read and often not meant to be included
+fn warn_network_mounts(options: &Options) {
+    for mount in netfs::network_mounts(&options.directory) {
+        if mount.path == options.directory {
+            warn!(
+                "{} is on a network file system ({}), so the scan may be slow",
+                mount.path.display(),
+                mount.fs_type
+            );
+        } else if !options.skip_network {
+            warn!(
+                "{} is a network file system ({}) and will be scanned; \
+                 use --skip-network to leave it out",
+                mount.path.display(),
+                mount.fs_type
+            );
+        }
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+fn run_server(addr: &str, options: &Options) {
+    let stop = install_interrupt_handler();
+    let config = server::Config {
+        directory: options.directory.clone(),
+        min_size_bytes: options.min_size_bytes,
+        backend: options.backend,
+        threads: options.threads,
+        ads: options.ads,
+        follow_links: options.follow_links,
+        bundle_as_file: options.bundle_as_file,
+        skip_network: options.skip_network,
+    };
+    info!("listening on http://{} (Ctrl+C to stop)", addr);
+    if let Err(err) = server::run(addr, config, &stop) {
+        error!("{}", err);
+        process::exit(1);
+    }
+}
 
-    // Check for help arguments
-    if args.len() > 1 && (args[1] == "--help" || args[1] == "help") {
-        print_help();
-        return;
+// Total the directories --dirs or a directory preset such as --preset dev
+// asks for
+fn run_dirs(options: &Options) {
+    let unit = options.display_unit;
+    let numbers = options.numbers;
+    let json = options.format == OutputFormat::Json;
+    let preset = options.preset.filter(|preset| preset.reports_dirs());
+    if !json {
+        println!(
+            "Scanning {:?} for {} >= {}...\n",
+            options.directory,
+            match preset {
+                Some(_) => "build and dependency directories",
+                None => "directories",
+            },
+            threshold(options.min_size_bytes, unit)
+        );
     }
 
-    // Parse directory argument, default to current directory if not provided
-    let directory = if args.len() > 1 {
-        Path::new(&args[1])
+    // Every file counts towards its directories, so nothing is filtered out
+    let started = SystemTime::now();
+    let start = Instant::now();
+    let progress = progress_bar();
+    let interrupted = install_interrupt_handler();
+    let stats = ScanStats::new();
+    let mut scanner = base_scanner(options, &interrupted)
+        .min_size(0)
+        .stats(&stats);
+    let totals_file = eta::totals_file(options.state_dir.as_deref());
+    if let Some(bar) = progress.clone() {
+        scanner = show_progress(scanner, bar, &options.directory, totals_file.as_deref());
+    }
+
+    let totals = Mutex::new(aggregate::DirTotals::new(&options.directory, options.depth));
+    let result = scanner.scan_with(|file| {
+        let Some(preset) = preset else {
+            totals.lock().unwrap().add(&file.path, file.size_bytes);
+            return;
+        };
+        // Files outside the directories the preset is after count for nothing
+        let relative = file
+            .path
+            .strip_prefix(&options.directory)
+            .unwrap_or(&file.path);
+        if let Some(dir) = preset.total_dir(relative) {
+            let dir = options.directory.join(dir);
+            totals.lock().unwrap().add_to(&dir, file.size_bytes);
+        }
+    });
+    if let Some(bar) = &progress {
+        bar.finish_and_clear();
+    }
+    let scanned_count = result.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
+    if options.stats {
+        print_stats(&stats, scanned_count, start.elapsed());
+    }
+    let partial = interrupted.load(Ordering::SeqCst);
+    if let Some(file) = totals_file.as_ref().filter(|_| !partial) {
+        let _ = eta::save_total(file, &options.directory, scanned_count);
+    }
+    let dirs = totals.into_inner().unwrap().largest(options.min_size_bytes);
+
+    if json {
+        let scan = ScanInfo::new(started, start.elapsed(), stats.errors());
+        let report = DirsReport::new(
+            &options.directory,
+            options.min_size_bytes,
+            scanned_count,
+            partial,
+            &dirs,
+            scan,
+        );
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
     } else {
-        Path::new(".")
-    };
+        let _pager = start_pager(options);
+        println!("Scanned in: {:.2}s", start.elapsed().as_secs_f64());
+        println!(
+            "{:<15} {:>10}  Directory",
+            size_header("Size", unit),
+            "Files"
+        );
+        println!("{}", "-".repeat(80));
+        for dir in &dirs {
+            println!(
+                "{:>14}  {:>10}  {}",
+                size_cell_in(dir.total.size_bytes, unit, numbers),
+                numbers.count(dir.total.files),
+                dir.path.display()
+            );
+        }
+        println!(
+            "\nTotal: {} directories (scanned {} files)",
+            numbers.count(dirs.len() as u64),
+            numbers.count(scanned_count as u64)
+        );
+    }
+
+    if partial {
+        if !json {
+            println!("Partial scan: interrupted before completion");
+        }
+        process::exit(INTERRUPTED_EXIT_CODE);
+    }
+}
+
+fn run_cleanup(options: &Options) {
+    let unit = options.display_unit;
+    let numbers = options.numbers;
+    let json = options.format == OutputFormat::Json;
+    if !json {
+        println!(
+            "Scanning {:?} for caches and build output >= {}...\n",
+            options.directory,
+            threshold(options.min_size_bytes, unit)
+        );
+    }
+
+    // Every file counts towards the directory holding it, as for --dirs
+    let start = Instant::now();
+    let progress = progress_bar();
+    let interrupted = install_interrupt_handler();
+    let stats = ScanStats::new();
+    let mut scanner = base_scanner(options, &interrupted)
+        .min_size(0)
+        .stats(&stats);
+    if let Some(bar) = progress.clone() {
+        scanner = show_progress(scanner, bar, &options.directory, None);
+    }
+
+    let cleanup = Mutex::new(cleanup::Cleanup::new(&options.directory));
+    let result = scanner.scan_with(|file| {
+        cleanup.lock().unwrap().add(&file.path, file.size_bytes);
+    });
+    if let Some(bar) = &progress {
+        bar.finish_and_clear();
+    }
+    let scanned_count = result.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
+    if options.stats {
+        print_stats(&stats, scanned_count, start.elapsed());
+    }
+    let suggestions = cleanup
+        .into_inner()
+        .unwrap()
+        .suggestions(options.min_size_bytes);
 
-    // Parse minimum size argument, default to 100MB if not provided
-    let (min_size_mb, display_unit) = if args.len() > 2 {
-        parse_size(&args[2])
+    if json {
+        println!("{}", serde_json::to_string_pretty(&suggestions).unwrap());
     } else {
-        (100.0, SizeUnit::MB)
-    };
+        let _pager = start_pager(options);
+        println!("Scanned in: {:.2}s", start.elapsed().as_secs_f64());
+        println!(
+            "{:<15} {:>10}  {:<20} Directory",
+            size_header("Size", unit),
+            "Files",
+            "Kind"
+        );
+        println!("{}", "-".repeat(80));
+        for suggestion in &suggestions {
+            println!(
+                "{:>14}  {:>10}  {:<20} {}",
+                size_cell_in(suggestion.total.size_bytes, unit, numbers),
+                numbers.count(suggestion.total.files),
+                suggestion.kind,
+                suggestion.path.display()
+            );
+            println!("{:>28}-> {}", "", suggestion.command);
+        }
+        let reclaimable: u64 = suggestions.iter().map(|s| s.total.size_bytes).sum();
+        println!(
+            "\nTotal: {} reclaimable in {} directories (scanned {} files)",
+            numbers.size(reclaimable, unit),
+            numbers.count(suggestions.len() as u64),
+            numbers.count(scanned_count as u64)
+        );
+    }
 
-    let min_size_bytes = (min_size_mb * 1024.0 * 1024.0) as u64;
+    if interrupted.load(Ordering::SeqCst) {
+        if !json {
+            println!("Partial scan: interrupted before completion");
+        }
+        process::exit(INTERRUPTED_EXIT_CODE);
+    }
+}
 
-    // Display scan progress information
+// List deleted files still held open below the directory, with the processes
+// holding them
+fn run_deleted_open(options: &Options) {
+    let unit = options.display_unit;
+    let numbers = options.numbers;
+    let (files, unreadable) = deleted::find(&options.directory, options.min_size_bytes)
+        .unwrap_or_else(|err| {
+            eprintln!("error: cannot list open files: {}", err);
+            process::exit(1);
+        });
+    if unreadable > 0 {
+        warn!(
+            "cannot see the open files of {} processes; run as root to include them",
+            numbers.count(unreadable as u64)
+        );
+    }
+
+    if options.format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&files).unwrap());
+        return;
+    }
+    let _pager = start_pager(options);
+    println!("{:<15} Path", size_header("Size", unit));
+    println!("{}", "-".repeat(80));
+    for file in &files {
+        println!(
+            "{:>14}  {}",
+            size_cell_in(file.size_bytes, unit, numbers),
+            file.path.display()
+        );
+        for holder in &file.holders {
+            println!(
+                "{:16}held by {} (pid {}, fd {})",
+                "", holder.command, holder.pid, holder.fd
+            );
+        }
+    }
+    let total: u64 = files.iter().map(|file| file.size_bytes).sum();
     println!(
-        "Scanning {:?} for files >= {} {}...\n",
-        directory,
-        format_size(min_size_bytes, display_unit),
-        get_unit_label(display_unit)
+        "\nTotal: {} deleted files, {} held open",
+        numbers.count(files.len() as u64),
+        numbers.size(total, unit)
     );
+}
 
-    // Scan for large files and sort results by size (largest first)
-    let (mut files, scanned_count) = list_big_files(directory, min_size_bytes);
-    files.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+fn run_by_user(options: &Options) {
+    let unit = options.display_unit;
+    let numbers = options.numbers;
+    let json = options.format == OutputFormat::Json;
+    if !json {
+        println!(
+            "Scanning {:?} for users owning >= {}...\n",
+            options.directory,
+            threshold(options.min_size_bytes, unit)
+        );
+    }
 
-    // Print table header for results
-    println!(
-        "{:<15} Path",
-        format!("Size ({})", get_unit_label(display_unit))
-    );
-    println!("{}", "-".repeat(80));
+    // Every file counts towards its owner's footprint, as for --dirs
+    let start = Instant::now();
+    let progress = progress_bar();
+    let interrupted = install_interrupt_handler();
+    let stats = ScanStats::new();
+    let mut scanner = base_scanner(options, &interrupted)
+        .min_size(0)
+        .stats(&stats);
+    if let Some(bar) = progress.clone() {
+        scanner = show_progress(scanner, bar, &options.directory, None);
+    }
 
-    // Iterate and display each file with formatted output
-    for file in &files {
+    let owners = stat::Owners::default();
+    let totals = Mutex::new(aggregate::UserTotals::default());
+    let result = scanner.scan_with(|file| {
+        let user = owners
+            .user(&file.path)
+            .unwrap_or_else(|| "(unknown)".to_string());
+        totals
+            .lock()
+            .unwrap()
+            .add(&user, &file.path, file.size_bytes);
+    });
+    if let Some(bar) = &progress {
+        bar.finish_and_clear();
+    }
+    let scanned_count = result.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
+    if options.stats {
+        print_stats(&stats, scanned_count, start.elapsed());
+    }
+    let users = totals.into_inner().unwrap().largest(options.min_size_bytes);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&users).unwrap());
+    } else {
+        let _pager = start_pager(options);
+        println!("Scanned in: {:.2}s", start.elapsed().as_secs_f64());
+        println!("{:<15} {:>10}  User", size_header("Size", unit), "Files");
+        println!("{}", "-".repeat(80));
+        for user in &users {
+            println!(
+                "{:>14}  {:>10}  {}",
+                size_cell_in(user.total.size_bytes, unit, numbers),
+                numbers.count(user.total.files),
+                user.user
+            );
+            for file in &user.largest {
+                println!(
+                    "{:>28}{:>14}  {}",
+                    "",
+                    size_cell_in(file.size_bytes, unit, numbers),
+                    file.path.display()
+                );
+            }
+        }
         println!(
-            "{:>14.2}  {}",
-            format_size(file.size_bytes, display_unit),
-            file.path
+            "\nTotal: {} users (scanned {} files)",
+            numbers.count(users.len() as u64),
+            numbers.count(scanned_count as u64)
         );
     }
 
-    // Display total count of large files found and total files scanned
-    println!(
-        "\nTotal: {} files (scanned {} files)",
-        files.len(),
-        scanned_count
+    if interrupted.load(Ordering::SeqCst) {
+        if !json {
+            println!("Partial scan: interrupted before completion");
+        }
+        process::exit(INTERRUPTED_EXIT_CODE);
+    }
+}
+
+// Page the table when it goes to a terminal, unless rows were already
+// printed as they were found or the terminal is needed once it is shown
+fn start_pager(options: &Options) -> Option<pager::Pager> {
+    if options.live || options.watch || options.output.is_some() || options.action().is_some() {
+        return None;
+    }
+    options.pager.as_deref().and_then(pager::start)
+}
+
+// Load the --plugin libraries and modules and the --filter-script, failing
+// the run if any cannot be used
+fn load_plugins(options: &Options) {
+    #[cfg(feature = "plugins")]
+    {
+        let plugins = options
+            .plugins
+            .iter()
+            .filter(|path| !cli::is_wasm(path))
+            .map(|path| {
+                // SAFETY: the plugins were named on the command line, so they
+                // are trusted as much as this binary
+                unsafe { list_big_files::Plugin::load(path) }.unwrap_or_else(|err| {
+                    eprintln!("error: {}", err);
+                    process::exit(1);
+                })
+            })
+            .collect();
+        let _ = PLUGINS.set(plugins);
+    }
+    #[cfg(feature = "wasm")]
+    {
+        let filters = options
+            .plugins
+            .iter()
+            .filter(|path| cli::is_wasm(path))
+            .map(|path| {
+                list_big_files::WasmFilter::load(path).unwrap_or_else(|err| {
+                    eprintln!("error: {}", err);
+                    process::exit(1);
+                })
+            })
+            .collect();
+        let _ = WASM_FILTERS.set(filters);
+    }
+    #[cfg(feature = "scripting")]
+    if let Some(path) = &options.filter_script {
+        let script = script::FilterScript::load(path).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+        let _ = FILTER_SCRIPT.set(script);
+    }
+    #[cfg(not(any(feature = "plugins", feature = "wasm", feature = "scripting")))]
+    let _ = options;
+}
+
+// Scanner for the command line's directory, threshold and tuning options
+fn base_scanner<'a>(options: &Options, stop: &Arc<AtomicBool>) -> Scanner<'a> {
+    let mut scanner = Scanner::new(&options.directory)
+        .min_size(options.min_size_bytes)
+        .cancel_on(stop.clone())
+        .backend(options.backend)
+        .alternate_streams(options.ads)
+        .follow_links(options.follow_links)
+        .bundles_as_files(options.bundle_as_file);
+    if options.skip_network {
+        scanner = scanner.skip_dirs(netfs::mounts_below(&options.directory));
+    }
+    if let Some(preset) = options.preset {
+        scanner = scanner.skip_dir_names(preset.skipped_dirs());
+    }
+    #[cfg(feature = "plugins")]
+    for plugin in PLUGINS.get().into_iter().flatten() {
+        if plugin.is_filter() {
+            scanner = scanner
+                .filter(move |file: &FileInfo| list_big_files::FileFilter::keep(plugin, file));
+        }
+    }
+    // A module that fails on a match keeps it, so nothing is hidden by a bug
+    #[cfg(feature = "wasm")]
+    for module in WASM_FILTERS.get().into_iter().flatten() {
+        scanner = scanner.filter(move |file: &FileInfo| {
+            module.call(file).unwrap_or_else(|err| {
+                warn!(
+                    "{} failed on {}, so it is kept: {}",
+                    module.path().display(),
+                    file.path.display(),
+                    err
+                );
+                true
+            })
+        });
+    }
+    #[cfg(feature = "scripting")]
+    if let Some(script) = FILTER_SCRIPT.get() {
+        scanner = scanner.filter(move |file: &FileInfo| {
+            script.call(file).unwrap_or_else(|err| {
+                warn!(
+                    "{} failed on {}, so it is kept: {}",
+                    script.path().display(),
+                    file.path.display(),
+                    err
+                );
+                true
+            })
+        });
+    }
+    if let Some(threads) = options.threads {
+        scanner = scanner.threads(threads);
+    }
+    debug!(
+        directory = %options.directory.display(),
+        min_size_bytes = options.min_size_bytes,
+        backend = options.backend.name(),
+        threads = options.threads,
+        "starting scan"
     );
+    scanner
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::{self, File};
-    use std::io::Write;
-    use tempfile::tempdir;
+// Drive `bar` from the scanner's progress, with an estimate of the time left
+fn show_progress<'a>(
+    scanner: Scanner<'a>,
+    bar: ProgressBar,
+    root: &Path,
+    totals_file: Option<&Path>,
+) -> Scanner<'a> {
+    let expected_files = totals_file.and_then(|file| eta::load_total(file, root));
+    let estimator = Mutex::new(EtaEstimator::new(root, expected_files));
+    scanner.on_progress(move |p| {
+        bar.set_position(p.files_scanned as u64);
+        let eta = estimator
+            .lock()
+            .unwrap()
+            .update(p.files_scanned, p.current_dir);
+        match eta {
+            Some(eta) => bar.set_message(format!(
+                "~{} left  {}",
+                HumanDuration(eta),
+                p.current_dir.display()
+            )),
+            None => bar.set_message(p.current_dir.display().to_string()),
+        }
+    })
+}
 
-    fn create_test_file(dir: &Path, name: &str, size_bytes: usize) -> std::io::Result<()> {
-        let file_path = dir.join(name);
-        let mut file = File::create(&file_path)?;
-        file.write_all(&vec![0u8; size_bytes])?;
-        Ok(())
+// Scan without any interactive output, for the daemon
+fn scan_report(options: &Options, stop: &Arc<AtomicBool>) -> Result<ScanReport, ScanError> {
+    let index_file = state::index_file(&options.directory, options.state_dir.as_deref())
+        .filter(|_| options.cache);
+    let cache = index_file
+        .as_deref()
+        .map(|file| ScanCache::load(file).unwrap_or_default());
+
+    let started = SystemTime::now();
+    let timer = Instant::now();
+    let stats = ScanStats::new();
+    let mut scanner = base_scanner(options, stop).stats(&stats);
+    if let Some(cache) = &cache {
+        scanner = scanner.cache(cache);
     }
+    let (mut files, files_scanned) = scanner.collect()?;
+    let partial = stop.load(Ordering::SeqCst);
+
+    if let (Some(cache), Some(file)) = (&cache, &index_file) {
+        if !partial {
+            let _ = cache.save(file);
+        }
+    }
+    sort_files(&mut files, options.sort);
+    add_details(options, &mut files);
+    Ok(ScanReport {
+        directory: options.directory.clone(),
+        min_size_bytes: options.min_size_bytes,
+        files_scanned,
+        partial,
+        volume: disk::volume(&options.directory).ok(),
+        files,
+        dry_run: None,
+        scan: Some(ScanInfo::new(started, timer.elapsed(), stats.errors())),
+    })
+}
 
-    #[test]
-    fn test_parse_size_mb() {
-        let (size, unit) = parse_size("100MB");
-        assert_eq!(size, 100.0);
-        assert_eq!(unit, SizeUnit::MB);
+fn run_daemon(options: &Options, metrics: Option<&Metrics>) {
+    let stop = install_interrupt_handler();
+    if let Some(dir) = &options.report_dir {
+        if let Err(err) = fs::create_dir_all(dir) {
+            error!("cannot create {}: {}", dir.display(), err);
+            process::exit(1);
+        }
     }
+    info!(
+        "scanning {:?} every {} (Ctrl+C to stop)",
+        options.directory,
+        HumanDuration(options.interval)
+    );
+
+    daemon::run(options.interval, &stop, || {
+        let started = SystemTime::now();
+        let timer = Instant::now();
+        let report = match scan_report(options, &stop) {
+            Ok(report) => report,
+            // The directory may be temporarily unavailable; try again next time
+            Err(err) => {
+                error!("{}", err);
+                return;
+            }
+        };
+        // An interrupted scan is incomplete, so it is not written out
+        if report.partial {
+            return;
+        }
+        if let Some(metrics) = metrics {
+            metrics.record_scan(&report.files, report.files_scanned, timer.elapsed());
+        }
+        publish_report(options, &report);
+
+        let destination = match &options.report_dir {
+            Some(dir) => {
+                let path = daemon::report_path(dir, started);
+                if let Err(err) = report.save(&path) {
+                    error!("{}", err);
+                    return;
+                }
+                if let Err(err) = daemon::rotate(dir, options.keep) {
+                    warn!("cannot rotate reports: {}", err);
+                }
+                path.display().to_string()
+            }
+            None => {
+                match options.format {
+                    OutputFormat::Table => {
+                        print_matches(&report.files, options, None);
+                        println!();
+                    }
+                    _ => print_output(&report, options),
+                }
+                "stdout".to_string()
+            }
+        };
+        info!(
+            matches = report.files.len(),
+            files_scanned = report.files_scanned,
+            duration_secs = timer.elapsed().as_secs_f64(),
+            destination,
+            "scan finished"
+        );
+    });
+}
+
+// Load a saved scan report, exiting with an error if it cannot be read
+fn load_report(path: &Path) -> ScanReport {
+    ScanReport::load(path).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    })
+}
 
-    #[test]
-    fn test_parse_size_m() {
-        let (size, unit) = parse_size("50M");
-        assert_eq!(size, 50.0);
-        assert_eq!(unit, SizeUnit::MB);
+// Sort results in the requested order; sizes are listed largest first
+fn sort_files(files: &mut [FileInfo], order: SortOrder) {
+    match order {
+        SortOrder::Size => files.sort_by_key(|f| Reverse(f.size_bytes)),
+        SortOrder::Path => files.sort_by(|a, b| a.path.cmp(&b.path)),
     }
+}
 
-    #[test]
-    fn test_parse_size_mb_lowercase() {
-        let (size, unit) = parse_size("100mb");
-        assert_eq!(size, 100.0);
-        assert_eq!(unit, SizeUnit::MB);
+// Print the result table with its header, coloring rows by size tier
+fn print_table(
+    files: &[FileInfo],
+    unit: SizeUnit,
+    numbers: NumberFormat,
+    columns: &[Column],
+    colors: Option<Tiers>,
+) {
+    print!("{}", render_table(files, unit, numbers, columns, colors));
+}
+
+// Print the result table, clustered into the groups --group-by asks for
+fn print_matches(files: &[FileInfo], options: &Options, colors: Option<Tiers>) {
+    print!("{}", render_matches(files, options, colors));
+}
+
+// The result table, or its --group-by groups
+fn render_matches(files: &[FileInfo], options: &Options, colors: Option<Tiers>) -> String {
+    let unit = options.display_unit;
+    let numbers = options.numbers;
+    let Some(key) = options.group_by else {
+        return render_table(files, unit, numbers, &options.columns, colors);
+    };
+    let mut groups = match key {
+        GroupBy::Dir => aggregate::by_dir(files),
+        GroupBy::Owner => aggregate::by_owner(files),
+        GroupBy::Age => aggregate::by_age(files, SystemTime::now()),
+    };
+    // Age groups keep their order from newest to oldest
+    if options.sort == SortOrder::Path && key != GroupBy::Age {
+        groups.sort_by(|a, b| a.label.cmp(&b.label));
     }
+    render_groups(files, &groups, unit, numbers, &options.columns, colors)
+}
 
-    #[test]
-    fn test_parse_size_m_lowercase() {
-        let (size, unit) = parse_size("50m");
-        assert_eq!(size, 50.0);
-        assert_eq!(unit, SizeUnit::MB);
+// Mark each match a warning or critical when --warn-size lists smaller
+// files than --min-size
+fn add_tiers(options: &Options, files: &mut [FileInfo]) {
+    if let Some(critical_bytes) = options.critical_bytes {
+        for file in files {
+            file.tier = Some(if file.size_bytes >= critical_bytes {
+                Tier::Critical
+            } else {
+                Tier::Warning
+            });
+        }
+    }
+}
+
+// Read the files again for the per-file details the options and --columns ask
+// for, dropping matches whose MIME type is not wanted before anything else is
+// read. Matches outside a --preset go first, as they need nothing read, then
+// cloud placeholders, since reading their contents would download them. Files
+// reached through several paths, e.g. bind mounts, are merged before any of
+// that, so each is listed, counted and read once
+fn add_details(options: &Options, files: &mut Vec<FileInfo>) {
+    stat::merge_same_files(files);
+    if let Some(preset) = options.preset {
+        files.retain(|file| {
+            let path = file
+                .path
+                .strip_prefix(&options.directory)
+                .unwrap_or(&file.path);
+            preset.matches(path)
+        });
+    }
+    add_tiers(options, files);
+    if options.placeholders != Placeholders::Include || options.wants(Column::Cloud) {
+        cloud::add_placeholders(files);
+    }
+    if options.placeholders == Placeholders::Exclude {
+        files.retain_mut(|file| {
+            let placeholder = file.placeholder == Some(true);
+            // The matches left are all local, which is not worth a column
+            if !options.wants(Column::Cloud) {
+                file.placeholder = None;
+            }
+            !placeholder
+        });
+    }
+    if !options.mime.is_empty() {
+        filetype::add_mime_types(files);
+        files.retain(|file| {
+            file.mime
+                .as_deref()
+                .is_some_and(|mime| filetype::mime_matches(&options.mime, mime))
+        });
+    } else if options.wants(Column::Mime) {
+        filetype::add_mime_types(files);
+    }
+    if options.estimate_compression || options.wants(Column::Ratio) {
+        compress::add_estimates(files);
+    }
+    if options.detect_type || options.wants(Column::Type) {
+        filetype::add_types(files);
+    }
+    if options.mtime
+        || options.wants(Column::Modified)
+        || options.wants(Column::Age)
+        || options.score.is_some()
+        || options.wants(Column::Score)
+        || options.group_by == Some(GroupBy::Age)
+    {
+        stat::add_modified(files);
+    }
+    // Scores replace the size order the matches were sorted in
+    if options.score.is_some() || options.wants(Column::Score) {
+        score::add_scores(files, options.score.unwrap_or_default(), SystemTime::now());
+    }
+    if options.score.is_some() {
+        score::sort(files);
+    }
+    if options.owner
+        || options.wants(Column::Owner)
+        || options.wants(Column::Group)
+        || options.group_by == Some(GroupBy::Owner)
+    {
+        stat::add_owners(files);
+    }
+    if options.inode || options.wants(Column::Device) || options.wants(Column::Inode) {
+        stat::add_inodes(files);
+    }
+    if options.show_perms || options.wants(Column::Perms) {
+        stat::add_permissions(files);
+    }
+    if options.percent_of_disk || options.wants(Column::Disk) {
+        disk::add_disk_shares(files);
+    }
+    if let Some(algorithm) = options.hash {
+        checksum::add_hashes(files, algorithm);
+    }
+}
+
+// Render the result table with its header and one line per file. Without
+// --columns, a detail gets a column when any file carries it.
+fn render_table(
+    files: &[FileInfo],
+    unit: SizeUnit,
+    numbers: NumberFormat,
+    columns: &[Column],
+    colors: Option<Tiers>,
+) -> String {
+    let layout = TableLayout::new(files, unit, numbers, columns);
+    let mut table = layout.header(unit);
+    for file in files {
+        table.push_str(&layout.row(file, unit, colors));
+    }
+    table
+}
+
+// Render the result table with the rows of each --group-by group under a
+// heading holding the group's subtotal. Columns line up across groups.
+fn render_groups(
+    files: &[FileInfo],
+    groups: &[aggregate::Group],
+    unit: SizeUnit,
+    numbers: NumberFormat,
+    columns: &[Column],
+    colors: Option<Tiers>,
+) -> String {
+    let layout = TableLayout::new(files, unit, numbers, columns);
+    let mut table = layout.header(unit);
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            table.push('\n');
+        }
+        let plural = if group.total.files == 1 { "" } else { "s" };
+        table.push_str(&format!(
+            "{}: {} file{}, {}\n",
+            group.label,
+            numbers.count(group.total.files),
+            plural,
+            numbers.size(group.total.size_bytes, unit)
+        ));
+        for file in &group.files {
+            table.push_str(&layout.row(file, unit, colors));
+        }
+    }
+    table
+}
+
+// The columns of a result table and how wide each is
+struct TableLayout {
+    columns: Vec<Column>,
+    widths: Vec<usize>,
+    numbers: NumberFormat,
+}
+
+impl TableLayout {
+    fn new(files: &[FileInfo], unit: SizeUnit, numbers: NumberFormat, columns: &[Column]) -> Self {
+        let columns: Vec<Column> = if columns.is_empty() {
+            columns::DEFAULT
+                .into_iter()
+                .filter(|&column| {
+                    matches!(column, Column::Size | Column::Path)
+                        || files.iter().any(|file| column.cell(file, unit).is_some())
+                })
+                .collect()
+        } else {
+            columns.to_vec()
+        };
+        // Every column but the last is padded to its widest cell; sizes line up
+        // on the right
+        let widths: Vec<usize> = columns
+            .iter()
+            .map(|&column| {
+                let widest = match column {
+                    Column::Size => 14,
+                    column => files
+                        .iter()
+                        .filter_map(|file| column.cell(file, unit))
+                        .map(|cell| cell.len())
+                        .max()
+                        .unwrap_or(1),
+                };
+                widest.max(column.header(unit).len())
+            })
+            .collect();
+        TableLayout {
+            columns,
+            widths,
+            numbers,
+        }
+    }
+
+    // The column titles and the rule below them
+    fn header(&self, unit: SizeUnit) -> String {
+        let last = self.columns.len() - 1;
+        let mut header = String::new();
+        for (i, (column, width)) in self.columns.iter().zip(&self.widths).enumerate() {
+            if i == last {
+                header.push_str(&column.header(unit));
+            } else {
+                header.push_str(&format!("{:<width$}  ", column.header(unit)));
+            }
+        }
+        header.push('\n');
+        header.push_str(&"-".repeat(80));
+        header.push('\n');
+        header
+    }
+
+    // One file's line, colored by its size tier
+    fn row(&self, file: &FileInfo, unit: SizeUnit, colors: Option<Tiers>) -> String {
+        let last = self.columns.len() - 1;
+        let mut row = String::new();
+        for (i, (&column, width)) in self.columns.iter().zip(&self.widths).enumerate() {
+            let cell = match column {
+                Column::Size => size_cell_in(file.size_bytes, unit, self.numbers),
+                column => column.cell(file, unit).unwrap_or_else(|| "-".to_string()),
+            };
+            if i == last {
+                row.push_str(&cell);
+            } else if column == Column::Size {
+                row.push_str(&format!("{:>width$}  ", cell));
+            } else {
+                row.push_str(&format!("{:<width$}  ", cell));
+            }
+        }
+        let mut row = match colors {
+            Some(tiers) => tiers.paint(file.size_bytes, &row),
+            None => row,
+        };
+        row.push('\n');
+        // Other paths of the same file go under its path
+        if let Some(at) = self
+            .columns
+            .iter()
+            .position(|&column| column == Column::Path)
+        {
+            let indent: usize = self.widths[..at].iter().map(|width| width + 2).sum();
+            for path in &file.alternate_paths {
+                row.push_str(&format!(
+                    "{:indent$}also at {}\n",
+                    "",
+                    path.display(),
+                    indent = indent
+                ));
+            }
+        }
+        row
+    }
+}
+
+// Print the directories holding the most matching bytes
+fn print_top_dirs(
+    root: &Path,
+    files: &[FileInfo],
+    limit: usize,
+    unit: SizeUnit,
+    numbers: NumberFormat,
+) {
+    println!("\nTop offender directories:");
+    println!(
+        "{:<15} {:>10}  Directory",
+        size_header("Size", unit),
+        "Matches"
+    );
+    println!("{}", "-".repeat(80));
+    for dir in aggregate::top_dirs(root, files, limit) {
+        println!(
+            "{:>14}  {:>10}  {}",
+            size_cell_in(dir.total.size_bytes, unit, numbers),
+            numbers.count(dir.total.files),
+            dir.path.display()
+        );
+    }
+}
+
+// Print sets of matches with identical contents
+fn print_duplicates(files: &[FileInfo], unit: SizeUnit, numbers: NumberFormat) {
+    let sets = dupes::find(files);
+    println!("\nDuplicates:");
+    if sets.is_empty() {
+        println!("No duplicate files found");
+        return;
+    }
+
+    for set in &sets {
+        println!(
+            "{} copies of {} ({} reclaimable):",
+            set.paths.len(),
+            numbers.size(set.size_bytes, unit),
+            numbers.size(set.reclaimable(), unit)
+        );
+        for path in &set.paths {
+            println!("    {}", path.display());
+        }
     }
+    let reclaimable: u64 = sets.iter().map(|set| set.reclaimable()).sum();
+    println!(
+        "{} duplicate sets, {} reclaimable",
+        numbers.count(sets.len() as u64),
+        numbers.size(reclaimable, unit)
+    );
+}
 
-    #[test]
-    fn test_parse_size_gb() {
-        let (size, unit) = parse_size("1GB");
-        assert_eq!(size, 1024.0);
-        assert_eq!(unit, SizeUnit::GB);
+// Print the matches grouped by extension, largest total first
+fn print_extension_stats(files: &[FileInfo], unit: SizeUnit, numbers: NumberFormat) {
+    println!("\nBy extension:");
+    println!(
+        "{:<12} {:>8}  {:>14}  {:>14}  Largest file",
+        "Extension",
+        "Files",
+        size_header("Total", unit),
+        size_header("Largest", unit)
+    );
+    println!("{}", "-".repeat(80));
+    for group in aggregate::by_extension(files) {
+        let extension = if group.extension.is_empty() {
+            "(none)".to_string()
+        } else {
+            format!(".{}", group.extension)
+        };
+        println!(
+            "{:<12} {:>8}  {:>14}  {:>14}  {}",
+            extension,
+            numbers.count(group.total.files),
+            size_cell_in(group.total.size_bytes, unit, numbers),
+            size_cell_in(group.largest.size_bytes, unit, numbers),
+            group.largest.path.display()
+        );
     }
+}
+
+// Print how many files fall into each size range, with a bar for their share
+// of the scanned bytes
+fn print_histogram(histogram: &aggregate::Histogram, unit: SizeUnit, numbers: NumberFormat) {
+    const BAR_WIDTH: u64 = 30;
+    let total_bytes = histogram.total().size_bytes.max(1);
 
-    #[test]
-    fn test_parse_size_g() {
-        let (size, unit) = parse_size("2G");
-        assert_eq!(size, 2048.0);
-        assert_eq!(unit, SizeUnit::GB);
+    println!("\nSize distribution of all scanned files:");
+    println!(
+        "{:<12} {:>10}  {:>14}  Share of bytes",
+        "Size",
+        "Files",
+        size_header("Total", unit)
+    );
+    println!("{}", "-".repeat(80));
+    for (label, bucket) in histogram.rows() {
+        let share = bucket.size_bytes as f64 / total_bytes as f64;
+        let bar = "#".repeat((share * BAR_WIDTH as f64).round() as usize);
+        println!(
+            "{:<12} {:>10}  {:>14}  {:<30} {:>5.1}%",
+            label,
+            numbers.count(bucket.files),
+            size_cell_in(bucket.size_bytes, unit, numbers),
+            bar,
+            share * 100.0
+        );
     }
+}
 
-    #[test]
-    fn test_parse_size_gb_lowercase() {
-        let (size, unit) = parse_size("1gb");
-        assert_eq!(size, 1024.0);
-        assert_eq!(unit, SizeUnit::GB);
+fn run_show(options: &ShowOptions) {
+    let mut report = load_report(&options.snapshot);
+    if options.min_size_bytes < report.min_size_bytes {
+        info!(
+            "the snapshot only contains files >= {}",
+            threshold(report.min_size_bytes, options.display_unit)
+        );
+    }
+    report.min_size_bytes = report.min_size_bytes.max(options.min_size_bytes);
+    report
+        .files
+        .retain(|file| file.size_bytes >= options.min_size_bytes);
+    sort_files(&mut report.files, options.sort);
+
+    if options.format != OutputFormat::Table {
+        print_report(&report, options.format, &[]);
+        return;
     }
 
-    #[test]
-    fn test_parse_size_g_lowercase() {
-        let (size, unit) = parse_size("2g");
-        assert_eq!(size, 2048.0);
-        assert_eq!(unit, SizeUnit::GB);
+    println!(
+        "Snapshot of {:?}, files >= {}{}\n",
+        report.directory,
+        threshold(report.min_size_bytes, options.display_unit),
+        if report.partial {
+            " (partial scan)"
+        } else {
+            ""
+        }
+    );
+    let numbers = options.numbers;
+    print_table(&report.files, options.display_unit, numbers, &[], None);
+    println!(
+        "\nTotal: {} files, {} (scanned {} files)",
+        numbers.count(report.files.len() as u64),
+        numbers.size(report.total_bytes(), options.display_unit),
+        numbers.count(report.files_scanned as u64)
+    );
+    if let Some(volume) = &report.volume {
+        print_volume(volume, report.total_bytes(), options.display_unit, numbers);
     }
+}
 
-    #[test]
-    fn test_parse_size_no_unit() {
-        let (size, unit) = parse_size("100");
-        assert_eq!(size, 100.0);
-        assert_eq!(unit, SizeUnit::MB);
+// Put the matches' total in the context of the scanned volume's free space
+fn print_volume(volume: &Volume, total_bytes: u64, unit: SizeUnit, numbers: NumberFormat) {
+    let share = if volume.available_bytes == 0 {
+        String::new()
+    } else {
+        format!(
+            " (matches equal {:.1}% of the free space)",
+            total_bytes as f64 * 100.0 / volume.available_bytes as f64
+        )
+    };
+    println!(
+        "Volume: {} free of {}{}",
+        numbers.size(volume.available_bytes, unit),
+        numbers.size(volume.total_bytes, unit),
+        share
+    );
+}
+
+fn run_diff(options: &DiffOptions) {
+    let old = load_report(&options.old);
+    let new = load_report(&options.new);
+    let diff = diff::compare(&old.files, &new.files, options.delta_bytes);
+    diff::print(&diff, options.display_unit);
+}
+
+fn run_bench(options: &BenchOptions) {
+    // The MFT is read whole, so it is only timed when asked for by name
+    let backends = if options.backends.is_empty() {
+        Backend::ALL
+            .into_iter()
+            .filter(|backend| *backend != Backend::Mft && backend.is_available())
+            .collect()
+    } else {
+        options.backends.clone()
+    };
+    let thread_counts = if options.thread_counts.is_empty() {
+        let cpus = std::thread::available_parallelism().map_or(1, |cpus| cpus.get());
+        bench::default_thread_counts(cpus)
+    } else {
+        options.thread_counts.clone()
+    };
+    let json = options.format == OutputFormat::Json;
+    if !json {
+        println!(
+            "Benchmarking {:?} with {} runs of each setting...\n",
+            options.directory, options.runs
+        );
+        println!(
+            "{:<10} {:>7} {:>12} {:>9} {:>9} {:>12}",
+            "Backend", "Threads", "Files", "Best", "Median", "Files/s"
+        );
+        println!("{}", "-".repeat(64));
     }
+    let measured = bench::run(
+        &options.directory,
+        &backends,
+        &thread_counts,
+        options.runs,
+        |measurement| {
+            if !json {
+                println!(
+                    "{:<10} {:>7} {:>12} {:>8.2}s {:>8.2}s {:>12.0}",
+                    measurement.backend,
+                    measurement.threads,
+                    measurement.files_scanned,
+                    measurement.best_secs,
+                    measurement.median_secs,
+                    measurement.files_per_sec
+                );
+            }
+        },
+    );
+    let measurements = measured.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
 
-    #[test]
-    fn test_parse_size_invalid() {
-        let (size, unit) = parse_size("invalid");
-        assert_eq!(size, 100.0);
-        assert_eq!(unit, SizeUnit::MB);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&measurements).unwrap());
+        return;
+    }
+    if let Some(fastest) = bench::fastest(&measurements) {
+        println!(
+            "\nFastest: --backend {} --threads {} ({:.0} files/s)",
+            fastest.backend, fastest.threads, fastest.files_per_sec
+        );
     }
+}
 
-    #[test]
-    fn test_parse_size_fractional() {
-        let (size, unit) = parse_size("0.5GB");
-        assert_eq!(size, 512.0);
-        assert_eq!(unit, SizeUnit::GB);
+fn run_self_update(options: &UpdateOptions) {
+    let current = env!("CARGO_PKG_VERSION");
+    let release = update::latest().unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
+    if !update::is_newer(release.version()) {
+        println!("list-big-files {} is the latest release", current);
+        return;
+    }
+    if options.check {
+        println!(
+            "list-big-files {} is available (installed: {}); run 'list-big-files self-update' to install it",
+            release.version(),
+            current
+        );
+        return;
     }
+    println!(
+        "Updating list-big-files {} to {}...",
+        current,
+        release.version()
+    );
+    match update::install(&release) {
+        Ok(path) => println!("Installed {} at {}", release.version(), path.display()),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    }
+}
 
-    #[test]
-    fn test_parse_size_zero() {
-        let (size, unit) = parse_size("0");
-        assert_eq!(size, 0.0);
-        assert_eq!(unit, SizeUnit::MB);
+fn run_git(options: &GitOptions) {
+    let unit = options.display_unit;
+    let json = options.format == OutputFormat::Json;
+    if !json {
+        println!(
+            "Searching the history of {:?} for blobs >= {}...\n",
+            options.repo,
+            threshold(options.min_size_bytes, unit)
+        );
     }
+    let blobs = git::large_blobs(&options.repo, options.min_size_bytes).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
 
-    #[test]
-    fn test_parse_size_large_value() {
-        let (size, unit) = parse_size("1000GB");
-        assert_eq!(size, 1024000.0);
-        assert_eq!(unit, SizeUnit::GB);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&blobs).unwrap());
+        return;
+    }
+    println!(
+        "{:<15} {:<10}  {:<10}  {:<7}  Path",
+        size_header("Size", unit),
+        "Blob",
+        "Commit",
+        "In HEAD"
+    );
+    println!("{}", "-".repeat(80));
+    for blob in &blobs {
+        println!(
+            "{:>14}  {:<10}  {:<10}  {:<7}  {}",
+            size_cell(blob.size_bytes, unit),
+            &blob.id[..blob.id.len().min(10)],
+            blob.commit.as_deref().unwrap_or("-"),
+            if blob.in_head { "yes" } else { "no" },
+            blob.path
+        );
     }
+    let total: u64 = blobs.iter().map(|blob| blob.size_bytes).sum();
+    let deleted = blobs.iter().filter(|blob| !blob.in_head).count();
+    println!(
+        "\nTotal: {} blobs, {} ({} only in history)",
+        blobs.len(),
+        display_size(total, unit),
+        deleted
+    );
+}
 
-    #[test]
-    fn test_format_size_mb_1mb() {
-        let mb_bytes = 1024 * 1024;
-        let size = format_size(mb_bytes, SizeUnit::MB);
-        assert!((size - 1.0).abs() < 0.001);
+fn run_docker(options: &DockerOptions) {
+    let unit = options.display_unit;
+    let json = options.format == OutputFormat::Json;
+    if !json {
+        println!(
+            "Searching the layers of {} for files >= {}...\n",
+            options.image,
+            threshold(options.min_size_bytes, unit)
+        );
     }
+    let layers = docker::layers(&options.image, options.min_size_bytes).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
 
-    #[test]
-    fn test_format_size_mb_100mb() {
-        let mb_bytes = 100 * 1024 * 1024;
-        let size = format_size(mb_bytes, SizeUnit::MB);
-        assert!((size - 100.0).abs() < 0.001);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&layers).unwrap());
+        return;
     }
+    // Each heading gives the whole layer; only its large files are listed
+    let groups: Vec<aggregate::Group> = layers
+        .iter()
+        .map(|layer| aggregate::Group {
+            label: format!(
+                "Layer {}/{} {}",
+                layer.index,
+                layers.len(),
+                short_id(&layer.id)
+            ),
+            total: aggregate::Total {
+                size_bytes: layer.size_bytes,
+                files: layer.file_count,
+            },
+            files: layer.files.iter().collect(),
+        })
+        .collect();
+    let shown: Vec<FileInfo> = layers
+        .iter()
+        .flat_map(|layer| layer.files.clone())
+        .collect();
+    print!(
+        "{}",
+        render_groups(&shown, &groups, unit, NumberFormat::PLAIN, &[], None)
+    );
+    let total: u64 = layers.iter().map(|layer| layer.size_bytes).sum();
+    println!(
+        "\nTotal: {} layers, {} ({} files >= {})",
+        layers.len(),
+        display_size(total, unit),
+        shown.len(),
+        threshold(options.min_size_bytes, unit)
+    );
+}
 
-    #[test]
-    fn test_format_size_mb_fractional() {
-        let mb_bytes = (1024 * 1024) / 2;
-        let size = format_size(mb_bytes, SizeUnit::MB);
-        assert!((size - 0.5).abs() < 0.001);
+// A layer digest cut to the 12 hex digits Docker shows
+fn short_id(id: &str) -> &str {
+    let hex = id.strip_prefix("sha256:").unwrap_or(id);
+    &hex[..hex.len().min(12)]
+}
+
+// The report as printed: with --relative, its paths are relative to the root,
+// and with --escape-paths those that are not valid Unicode are escaped
+fn shown_report<'r>(options: &Options, report: &'r ScanReport) -> Cow<'r, ScanReport> {
+    if options.paths != PathStyle::Relative && !options.escape_paths {
+        return Cow::Borrowed(report);
+    }
+    let mut shown = report.clone();
+    for file in &mut shown.files {
+        file.path = shown_path(options, &file.path);
+        for path in &mut file.alternate_paths {
+            *path = shown_path(options, path);
+        }
+    }
+    if let Some(plan) = &mut shown.dry_run {
+        for step in &mut plan.steps {
+            step.path = shown_path(options, &step.path);
+        }
     }
+    Cow::Owned(shown)
+}
 
-    #[test]
-    fn test_format_size_gb_1gb() {
-        let gb_bytes = 1024 * 1024 * 1024;
-        let size = format_size(gb_bytes, SizeUnit::GB);
-        assert!((size - 1.0).abs() < 0.001);
+// `path` as printed in a report
+fn shown_path(options: &Options, path: &Path) -> PathBuf {
+    let path = if options.paths == PathStyle::Relative {
+        relative_path(&options.directory, path)
+    } else {
+        path.to_path_buf()
+    };
+    match escape_path(&path) {
+        Cow::Owned(escaped) if options.escape_paths => PathBuf::from(escaped),
+        _ => path,
     }
+}
+
+// `path` relative to the scan root, or unchanged if it is not below it
+fn relative_path(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
 
-    #[test]
-    fn test_format_size_gb_10gb() {
-        let gb_bytes = 10 * 1024 * 1024 * 1024;
-        let size = format_size(gb_bytes, SizeUnit::GB);
-        assert!((size - 10.0).abs() < 0.001);
+// Print a scan's matches in its --format, or one --template line per match
+fn print_output(report: &ScanReport, options: &Options) {
+    print!("{}", render_output(report, options));
+}
+
+// A scan's matches in its --format, or one --template line per match
+fn render_output(report: &ScanReport, options: &Options) -> String {
+    match &options.template {
+        Some(template) => report
+            .files
+            .iter()
+            .map(|file| template.render(file, options.display_unit) + "\n")
+            .collect(),
+        // A file has no terminal to color the table for
+        None if options.format == OutputFormat::Table => {
+            render_matches(&report.files, options, None)
+        }
+        None => render_report(report, options.format, &options.columns),
     }
+}
 
-    #[test]
-    fn test_format_size_gb_fractional() {
-        let gb_bytes = (1024 * 1024 * 1024) / 2;
-        let size = format_size(gb_bytes, SizeUnit::GB);
-        assert!((size - 0.5).abs() < 0.001);
+// Write a scan's output to the --output file, exiting if it cannot be written
+fn save_output(report: &ScanReport, options: &Options, path: &Path) {
+    if let Err(err) = report::write_atomically(path, &render_output(report, options)) {
+        eprintln!("error: cannot write {}: {}", path.display(), err);
+        process::exit(1);
     }
+}
+
+// Write a scan's output to the --output file and print its totals in place of
+// it
+fn write_output(report: &ScanReport, options: &Options, path: &Path, elapsed: Duration) {
+    save_output(report, options, path);
+    let numbers = options.numbers;
+    println!("Scanned in: {:.2}s", elapsed.as_secs_f64());
+    println!(
+        "Total: {} files, {} (scanned {} files)",
+        numbers.count(report.files.len() as u64),
+        numbers.size(report.total_bytes(), options.display_unit),
+        numbers.count(report.files_scanned as u64)
+    );
+    println!("Wrote {}", path.display());
+}
+
+// Print a report in one of the machine-readable formats
+fn print_report(report: &ScanReport, format: OutputFormat, columns: &[Column]) {
+    print!("{}", render_report(report, format, columns));
+}
 
-    #[test]
-    fn test_format_size_zero() {
-        let size = format_size(0, SizeUnit::MB);
-        assert_eq!(size, 0.0);
+// A report in one of the machine-readable formats, ending in a newline
+fn render_report(report: &ScanReport, format: OutputFormat, columns: &[Column]) -> String {
+    match format {
+        OutputFormat::Treemap => {
+            let tree = treemap::build(&report.directory, &report.files);
+            serde_json::to_string_pretty(&tree).unwrap() + "\n"
+        }
+        OutputFormat::Yaml => report.to_yaml(columns),
+        _ if columns.is_empty() => report.to_json() + "\n",
+        _ => report.to_json_with_columns(columns) + "\n",
     }
+}
 
-    #[test]
-    fn test_format_size_large_value() {
-        let large_bytes = 1000 * 1024 * 1024 * 1024;
-        let size = format_size(large_bytes, SizeUnit::GB);
-        assert!((size - 1000.0).abs() < 0.001);
+// Print the totals --summary asks for: as lines in the table format, or as a
+// single JSON or YAML object. --count prints the number of matches alone
+fn print_summary(options: &Options, report: &ScanReport, elapsed: Duration) {
+    if options.count {
+        println!("{}", report.files.len());
+        return;
+    }
+    let report = shown_report(options, report);
+    let summary = report.summary(elapsed);
+    match options.format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+            return;
+        }
+        OutputFormat::Yaml => {
+            print!("{}", serde_yaml::to_string(&summary).unwrap());
+            return;
+        }
+        _ => {}
+    }
+    let unit = options.display_unit;
+    let numbers = options.numbers;
+    println!("Matches:        {}", numbers.count(summary.matches as u64));
+    println!(
+        "Total size:     {}",
+        numbers.size(summary.total_bytes, unit)
+    );
+    if let Some(largest) = summary.largest {
+        println!(
+            "Largest file:   {}  {}",
+            numbers.size(largest.size_bytes, unit),
+            largest.path.display()
+        );
     }
+    println!(
+        "Files scanned:  {}",
+        numbers.count(summary.files_scanned as u64)
+    );
+    println!("Scan time:      {:.2}s", summary.duration_secs);
+    if summary.partial {
+        println!("Partial scan: interrupted before completion");
+    }
+}
+
+// Print the counters --stats asks for as one JSON line on stderr, apart from
+// the scan's output on stdout
+fn print_stats(stats: &ScanStats, files_scanned: usize, elapsed: Duration) {
+    let metrics = ScanMetrics::new(stats, files_scanned, elapsed);
+    eprintln!("{}", serde_json::to_string(&metrics).unwrap());
+}
+
+fn run_scan(options: &Options, metrics: Option<&Metrics>) {
+    let display_unit = options.display_unit;
+    // JSON and treemap output go to stdout alone, so it stays parseable,
+    // unless --output writes them to a file
+    let structured = options.format != OutputFormat::Table;
+    let to_stdout = structured && options.output.is_none();
+    // Read the report to compare against up front so a bad path fails fast
+    let previous = options.compare.as_deref().map(load_report);
 
-    #[test]
-    fn test_get_unit_label_mb() {
-        assert_eq!(get_unit_label(SizeUnit::MB), "MB");
+    // Display scan progress information
+    if !to_stdout && !options.summary && !options.count {
+        println!(
+            "Scanning {:?} for files >= {}...\n",
+            options.directory,
+            threshold(options.min_size_bytes, display_unit)
+        );
     }
 
-    #[test]
-    fn test_get_unit_label_gb() {
-        assert_eq!(get_unit_label(SizeUnit::GB), "GB");
+    // Scan for large files and sort results by size (largest first)
+    let started = SystemTime::now();
+    let start = Instant::now();
+    let progress = progress_bar();
+    let interrupted = install_interrupt_handler();
+    // Load the index of directories seen by the previous --cache scan
+    let index_file = state::index_file(&options.directory, options.state_dir.as_deref())
+        .filter(|_| options.cache);
+    let cache = index_file.as_deref().map(|file| {
+        ScanCache::load(file).unwrap_or_else(|err| {
+            warn!("cannot read scan cache {}: {}", file.display(), err);
+            ScanCache::new()
+        })
+    });
+    let stats = ScanStats::new();
+    let mut scanner = base_scanner(options, &interrupted).stats(&stats);
+    if let Some(cache) = &cache {
+        scanner = scanner.cache(cache);
+    }
+    // The histogram covers every file, and archives below the threshold may
+    // hold larger members, so the threshold is applied below instead
+    let histogram = options
+        .histogram
+        .then(|| Mutex::new(aggregate::Histogram::default()));
+    let every_file = histogram.is_some() || !options.scan_archives.is_empty();
+    if every_file {
+        scanner = scanner.min_size(0);
+    }
+    let totals_file = eta::totals_file(options.state_dir.as_deref());
+    if let Some(bar) = progress.clone() {
+        scanner = show_progress(scanner, bar, &options.directory, totals_file.as_deref());
     }
 
-    #[test]
-    fn test_list_big_files_empty_directory() {
-        let dir = tempdir().unwrap();
-        let (files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024);
-        assert_eq!(files.len(), 0);
-        assert_eq!(scanned_count, 0);
+    let colors = color::for_stdout(options.color, options.color_tiers);
+    let found = Mutex::new(Vec::new());
+    let archives = Mutex::new(Vec::new());
+    let result = scanner.scan_with(|file| {
+        if let Some(histogram) = &histogram {
+            histogram.lock().unwrap().add(file.size_bytes);
+        }
+        if archive::format_of(&file.path, &options.scan_archives).is_some() {
+            archives.lock().unwrap().push(file.path.clone());
+        }
+        if every_file && file.size_bytes < options.min_size_bytes {
+            return;
+        }
+        // In live mode echo each match immediately, keeping the spinner intact
+        if options.live {
+            let mut row = if options.paths == PathStyle::Relative {
+                let mut shown = file.clone();
+                shown.path = relative_path(&options.directory, &file.path);
+                format_row(&shown, display_unit, options.numbers)
+            } else {
+                format_row(&file, display_unit, options.numbers)
+            };
+            if let Some(tiers) = colors {
+                row = tiers.paint(file.size_bytes, &row);
+            }
+            match &progress {
+                Some(bar) => bar.suspend(|| println!("{}", row)),
+                None => println!("{}", row),
+            }
+        }
+        found.lock().unwrap().push(file);
+    });
+    if let Some(bar) = &progress {
+        bar.finish_and_clear();
     }
+    let elapsed = start.elapsed();
+    let scanned_count = match result {
+        Ok(scanned_count) => scanned_count,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+    debug!(
+        files_scanned = scanned_count,
+        duration_secs = elapsed.as_secs_f64(),
+        errors = stats.errors(),
+        "scan finished"
+    );
+    if options.stats {
+        print_stats(&stats, scanned_count, elapsed);
+    }
+    let mut files = found.into_inner().unwrap();
+    let partial = interrupted.load(Ordering::SeqCst);
+    files.extend(archive::large_members(
+        &archives.into_inner().unwrap(),
+        &options.scan_archives,
+        options.min_size_bytes,
+    ));
+
+    // Remember the tree size so the next scan of this root can estimate its ETA
+    if let Some(file) = totals_file.as_ref().filter(|_| !partial) {
+        let _ = eta::save_total(file, &options.directory, scanned_count);
+    }
+    // Only a complete scan knows every directory, so a partial one keeps the old index
+    if let (Some(cache), Some(file)) = (&cache, &index_file) {
+        if !partial {
+            if let Err(err) = cache.save(file) {
+                warn!("cannot write scan cache {}: {}", file.display(), err);
+            }
+        }
+    }
+    sort_files(&mut files, options.sort);
+    add_details(options, &mut files);
+    let report = ScanReport {
+        directory: options.directory.clone(),
+        min_size_bytes: options.min_size_bytes,
+        files_scanned: scanned_count,
+        partial,
+        volume: disk::volume(&options.directory).ok(),
+        files,
+        dry_run: None,
+        scan: Some(ScanInfo::new(started, elapsed, stats.errors())),
+    };
+    if let Some(snapshot) = &options.save_snapshot {
+        if let Err(err) = report.save(snapshot) {
+            warn!("cannot save snapshot: {}", err);
+        }
+    }
+    publish_report(options, &report);
 
-    #[test]
-    fn test_list_big_files_all_small_files() {
-        let dir = tempdir().unwrap();
-        create_test_file(dir.path(), "small1.txt", 1024).unwrap();
-        create_test_file(dir.path(), "small2.txt", 2048).unwrap();
-        create_test_file(dir.path(), "small3.txt", 4096).unwrap();
+    if options.summary || options.count {
+        print_summary(options, &report, elapsed);
+        if partial {
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        return;
+    }
 
-        let (files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024);
-        assert_eq!(files.len(), 0);
-        assert_eq!(scanned_count, 3);
+    if options.interactive_delete {
+        println!("{} matching files", report.files.len());
+        let stdin = io::stdin();
+        if let Err(err) = interactive::run(&report.files, display_unit, stdin.lock(), io::stdout())
+        {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+        if partial {
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        return;
     }
 
-    #[test]
-    fn test_list_big_files_all_large_files() {
-        let dir = tempdir().unwrap();
-        create_test_file(dir.path(), "large1.txt", 150 * 1024 * 1024).unwrap();
-        create_test_file(dir.path(), "large2.txt", 200 * 1024 * 1024).unwrap();
+    if options.tui {
+        let summary = match tui::run(report.files, display_unit) {
+            Ok(summary) => summary,
+            Err(err) => {
+                eprintln!("error: cannot run the interactive browser: {}", err);
+                process::exit(1);
+            }
+        };
+        println!(
+            "Removed {} files, freed {}",
+            summary.removed,
+            display_size(summary.freed_bytes, display_unit)
+        );
+        if partial {
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        return;
+    }
 
-        let (mut files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024);
-        files.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
-        assert_eq!(files.len(), 2);
-        assert_eq!(scanned_count, 2);
-        assert!(files[0].size_bytes > files[1].size_bytes);
+    if structured {
+        let action = options.action();
+        let mut report = report;
+        // A dry run's plan goes in the report as well as on stderr
+        if options.dry_run && !partial {
+            report.dry_run = action
+                .as_ref()
+                .map(|action| actions::plan(&report.files, action));
+        }
+        let shown = shown_report(options, &report);
+        match &options.output {
+            Some(path) => write_output(&shown, options, path, elapsed),
+            None => print_output(&shown, options),
+        }
+        if partial {
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        if let Some(action) = action {
+            act_on_matches(options, &report.files, &action, io::stderr());
+        }
+        return;
     }
 
-    #[test]
-    fn test_list_big_files_mixed_sizes() {
-        let dir = tempdir().unwrap();
-        create_test_file(dir.path(), "small.txt", 1024).unwrap();
-        create_test_file(dir.path(), "large.txt", 150 * 1024 * 1024).unwrap();
-        create_test_file(dir.path(), "medium.txt", 50 * 1024 * 1024).unwrap();
-        create_test_file(dir.path(), "huge.txt", 500 * 1024 * 1024).unwrap();
+    if options.live {
+        println!();
+    }
+    let pager = start_pager(options);
+    println!("Scanned in: {:.2}s", elapsed.as_secs_f64());
+    let files = &report.files;
+    let shown = shown_report(options, &report);
+    match &options.output {
+        Some(path) => {
+            save_output(&shown, options, path);
+            println!("Wrote {}", path.display());
+        }
+        None => print_matches(&shown.files, options, colors),
+    }
 
-        let (mut files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024);
-        files.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
-        assert_eq!(files.len(), 2);
-        assert_eq!(scanned_count, 4);
-        assert!(files[0].size_bytes > files[1].size_bytes);
+    // Display total count of large files found and total files scanned
+    let numbers = options.numbers;
+    println!(
+        "\nTotal: {} files, {} (scanned {} files)",
+        numbers.count(files.len() as u64),
+        numbers.size(report.total_bytes(), display_unit),
+        numbers.count(scanned_count as u64)
+    );
+    if let Some(volume) = &report.volume {
+        print_volume(volume, report.total_bytes(), display_unit, numbers);
+    }
+    if options.estimate_compression {
+        let total = report.total_bytes();
+        println!(
+            "Compression estimate: zstd would save about {} of {}",
+            numbers.size(compress::estimated_savings(files), display_unit),
+            numbers.size(total, display_unit)
+        );
+    }
+    if let Some(limit) = options.top_dirs {
+        print_top_dirs(&options.directory, files, limit, display_unit, numbers);
+    }
+    if options.stats_by_ext {
+        print_extension_stats(files, display_unit, numbers);
+    }
+    if options.find_duplicates {
+        print_duplicates(files, display_unit, numbers);
+    }
+    if let Some(histogram) = histogram {
+        print_histogram(&histogram.into_inner().unwrap(), display_unit, numbers);
+    }
+    if let Some(cache) = &cache {
+        println!(
+            "Cache: reused {} of {} directories",
+            cache.reused(),
+            cache.reused() + cache.listed()
+        );
+    }
+    if let Some(previous) = &previous {
+        println!();
+        let diff = diff::compare(&previous.files, files, options.delta_bytes);
+        diff::print(&diff, display_unit);
     }
 
-    #[test]
-    fn test_list_big_files_nested_directories() {
-        let dir = tempdir().unwrap();
-        let subdir = dir.path().join("subdir");
-        fs::create_dir(&subdir).unwrap();
-        let nested = subdir.join("nested");
-        fs::create_dir(&nested).unwrap();
+    if partial {
+        println!("Partial scan: interrupted before completion");
+        if let Some(action) = options.action() {
+            println!(
+                "Nothing changed by {}, since the scan did not finish",
+                action.flag()
+            );
+        }
+        drop(pager);
+        process::exit(INTERRUPTED_EXIT_CODE);
+    }
+    drop(pager);
+    if let Some(action) = options.action() {
+        println!();
+        act_on_matches(options, &report.files, &action, io::stdout());
+    }
 
-        create_test_file(dir.path(), "root_file.txt", 150 * 1024 * 1024).unwrap();
-        create_test_file(&subdir, "sub_file.txt", 200 * 1024 * 1024).unwrap();
-        create_test_file(&nested, "nested_file.txt", 100 * 1024 * 1024).unwrap();
+    if options.watch {
+        if let Some(metrics) = metrics {
+            metrics.record_scan(&report.files, report.files_scanned, elapsed);
+        }
+        watch_tree(options, &report.files, &interrupted, metrics);
+    }
+}
 
-        let (files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024);
-        assert_eq!(files.len(), 3);
-        assert_eq!(scanned_count, 3);
+// List the objects under a remote root and report those over the threshold
+// as a scan would, minus the details only a local file has
+fn run_remote(options: &Options, remote: &Remote) {
+    let display_unit = options.display_unit;
+    let structured = options.format != OutputFormat::Table;
+    let to_stdout = structured && options.output.is_none();
+    let previous = options.compare.as_deref().map(load_report);
+    if !to_stdout && !options.summary && !options.count {
+        println!(
+            "Listing {} for files >= {}...\n",
+            options.directory.display(),
+            threshold(options.min_size_bytes, display_unit)
+        );
     }
 
-    #[test]
-    fn test_list_big_files_threshold_boundary() {
-        let dir = tempdir().unwrap();
-        create_test_file(dir.path(), "exactly_100mb.txt", 100 * 1024 * 1024).unwrap();
-        create_test_file(dir.path(), "just_under_100mb.txt", 100 * 1024 * 1024 - 1).unwrap();
+    let started = SystemTime::now();
+    let start = Instant::now();
+    let colors = color::for_stdout(options.color, options.color_tiers);
+    let mut files = Vec::new();
+    let listed = remote::list(remote, |file| {
+        if file.size_bytes < options.min_size_bytes {
+            return;
+        }
+        if options.live {
+            let row = format_row(&file, display_unit, options.numbers);
+            match colors {
+                Some(tiers) => println!("{}", tiers.paint(file.size_bytes, &row)),
+                None => println!("{}", row),
+            }
+        }
+        files.push(file);
+    });
+    let scanned_count = listed.unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }) as usize;
+    let elapsed = start.elapsed();
+    sort_files(&mut files, options.sort);
+    add_tiers(options, &mut files);
+    // Listings always carry the modification time; it is only shown when asked for
+    let wants_modified = options.mtime
+        || options.wants(Column::Modified)
+        || options.wants(Column::Age)
+        || options.group_by == Some(GroupBy::Age);
+    if !wants_modified {
+        for file in &mut files {
+            file.modified = None;
+        }
+    }
+    let report = ScanReport {
+        directory: options.directory.clone(),
+        min_size_bytes: options.min_size_bytes,
+        files_scanned: scanned_count,
+        partial: false,
+        volume: None,
+        files,
+        dry_run: None,
+        // A listing fails as a whole, so nothing it returns was left out
+        scan: Some(ScanInfo::new(started, elapsed, 0)),
+    };
+    if let Some(snapshot) = &options.save_snapshot {
+        if let Err(err) = report.save(snapshot) {
+            warn!("cannot save snapshot: {}", err);
+        }
+    }
+    publish_report(options, &report);
+    if options.summary || options.count {
+        print_summary(options, &report, elapsed);
+        return;
+    }
+    if structured {
+        let shown = shown_report(options, &report);
+        match &options.output {
+            Some(path) => write_output(&shown, options, path, elapsed),
+            None => print_output(&shown, options),
+        }
+        return;
+    }
 
-        let (files, scanned_count) = list_big_files(dir.path(), 100 * 1024 * 1024);
-        assert_eq!(files.len(), 1);
-        assert_eq!(scanned_count, 2);
-        assert_eq!(files[0].size_bytes, 100 * 1024 * 1024);
+    if options.live {
+        println!();
+    }
+    println!("Listed in: {:.2}s", elapsed.as_secs_f64());
+    let shown = shown_report(options, &report);
+    match &options.output {
+        Some(path) => {
+            save_output(&shown, options, path);
+            println!("Wrote {}", path.display());
+        }
+        None => print_matches(&shown.files, options, colors),
+    }
+    let numbers = options.numbers;
+    println!(
+        "\nTotal: {} files, {} (listed {} objects)",
+        numbers.count(report.files.len() as u64),
+        numbers.size(report.total_bytes(), display_unit),
+        numbers.count(scanned_count as u64)
+    );
+    if let Some(limit) = options.top_dirs {
+        let files = &report.files;
+        print_top_dirs(&options.directory, files, limit, display_unit, numbers);
+    }
+    if options.stats_by_ext {
+        print_extension_stats(&report.files, display_unit, numbers);
     }
+    if let Some(previous) = &previous {
+        println!();
+        let diff = diff::compare(&previous.files, &report.files, options.delta_bytes);
+        diff::print(&diff, display_unit);
+    }
+}
+
+// Apply the action --delete, --trash or --move-to asks for to every match,
+// once confirmed by --yes or at a prompt, writing progress to `out`; exits with
+// failure if the action failed for any file
+fn act_on_matches<W: Write>(options: &Options, files: &[FileInfo], action: &Action, mut out: W) {
+    let unit = options.display_unit;
+    let total = display_size(files.iter().map(|file| file.size_bytes).sum(), unit);
+    let verb = action.verb();
+    // " to DEST" for actions that keep the file somewhere else
+    let to = |file: &FileInfo| match action.destination(&file.path) {
+        Some(destination) => format!(" to {}", destination.display()),
+        None => String::new(),
+    };
 
-    #[test]
-    fn test_list_big_files_size_threshold_bytes() {
-        let dir = tempdir().unwrap();
-        create_test_file(dir.path(), "1mb.txt", 1024 * 1024).unwrap();
-        create_test_file(dir.path(), "2mb.txt", 2 * 1024 * 1024).unwrap();
+    if options.dry_run {
+        for step in actions::plan(files, action).steps {
+            let _ = match (&step.command, &step.destination) {
+                (Some(command), _) => {
+                    writeln!(out, "{}Would run {}", DRY_RUN, shell_words(command))
+                }
+                (None, Some(destination)) => writeln!(
+                    out,
+                    "{}Would {} {} to {}",
+                    DRY_RUN,
+                    verb,
+                    step.path.display(),
+                    destination.display()
+                ),
+                (None, None) => writeln!(out, "{}Would {} {}", DRY_RUN, verb, step.path.display()),
+            };
+        }
+        let _ = writeln!(
+            out,
+            "{}Would {} {} files ({}); nothing was changed",
+            DRY_RUN,
+            verb,
+            files.len(),
+            total
+        );
+        return;
+    }
+    if files.is_empty() {
+        let _ = writeln!(out, "Nothing to {}", verb);
+        return;
+    }
+    if !options.yes {
+        if !io::stdin().is_terminal() {
+            eprintln!(
+                "error: {} needs --yes when not run from a terminal",
+                action.flag()
+            );
+            process::exit(1);
+        }
+        eprint!(
+            "{}{} {} files totalling {}? [y/N] ",
+            verb[..1].to_uppercase(),
+            &verb[1..],
+            files.len(),
+            total
+        );
+        let mut answer = String::new();
+        let _ = io::stdin().read_line(&mut answer);
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            let _ = writeln!(out, "Nothing changed");
+            return;
+        }
+    }
 
-        let (files, scanned_count) = list_big_files(dir.path(), 1024 * 1024);
-        assert_eq!(files.len(), 2);
-        assert_eq!(scanned_count, 2);
+    let mut failed = 0;
+    let mut skipped = 0;
+    let summary = actions::apply_all(files, action, |file, result| match result {
+        Ok(Outcome::Done(freed_bytes)) => {
+            let _ = match action {
+                // Compression reports how much smaller each file got
+                Action::Compress { .. } => writeln!(
+                    out,
+                    "Compressed {}{} ({} -> {})",
+                    file.path.display(),
+                    to(file),
+                    display_size(file.size_bytes, unit),
+                    display_size(file.size_bytes - freed_bytes, unit)
+                ),
+                // Commands speak for themselves
+                Action::Exec { .. } => Ok(()),
+                _ => writeln!(out, "{} {}{}", action.done(), file.path.display(), to(file)),
+            };
+        }
+        Ok(Outcome::Skipped(reason)) => {
+            skipped += 1;
+            let _ = writeln!(out, "Skipped {}: {}", file.path.display(), reason);
+        }
+        Err(err) => {
+            failed += 1;
+            error!("cannot {} {}: {}", verb, file.path.display(), err);
+        }
+    });
+    let freed = display_size(summary.freed_bytes, unit);
+    let _ = match action {
+        Action::Remove(Removal::Delete) => {
+            writeln!(out, "Deleted {} files, freed {}", summary.removed, freed)
+        }
+        // Trashed files still take up space until the trash is emptied
+        Action::Remove(Removal::Trash) => writeln!(
+            out,
+            "Trashed {} files ({}, freed once the trash is emptied)",
+            summary.removed, freed
+        ),
+        Action::Move { target, .. } => writeln!(
+            out,
+            "Moved {} files ({}) to {}",
+            summary.removed,
+            freed,
+            target.display()
+        ),
+        Action::Compress { .. } => writeln!(
+            out,
+            "Compressed {} files, saved {} ({} skipped)",
+            summary.removed, freed, skipped
+        ),
+        Action::Exec { .. } => writeln!(
+            out,
+            "Ran the command for {} files ({} failed)",
+            summary.removed, failed
+        ),
+    };
+    if failed > 0 {
+        process::exit(1);
     }
+}
 
-    #[test]
-    fn test_list_big_files_zero_threshold() {
-        let dir = tempdir().unwrap();
-        create_test_file(dir.path(), "tiny.txt", 1).unwrap();
+// Join `args` for display, quoting those a shell would split or expand
+fn shell_words(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "-_./=:,+@%".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Log a report, add it to the history log and notify the configured sinks
+// about its matches
+fn publish_report(options: &Options, report: &ScanReport) {
+    // Threshold crossings in watch mode are not scans, so they are not logged
+    if let (Some(path), Some(entry)) = (&options.append_history, report.history_entry()) {
+        if let Err(err) = report::append_history(path, &entry) {
+            warn!("cannot append to {}: {}", path.display(), err);
+        }
+    }
+    if options.log_syslog {
+        if let Err(err) = syslog::log_report(report) {
+            warn!("cannot log to syslog: {}", err);
+        }
+    }
+    // Plugins take whole scans, not single threshold crossings
+    #[cfg(feature = "plugins")]
+    if report.scan.is_some() {
+        use list_big_files::ResultSink;
+        for mut plugin in PLUGINS.get().into_iter().flatten() {
+            if let Err(err) = plugin.write(&report.files, report.files_scanned) {
+                warn!("{}", err);
+            }
+        }
+    }
+    // Threshold crossings in watch mode are single files, not totals to budget
+    let alert = match options.alert_total.filter(|_| report.scan.is_some()) {
+        Some(budget) => {
+            let over = alert::over_budget(&report.files, budget);
+            if over {
+                warn!(
+                    "matches in {} add up to {}, over the budget of {}",
+                    report.directory.display(),
+                    display_size(report.total_bytes(), options.display_unit),
+                    threshold(budget, options.display_unit)
+                );
+                OVER_BUDGET.store(true, Ordering::SeqCst);
+            }
+            over
+        }
+        None => alert::should_alert(&report.files, options.alert_threshold),
+    };
+    if !alert {
+        return;
+    }
+    if let Some(url) = &options.webhook {
+        let payload = alert::payload(report, options.display_unit);
+        if let Err(err) = alert::send_webhook(url, &payload) {
+            warn!("{}", err);
+        }
+    }
+    if let Some(server) = &options.smtp_server {
+        let mail = email::Mail {
+            server,
+            from: &options.email_from,
+            to: &options.email_to,
+        };
+        if let Err(err) = email::send(&mail, report, options.display_unit) {
+            warn!("{}", err);
+        }
+    }
+}
 
-        let (files, scanned_count) = list_big_files(dir.path(), 0);
-        assert_eq!(files.len(), 1);
-        assert_eq!(scanned_count, 1);
-    }
+// Report files crossing the threshold until Ctrl+C
+fn watch_tree(
+    options: &Options,
+    matches: &[FileInfo],
+    stop: &AtomicBool,
+    metrics: Option<&Metrics>,
+) {
+    let unit = options.display_unit;
+    println!(
+        "\nWatching {:?} for files crossing {} (Ctrl+C to stop)...",
+        options.directory,
+        threshold(options.min_size_bytes, unit)
+    );
 
-    #[test]
-    fn test_file_info_contains_correct_data() {
-        let dir = tempdir().unwrap();
-        let test_size = 150 * 1024 * 1024;
-        create_test_file(dir.path(), "test.txt", test_size).unwrap();
-
-        let (files, _) = list_big_files(dir.path(), 100 * 1024 * 1024);
-        assert_eq!(files.len(), 1);
-        assert_eq!(files[0].size_bytes, test_size as u64);
-        assert!(files[0].path.contains("test.txt"));
+    let tracker = watch::Tracker::new(options.min_size_bytes, matches);
+    let result = watch::run(&options.directory, tracker, stop, |crossing| {
+        if let Some(metrics) = metrics {
+            metrics.record_crossing(&crossing);
+        }
+        match crossing {
+            Crossing::Above(file) => {
+                println!("+ {}", format_row(&file, unit, options.numbers));
+                // Every file that reaches the threshold is worth a notification
+                let report = ScanReport {
+                    directory: options.directory.clone(),
+                    min_size_bytes: options.min_size_bytes,
+                    files_scanned: 1,
+                    partial: false,
+                    volume: None,
+                    files: vec![file],
+                    dry_run: None,
+                    scan: None,
+                };
+                publish_report(options, &report);
+            }
+            Crossing::Below(file) => println!("- {}", format_row(&file, unit, options.numbers)),
+            Crossing::Removed(path) => println!("- {:>14}  {}", "removed", path.display()),
+        }
+    });
+    if let Err(err) = result {
+        error!("cannot watch {:?}: {}", options.directory, err);
+        process::exit(1);
     }
 }
@@ -0,0 +1,217 @@
+// Import the crossings reported in watch mode
+use crate::watch::Crossing;
+// Import FileInfo for the matches of a scan
+use list_big_files::FileInfo;
+// Import HashMap for the current matches and their sizes
+use std::collections::HashMap;
+// Import fmt::Write for building the exposition text
+use std::fmt::Write;
+// Import Path for the root label, and PathBuf for the matches
+use std::path::{Path, PathBuf};
+// Import Arc and Mutex for sharing metrics with the HTTP thread
+use std::sync::{Arc, Mutex};
+// Import thread for serving requests in the background
+use std::thread;
+// Import time types for scan durations and timestamps
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Gauges describing the latest scan of one root, exposed to Prometheus.
+pub struct Metrics {
+    root: String,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    matches: HashMap<PathBuf, u64>,
+    last_scan: Option<LastScan>,
+}
+
+struct LastScan {
+    files_scanned: usize,
+    duration: Duration,
+    finished: SystemTime,
+}
+
+impl Metrics {
+    pub fn new(root: &Path) -> Self {
+        Metrics {
+            root: root.display().to_string(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Replace the matches with those of a completed scan.
+    pub fn record_scan(&self, files: &[FileInfo], files_scanned: usize, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.matches = files
+            .iter()
+            .map(|file| (file.path.clone(), file.size_bytes))
+            .collect();
+        state.last_scan = Some(LastScan {
+            files_scanned,
+            duration,
+            finished: SystemTime::now(),
+        });
+    }
+
+    /// Update the matches after a file crossed the threshold while watching.
+    pub fn record_crossing(&self, crossing: &Crossing) {
+        let mut state = self.state.lock().unwrap();
+        match crossing {
+            Crossing::Above(file) => {
+                state.matches.insert(file.path.clone(), file.size_bytes);
+            }
+            Crossing::Below(file) => {
+                state.matches.remove(&file.path);
+            }
+            Crossing::Removed(path) => {
+                state.matches.remove(path);
+            }
+        }
+    }
+
+    /// Metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let label = format!("{{root=\"{}\"}}", escape_label(&self.root));
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, value: String| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{}{} {}", name, label, value);
+        };
+
+        // Nothing is known until the first scan has finished
+        let Some(last_scan) = &state.last_scan else {
+            return out;
+        };
+        gauge(
+            "lbf_big_files_total",
+            "Files at or above the size threshold.",
+            state.matches.len().to_string(),
+        );
+        gauge(
+            "lbf_big_files_bytes",
+            "Combined size in bytes of files at or above the size threshold.",
+            state.matches.values().sum::<u64>().to_string(),
+        );
+        gauge(
+            "lbf_files_scanned",
+            "Files examined by the last scan.",
+            last_scan.files_scanned.to_string(),
+        );
+        gauge(
+            "lbf_last_scan_duration_seconds",
+            "Duration of the last scan.",
+            last_scan.duration.as_secs_f64().to_string(),
+        );
+        let finished = last_scan
+            .finished
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        gauge(
+            "lbf_last_scan_timestamp_seconds",
+            "Unix time at which the last scan finished.",
+            finished.as_secs().to_string(),
+        );
+        out
+    }
+}
+
+// Escape a label value as required by the exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serve `metrics` at `http://addr/metrics` from a background thread.
+pub fn serve(addr: &str, metrics: Arc<Metrics>) -> Result<(), String> {
+    let server = tiny_http::Server::http(addr)
+        .map_err(|err| format!("cannot listen on {}: {}", addr, err))?;
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = if request.url() == "/metrics" {
+                let content_type = tiny_http::Header::from_bytes(
+                    "Content-Type",
+                    "text/plain; version=0.0.4; charset=utf-8",
+                )
+                .unwrap();
+                tiny_http::Response::from_string(metrics.render()).with_header(content_type)
+            } else {
+                tiny_http::Response::from_string("not found\n").with_status_code(404)
+            };
+            let _ = request.respond(response);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write as _};
+    use std::net::TcpStream;
+
+    fn file(path: &str, size_bytes: u64) -> FileInfo {
+        FileInfo::new(path.to_string(), size_bytes)
+    }
+
+    #[test]
+    fn test_render_before_first_scan_is_empty() {
+        assert_eq!(Metrics::new(Path::new("/srv")).render(), "");
+    }
+
+    #[test]
+    fn test_render_scan_and_crossings() {
+        let metrics = Metrics::new(Path::new("/srv"));
+        metrics.record_scan(
+            &[file("a", 100), file("b", 50)],
+            10,
+            Duration::from_millis(1500),
+        );
+        let text = metrics.render();
+        assert!(text.contains("# TYPE lbf_big_files_total gauge\n"));
+        assert!(text.contains("lbf_big_files_total{root=\"/srv\"} 2\n"));
+        assert!(text.contains("lbf_big_files_bytes{root=\"/srv\"} 150\n"));
+        assert!(text.contains("lbf_files_scanned{root=\"/srv\"} 10\n"));
+        assert!(text.contains("lbf_last_scan_duration_seconds{root=\"/srv\"} 1.5\n"));
+
+        metrics.record_crossing(&Crossing::Above(file("c", 25)));
+        metrics.record_crossing(&Crossing::Removed(PathBuf::from("a")));
+        let text = metrics.render();
+        assert!(text.contains("lbf_big_files_total{root=\"/srv\"} 2\n"));
+        assert!(text.contains("lbf_big_files_bytes{root=\"/srv\"} 75\n"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label(r#"C:\data "x""#), r#"C:\\data \"x\""#);
+    }
+
+    #[test]
+    fn test_serve_metrics_endpoint() {
+        let metrics = Arc::new(Metrics::new(Path::new("/srv")));
+        metrics.record_scan(&[file("a", 100)], 1, Duration::from_secs(1));
+        // Find a free port, then hand it to the server
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let addr = format!("127.0.0.1:{}", port);
+        serve(&addr, metrics).unwrap();
+
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.0\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.0 200") || response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("lbf_big_files_total{root=\"/srv\"} 1"));
+    }
+}
@@ -0,0 +1,706 @@
+// Fast scanning of NTFS volumes by reading the Master File Table directly,
+// the way tools like WizTree do. Every file on the volume has a record in the
+// MFT holding its name, parent directory and size, so reading the table
+// sequentially replaces walking and statting millions of files one by one.
+//
+// The record parsing below is plain byte manipulation and builds everywhere
+// so it can be tested; only reading the raw volume is Windows specific.
+#![cfg_attr(not(windows), allow(dead_code))]
+
+// Import FileInfo for reporting matches
+use crate::FileInfo;
+// Import HashMap for memoizing directory paths
+use std::collections::HashMap;
+// Import Path types for rebuilding file paths
+use std::path::{Path, PathBuf};
+
+// Record number of the volume's root directory
+const ROOT_RECORD: u64 = 5;
+
+// Records below this hold NTFS metadata files ($MFT, $Bitmap, ...) that a
+// directory listing never shows
+const FIRST_USER_RECORD: u64 = 16;
+
+// File references carry a sequence number in their top 16 bits
+const REFERENCE_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
+
+// Attribute type codes
+const ATTR_FILE_NAME: u32 = 0x30;
+const ATTR_DATA: u32 = 0x80;
+const ATTR_END: u32 = 0xFFFF_FFFF;
+
+// Record header flags
+const FLAG_IN_USE: u16 = 0x01;
+const FLAG_DIRECTORY: u16 = 0x02;
+
+// $FILE_NAME namespace holding the 8.3 short name, which is skipped when a
+// long name is available
+const NAMESPACE_DOS: u8 = 2;
+
+// Give up rebuilding a path past this depth; only a corrupt table loops
+const MAX_DEPTH: usize = 4096;
+
+/// Volume geometry read from the NTFS boot sector.
+#[derive(Debug, PartialEq)]
+struct BootSector {
+    bytes_per_sector: usize,
+    bytes_per_cluster: u64,
+    mft_lcn: u64,
+    record_size: usize,
+}
+
+/// What a single FILE record says about its file.
+#[derive(Debug, Default, PartialEq)]
+struct ParsedRecord {
+    base: u64,
+    flags: u16,
+    name: Option<(u64, String, u8)>,
+    data_size: Option<u64>,
+    data_runs: Vec<(u64, u64)>,
+}
+
+/// A file or directory assembled from its base and extension records.
+#[derive(Debug, Default, Clone)]
+struct Entry {
+    parent: u64,
+    name: Option<String>,
+    namespace: u8,
+    is_dir: bool,
+    in_use: bool,
+    size: u64,
+}
+
+fn u16_at(buf: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        buf.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        buf.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn u64_at(buf: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        buf.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+// Read the geometry fields of an NTFS boot sector
+fn parse_boot_sector(buf: &[u8]) -> Option<BootSector> {
+    if buf.get(3..11)? != b"NTFS    " {
+        return None;
+    }
+    let bytes_per_sector = u16_at(buf, 0x0B)? as usize;
+    let sectors_per_cluster = *buf.get(0x0D)? as u64;
+    let bytes_per_cluster = bytes_per_sector as u64 * sectors_per_cluster;
+    let mft_lcn = u64_at(buf, 0x30)?;
+
+    // Positive values count clusters, negative ones are a power of two in bytes
+    let clusters_per_record = *buf.get(0x40)? as i8;
+    let record_size = if clusters_per_record < 0 {
+        1usize << (-clusters_per_record as u32)
+    } else {
+        clusters_per_record as usize * bytes_per_cluster as usize
+    };
+
+    (bytes_per_sector > 0 && bytes_per_cluster > 0 && record_size > 0).then_some(BootSector {
+        bytes_per_sector,
+        bytes_per_cluster,
+        mft_lcn,
+        record_size,
+    })
+}
+
+// Undo the update sequence protection: NTFS replaces the last two bytes of
+// every sector with a check value and stores the originals in an array in
+// the header. Returns false for torn or corrupt records.
+fn apply_fixups(record: &mut [u8], sector_size: usize) -> bool {
+    let (Some(usa_offset), Some(usa_count)) = (u16_at(record, 4), u16_at(record, 6)) else {
+        return false;
+    };
+    let (usa_offset, usa_count) = (usa_offset as usize, usa_count as usize);
+    if usa_count == 0 || usa_offset + usa_count * 2 > record.len() {
+        return false;
+    }
+    let check = [record[usa_offset], record[usa_offset + 1]];
+
+    for i in 1..usa_count {
+        let end = i * sector_size;
+        if end > record.len() || record[end - 2..end] != check {
+            return false;
+        }
+        let original = usa_offset + i * 2;
+        record[end - 2] = record[original];
+        record[end - 1] = record[original + 1];
+    }
+    true
+}
+
+// Decode a non-resident attribute's run list into (cluster, length) pairs.
+// Sparse runs have no clusters on disk and are skipped.
+fn decode_runs(buf: &[u8]) -> Vec<(u64, u64)> {
+    let mut runs = Vec::new();
+    let mut pos = 0;
+    let mut lcn: i64 = 0;
+
+    while let Some(&header) = buf.get(pos) {
+        if header == 0 {
+            break;
+        }
+        let length_size = (header & 0x0F) as usize;
+        let offset_size = (header >> 4) as usize;
+        let Some(fields) = buf.get(pos + 1..pos + 1 + length_size + offset_size) else {
+            break;
+        };
+        let (length_bytes, offset_bytes) = fields.split_at(length_size);
+
+        let length = length_bytes
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        if offset_size > 0 {
+            // Offsets are signed and relative to the previous run
+            let mut delta = offset_bytes
+                .iter()
+                .rev()
+                .fold(0i64, |acc, &b| (acc << 8) | b as i64);
+            let shift = 64 - 8 * offset_size as u32;
+            delta = (delta << shift) >> shift;
+            lcn += delta;
+            runs.push((lcn as u64, length));
+        }
+        pos += 1 + length_size + offset_size;
+    }
+    runs
+}
+
+// Pull the name, parent, size and (for $MFT itself) data runs out of a
+// fixed-up FILE record
+fn parse_record(buf: &[u8]) -> Option<ParsedRecord> {
+    if buf.get(0..4)? != b"FILE" {
+        return None;
+    }
+    let mut parsed = ParsedRecord {
+        base: u64_at(buf, 0x20)? & REFERENCE_MASK,
+        flags: u16_at(buf, 0x16)?,
+        ..ParsedRecord::default()
+    };
+
+    let mut offset = u16_at(buf, 0x14)? as usize;
+    loop {
+        let kind = u32_at(buf, offset)?;
+        let length = u32_at(buf, offset + 4)? as usize;
+        if kind == ATTR_END || length == 0 {
+            break;
+        }
+        let attr = buf.get(offset..offset + length)?;
+        let non_resident = attr[8] != 0;
+        let name_length = attr[9];
+
+        match (kind, non_resident) {
+            (ATTR_FILE_NAME, false) => {
+                let value = resident_value(attr)?;
+                let parent = u64_at(value, 0)? & REFERENCE_MASK;
+                let chars = *value.get(0x40)? as usize;
+                let namespace = *value.get(0x41)?;
+                let units: Vec<u16> = value
+                    .get(0x42..0x42 + chars * 2)?
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                let name = String::from_utf16_lossy(&units);
+
+                // Prefer the long name over the DOS 8.3 alias
+                let replace = match &parsed.name {
+                    None => true,
+                    Some((_, _, existing)) => *existing == NAMESPACE_DOS,
+                };
+                if replace {
+                    parsed.name = Some((parent, name, namespace));
+                }
+            }
+            // Only the unnamed data stream is the file's content
+            (ATTR_DATA, false) if name_length == 0 => {
+                parsed.data_size = Some(u32_at(attr, 0x10)? as u64);
+            }
+            (ATTR_DATA, true) if name_length == 0 && u64_at(attr, 0x10)? == 0 => {
+                parsed.data_size = Some(u64_at(attr, 0x30)?);
+                let runs_offset = u16_at(attr, 0x20)? as usize;
+                parsed.data_runs = decode_runs(attr.get(runs_offset..)?);
+            }
+            _ => {}
+        }
+        offset += length;
+    }
+
+    Some(parsed)
+}
+
+// The value of a resident attribute
+fn resident_value(attr: &[u8]) -> Option<&[u8]> {
+    let length = u32_at(attr, 0x10)? as usize;
+    let offset = u16_at(attr, 0x14)? as usize;
+    attr.get(offset..offset + length)
+}
+
+/// Every file and directory found in the table, by record number.
+#[derive(Debug, Default)]
+struct MftIndex {
+    entries: Vec<Entry>,
+}
+
+impl MftIndex {
+    // Merge a record into the entry it describes; extension records add
+    // attributes to their base record
+    fn add(&mut self, number: u64, record: ParsedRecord) {
+        let target = if record.base != 0 {
+            record.base
+        } else {
+            number
+        };
+        if self.entries.len() <= target as usize {
+            self.entries.resize(target as usize + 1, Entry::default());
+        }
+        let entry = &mut self.entries[target as usize];
+
+        if record.base == 0 {
+            entry.in_use = record.flags & FLAG_IN_USE != 0;
+            entry.is_dir = record.flags & FLAG_DIRECTORY != 0;
+        }
+        if let Some((parent, name, namespace)) = record.name {
+            if entry.name.is_none() || entry.namespace == NAMESPACE_DOS {
+                entry.parent = parent;
+                entry.name = Some(name);
+                entry.namespace = namespace;
+            }
+        }
+        if let Some(size) = record.data_size {
+            entry.size = size;
+        }
+    }
+
+    // Path of a directory relative to the volume root, memoized
+    fn dir_path(&self, number: u64, memo: &mut HashMap<u64, Option<PathBuf>>) -> Option<PathBuf> {
+        let mut chain = Vec::new();
+        let mut current = number;
+        let mut base = loop {
+            if current == ROOT_RECORD {
+                break PathBuf::new();
+            }
+            if let Some(known) = memo.get(&current) {
+                break known.clone()?;
+            }
+            let entry = self.entries.get(current as usize)?;
+            if !entry.in_use || chain.len() > MAX_DEPTH {
+                return None;
+            }
+            chain.push(current);
+            current = entry.parent;
+        };
+
+        while let Some(dir) = chain.pop() {
+            base.push(self.entries[dir as usize].name.as_deref()?);
+            memo.insert(dir, Some(base.clone()));
+        }
+        Some(base)
+    }
+
+    // Report files under `root_relative` (a path relative to the volume
+    // root), returning how many files are under it. Reported paths are
+    // `display_root` joined with the rest of the file's path.
+    fn report<F>(
+        &self,
+        root_relative: &Path,
+        display_root: &Path,
+        min_size_bytes: u64,
+        callback: F,
+    ) -> usize
+    where
+        F: Fn(FileInfo),
+    {
+        let mut memo = HashMap::new();
+        let mut scanned = 0;
+
+        for (number, entry) in self.entries.iter().enumerate() {
+            if (number as u64) < FIRST_USER_RECORD || !entry.in_use || entry.is_dir {
+                continue;
+            }
+            let Some(name) = &entry.name else {
+                continue;
+            };
+            let Some(dir) = self.dir_path(entry.parent, &mut memo) else {
+                continue;
+            };
+            let Ok(rest) = dir.strip_prefix(root_relative) else {
+                continue;
+            };
+
+            scanned += 1;
+            if entry.size >= min_size_bytes {
+                callback(FileInfo::new(
+                    display_root.join(rest).join(name),
+                    entry.size,
+                ));
+            }
+        }
+        scanned
+    }
+}
+
+#[cfg(windows)]
+mod volume {
+    use super::*;
+    // Import File and positioned reads for raw volume access
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::fs::FileExt;
+    // Import Component and Prefix to find the drive letter of the root
+    use std::path::{Component, Prefix};
+
+    // Bytes of the table read per request
+    const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+    fn read_exact_at(volume: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            let read = volume.seek_read(&mut buf[done..], offset + done as u64)?;
+            if read == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            done += read;
+        }
+        Ok(())
+    }
+
+    fn invalid(message: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+    }
+
+    /// Scan `root` by reading its volume's MFT. Needs administrator rights
+    /// to open the raw volume. Returns the number of files under `root`.
+    pub fn scan(
+        root: &Path,
+        min_size_bytes: u64,
+        is_cancelled: &dyn Fn() -> bool,
+        report_progress: &dyn Fn(usize),
+        callback: &dyn Fn(FileInfo),
+    ) -> io::Result<usize> {
+        let canonical = root.canonicalize()?;
+        let mut components = canonical.components();
+        let drive = match components.next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => letter as char,
+                _ => return Err(invalid("the MFT backend needs a path on a lettered drive")),
+            },
+            _ => return Err(invalid("the MFT backend needs an absolute path")),
+        };
+        let root_relative: PathBuf = components
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect();
+
+        let volume = File::open(format!(r"\\.\{}:", drive))?;
+        let mut boot = vec![0u8; 512];
+        read_exact_at(&volume, &mut boot, 0)?;
+        let boot = parse_boot_sector(&boot).ok_or_else(|| invalid("not an NTFS volume"))?;
+
+        // Record 0 describes $MFT itself, including where its data lives
+        let mut record = vec![0u8; boot.record_size];
+        read_exact_at(&volume, &mut record, boot.mft_lcn * boot.bytes_per_cluster)?;
+        if !apply_fixups(&mut record, boot.bytes_per_sector) {
+            return Err(invalid("corrupt $MFT record"));
+        }
+        let runs = parse_record(&record)
+            .map(|mft| mft.data_runs)
+            .filter(|runs| !runs.is_empty())
+            .ok_or_else(|| invalid("cannot locate the $MFT data"))?;
+
+        let mut index = MftIndex::default();
+        let mut number = 0u64;
+        let mut buf = vec![0u8; CHUNK_SIZE as usize];
+        'runs: for (lcn, clusters) in runs {
+            let mut offset = lcn * boot.bytes_per_cluster;
+            let mut remaining = clusters * boot.bytes_per_cluster;
+            while remaining > 0 {
+                if is_cancelled() {
+                    break 'runs;
+                }
+                let chunk = remaining.min(CHUNK_SIZE) as usize;
+                read_exact_at(&volume, &mut buf[..chunk], offset)?;
+                for record in buf[..chunk].chunks_exact_mut(boot.record_size) {
+                    if apply_fixups(record, boot.bytes_per_sector) {
+                        if let Some(parsed) = parse_record(record) {
+                            index.add(number, parsed);
+                        }
+                    }
+                    number += 1;
+                }
+                offset += chunk as u64;
+                remaining -= chunk as u64;
+                report_progress(number as usize);
+            }
+        }
+
+        Ok(index.report(&root_relative, root, min_size_bytes, callback))
+    }
+}
+
+#[cfg(windows)]
+pub use volume::scan;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const SECTOR: usize = 512;
+    const RECORD: usize = 1024;
+
+    fn attribute(kind: u32, non_resident: bool, body: &[u8]) -> Vec<u8> {
+        let mut attr = vec![0u8; 0x18];
+        attr[0..4].copy_from_slice(&kind.to_le_bytes());
+        attr[8] = non_resident as u8;
+        if !non_resident {
+            attr[0x10..0x14].copy_from_slice(&(body.len() as u32).to_le_bytes());
+            attr[0x14..0x16].copy_from_slice(&0x18u16.to_le_bytes());
+        }
+        attr.extend_from_slice(body);
+        while !attr.len().is_multiple_of(8) {
+            attr.push(0);
+        }
+        let length = attr.len() as u32;
+        attr[4..8].copy_from_slice(&length.to_le_bytes());
+        attr
+    }
+
+    fn file_name(parent: u64, name: &str, namespace: u8) -> Vec<u8> {
+        let units: Vec<u16> = name.encode_utf16().collect();
+        let mut value = vec![0u8; 0x42];
+        value[0..8].copy_from_slice(&(parent | (1 << 48)).to_le_bytes());
+        value[0x40] = units.len() as u8;
+        value[0x41] = namespace;
+        for unit in units {
+            value.extend_from_slice(&unit.to_le_bytes());
+        }
+        attribute(ATTR_FILE_NAME, false, &value)
+    }
+
+    fn non_resident_data(size: u64, runs: &[u8]) -> Vec<u8> {
+        let mut attr = vec![0u8; 0x40];
+        attr[0..4].copy_from_slice(&ATTR_DATA.to_le_bytes());
+        attr[8] = 1;
+        attr[0x20..0x22].copy_from_slice(&0x40u16.to_le_bytes());
+        attr[0x30..0x38].copy_from_slice(&size.to_le_bytes());
+        attr.extend_from_slice(runs);
+        attr.push(0);
+        while !attr.len().is_multiple_of(8) {
+            attr.push(0);
+        }
+        let length = attr.len() as u32;
+        attr[4..8].copy_from_slice(&length.to_le_bytes());
+        attr
+    }
+
+    // Build a FILE record with update sequence protection applied
+    fn record(flags: u16, base: u64, attributes: &[Vec<u8>]) -> Vec<u8> {
+        let mut buf = vec![0u8; RECORD];
+        buf[0..4].copy_from_slice(b"FILE");
+        buf[4..6].copy_from_slice(&0x30u16.to_le_bytes());
+        buf[6..8].copy_from_slice(&3u16.to_le_bytes());
+        buf[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes());
+        buf[0x16..0x18].copy_from_slice(&flags.to_le_bytes());
+        buf[0x20..0x28].copy_from_slice(&base.to_le_bytes());
+
+        let mut offset = 0x38;
+        for attr in attributes {
+            buf[offset..offset + attr.len()].copy_from_slice(attr);
+            offset += attr.len();
+        }
+        buf[offset..offset + 4].copy_from_slice(&ATTR_END.to_le_bytes());
+
+        // Move each sector's last two bytes into the update sequence array
+        let check = [0xAB, 0xCD];
+        buf[0x30..0x32].copy_from_slice(&check);
+        for i in 1..3 {
+            let end = i * SECTOR;
+            let original = [buf[end - 2], buf[end - 1]];
+            buf[0x30 + i * 2..0x32 + i * 2].copy_from_slice(&original);
+            buf[end - 2..end].copy_from_slice(&check);
+        }
+        buf
+    }
+
+    fn parse(mut buf: Vec<u8>) -> ParsedRecord {
+        assert!(apply_fixups(&mut buf, SECTOR));
+        parse_record(&buf).unwrap()
+    }
+
+    #[test]
+    fn test_parse_boot_sector() {
+        let mut boot = vec![0u8; 512];
+        boot[3..11].copy_from_slice(b"NTFS    ");
+        boot[0x0B..0x0D].copy_from_slice(&512u16.to_le_bytes());
+        boot[0x0D] = 8;
+        boot[0x30..0x38].copy_from_slice(&786432u64.to_le_bytes());
+        boot[0x40] = (-10i8) as u8;
+
+        assert_eq!(
+            parse_boot_sector(&boot),
+            Some(BootSector {
+                bytes_per_sector: 512,
+                bytes_per_cluster: 4096,
+                mft_lcn: 786432,
+                record_size: 1024,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_boot_sector_rejects_other_filesystems() {
+        let mut boot = vec![0u8; 512];
+        boot[3..11].copy_from_slice(b"MSDOS5.0");
+        assert_eq!(parse_boot_sector(&boot), None);
+    }
+
+    #[test]
+    fn test_apply_fixups_detects_torn_record() {
+        let mut buf = record(FLAG_IN_USE, 0, &[]);
+        buf[SECTOR - 1] ^= 0xFF;
+        assert!(!apply_fixups(&mut buf, SECTOR));
+    }
+
+    #[test]
+    fn test_decode_runs() {
+        // 0x18 clusters at 0x5634, then 0x10 clusters 0x100 clusters earlier,
+        // then a sparse run
+        let runs = [
+            0x21, 0x18, 0x34, 0x56, 0x21, 0x10, 0x00, 0xFF, 0x01, 0x05, 0x00,
+        ];
+        assert_eq!(decode_runs(&runs), vec![(0x5634, 0x18), (0x5534, 0x10)]);
+    }
+
+    #[test]
+    fn test_parse_record_prefers_long_name_and_reads_size() {
+        let parsed = parse(record(
+            FLAG_IN_USE,
+            0,
+            &[
+                file_name(5, "BIGFIL~1.ISO", NAMESPACE_DOS),
+                file_name(5, "big file.iso", 1),
+                non_resident_data(5_000_000_000, &[0x21, 0x10, 0x00, 0x10]),
+            ],
+        ));
+
+        assert_eq!(parsed.name, Some((5, "big file.iso".to_string(), 1)));
+        assert_eq!(parsed.data_size, Some(5_000_000_000));
+        assert_eq!(parsed.data_runs, vec![(0x1000, 0x10)]);
+    }
+
+    #[test]
+    fn test_parse_record_resident_data_and_named_streams() {
+        let mut named_stream = attribute(ATTR_DATA, false, &[0u8; 100]);
+        named_stream[9] = 4;
+        let parsed = parse(record(
+            FLAG_IN_USE,
+            0,
+            &[
+                file_name(5, "small.txt", 1),
+                attribute(ATTR_DATA, false, &[0u8; 42]),
+                named_stream,
+            ],
+        ));
+
+        assert_eq!(parsed.data_size, Some(42));
+    }
+
+    #[test]
+    fn test_index_rebuilds_paths_under_root() {
+        let mut index = MftIndex::default();
+        let dir = FLAG_IN_USE | FLAG_DIRECTORY;
+        index.add(5, parse(record(dir, 0, &[file_name(5, ".", 1)])));
+        index.add(20, parse(record(dir, 0, &[file_name(5, "Users", 1)])));
+        index.add(21, parse(record(dir, 0, &[file_name(20, "me", 1)])));
+        index.add(
+            30,
+            parse(record(
+                FLAG_IN_USE,
+                0,
+                &[
+                    file_name(21, "movie.mkv", 1),
+                    non_resident_data(900, &[0x11, 0x01, 0x01]),
+                ],
+            )),
+        );
+        index.add(
+            31,
+            parse(record(
+                FLAG_IN_USE,
+                0,
+                &[
+                    file_name(5, "pagefile.sys", 1),
+                    non_resident_data(5000, &[0x11, 0x01, 0x01]),
+                ],
+            )),
+        );
+        // A deleted file is ignored
+        index.add(
+            32,
+            parse(record(
+                0,
+                0,
+                &[
+                    file_name(21, "gone.bin", 1),
+                    non_resident_data(5000, &[0x11, 0x01, 0x01]),
+                ],
+            )),
+        );
+
+        let found = Mutex::new(Vec::new());
+        let scanned = index.report(Path::new("Users"), Path::new(r"C:\Users"), 100, |file| {
+            found.lock().unwrap().push(file)
+        });
+
+        let found = found.into_inner().unwrap();
+        assert_eq!(scanned, 1);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].size_bytes, 900);
+        assert_eq!(
+            found[0].path,
+            Path::new(r"C:\Users")
+                .join("me")
+                .join("movie.mkv")
+                .display()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_index_merges_extension_records() {
+        let mut index = MftIndex::default();
+        index.add(5, parse(record(FLAG_IN_USE | FLAG_DIRECTORY, 0, &[])));
+        index.add(
+            40,
+            parse(record(FLAG_IN_USE, 0, &[file_name(5, "huge.vhdx", 1)])),
+        );
+        // The data attribute lives in an extension record pointing at 40
+        index.add(
+            41,
+            parse(record(
+                FLAG_IN_USE,
+                40 | (3 << 48),
+                &[non_resident_data(1 << 40, &[0x11, 0x01, 0x01])],
+            )),
+        );
+
+        let found = Mutex::new(Vec::new());
+        let scanned = index.report(Path::new(""), Path::new("C:\\"), 0, |file| {
+            found.lock().unwrap().push(file)
+        });
+
+        let found = found.into_inner().unwrap();
+        assert_eq!(scanned, 1);
+        assert_eq!(found[0].size_bytes, 1 << 40);
+    }
+}
@@ -0,0 +1,255 @@
+// Import fs to resolve the scan root and read the mount table
+use std::fs;
+// Import Path types for mount points and the directories they are shown as
+use std::path::{Path, PathBuf};
+
+// File system types served over the network, as Linux and macOS name them.
+// FUSE file systems are matched by prefix, since most of them (sshfs, rclone,
+// s3fs) are remote too
+const NETWORK_TYPES: [&str; 16] = [
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb",
+    "smb3",
+    "smbfs",
+    "afpfs",
+    "webdav",
+    "davfs",
+    "ncpfs",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "lustre",
+    "9p",
+    "remote",
+];
+
+/// A network or FUSE file system mounted at or below a scan root.
+#[derive(Debug, PartialEq)]
+pub struct Mount {
+    /// Where it is mounted, under the root as it was given, or the root itself
+    /// when the whole tree is on it.
+    pub path: PathBuf,
+    /// Its file system type, e.g. `nfs4`, `cifs` or `fuse.sshfs`.
+    pub fs_type: String,
+}
+
+/// The network and FUSE file systems `root` is on or has mounted below it,
+/// read from /proc/self/mountinfo on Linux and getfsstat on macOS. Windows
+/// only tells whether the root is on a mapped drive or share, since shares
+/// cannot be mounted inside a local directory.
+pub fn network_mounts(root: &Path) -> Vec<Mount> {
+    let Ok(canonical) = fs::canonicalize(root) else {
+        return Vec::new();
+    };
+    let mut found: Vec<Mount> = Vec::new();
+    for (point, fs_type) in mounts(&canonical) {
+        if !is_network(&fs_type) {
+            continue;
+        }
+        let path = match point.strip_prefix(&canonical) {
+            Ok(rest) if rest.as_os_str().is_empty() => root.to_path_buf(),
+            Ok(rest) => root.join(rest),
+            Err(_) if canonical.starts_with(&point) => root.to_path_buf(),
+            Err(_) => continue,
+        };
+        // Later mounts hide earlier ones at the same place
+        found.retain(|mount| mount.path != path);
+        found.push(Mount { path, fs_type });
+    }
+    found
+}
+
+/// Where the network and FUSE file systems below `root` are mounted, to leave
+/// them out with [`list_big_files::Scanner::skip_dirs`]. One holding the root
+/// itself is not included, since that would leave nothing to scan.
+pub fn mounts_below(root: &Path) -> Vec<PathBuf> {
+    network_mounts(root)
+        .into_iter()
+        .map(|mount| mount.path)
+        .filter(|path| path != root)
+        .collect()
+}
+
+// Whether the file system type `fs_type` is served over the network or FUSE.
+// fuseblk is ntfs-3g and exfat on local disks
+fn is_network(fs_type: &str) -> bool {
+    NETWORK_TYPES.contains(&fs_type)
+        || fs_type.starts_with("nfs")
+        || fs_type.starts_with("macfuse")
+        || fs_type.starts_with("osxfuse")
+        || (fs_type.starts_with("fuse") && fs_type != "fuseblk" && fs_type != "fusectl")
+}
+
+// Every mount point with its file system type
+#[cfg(target_os = "linux")]
+fn mounts(_root: &Path) -> Vec<(PathBuf, String)> {
+    fs::read_to_string("/proc/self/mountinfo")
+        .map(|info| parse_mountinfo(&info))
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+fn mounts(_root: &Path) -> Vec<(PathBuf, String)> {
+    use std::ffi::{CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    // SAFETY: a null buffer only asks for the number of mounts
+    let count = unsafe { libc::getfsstat(std::ptr::null_mut(), 0, libc::MNT_NOWAIT) };
+    if count <= 0 {
+        return Vec::new();
+    }
+    let mut stats: Vec<libc::statfs> = Vec::with_capacity(count as usize);
+    let size = (stats.capacity() * std::mem::size_of::<libc::statfs>()) as libc::c_int;
+    // SAFETY: the buffer holds `size` bytes, and getfsstat fills at most that
+    let filled = unsafe { libc::getfsstat(stats.as_mut_ptr(), size, libc::MNT_NOWAIT) };
+    if filled <= 0 {
+        return Vec::new();
+    }
+    // SAFETY: getfsstat initialized the first `filled` entries
+    unsafe { stats.set_len((filled as usize).min(stats.capacity())) };
+    stats
+        .iter()
+        .map(|stat| {
+            // SAFETY: both names are NUL-terminated within their arrays
+            let (point, fs_type) = unsafe {
+                (
+                    CStr::from_ptr(stat.f_mntonname.as_ptr()),
+                    CStr::from_ptr(stat.f_fstypename.as_ptr()),
+                )
+            };
+            (
+                PathBuf::from(OsStr::from_bytes(point.to_bytes())),
+                fs_type.to_string_lossy().into_owned(),
+            )
+        })
+        .collect()
+}
+
+// The root itself when it is on a share or a mapped network drive
+#[cfg(windows)]
+fn mounts(root: &Path) -> Vec<(PathBuf, String)> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDriveTypeW;
+    const DRIVE_REMOTE: u32 = 4;
+
+    // The resolved root carries the \\?\ prefix, which GetDriveTypeW does not take
+    let plain = list_big_files::plain_path(root);
+    if plain.to_string_lossy().starts_with(r"\\") {
+        return vec![(root.to_path_buf(), "smb".to_string())];
+    }
+    let Some(drive) = plain.components().next() else {
+        return Vec::new();
+    };
+    let mut drive_root = drive.as_os_str().to_os_string();
+    drive_root.push(r"\");
+    let wide: Vec<u16> = drive_root.encode_wide().chain(Some(0)).collect();
+    // SAFETY: the drive root is NUL-terminated
+    if unsafe { GetDriveTypeW(wide.as_ptr()) } == DRIVE_REMOTE {
+        return vec![(root.to_path_buf(), "remote".to_string())];
+    }
+    Vec::new()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn mounts(_root: &Path) -> Vec<(PathBuf, String)> {
+    Vec::new()
+}
+
+// The mount points and types in /proc/self/mountinfo, whose lines read
+// `id parent major:minor root mount-point options [tags] - type source ...`
+#[cfg(any(target_os = "linux", test))]
+fn parse_mountinfo(info: &str) -> Vec<(PathBuf, String)> {
+    info.lines()
+        .filter_map(|line| {
+            let (fields, rest) = line.split_once(" - ")?;
+            let point = fields.split(' ').nth(4)?;
+            let fs_type = rest.split(' ').next()?;
+            Some((PathBuf::from(unescape(point)), fs_type.to_string()))
+        })
+        .collect()
+}
+
+// A mount point with the octal escapes the kernel writes for spaces, tabs,
+// newlines and backslashes, e.g. `\040`, replaced by the characters
+#[cfg(any(target_os = "linux", test))]
+fn unescape(point: &str) -> String {
+    let mut plain = String::with_capacity(point.len());
+    let mut rest = point;
+    while let Some(at) = rest.find('\\') {
+        plain.push_str(&rest[..at]);
+        let escape = rest.get(at + 1..at + 4);
+        match escape.and_then(|digits| u8::from_str_radix(digits, 8).ok()) {
+            Some(byte) => {
+                plain.push(byte as char);
+                rest = &rest[at + 4..];
+            }
+            None => {
+                plain.push('\\');
+                rest = &rest[at + 1..];
+            }
+        }
+    }
+    plain.push_str(rest);
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let info = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+41 22 0:40 / /mnt/nas rw,relatime shared:20 - nfs4 nas:/export rw,vers=4.2
+42 22 0:41 / /home/dev/My\\040Share rw - cifs //server/share rw
+43 22 0:42 / /media/usb rw - fuseblk /dev/sdb1 rw
+";
+        assert_eq!(
+            parse_mountinfo(info),
+            [
+                (PathBuf::from("/"), "ext4".to_string()),
+                (PathBuf::from("/mnt/nas"), "nfs4".to_string()),
+                (PathBuf::from("/home/dev/My Share"), "cifs".to_string()),
+                (PathBuf::from("/media/usb"), "fuseblk".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"/mnt/a\040b\011c"), "/mnt/a b\tc");
+        assert_eq!(unescape(r"/mnt/back\134slash"), r"/mnt/back\slash");
+        assert_eq!(unescape(r"/mnt/odd\x"), r"/mnt/odd\x");
+    }
+
+    #[test]
+    fn test_is_network() {
+        for fs_type in [
+            "nfs",
+            "nfs4",
+            "cifs",
+            "smbfs",
+            "fuse.sshfs",
+            "fuse.rclone",
+            "macfuse",
+        ] {
+            assert!(is_network(fs_type), "{}", fs_type);
+        }
+        for fs_type in ["ext4", "apfs", "tmpfs", "fuseblk", "fusectl", "overlay"] {
+            assert!(!is_network(fs_type), "{}", fs_type);
+        }
+    }
+
+    #[test]
+    fn test_local_temp_dir_has_no_network_mounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mounts = network_mounts(dir.path());
+        // A temp dir on a network home directory is possible, but then it
+        // is the tree itself that is remote
+        assert!(mounts.iter().all(|mount| mount.path == dir.path()));
+        assert!(network_mounts(&dir.path().join("missing")).is_empty());
+    }
+}
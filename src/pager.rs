@@ -0,0 +1,72 @@
+// Only Unix has a pager to hand stdout to
+#![cfg_attr(not(unix), allow(dead_code, unused_imports))]
+
+// Import env for the pager's own settings
+use std::env;
+// Import io for the terminal stdout is written to
+use std::io::{self, IsTerminal, Write};
+// Import process types for running the pager
+use std::process::{Child, Command, Stdio};
+
+/// A pager reading everything printed to stdout, until it is dropped and
+/// waited for.
+pub struct Pager {
+    child: Child,
+    // The terminal stdout wrote to before the pager took its place
+    #[cfg(unix)]
+    terminal: libc::c_int,
+}
+
+/// Send stdout through `command`, run by the shell, when stdout is a
+/// terminal. Unless `LESS` says otherwise, less quits straight away when the
+/// output fits on one screen, keeps colors, and leaves the output on the
+/// screen, as git sets it up.
+#[cfg(unix)]
+pub fn start(command: &str) -> Option<Pager> {
+    use std::os::unix::io::AsRawFd;
+
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    let mut pager = Command::new("sh");
+    pager.arg("-c").arg(command).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        pager.env("LESS", "FRX");
+    }
+    if env::var_os("LV").is_none() {
+        pager.env("LV", "-c");
+    }
+    let mut child = pager.spawn().ok()?;
+    let input = child.stdin.take()?;
+    let _ = io::stdout().flush();
+    // SAFETY: only file descriptors owned by this process are duplicated
+    let terminal = unsafe { libc::dup(libc::STDOUT_FILENO) };
+    if terminal < 0 || unsafe { libc::dup2(input.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        let _ = child.kill();
+        return None;
+    }
+    // Quitting the pager early ends the process quietly, as it does for git,
+    // rather than failing the next write
+    unsafe { libc::signal(libc::SIGPIPE, libc::SIG_DFL) };
+    Some(Pager { child, terminal })
+}
+
+/// Windows has no pager to rely on, so output goes straight to the console.
+#[cfg(not(unix))]
+pub fn start(_command: &str) -> Option<Pager> {
+    None
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        // Pointing stdout back at the terminal closes the pager's input, so it
+        // sees the end of the output
+        #[cfg(unix)]
+        unsafe {
+            libc::dup2(self.terminal, libc::STDOUT_FILENO);
+            libc::close(self.terminal);
+        }
+        let _ = self.child.wait();
+    }
+}
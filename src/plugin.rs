@@ -0,0 +1,226 @@
+// Import FileInfo for the matches plugins see
+use crate::FileInfo;
+// Import Error for failures sinks report back
+use std::error::Error;
+// Import Path for where plugins are loaded from
+#[cfg(feature = "plugins")]
+use std::path::{Path, PathBuf};
+
+/// Error a [`ResultSink`] returns when it cannot take the results.
+pub type SinkError = Box<dyn Error + Send + Sync>;
+
+/// Decides which matches a scan reports, e.g. to leave out files under legal
+/// hold. Filters see every file that meets the size threshold and are called
+/// from the scan's worker threads, so they must be cheap and thread-safe.
+///
+/// Closures taking a `&FileInfo` and returning a `bool` are filters:
+///
+/// ```no_run
+/// use list_big_files::Scanner;
+///
+/// let (files, _) = Scanner::new("/srv")
+///     .min_size(1 << 30)
+///     .filter(|file: &list_big_files::FileInfo| !file.path.starts_with("/srv/hold"))
+///     .collect()
+///     .unwrap();
+/// ```
+pub trait FileFilter: Send + Sync {
+    /// Whether `file` is reported.
+    fn keep(&self, file: &FileInfo) -> bool;
+}
+
+impl<F> FileFilter for F
+where
+    F: Fn(&FileInfo) -> bool + Send + Sync,
+{
+    fn keep(&self, file: &FileInfo) -> bool {
+        self(file)
+    }
+}
+
+/// Receives the matches of a finished scan, e.g. to store them in an
+/// inventory system. See [`Scanner::collect_into`](crate::Scanner::collect_into).
+pub trait ResultSink {
+    /// Take the matches, unsorted, and the number of files scanned.
+    fn write(&mut self, files: &[FileInfo], files_scanned: usize) -> Result<(), SinkError>;
+}
+
+// The plugin interface version this build speaks
+#[cfg(feature = "plugins")]
+const ABI_VERSION: u32 = 1;
+
+// fn(path, path_len, size_bytes) -> keep
+#[cfg(feature = "plugins")]
+type FilterFn = unsafe extern "C" fn(*const u8, usize, u64) -> bool;
+// fn(path, path_len, size_bytes), once per match
+#[cfg(feature = "plugins")]
+type SinkFileFn = unsafe extern "C" fn(*const u8, usize, u64);
+// fn(files_scanned) -> status, 0 for success
+#[cfg(feature = "plugins")]
+type SinkFinishFn = unsafe extern "C" fn(u64) -> i32;
+
+/// A filter and sink loaded from a shared library, for organizations that
+/// cannot add theirs to the source. The library exports C functions:
+///
+/// - `uint32_t lbf_plugin_abi_version(void)`, returning 1 (required)
+/// - `bool lbf_filter(const uint8_t *path, size_t len, uint64_t size_bytes)`,
+///   returning whether the match is kept
+/// - `void lbf_sink_file(const uint8_t *path, size_t len, uint64_t size_bytes)`,
+///   called for each match of a finished scan
+/// - `int32_t lbf_sink_finish(uint64_t files_scanned)`, called after the last
+///   match, returning 0 on success
+///
+/// Each of the last three is optional. Paths are passed as bytes, not
+/// NUL-terminated: as the OS gave them on Unix, and as UTF-8 on Windows
+/// (WTF-8 for names that are not valid Unicode). `lbf_filter` is called from
+/// several threads at once.
+#[cfg(feature = "plugins")]
+pub struct Plugin {
+    path: PathBuf,
+    filter: Option<FilterFn>,
+    sink: Option<(SinkFileFn, SinkFinishFn)>,
+    // Keeps the functions above loaded; dropped last
+    _library: libloading::Library,
+}
+
+#[cfg(feature = "plugins")]
+impl Plugin {
+    /// Load the plugin at `path`, checking that it speaks this interface.
+    ///
+    /// # Safety
+    ///
+    /// Loading runs the library's initialization code, and its functions are
+    /// trusted to match the signatures above. Only load plugins you trust as
+    /// much as this binary.
+    pub unsafe fn load(path: &Path) -> Result<Plugin, String> {
+        let failed = |err: libloading::Error| format!("cannot load {}: {}", path.display(), err);
+        let library = libloading::Library::new(path).map_err(failed)?;
+        let version = library
+            .get::<unsafe extern "C" fn() -> u32>(b"lbf_plugin_abi_version")
+            .map_err(failed)?();
+        if version != ABI_VERSION {
+            return Err(format!(
+                "{} is built for plugin interface {}, but this build speaks {}",
+                path.display(),
+                version,
+                ABI_VERSION
+            ));
+        }
+        let filter = library.get::<FilterFn>(b"lbf_filter").ok().map(|f| *f);
+        let sink_file = library.get::<SinkFileFn>(b"lbf_sink_file").ok().map(|f| *f);
+        let sink_finish = library
+            .get::<SinkFinishFn>(b"lbf_sink_finish")
+            .ok()
+            .map(|f| *f);
+        let sink = match (sink_file, sink_finish) {
+            (Some(file), Some(finish)) => Some((file, finish)),
+            (None, None) => None,
+            _ => {
+                return Err(format!(
+                    "{} must export both lbf_sink_file and lbf_sink_finish, or neither",
+                    path.display()
+                ))
+            }
+        };
+        if filter.is_none() && sink.is_none() {
+            return Err(format!(
+                "{} exports neither lbf_filter nor lbf_sink_file",
+                path.display()
+            ));
+        }
+        Ok(Plugin {
+            path: path.to_path_buf(),
+            filter,
+            sink,
+            _library: library,
+        })
+    }
+
+    /// Where the plugin was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the plugin exports `lbf_filter`.
+    pub fn is_filter(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    /// Whether the plugin exports `lbf_sink_file` and `lbf_sink_finish`.
+    pub fn is_sink(&self) -> bool {
+        self.sink.is_some()
+    }
+}
+
+#[cfg(feature = "plugins")]
+impl FileFilter for Plugin {
+    fn keep(&self, file: &FileInfo) -> bool {
+        let Some(filter) = self.filter else {
+            return true;
+        };
+        let path = file.path.as_os_str().as_encoded_bytes();
+        // SAFETY: the path outlives the call, and load checked the interface
+        unsafe { filter(path.as_ptr(), path.len(), file.size_bytes) }
+    }
+}
+
+// The plugin keeps whatever state it needs on its side, so a shared
+// reference is enough to hand it results
+#[cfg(feature = "plugins")]
+impl ResultSink for &Plugin {
+    fn write(&mut self, files: &[FileInfo], files_scanned: usize) -> Result<(), SinkError> {
+        let Some((sink_file, sink_finish)) = self.sink else {
+            return Ok(());
+        };
+        // SAFETY: each path outlives its call, and load checked the interface
+        let status = unsafe {
+            for file in files {
+                let path = file.path.as_os_str().as_encoded_bytes();
+                sink_file(path.as_ptr(), path.len(), file.size_bytes);
+            }
+            sink_finish(files_scanned as u64)
+        };
+        if status != 0 {
+            return Err(format!("{} failed with status {}", self.path.display(), status).into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Collect(Vec<String>);
+
+    impl ResultSink for Collect {
+        fn write(&mut self, files: &[FileInfo], _: usize) -> Result<(), SinkError> {
+            self.0
+                .extend(files.iter().map(|file| file.path.display().to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_closures_are_filters() {
+        let small = |file: &FileInfo| file.size_bytes < 10;
+        assert!(small.keep(&FileInfo::new("a", 5)));
+        assert!(!small.keep(&FileInfo::new("b", 50)));
+    }
+
+    #[test]
+    fn test_sinks_receive_matches() {
+        let mut sink = Collect(Vec::new());
+        sink.write(&[FileInfo::new("a", 5)], 3).unwrap();
+        assert_eq!(sink.0, ["a"]);
+    }
+
+    #[cfg(feature = "plugins")]
+    #[test]
+    fn test_load_missing_plugin() {
+        let err = unsafe { Plugin::load(Path::new("/nonexistent/libplugin.so")) }.err();
+        assert!(err
+            .unwrap()
+            .starts_with("cannot load /nonexistent/libplugin.so"));
+    }
+}
@@ -0,0 +1,214 @@
+// Import Path types for matching files against a preset
+use std::path::{Component, Path, PathBuf};
+
+/// A bundle of what a common audit looks for, chosen with `--preset`: which
+/// files count as matches, or for dev which directories are totalled, which
+/// directories are not worth walking, and the threshold used when no SIZE is
+/// given.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    /// Videos, music, photos and image editor files.
+    Media,
+    /// Dependencies, build output and virtual environments.
+    Dev,
+    /// Log files and rotated logs.
+    Logs,
+    /// Backups, database dumps, disk images and archives.
+    Backups,
+}
+
+// Directories whose contents no preset but dev is after: caches and history
+// that belong to other tools
+const SKIPPED_DIRS: &[&str] = &[".git", ".hg", ".svn", "node_modules", ".cache"];
+
+// Folders dev reports the total size of
+const DEV_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".gradle",
+    ".tox",
+    ".mypy_cache",
+    ".next",
+    ".terraform",
+    "build",
+    "dist",
+];
+
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "mov", "avi", "wmv", "webm", "m4v", "flv", "mpg", "mpeg", "mp3", "flac", "wav",
+    "aac", "ogg", "m4a", "jpg", "jpeg", "png", "heic", "tif", "tiff", "raw", "cr2", "nef", "arw",
+    "dng", "psd",
+];
+
+const BACKUP_EXTENSIONS: &[&str] = &[
+    "bak", "backup", "old", "orig", "dump", "sql", "iso", "img", "dmg", "vmdk", "vdi", "qcow2",
+    "tar", "tgz", "gz", "zst", "xz", "bz2", "zip", "7z", "rar",
+];
+
+// Folders logs reports everything inside, whatever the files are called
+const LOG_DIRS: &[&str] = &["log", "logs"];
+
+/// Parse a `--preset` name.
+pub fn parse(name: &str) -> Result<Preset, String> {
+    match name {
+        "media" => Ok(Preset::Media),
+        "dev" => Ok(Preset::Dev),
+        "logs" => Ok(Preset::Logs),
+        "backups" => Ok(Preset::Backups),
+        name => Err(format!(
+            "unknown preset {:?} (expected media, dev, logs or backups)",
+            name
+        )),
+    }
+}
+
+impl Preset {
+    /// The threshold used when no SIZE is given, in MiB (or MB with `--si`).
+    pub fn min_size(self) -> u64 {
+        match self {
+            Preset::Media => 50,
+            Preset::Dev | Preset::Logs => 10,
+            Preset::Backups => 100,
+        }
+    }
+
+    /// Whether this preset reports directory totals rather than files.
+    pub fn reports_dirs(self) -> bool {
+        self == Preset::Dev
+    }
+
+    /// Names of directories this preset leaves out of the walk.
+    pub fn skipped_dirs(self) -> &'static [&'static str] {
+        match self {
+            Preset::Dev => &[],
+            _ => SKIPPED_DIRS,
+        }
+    }
+
+    /// For a preset that reports directories, the one the file at `path`,
+    /// relative to the scanned directory, counts towards: the outermost of
+    /// its directories the preset is after, so node_modules inside
+    /// node_modules is reported once.
+    pub fn total_dir(self, path: &Path) -> Option<PathBuf> {
+        if !self.reports_dirs() {
+            return None;
+        }
+        let mut dir = PathBuf::new();
+        for component in path.parent()?.components() {
+            dir.push(component);
+            if let Component::Normal(name) = component {
+                let name = name.to_string_lossy().to_lowercase();
+                if DEV_DIRS.contains(&name.as_str()) {
+                    return Some(dir);
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether the file at `path`, relative to the scanned directory, is one
+    /// this preset reports. The walk already leaves out the preset's skipped
+    /// directories; they are checked here too for the MFT backend, which
+    /// reads the whole volume.
+    pub fn matches(self, path: &Path) -> bool {
+        let dirs: Vec<String> = path
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().to_lowercase()),
+                _ => None,
+            })
+            .collect();
+        let inside = |names: &[&str]| dirs.iter().any(|dir| names.contains(&dir.as_str()));
+        if self.reports_dirs() {
+            return self.total_dir(path).is_some();
+        }
+        if inside(SKIPPED_DIRS) {
+            return false;
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let extension = name.rsplit_once('.').map_or("", |(_, extension)| extension);
+        match self {
+            Preset::Media => MEDIA_EXTENSIONS.contains(&extension),
+            Preset::Logs => is_log(&name) || inside(LOG_DIRS),
+            Preset::Backups => BACKUP_EXTENSIONS.contains(&extension) || name.ends_with('~'),
+            Preset::Dev => unreachable!(),
+        }
+    }
+}
+
+// Whether `name` is a log file, or one rotated away as app.log.1 or
+// app.log.2.gz
+fn is_log(name: &str) -> bool {
+    name.ends_with(".log") || name.ends_with(".out") || name.contains(".log.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("dev"), Ok(Preset::Dev));
+        assert_eq!(parse("backups"), Ok(Preset::Backups));
+        assert!(parse("videos").is_err());
+    }
+
+    #[test]
+    fn test_dev_matches_inside_build_dirs() {
+        let dev = Preset::Dev;
+        assert!(dev.matches(Path::new("web/node_modules/react/index.js")));
+        assert!(dev.matches(Path::new("target/debug/app")));
+        assert!(dev.matches(Path::new("api/.venv/lib/libpython.so")));
+        assert!(!dev.matches(Path::new("src/target.rs")));
+        assert!(!dev.matches(Path::new("videos/clip.mp4")));
+    }
+
+    #[test]
+    fn test_dev_totals_outermost_build_dir() {
+        let dev = Preset::Dev;
+        assert_eq!(
+            dev.total_dir(Path::new("web/node_modules/a/node_modules/b/index.js")),
+            Some(PathBuf::from("web/node_modules"))
+        );
+        assert_eq!(
+            dev.total_dir(Path::new("Target/debug/app")),
+            Some(PathBuf::from("Target"))
+        );
+        assert_eq!(dev.total_dir(Path::new("src/target.rs")), None);
+        assert_eq!(dev.total_dir(Path::new("target")), None);
+        assert_eq!(Preset::Media.total_dir(Path::new("target/a.mp4")), None);
+    }
+
+    #[test]
+    fn test_media_matches_extensions() {
+        assert!(Preset::Media.matches(Path::new("Videos/Trip.MP4")));
+        assert!(Preset::Media.matches(Path::new("photo.heic")));
+        assert!(!Preset::Media.matches(Path::new("disk.iso")));
+        assert!(!Preset::Media.matches(Path::new("site/node_modules/demo.mp4")));
+    }
+
+    #[test]
+    fn test_logs_matches_rotated_logs() {
+        assert!(Preset::Logs.matches(Path::new("app.log")));
+        assert!(Preset::Logs.matches(Path::new("nginx/access.log.2.gz")));
+        assert!(Preset::Logs.matches(Path::new("var/log/journal/system.journal")));
+        assert!(!Preset::Logs.matches(Path::new("catalog.db")));
+    }
+
+    #[test]
+    fn test_backups_matches_dumps_and_images() {
+        assert!(Preset::Backups.matches(Path::new("db/prod.sql")));
+        assert!(Preset::Backups.matches(Path::new("vm/disk.qcow2")));
+        assert!(Preset::Backups.matches(Path::new("notes.txt~")));
+        assert!(!Preset::Backups.matches(Path::new("movie.mkv")));
+        assert!(!Preset::Backups.matches(Path::new(".git/objects/pack/pack-1.pack")));
+    }
+}
@@ -0,0 +1,610 @@
+// Import the plan --dry-run adds to a report
+use crate::actions::Plan;
+// Import DirEntry for the directory totals --dirs reports
+use crate::aggregate::DirEntry;
+// Import the timestamp format shared with the daemon's report names
+use crate::daemon;
+// Import the fields --columns keeps in each match
+use crate::columns::{Column, Row};
+// Import Volume for the free space recorded with a report
+use crate::disk::Volume;
+// Import FileInfo for the matches stored in a report, and the scan counters
+// --stats prints
+use list_big_files::{FileInfo, ScanStats, Skipped};
+// Import serde for the JSON layout
+use serde::{Deserialize, Serialize};
+// Import OsString for the name of the temporary file --output writes first
+use std::ffi::OsString;
+// Import fs for reading saved reports
+use std::fs;
+// Import io for writing --output files and --append-history logs
+use std::io::{self, Write};
+// Import Path and PathBuf for the scanned directory and report files
+use std::path::{Path, PathBuf};
+// Import time types for when the scan ran and how long it took
+use std::time::{Duration, SystemTime};
+
+/// Version of the layout of written reports. Fields may be added without
+/// changing it; it goes up only when a field is removed, renamed or changes
+/// meaning, so parsers can refuse reports they would misread.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// The release that wrote a report
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Result of a scan as written by `--format json` and `--save-snapshot`, and
+/// read back by `diff`, `show` and `--compare`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanReport {
+    #[serde(serialize_with = "list_big_files::serialize_path")]
+    pub directory: PathBuf,
+    pub min_size_bytes: u64,
+    pub files_scanned: usize,
+    pub partial: bool,
+    /// Capacity and free space of the scanned directory's volume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<Volume>,
+    pub files: Vec<FileInfo>,
+    /// What the requested action would have done, for `--dry-run`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<Plan>,
+    /// When the scan ran and how it went; missing from reports of older
+    /// releases and of watch mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan: Option<ScanInfo>,
+}
+
+/// When a scan ran, how long it took and what it could not read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanInfo {
+    /// UTC start time in ISO 8601, e.g. `2024-05-01T12:00:00Z`.
+    pub started_at: String,
+    pub duration_secs: f64,
+    /// Directories and entries that could not be read and were left out.
+    pub errors: usize,
+}
+
+impl ScanInfo {
+    /// A scan that started at `started` and took `elapsed`.
+    pub fn new(started: SystemTime, elapsed: Duration, errors: usize) -> Self {
+        ScanInfo {
+            started_at: daemon::timestamp(started),
+            duration_secs: elapsed.as_secs_f64(),
+            errors,
+        }
+    }
+}
+
+// The part of a report read first, to refuse layouts from newer releases.
+// Reports from before the schema was versioned count as the first one
+#[derive(Deserialize)]
+struct Versioned {
+    #[serde(default = "first_schema")]
+    schema_version: u32,
+}
+
+fn first_schema() -> u32 {
+    1
+}
+
+// A report as written out, led by its schema and the combined size of its
+// matches, with the matches in full or cut down to the fields --columns chose
+#[derive(Serialize)]
+struct Written<'a, F> {
+    schema_version: u32,
+    tool_version: &'static str,
+    total_bytes: u64,
+    #[serde(serialize_with = "list_big_files::serialize_path")]
+    directory: &'a Path,
+    min_size_bytes: u64,
+    files_scanned: usize,
+    partial: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume: Option<Volume>,
+    files: F,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run: Option<&'a Plan>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scan: Option<&'a ScanInfo>,
+}
+
+/// The totals `--summary` prints in place of the matches.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Summary<'a> {
+    pub schema_version: u32,
+    pub tool_version: &'static str,
+    #[serde(serialize_with = "list_big_files::serialize_path")]
+    pub directory: &'a Path,
+    pub min_size_bytes: u64,
+    pub matches: usize,
+    pub total_bytes: u64,
+    /// The biggest match, if there is any.
+    pub largest: Option<&'a FileInfo>,
+    pub files_scanned: usize,
+    pub duration_secs: f64,
+    pub partial: bool,
+}
+
+/// Directory totals as `--dirs --format json` writes them, with the same
+/// schema and scan details as scan reports.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DirsReport<'a> {
+    pub schema_version: u32,
+    pub tool_version: &'static str,
+    #[serde(serialize_with = "list_big_files::serialize_path")]
+    pub directory: &'a Path,
+    pub min_size_bytes: u64,
+    pub files_scanned: usize,
+    pub partial: bool,
+    pub dirs: &'a [DirEntry],
+    pub scan: ScanInfo,
+}
+
+impl<'a> DirsReport<'a> {
+    /// The totals `dirs` of a scan of `directory` that counted
+    /// `files_scanned` files.
+    pub fn new(
+        directory: &'a Path,
+        min_size_bytes: u64,
+        files_scanned: usize,
+        partial: bool,
+        dirs: &'a [DirEntry],
+        scan: ScanInfo,
+    ) -> Self {
+        DirsReport {
+            schema_version: SCHEMA_VERSION,
+            tool_version: TOOL_VERSION,
+            directory,
+            min_size_bytes,
+            files_scanned,
+            partial,
+            dirs,
+            scan,
+        }
+    }
+}
+
+/// One line of the `--append-history` log: what a scan found, so totals can
+/// be charted over time.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct HistoryEntry<'a> {
+    pub schema_version: u32,
+    /// When the scan started, in UTC ISO 8601.
+    pub timestamp: &'a str,
+    #[serde(serialize_with = "list_big_files::serialize_path")]
+    pub directory: &'a Path,
+    pub min_size_bytes: u64,
+    pub matches: usize,
+    pub total_bytes: u64,
+    pub files_scanned: usize,
+    pub duration_secs: f64,
+    pub partial: bool,
+}
+
+/// How a scan went, as `--stats` prints it for tracking performance.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ScanMetrics {
+    pub schema_version: u32,
+    pub tool_version: &'static str,
+    pub dirs_visited: usize,
+    pub files_scanned: usize,
+    pub files_statted: usize,
+    pub bytes_considered: u64,
+    pub skipped: Skipped,
+    pub wall_secs: f64,
+    /// Time each walker thread spent, by its index in the pool.
+    pub thread_secs: Vec<f64>,
+}
+
+impl ScanMetrics {
+    /// The counters of a scan of `files_scanned` files that took `elapsed`.
+    pub fn new(stats: &ScanStats, files_scanned: usize, elapsed: Duration) -> Self {
+        ScanMetrics {
+            schema_version: SCHEMA_VERSION,
+            tool_version: TOOL_VERSION,
+            dirs_visited: stats.dirs_visited(),
+            files_scanned,
+            files_statted: stats.files_statted(),
+            bytes_considered: stats.bytes_considered(),
+            skipped: stats.skipped(),
+            wall_secs: elapsed.as_secs_f64(),
+            thread_secs: stats
+                .thread_times()
+                .iter()
+                .map(Duration::as_secs_f64)
+                .collect(),
+        }
+    }
+}
+
+impl ScanReport {
+    /// The totals of this report, for a scan that took `elapsed`.
+    pub fn summary(&self, elapsed: Duration) -> Summary<'_> {
+        Summary {
+            schema_version: SCHEMA_VERSION,
+            tool_version: TOOL_VERSION,
+            directory: &self.directory,
+            min_size_bytes: self.min_size_bytes,
+            matches: self.files.len(),
+            total_bytes: self.total_bytes(),
+            largest: self.files.iter().max_by_key(|file| file.size_bytes),
+            files_scanned: self.files_scanned,
+            duration_secs: elapsed.as_secs_f64(),
+            partial: self.partial,
+        }
+    }
+
+    /// This report's line in a history log, or None when it records no scan,
+    /// as for the threshold crossings of watch mode.
+    pub fn history_entry(&self) -> Option<HistoryEntry<'_>> {
+        let scan = self.scan.as_ref()?;
+        Some(HistoryEntry {
+            schema_version: SCHEMA_VERSION,
+            timestamp: &scan.started_at,
+            directory: &self.directory,
+            min_size_bytes: self.min_size_bytes,
+            matches: self.files.len(),
+            total_bytes: self.total_bytes(),
+            files_scanned: self.files_scanned,
+            duration_secs: scan.duration_secs,
+            partial: self.partial,
+        })
+    }
+
+    /// Combined size of the matches.
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.size_bytes).sum()
+    }
+
+    /// Read a report previously written with `--format json`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let not_a_report =
+            |err: serde_json::Error| format!("{} is not a scan report: {}", path.display(), err);
+        let versioned: Versioned = serde_json::from_str(&contents).map_err(not_a_report)?;
+        if versioned.schema_version > SCHEMA_VERSION {
+            return Err(format!(
+                "{} was written by a newer release (report schema {}, this release reads {})",
+                path.display(),
+                versioned.schema_version,
+                SCHEMA_VERSION
+            ));
+        }
+        serde_json::from_str(&contents).map_err(not_a_report)
+    }
+
+    /// Write this report to `path`, as `--save-snapshot` does.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        // Snapshots saved by name go to a directory that may not exist yet
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("cannot create {}: {}", dir.display(), err))?;
+        }
+        fs::write(path, self.to_json())
+            .map_err(|err| format!("cannot write {}: {}", path.display(), err))
+    }
+
+    /// Pretty-printed JSON for this report, with its schema version and a
+    /// `total_bytes` summary.
+    pub fn to_json(&self) -> String {
+        let written = self.written(&self.files);
+        serde_json::to_string_pretty(&written).expect("scan reports always serialize")
+    }
+
+    /// Like [`to_json`](Self::to_json), but each match holds only `columns`,
+    /// in that order. Such reports are for other tools and cannot be loaded.
+    pub fn to_json_with_columns(&self, columns: &[Column]) -> String {
+        let written = self.written(self.rows(columns));
+        serde_json::to_string_pretty(&written).expect("scan reports always serialize")
+    }
+
+    /// The same fields as [`to_json`](Self::to_json) as YAML, with each
+    /// match cut down to `columns` when any are given.
+    pub fn to_yaml(&self, columns: &[Column]) -> String {
+        let yaml = if columns.is_empty() {
+            serde_yaml::to_string(&self.written(&self.files))
+        } else {
+            serde_yaml::to_string(&self.written(self.rows(columns)))
+        };
+        yaml.expect("scan reports always serialize")
+    }
+
+    // The matches cut down to `columns`
+    fn rows<'a>(&'a self, columns: &'a [Column]) -> Vec<Row<'a>> {
+        self.files
+            .iter()
+            .map(|file| Row { file, columns })
+            .collect()
+    }
+
+    // This report as written, with its matches written as `files`
+    fn written<F: Serialize>(&self, files: F) -> Written<'_, F> {
+        Written {
+            schema_version: SCHEMA_VERSION,
+            tool_version: TOOL_VERSION,
+            total_bytes: self.total_bytes(),
+            directory: &self.directory,
+            min_size_bytes: self.min_size_bytes,
+            files_scanned: self.files_scanned,
+            partial: self.partial,
+            volume: self.volume,
+            files,
+            dry_run: self.dry_run.as_ref(),
+            scan: self.scan.as_ref(),
+        }
+    }
+}
+
+/// Write `contents` to `path` in one step: into a temporary file beside it
+/// that is then renamed over it, so nothing ever reads a half-written report.
+/// Missing parent directories are created.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let Some(name) = path.file_name() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a file name",
+        ));
+    };
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty());
+    if let Some(parent) = parent {
+        fs::create_dir_all(parent)?;
+    }
+    let mut temp_name = OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+    let written = fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Add `entry` to the end of the history log at `path`, one JSON object per
+/// line, creating the log and its directory. Each line is written in a single
+/// call, so scans appending to the same log at once do not mix their lines.
+pub fn append_history(path: &Path, entry: &HistoryEntry) -> io::Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(entry).map_err(io::Error::other)?;
+    line.push('\n');
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn report() -> ScanReport {
+        ScanReport {
+            directory: PathBuf::from("/data"),
+            min_size_bytes: 1024,
+            files_scanned: 10,
+            partial: false,
+            volume: None,
+            files: vec![FileInfo::new("/data/big.iso".to_string(), 4096)],
+            dry_run: None,
+            scan: Some(ScanInfo::new(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(86_400),
+                Duration::from_millis(250),
+                2,
+            )),
+        }
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("scan.json");
+        report().save(&file).unwrap();
+        assert_eq!(ScanReport::load(&file), Ok(report()));
+    }
+
+    #[test]
+    fn test_to_json_includes_total() {
+        let mut report = report();
+        report
+            .files
+            .push(FileInfo::new("/data/other.bin".to_string(), 1024));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["total_bytes"], 5120);
+        assert_eq!(json["files"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_to_json_with_columns() {
+        let report = report();
+        let json: serde_json::Value =
+            serde_json::from_str(&report.to_json_with_columns(&[Column::Path])).unwrap();
+        assert_eq!(json["total_bytes"], report.total_bytes());
+        assert_eq!(
+            json["files"][0],
+            serde_json::json!({"path": report.files[0].path})
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut report = report();
+        report
+            .files
+            .insert(0, FileInfo::new("/data/small.bin", 2048));
+        let summary = report.summary(Duration::from_millis(1500));
+        assert_eq!(summary.matches, 2);
+        assert_eq!(summary.total_bytes, 6144);
+        assert_eq!(summary.largest, Some(&report.files[1]));
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["largest"]["path"], "/data/big.iso");
+        assert_eq!(json["duration_secs"], 1.5);
+        report.files.clear();
+        assert_eq!(report.summary(Duration::ZERO).largest, None);
+    }
+
+    #[test]
+    fn test_append_history() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("history/scans.ndjson");
+        let report = report();
+        let entry = report.history_entry().unwrap();
+        append_history(&log, &entry).unwrap();
+        append_history(&log, &entry).unwrap();
+        let contents = fs::read_to_string(&log).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], lines[1]);
+        assert_eq!(lines[0]["timestamp"], "1970-01-02T00:00:00Z");
+        assert_eq!(lines[0]["directory"], "/data");
+        assert_eq!(lines[0]["matches"], 1);
+        assert_eq!(lines[0]["total_bytes"], 4096);
+        assert_eq!(lines[0]["duration_secs"], 0.25);
+
+        let watched = ScanReport {
+            scan: None,
+            ..report
+        };
+        assert_eq!(watched.history_entry(), None);
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("reports/nightly/scan.json");
+        write_atomically(&file, "first").unwrap();
+        write_atomically(&file, "second").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "second");
+        // Only the report itself is left behind
+        let names: Vec<_> = fs::read_dir(file.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["scan.json"]);
+        assert!(write_atomically(dir.path(), "x").is_err());
+        assert!(dir.path().is_dir());
+    }
+
+    #[test]
+    fn test_scan_metrics() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big.iso"), [0u8; 4096]).unwrap();
+        let stats = ScanStats::new();
+        let (_, files_scanned) = list_big_files::Scanner::new(dir.path())
+            .stats(&stats)
+            .collect()
+            .unwrap();
+        let metrics = ScanMetrics::new(&stats, files_scanned, Duration::from_secs(2));
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["dirs_visited"], 1);
+        assert_eq!(json["files_scanned"], 1);
+        assert_eq!(json["bytes_considered"], 4096);
+        assert_eq!(json["skipped"]["unreadable"], 0);
+        assert_eq!(json["wall_secs"], 2.0);
+        assert!(!metrics.thread_secs.is_empty());
+    }
+
+    #[test]
+    fn test_to_json_includes_schema_and_scan() {
+        let json: serde_json::Value = serde_json::from_str(&report().to_json()).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["tool_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["scan"]["started_at"], "1970-01-02T00:00:00Z");
+        assert_eq!(json["scan"]["duration_secs"], 0.25);
+        assert_eq!(json["scan"]["errors"], 2);
+    }
+
+    #[test]
+    fn test_dirs_report_includes_schema_and_scan() {
+        let dirs = [DirEntry {
+            path: PathBuf::from("/data/cache"),
+            total: crate::aggregate::Total {
+                size_bytes: 4096,
+                files: 3,
+            },
+        }];
+        let scan = ScanInfo::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(86400),
+            Duration::from_millis(250),
+            1,
+        );
+        let report = DirsReport::new(Path::new("/data"), 1024, 10, false, &dirs, scan);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["tool_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["directory"], "/data");
+        assert_eq!(json["files_scanned"], 10);
+        assert_eq!(json["scan"]["started_at"], "1970-01-02T00:00:00Z");
+        assert_eq!(json["scan"]["errors"], 1);
+        assert_eq!(
+            json["dirs"],
+            serde_json::json!([{"path": "/data/cache", "size_bytes": 4096, "files": 3}])
+        );
+    }
+
+    #[test]
+    fn test_to_yaml() {
+        let report = report();
+        let yaml: serde_json::Value = serde_yaml::from_str(&report.to_yaml(&[])).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(yaml, json);
+        let yaml: serde_json::Value =
+            serde_yaml::from_str(&report.to_yaml(&[Column::Size])).unwrap();
+        assert_eq!(yaml["files"][0], serde_json::json!({"size_bytes": 4096}));
+    }
+
+    #[test]
+    fn test_load_reports_without_schema() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("old.json");
+        let old = r#"{"directory": "/data", "min_size_bytes": 1024, "files_scanned": 10,
+            "partial": false, "files": [{"path": "/data/big.iso", "size_bytes": 4096}]}"#;
+        fs::write(&file, old).unwrap();
+        let report = ScanReport::load(&file).unwrap();
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.scan, None);
+    }
+
+    #[test]
+    fn test_load_rejects_newer_schema() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("new.json");
+        let mut json: serde_json::Value = serde_json::from_str(&report().to_json()).unwrap();
+        json["schema_version"] = (SCHEMA_VERSION + 1).into();
+        fs::write(&file, json.to_string()).unwrap();
+        let err = ScanReport::load(&file).unwrap_err();
+        assert!(err.contains("newer release"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_rejects_other_json() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("scan.json");
+        fs::write(&file, "{\"hello\": 1}").unwrap();
+        let err = ScanReport::load(&file).unwrap_err();
+        assert!(err.contains("not a scan report"));
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let dir = tempdir().unwrap();
+        assert!(ScanReport::load(&dir.path().join("missing.json")).is_err());
+    }
+}
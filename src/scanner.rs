@@ -0,0 +1,1240 @@
+// Import the shared result type and root validation
+use crate::cache::CachedDir;
+// Import the alternate data stream listing for scans that report streams
+use crate::ads;
+use crate::{check_root, long_path, Backend, FileInfo, ScanCache, ScanError, ScanStats};
+// Import the plugin traits for filtering and handing off matches
+use crate::{FileFilter, ResultSink};
+// Import rayon's scope for spawning a task per directory, and the pool
+// builder for scans limited to a number of threads
+use rayon::{Scope, ThreadPoolBuilder};
+// Import HashSet for the directories walked when following links
+use std::collections::HashSet;
+// Import OsString for names of files statted in a batch
+use std::ffi::{OsStr, OsString};
+// Import fs and io for reading directories
+use std::fs;
+use std::io;
+// Import Cow for paths shown unchanged when no long path prefix was added
+use std::borrow::Cow;
+// Import Path types for the scan root and progress reports
+use std::path::{Path, PathBuf};
+// Import atomics for cooperative cancellation and counting files
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+// Import Arc and Mutex for sharing the cancel flag and collecting matches
+use std::sync::{Arc, Mutex};
+// Import SystemTime for directory modification times checked by the cache
+use std::time::SystemTime;
+
+// Report progress at least this often while walking a single large directory
+const PROGRESS_INTERVAL: usize = 1024;
+
+// Extensions of the directories Finder presents as single items: apps and
+// their plugins, libraries, projects and document packages
+const BUNDLE_EXTENSIONS: [&str; 24] = [
+    "app",
+    "appex",
+    "band",
+    "bundle",
+    "fcpbundle",
+    "framework",
+    "imovielibrary",
+    "kext",
+    "key",
+    "logicx",
+    "musiclibrary",
+    "numbers",
+    "pages",
+    "photoslibrary",
+    "playground",
+    "plugin",
+    "pvm",
+    "rtfd",
+    "sparsebundle",
+    "tvlibrary",
+    "vmwarevm",
+    "xcarchive",
+    "xcodeproj",
+    "xcworkspace",
+];
+
+/// Snapshot of a scan in flight, passed to [`Scanner::on_progress`].
+#[derive(Debug)]
+pub struct Progress<'p> {
+    pub files_scanned: usize,
+    pub current_dir: &'p Path,
+}
+
+// Boxed progress hook so Scanner stays a plain, non-generic type
+type ProgressFn<'a> = Box<dyn Fn(&Progress) + Send + Sync + 'a>;
+// Boxed filters, for the same reason
+type FilterBox<'a> = Box<dyn FileFilter + 'a>;
+
+/// Configurable scan of a directory tree.
+///
+/// ```no_run
+/// use list_big_files::Scanner;
+///
+/// let (files, scanned) = Scanner::new("/var/log")
+///     .min_size(100 * 1024 * 1024)
+///     .on_progress(|p| eprintln!("{} files, in {}", p.files_scanned, p.current_dir.display()))
+///     .collect()
+///     .unwrap();
+/// ```
+pub struct Scanner<'a> {
+    root: PathBuf,
+    // The root as walked, with the prefix Windows needs for paths past
+    // MAX_PATH; paths are shown relative to `root` again
+    walk_root: PathBuf,
+    min_size_bytes: u64,
+    progress: Option<ProgressFn<'a>>,
+    cancel: Option<Arc<AtomicBool>>,
+    threads: Option<usize>,
+    backend: Backend,
+    cache: Option<&'a ScanCache>,
+    stats: Option<&'a ScanStats>,
+    streams: bool,
+    follow_links: bool,
+    bundles: bool,
+    // Directories left out of the walk, as shown
+    skipped: Vec<PathBuf>,
+    // Names of directories left out wherever they are, lowercase
+    skipped_names: Vec<String>,
+    filters: Vec<FilterBox<'a>>,
+}
+
+// State shared by the tasks of one walk
+struct Walk {
+    files_scanned: AtomicUsize,
+    // Canonical paths of the directories walked when links are followed, so
+    // each is walked once however many links lead to it
+    visited: Option<Mutex<HashSet<PathBuf>>>,
+}
+
+impl<'a> Scanner<'a> {
+    /// Scan `root` for files of any size until configured otherwise.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Scanner {
+            walk_root: long_path(&root).into_owned(),
+            root,
+            min_size_bytes: 0,
+            progress: None,
+            cancel: None,
+            threads: None,
+            backend: Backend::Std,
+            cache: None,
+            stats: None,
+            streams: false,
+            follow_links: false,
+            bundles: false,
+            skipped: Vec::new(),
+            skipped_names: Vec::new(),
+            filters: Vec::new(),
+        }
+    }
+
+    /// Only report files of at least `bytes`.
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.min_size_bytes = bytes;
+        self
+    }
+
+    /// Call `hook` whenever the walk enters a directory, and periodically
+    /// while it works through a large one.
+    pub fn on_progress(mut self, hook: impl Fn(&Progress) + Send + Sync + 'a) -> Self {
+        self.progress = Some(Box::new(hook));
+        self
+    }
+
+    /// Stop early once `flag` is set, e.g. from a Ctrl+C handler. Matches
+    /// reported before that point are kept; check the flag afterwards to
+    /// tell a partial scan from a complete one.
+    pub fn cancel_on(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    /// Run the scan on a dedicated pool of `threads` workers instead of
+    /// rayon's global pool, which uses every core.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Read file sizes with `backend` instead of the portable default.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Reuse listings from `cache` for directories that have not been
+    /// modified since it was filled, and record this scan's listings in it.
+    /// Not used by the MFT backend.
+    pub fn cache(mut self, cache: &'a ScanCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Count in `stats` what the walk read, passed over and could not read,
+    /// and how long each thread spent on it. Not used by the MFT backend,
+    /// which reads the whole volume at once.
+    pub fn stats(mut self, stats: &'a ScanStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Also report NTFS alternate data streams of at least the minimum size,
+    /// as `file:stream`. Their space does not show in normal listings, nor in
+    /// a file's size. Only Windows has streams, and the MFT backend does not
+    /// read them.
+    pub fn alternate_streams(mut self, enabled: bool) -> Self {
+        self.streams = enabled;
+        self
+    }
+
+    /// Walk into directories behind symlinks, and on Windows junctions and
+    /// mount points, which are skipped by default. Each directory is walked
+    /// once, under whichever path reaches it first, so links back up the tree
+    /// do not loop and linked trees are not counted twice. Links to files are
+    /// never reported; the file is reported where it lives.
+    pub fn follow_links(mut self, follow: bool) -> Self {
+        self.follow_links = follow;
+        self
+    }
+
+    /// Report directories that Finder presents as single items, such as `.app`
+    /// bundles and `.photoslibrary` libraries, as one match of their total
+    /// size instead of walking into them. Links inside are not followed.
+    pub fn bundles_as_files(mut self, enabled: bool) -> Self {
+        self.bundles = enabled;
+        self
+    }
+
+    /// Leave the directories `dirs` and everything below them out of the
+    /// walk, e.g. network shares mounted inside the tree. They are given under
+    /// the root as it was passed to [`Scanner::new`]. Not used by the MFT
+    /// backend, which only reads one volume.
+    pub fn skip_dirs(mut self, dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.skipped.extend(dirs);
+        self
+    }
+
+    /// Leave directories called any of `names`, compared case-insensitively,
+    /// out of the walk wherever they are below the root, e.g. the caches and
+    /// dependencies a search for media has no use for. Not used by the MFT
+    /// backend, which only reads one volume.
+    pub fn skip_dir_names<S: AsRef<str>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.skipped_names
+            .extend(names.into_iter().map(|name| name.as_ref().to_lowercase()));
+        self
+    }
+
+    /// Only report files that `filter` keeps, on top of the size threshold.
+    /// Each filter added must keep a file for it to be reported.
+    pub fn filter(mut self, filter: impl FileFilter + 'a) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Invoke `callback` for every matching file, returning the number of
+    /// files scanned. See [`crate::scan_with`].
+    pub fn scan_with<F>(&self, callback: F) -> Result<usize, ScanError>
+    where
+        F: Fn(FileInfo) + Send + Sync,
+    {
+        check_root(&self.root)?;
+        if !self.backend.is_available() {
+            return Err(ScanError::BackendUnavailable(self.backend));
+        }
+        let callback = |file: FileInfo| {
+            if self.filters.iter().all(|filter| filter.keep(&file)) {
+                callback(file);
+            }
+        };
+
+        match self.threads {
+            Some(threads) => ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?
+                .install(|| self.walk(callback)),
+            None => self.walk(callback),
+        }
+    }
+
+    // Walk the tree on the current rayon pool. Every directory is read by
+    // its own task, so idle workers steal unread directories and directory
+    // listing is parallelized along with the stat calls
+    fn walk<F>(&self, callback: F) -> Result<usize, ScanError>
+    where
+        F: Fn(FileInfo) + Send + Sync,
+    {
+        #[cfg(windows)]
+        if self.backend == Backend::Mft {
+            return crate::mft::scan(
+                &self.root,
+                self.min_size_bytes,
+                &|| self.is_cancelled(),
+                &|records| self.report(records, &self.root),
+                &callback,
+            )
+            .map_err(|source| ScanError::Io {
+                path: self.root.clone(),
+                source,
+            });
+        }
+
+        let root_mtime = self.cache.and_then(|_| modified(&self.walk_root));
+        let walk = Walk {
+            files_scanned: AtomicUsize::new(0),
+            visited: self.follow_links.then(Mutex::default),
+        };
+        rayon::scope(|scope| {
+            self.walk_dir(scope, self.walk_root.clone(), root_mtime, &walk, &callback);
+        });
+
+        Ok(walk.files_scanned.into_inner())
+    }
+
+    // Report every file in `dir` and queue its subdirectories as new tasks.
+    // `mtime` is the directory's modification time when a cache is in use
+    fn walk_dir<'s, F>(
+        &'s self,
+        scope: &Scope<'s>,
+        dir: PathBuf,
+        mtime: Option<SystemTime>,
+        walk: &'s Walk,
+        callback: &'s F,
+    ) where
+        F: Fn(FileInfo) + Send + Sync,
+    {
+        if self.is_cancelled() {
+            return;
+        }
+        if self.is_skipped(&dir) {
+            self.counted(ScanStats::add_excluded);
+            return;
+        }
+        let _timer = self.stats.map(ScanStats::time_task);
+        // Links can lead to a directory already walked, or to one above it
+        if let Some(visited) = &walk.visited {
+            let Some(canonical) = self.readable(fs::canonicalize(&dir)) else {
+                return;
+            };
+            if !visited.lock().unwrap().insert(canonical) {
+                self.counted(ScanStats::add_revisited);
+                return;
+            }
+        }
+        let files_scanned = &walk.files_scanned;
+        self.report(files_scanned.load(Ordering::Relaxed), &dir);
+
+        // An unmodified directory still has the same entries, so replay them
+        if let Some(cache) = self.cache {
+            if let Some(mut cached) = mtime.and_then(|mtime| cache.lookup(&dir, mtime)) {
+                self.counted(ScanStats::add_dir);
+                self.replay(scope, &dir, &mut cached, walk, callback);
+                cache.store(&dir, cached, true);
+                return;
+            }
+        }
+
+        // Unreadable directories are skipped, like unreadable files
+        let Ok(entries) = fs::read_dir(&dir) else {
+            self.counted(ScanStats::add_error);
+            return;
+        };
+        self.counted(ScanStats::add_dir);
+
+        // Files whose size is read in one batch after the listing
+        let mut batch = Vec::new();
+        // What the cache will remember about this directory, if there is one
+        let mut listing = self.cache.and(mtime).map(|mtime| CachedDir {
+            mtime,
+            files: Vec::new(),
+            subdirs: Vec::new(),
+        });
+
+        for entry in entries.filter_map(|entry| self.readable(entry)) {
+            if self.is_cancelled() {
+                return;
+            }
+            // Symlinks, and on Windows junctions and mount points, are reported
+            // as neither files nor directories and skipped unless followed.
+            // Links to files always are, so each file is counted where it lives
+            let Some(mut file_type) = self.readable(entry.file_type()) else {
+                continue;
+            };
+            let link = file_type.is_symlink();
+            if link {
+                match self.follow_links.then(|| fs::metadata(entry.path())) {
+                    Some(Ok(target)) if target.is_dir() => file_type = target.file_type(),
+                    _ => {
+                        self.counted(ScanStats::add_link);
+                        continue;
+                    }
+                }
+            }
+
+            if file_type.is_dir() {
+                self.check_streams(&dir, &entry.file_name(), callback);
+                if self.bundles && is_bundle(&entry.file_name()) {
+                    if let Some(listing) = &mut listing {
+                        listing.subdirs.push(entry.file_name());
+                    }
+                    let (dir, name) = (dir.clone(), entry.file_name());
+                    scope.spawn(move |_| self.check_bundle(&dir, &name, walk, callback));
+                    continue;
+                }
+                let subdir = entry.path();
+                let subdir_mtime = match &mut listing {
+                    Some(listing) => {
+                        listing.subdirs.push(entry.file_name());
+                        if link {
+                            modified(&subdir)
+                        } else {
+                            entry.metadata().and_then(|m| m.modified()).ok()
+                        }
+                    }
+                    None => None,
+                };
+                scope
+                    .spawn(move |scope| self.walk_dir(scope, subdir, subdir_mtime, walk, callback));
+            } else if file_type.is_file() {
+                self.count_file(&dir, files_scanned);
+                self.check_streams(&dir, &entry.file_name(), callback);
+
+                if self.backend == Backend::IoUring {
+                    batch.push(entry.file_name());
+                    continue;
+                }
+
+                // The entry is already known to be a regular file, so its own
+                // metadata is enough: on Unix it is a single stat relative to
+                // the open directory, and on Windows it comes with the listing
+                let Some(metadata) = self.readable(entry.metadata()) else {
+                    continue;
+                };
+                self.counted(ScanStats::add_stat);
+                let name = entry.file_name();
+                if let Some(listing) = &mut listing {
+                    listing.files.push((name.clone(), metadata.len()));
+                }
+                self.check_size(&dir, &name, metadata.len(), callback);
+            } else {
+                self.counted(ScanStats::add_special);
+            }
+        }
+
+        if !batch.is_empty() {
+            let sizes = stat_batch(&dir, &batch);
+            for (name, size_bytes) in batch.into_iter().zip(sizes) {
+                let Some(size_bytes) = size_bytes else {
+                    self.counted(ScanStats::add_error);
+                    continue;
+                };
+                self.counted(ScanStats::add_stat);
+                self.check_size(&dir, &name, size_bytes, callback);
+                if let Some(listing) = &mut listing {
+                    listing.files.push((name, size_bytes));
+                }
+            }
+        }
+
+        if let (Some(cache), Some(listing)) = (self.cache, listing) {
+            cache.store(&dir, listing, false);
+        }
+    }
+
+    // Report the files of a cached directory listing and walk its subdirectories.
+    // Files can grow in place without their directory changing, so each one
+    // is statted again and the listing updated with its current size
+    fn replay<'s, F>(
+        &'s self,
+        scope: &Scope<'s>,
+        dir: &Path,
+        cached: &mut CachedDir,
+        walk: &'s Walk,
+        callback: &'s F,
+    ) where
+        F: Fn(FileInfo) + Send + Sync,
+    {
+        cached.files.retain_mut(|(name, size_bytes)| {
+            self.count_file(dir, &walk.files_scanned);
+            let Some(metadata) = self.readable(fs::symlink_metadata(dir.join(&*name))) else {
+                return false;
+            };
+            if !metadata.is_file() {
+                return false;
+            }
+            self.counted(ScanStats::add_stat);
+            *size_bytes = metadata.len();
+            self.check_size(dir, name, *size_bytes, callback);
+            self.check_streams(dir, name, callback);
+            true
+        });
+
+        for name in &cached.subdirs {
+            self.check_streams(dir, name, callback);
+            if self.bundles && is_bundle(name) {
+                let (dir, name) = (dir.to_path_buf(), name.clone());
+                scope.spawn(move |_| self.check_bundle(&dir, &name, walk, callback));
+                continue;
+            }
+            let subdir = dir.join(name);
+            // Subdirectories are checked again since their own contents may
+            // have changed; one replaced by a file, or by a link that is not
+            // followed, is skipped
+            let metadata = if self.follow_links {
+                fs::metadata(&subdir)
+            } else {
+                fs::symlink_metadata(&subdir)
+            };
+            let Ok(metadata) = metadata else {
+                continue;
+            };
+            if !metadata.is_dir() {
+                continue;
+            }
+            let subdir_mtime = metadata.modified().ok();
+            scope.spawn(move |scope| self.walk_dir(scope, subdir, subdir_mtime, walk, callback));
+        }
+    }
+
+    // The value of a read that may fail, counting the failure as an error
+    fn readable<T>(&self, result: io::Result<T>) -> Option<T> {
+        result
+            .inspect_err(|_| self.counted(ScanStats::add_error))
+            .ok()
+    }
+
+    // Update the attached counters, if any, with `add`
+    fn counted(&self, add: fn(&ScanStats)) {
+        if let Some(stats) = self.stats {
+            add(stats);
+        }
+    }
+
+    // Count a scanned file, reporting progress every so often
+    fn count_file(&self, dir: &Path, files_scanned: &AtomicUsize) {
+        let scanned = files_scanned.fetch_add(1, Ordering::Relaxed) + 1;
+        if scanned.is_multiple_of(PROGRESS_INTERVAL) {
+            self.report(scanned, dir);
+        }
+    }
+
+    // Report the file `name` in `dir` if it meets the threshold. The full
+    // path is only built for files that are reported
+    fn check_size<F>(&self, dir: &Path, name: &OsString, size_bytes: u64, callback: &F)
+    where
+        F: Fn(FileInfo),
+    {
+        if let Some(stats) = self.stats {
+            stats.add_bytes(size_bytes);
+        }
+        if size_bytes >= self.min_size_bytes {
+            callback(FileInfo::new(
+                self.shown(&dir.join(name)).into_owned(),
+                size_bytes,
+            ));
+        }
+    }
+
+    // Report the alternate data streams of `name` in `dir` that meet the
+    // threshold, as `name:stream`
+    fn check_streams<F>(&self, dir: &Path, name: &OsStr, callback: &F)
+    where
+        F: Fn(FileInfo),
+    {
+        if !self.streams {
+            return;
+        }
+        for (stream, size_bytes) in ads::streams(&dir.join(name)) {
+            let mut shown = name.to_os_string();
+            shown.push(":");
+            shown.push(stream);
+            self.check_size(dir, &shown, size_bytes, callback);
+        }
+    }
+
+    // Report the bundle `name` in `dir` as one file holding everything inside
+    // it, counting each of those files as scanned
+    fn check_bundle<F>(&self, dir: &Path, name: &OsString, walk: &Walk, callback: &F)
+    where
+        F: Fn(FileInfo),
+    {
+        let _timer = self.stats.map(ScanStats::time_task);
+        let mut size_bytes = 0;
+        let mut pending = vec![dir.join(name)];
+        while let Some(path) = pending.pop() {
+            if self.is_cancelled() {
+                return;
+            }
+            let Some(entries) = self.readable(fs::read_dir(&path)) else {
+                continue;
+            };
+            self.counted(ScanStats::add_dir);
+            for entry in entries.filter_map(|entry| self.readable(entry)) {
+                let Some(file_type) = self.readable(entry.file_type()) else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    pending.push(entry.path());
+                } else if file_type.is_file() {
+                    self.count_file(&path, &walk.files_scanned);
+                    if let Some(metadata) = self.readable(entry.metadata()) {
+                        self.counted(ScanStats::add_stat);
+                        size_bytes += metadata.len();
+                    }
+                }
+            }
+        }
+        self.check_size(dir, name, size_bytes, callback);
+    }
+
+    // A walked path as it is shown, under the root as given
+    fn shown<'p>(&self, path: &'p Path) -> Cow<'p, Path> {
+        match path.strip_prefix(&self.walk_root) {
+            Ok(rest) if self.walk_root != self.root => Cow::Owned(self.root.join(rest)),
+            _ => Cow::Borrowed(path),
+        }
+    }
+
+    /// Collect every matching file, returning the matches (unsorted) and the
+    /// number of files scanned.
+    pub fn collect(&self) -> Result<(Vec<FileInfo>, usize), ScanError> {
+        let files = Mutex::new(Vec::new());
+        let scanned_count = self.scan_with(|file| {
+            files.lock().unwrap().push(file);
+        })?;
+
+        Ok((files.into_inner().unwrap(), scanned_count))
+    }
+
+    /// Collect every matching file and hand the matches to `sink`, returning
+    /// the number of files scanned.
+    pub fn collect_into(&self, sink: &mut dyn ResultSink) -> Result<usize, ScanError> {
+        let (files, scanned_count) = self.collect()?;
+        sink.write(&files, scanned_count).map_err(ScanError::Sink)?;
+        Ok(scanned_count)
+    }
+
+    // Whether the walked directory `dir` was left out with skip_dirs or
+    // skip_dir_names. The root is walked whatever it is called
+    fn is_skipped(&self, dir: &Path) -> bool {
+        let named = !self.skipped_names.is_empty()
+            && dir != self.walk_root
+            && dir.file_name().is_some_and(|name| {
+                let name = name.to_string_lossy().to_lowercase();
+                self.skipped_names.contains(&name)
+            });
+        named
+            || !self.skipped.is_empty() && {
+                let shown = self.shown(dir);
+                self.skipped.iter().any(|skipped| skipped == shown.as_ref())
+            }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    fn report(&self, files_scanned: usize, current_dir: &Path) {
+        if let Some(hook) = &self.progress {
+            hook(&Progress {
+                files_scanned,
+                current_dir: &self.shown(current_dir),
+            });
+        }
+    }
+}
+
+// Modification time of `path`, if it can be read
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Whether the directory `name` is a bundle, by its extension
+fn is_bundle(name: &OsStr) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            BUNDLE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
+
+// Sizes of the files `names` in `dir`, through io_uring when it is compiled
+// in and works for this thread, and one stat at a time otherwise
+fn stat_batch(dir: &Path, names: &[OsString]) -> Vec<Option<u64>> {
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    if let Ok(sizes) = crate::uring::stat_sizes(dir, names) {
+        return sizes;
+    }
+
+    names
+        .iter()
+        .map(|name| fs::symlink_metadata(dir.join(name)).ok().map(|m| m.len()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Skipped;
+    use std::fs::{self, File};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_progress_reports_each_directory() {
+        let dir = tempdir().unwrap();
+        let subdir = dir.path().join("subdir");
+        fs::create_dir(&subdir).unwrap();
+        File::create(dir.path().join("a.txt")).unwrap();
+        File::create(subdir.join("b.txt")).unwrap();
+
+        let seen = Mutex::new(Vec::new());
+        let scanned_count = Scanner::new(dir.path())
+            .on_progress(|p| {
+                seen.lock().unwrap().push(p.current_dir.to_path_buf());
+            })
+            .scan_with(|_| {})
+            .unwrap();
+
+        let seen = seen.into_inner().unwrap();
+        assert_eq!(scanned_count, 2);
+        assert!(seen.contains(&dir.path().to_path_buf()));
+        assert!(seen.contains(&subdir));
+    }
+
+    #[test]
+    fn test_progress_counts_files_in_large_directory() {
+        let dir = tempdir().unwrap();
+        for i in 0..PROGRESS_INTERVAL {
+            File::create(dir.path().join(format!("{}.txt", i))).unwrap();
+        }
+
+        let max_seen = Mutex::new(0);
+        Scanner::new(dir.path())
+            .on_progress(|p| {
+                let mut max_seen = max_seen.lock().unwrap();
+                *max_seen = (*max_seen).max(p.files_scanned);
+            })
+            .scan_with(|_| {})
+            .unwrap();
+
+        assert_eq!(max_seen.into_inner().unwrap(), PROGRESS_INTERVAL);
+    }
+
+    #[test]
+    fn test_scanner_min_size_filters() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("small.txt"), [0u8; 10]).unwrap();
+        fs::write(dir.path().join("big.txt"), [0u8; 100]).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path()).min_size(50).collect().unwrap();
+        assert_eq!(scanned_count, 2);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].size_bytes, 100);
+    }
+
+    #[test]
+    fn test_filters_and_sinks() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("keep.bin"), [0u8; 100]).unwrap();
+        fs::write(dir.path().join("hold.bin"), [0u8; 100]).unwrap();
+        fs::write(dir.path().join("small.bin"), [0u8; 10]).unwrap();
+
+        struct Names(Vec<String>);
+        impl ResultSink for Names {
+            fn write(&mut self, files: &[FileInfo], _: usize) -> Result<(), crate::SinkError> {
+                self.0.extend(
+                    files
+                        .iter()
+                        .map(|file| file.path.file_name().unwrap().to_string_lossy().into()),
+                );
+                Ok(())
+            }
+        }
+        let mut names = Names(Vec::new());
+        let scanned_count = Scanner::new(dir.path())
+            .min_size(50)
+            .filter(|file: &FileInfo| !file.path.ends_with("hold.bin"))
+            .collect_into(&mut names)
+            .unwrap();
+        assert_eq!(scanned_count, 3);
+        assert_eq!(names.0, ["keep.bin"]);
+    }
+
+    #[test]
+    fn test_cancelled_scan_stops_early() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big.txt"), [0u8; 100]).unwrap();
+
+        let flag = Arc::new(AtomicBool::new(true));
+        let (files, scanned_count) = Scanner::new(dir.path()).cancel_on(flag).collect().unwrap();
+        assert_eq!(scanned_count, 0);
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_from_progress_hook_stops_walk() {
+        let dir = tempdir().unwrap();
+        for i in 0..10 {
+            fs::write(dir.path().join(format!("{}.txt", i)), [0u8; 100]).unwrap();
+        }
+
+        // Cancel from inside the walk, once the first directory is entered
+        let flag = Arc::new(AtomicBool::new(false));
+        let trigger = flag.clone();
+        let (files, scanned_count) = Scanner::new(dir.path())
+            .cancel_on(flag)
+            .on_progress(move |_| trigger.store(true, Ordering::Relaxed))
+            .collect()
+            .unwrap();
+        assert_eq!(scanned_count, 0);
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_unset_cancel_flag_scans_everything() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big.txt"), [0u8; 100]).unwrap();
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let (files, scanned_count) = Scanner::new(dir.path()).cancel_on(flag).collect().unwrap();
+        assert_eq!(scanned_count, 1);
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_scan_runs_on_requested_thread_count() {
+        let dir = tempdir().unwrap();
+        for i in 0..8 {
+            fs::write(dir.path().join(format!("{}.txt", i)), [0u8; 100]).unwrap();
+        }
+
+        let pool_sizes = Mutex::new(Vec::new());
+        let scanned_count = Scanner::new(dir.path())
+            .threads(2)
+            .scan_with(|_| {
+                pool_sizes
+                    .lock()
+                    .unwrap()
+                    .push(rayon::current_num_threads())
+            })
+            .unwrap();
+
+        assert_eq!(scanned_count, 8);
+        assert!(pool_sizes.into_inner().unwrap().iter().all(|&n| n == 2));
+    }
+
+    #[test]
+    fn test_scan_many_files_in_one_directory() {
+        let dir = tempdir().unwrap();
+        for i in 0..PROGRESS_INTERVAL * 2 + 1 {
+            File::create(dir.path().join(format!("{}.txt", i))).unwrap();
+        }
+        fs::write(dir.path().join("big.txt"), [0u8; 100]).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path()).min_size(50).collect().unwrap();
+        assert_eq!(scanned_count, PROGRESS_INTERVAL * 2 + 2);
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_scan_wide_and_deep_tree() {
+        let dir = tempdir().unwrap();
+        let mut expected = 0;
+        for i in 0..20 {
+            let mut nested = dir.path().join(format!("dir{}", i));
+            for depth in 0..5 {
+                nested = nested.join(format!("level{}", depth));
+                fs::create_dir_all(&nested).unwrap();
+                fs::write(nested.join("data.bin"), [0u8; 100]).unwrap();
+                expected += 1;
+            }
+        }
+
+        let (files, scanned_count) = Scanner::new(dir.path())
+            .threads(4)
+            .min_size(50)
+            .collect()
+            .unwrap();
+        assert_eq!(scanned_count, expected);
+        assert_eq!(files.len(), expected);
+    }
+
+    #[test]
+    fn test_scan_paths_past_max_path() {
+        // Deeper than the 260 characters Windows allows without a prefix,
+        // as in nested node_modules trees
+        let dir = tempdir().unwrap();
+        let relative: PathBuf = (0..12).map(|_| "node_modules/some-package-name").collect();
+        let deep = dir.path().join(&relative);
+        assert!(deep.as_os_str().len() > 260);
+        fs::create_dir_all(long_path(&deep)).unwrap();
+        fs::write(long_path(&deep.join("bundle.js")), [0u8; 100]).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path()).collect().unwrap();
+        assert_eq!(scanned_count, 1);
+        assert_eq!(files[0].path, deep.join("bundle.js"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_alternate_streams_only_exist_on_ntfs() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big.txt"), [0u8; 100]).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path())
+            .alternate_streams(true)
+            .collect()
+            .unwrap();
+        assert_eq!(scanned_count, 1);
+        assert_eq!(files.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_not_followed() {
+        let dir = tempdir().unwrap();
+        let real = dir.path().join("real");
+        fs::create_dir(&real).unwrap();
+        fs::write(real.join("big.txt"), [0u8; 100]).unwrap();
+        std::os::unix::fs::symlink(&real, dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink(real.join("big.txt"), dir.path().join("file-link")).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path()).collect().unwrap();
+        assert_eq!(scanned_count, 1);
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_skipped_dirs_are_not_walked() {
+        let dir = tempdir().unwrap();
+        let share = dir.path().join("mnt").join("share");
+        fs::create_dir_all(&share).unwrap();
+        fs::write(share.join("remote.bin"), [0u8; 200]).unwrap();
+        fs::write(dir.path().join("mnt").join("local.bin"), [0u8; 200]).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path())
+            .skip_dirs([share])
+            .collect()
+            .unwrap();
+        assert_eq!(scanned_count, 1);
+        assert!(files[0].path.ends_with("local.bin"));
+    }
+
+    #[test]
+    fn test_skipped_dir_names_are_not_walked() {
+        let dir = tempdir().unwrap();
+        let deps = dir.path().join("web").join("Node_Modules");
+        fs::create_dir_all(&deps).unwrap();
+        fs::write(deps.join("video.mp4"), [0u8; 200]).unwrap();
+        fs::write(dir.path().join("web").join("clip.mp4"), [0u8; 200]).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path())
+            .skip_dir_names(["node_modules"])
+            .collect()
+            .unwrap();
+        assert_eq!(scanned_count, 1);
+        assert!(files[0].path.ends_with("clip.mp4"));
+
+        // A root of that name is still scanned
+        let (files, _) = Scanner::new(&deps)
+            .skip_dir_names(["node_modules"])
+            .collect()
+            .unwrap();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_dirs_are_counted() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(dir.path().join("open.bin"), [0u8; 100]).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        // Root reads the directory anyway, and then nothing is left out
+        let expected = usize::from(fs::read_dir(&locked).is_err());
+
+        let stats = ScanStats::new();
+        let (_, scanned_count) = Scanner::new(dir.path()).stats(&stats).collect().unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(scanned_count, 1);
+        assert_eq!(stats.errors(), expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stats_count_the_walk() {
+        let dir = tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        let share = dir.path().join("share");
+        fs::create_dir(&sub).unwrap();
+        fs::create_dir(&share).unwrap();
+        fs::write(dir.path().join("a.bin"), [0u8; 100]).unwrap();
+        fs::write(sub.join("b.bin"), [0u8; 30]).unwrap();
+        std::os::unix::fs::symlink(&sub, dir.path().join("link")).unwrap();
+
+        let stats = ScanStats::new();
+        let (files, _) = Scanner::new(dir.path())
+            .min_size(50)
+            .skip_dirs([share])
+            .stats(&stats)
+            .collect()
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(stats.dirs_visited(), 2);
+        assert_eq!(stats.files_statted(), 2);
+        assert_eq!(stats.bytes_considered(), 130);
+        assert_eq!(
+            stats.skipped(),
+            Skipped {
+                links: 1,
+                excluded: 1,
+                ..Skipped::default()
+            }
+        );
+        assert!(!stats.thread_times().is_empty());
+    }
+
+    #[test]
+    fn test_bundles_are_reported_whole() {
+        let dir = tempdir().unwrap();
+        let macos = dir.path().join("Editor.app").join("Contents").join("MacOS");
+        fs::create_dir_all(&macos).unwrap();
+        fs::write(macos.join("Editor"), [0u8; 300]).unwrap();
+        fs::write(
+            dir.path()
+                .join("Editor.app")
+                .join("Contents")
+                .join("Info.plist"),
+            [0u8; 50],
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("Photos.photoslibrary")).unwrap();
+        fs::write(dir.path().join("notes.txt"), [0u8; 100]).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path())
+            .bundles_as_files(true)
+            .min_size(100)
+            .collect()
+            .unwrap();
+        assert_eq!(scanned_count, 3);
+        let mut found: Vec<(String, u64)> = files
+            .iter()
+            .map(|file| {
+                let name = file.path.file_name().unwrap();
+                (name.to_string_lossy().into_owned(), file.size_bytes)
+            })
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                ("Editor.app".to_string(), 350),
+                ("notes.txt".to_string(), 100)
+            ]
+        );
+
+        // Without the option the bundle's files are reported one by one
+        let (files, _) = Scanner::new(dir.path()).min_size(100).collect().unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn test_is_bundle() {
+        assert!(is_bundle(OsStr::new("Xcode.app")));
+        assert!(is_bundle(OsStr::new("Photos Library.PHOTOSLIBRARY")));
+        assert!(!is_bundle(OsStr::new("src")));
+        assert!(!is_bundle(OsStr::new("app")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_followed_links_are_walked_once() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        let real = dir.path().join("real");
+        fs::create_dir(&real).unwrap();
+        fs::write(real.join("big.txt"), [0u8; 100]).unwrap();
+        fs::write(outside.path().join("other.txt"), [0u8; 100]).unwrap();
+        // A second way into the tree, a loop back to the root, a tree outside
+        // it and a link to a file
+        symlink(&real, dir.path().join("alias")).unwrap();
+        symlink(dir.path(), real.join("loop")).unwrap();
+        symlink(outside.path(), dir.path().join("outside")).unwrap();
+        symlink(real.join("big.txt"), dir.path().join("file-link")).unwrap();
+
+        let (files, scanned_count) = Scanner::new(dir.path())
+            .follow_links(true)
+            .threads(4)
+            .collect()
+            .unwrap();
+        assert_eq!(scanned_count, 2);
+        let mut names: Vec<String> = files
+            .iter()
+            .map(|file| {
+                file.path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names, ["big.txt", "other.txt"]);
+    }
+
+    #[test]
+    fn test_mft_backend_only_on_windows() {
+        let dir = tempdir().unwrap();
+        let result = Scanner::new(dir.path()).backend(Backend::Mft).collect();
+        if cfg!(windows) {
+            // The temp directory may not be on NTFS, or the tests may lack
+            // administrator rights; either way the backend must be accepted
+            assert!(!matches!(result, Err(ScanError::BackendUnavailable(_))));
+        } else {
+            assert!(matches!(
+                result,
+                Err(ScanError::BackendUnavailable(Backend::Mft))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_io_uring_backend_matches_std() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("small.txt"), [0u8; 10]).unwrap();
+        fs::write(dir.path().join("big.txt"), [0u8; 100]).unwrap();
+        fs::write(dir.path().join("sub").join("big.txt"), [0u8; 200]).unwrap();
+
+        let result = Scanner::new(dir.path())
+            .backend(Backend::IoUring)
+            .min_size(50)
+            .collect();
+        if !Backend::IoUring.is_available() {
+            assert!(matches!(
+                result,
+                Err(ScanError::BackendUnavailable(Backend::IoUring))
+            ));
+            return;
+        }
+
+        let (mut files, scanned_count) = result.unwrap();
+        files.sort_by_key(|f| f.size_bytes);
+        assert_eq!(scanned_count, 3);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].size_bytes, 100);
+        assert_eq!(files[1].size_bytes, 200);
+    }
+
+    #[test]
+    fn test_cache_reuses_unchanged_directories() {
+        let dir = tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(dir.path().join("big.txt"), [0u8; 100]).unwrap();
+        fs::write(sub.join("big.txt"), [0u8; 200]).unwrap();
+
+        let cache = ScanCache::new();
+        let (first, _) = Scanner::new(dir.path()).cache(&cache).collect().unwrap();
+        assert_eq!(cache.listed(), 2);
+
+        let index = dir.path().join("index.bin");
+        cache.save(&index).unwrap();
+        let cache = ScanCache::load(&index).unwrap();
+        let (second, scanned_count) = Scanner::new(dir.path()).cache(&cache).collect().unwrap();
+
+        // index.bin was added to the root, so only the root is listed again
+        assert_eq!(cache.reused(), 1);
+        assert_eq!(cache.listed(), 1);
+        assert_eq!(scanned_count, 3);
+        assert_eq!(second.len(), first.len() + 1);
+    }
+
+    #[test]
+    fn test_cache_relists_modified_directory() {
+        let dir = tempdir().unwrap();
+        let index = tempdir().unwrap();
+        let index = index.path().join("index.bin");
+        fs::write(dir.path().join("a.txt"), [0u8; 100]).unwrap();
+
+        let cache = ScanCache::new();
+        Scanner::new(dir.path()).cache(&cache).collect().unwrap();
+        cache.save(&index).unwrap();
+
+        // Ensure the directory's modification time moves forward
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(dir.path().join("b.txt"), [0u8; 100]).unwrap();
+
+        let cache = ScanCache::load(&index).unwrap();
+        let (files, scanned_count) = Scanner::new(dir.path()).cache(&cache).collect().unwrap();
+        assert_eq!(cache.reused(), 0);
+        assert_eq!(scanned_count, 2);
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn test_cache_keeps_reporting_unchanged_files() {
+        let dir = tempdir().unwrap();
+        let index = tempdir().unwrap();
+        let index = index.path().join("index.bin");
+        fs::write(dir.path().join("big.txt"), [0u8; 100]).unwrap();
+
+        let cache = ScanCache::new();
+        Scanner::new(dir.path()).cache(&cache).collect().unwrap();
+        cache.save(&index).unwrap();
+
+        let cache = ScanCache::load(&index).unwrap();
+        let (files, _) = Scanner::new(dir.path())
+            .min_size(50)
+            .cache(&cache)
+            .collect()
+            .unwrap();
+        assert_eq!(cache.reused(), 1);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].path.ends_with("big.txt"));
+    }
+
+    #[test]
+    fn test_cache_reports_files_grown_in_place() {
+        use std::io::Write;
+
+        let dir = tempdir().unwrap();
+        let index = tempdir().unwrap();
+        let index = index.path().join("index.bin");
+        let log = dir.path().join("app.log");
+        fs::write(&log, [0u8; 10]).unwrap();
+
+        let cache = ScanCache::new();
+        Scanner::new(dir.path()).cache(&cache).collect().unwrap();
+        cache.save(&index).unwrap();
+
+        // Appending leaves the directory's modification time alone
+        let mtime = fs::metadata(dir.path()).unwrap().modified().unwrap();
+        let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+        file.write_all(&[0u8; 990]).unwrap();
+        assert_eq!(fs::metadata(dir.path()).unwrap().modified().unwrap(), mtime);
+
+        let cache = ScanCache::load(&index).unwrap();
+        let (files, _) = Scanner::new(dir.path())
+            .min_size(500)
+            .cache(&cache)
+            .collect()
+            .unwrap();
+        assert_eq!(cache.reused(), 1);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].size_bytes, 1000);
+    }
+}
@@ -0,0 +1,146 @@
+// Import FileInfo for the matches being scored
+use list_big_files::FileInfo;
+// Import Reverse for ranking the stalest matches first
+use std::cmp::Reverse;
+// Import time types for how long ago matches were modified
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How much size and age count towards a match's score, set with
+/// `--score-weights`. A weight of 0 leaves that factor out; above 1 it
+/// counts for more than the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights {
+    pub size: f64,
+    pub age: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            size: 1.0,
+            age: 1.0,
+        }
+    }
+}
+
+/// Parse the `SIZE,AGE` weights given to `--score-weights`.
+pub fn parse_weights(value: &str) -> Result<Weights, String> {
+    let invalid = || {
+        format!(
+            "invalid score weights {:?} (expected SIZE,AGE, e.g. 1,2)",
+            value
+        )
+    };
+    let (size, age) = value.split_once(',').ok_or_else(invalid)?;
+    let weight = |weight: &str| {
+        weight
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|weight| weight.is_finite() && *weight >= 0.0)
+            .ok_or_else(invalid)
+    };
+    Ok(Weights {
+        size: weight(size)?,
+        age: weight(age)?,
+    })
+}
+
+/// Score every file in `files` that has a modification time: its size in
+/// MiB times the days since it was modified, each raised to its weight.
+/// Files modified less than a day ago count as one day old.
+pub fn add_scores(files: &mut [FileInfo], weights: Weights, now: SystemTime) {
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    for file in files {
+        file.score = file.modified.map(|modified| {
+            let days = (now.saturating_sub(modified) as f64 / 86_400.0).max(1.0);
+            let mib = file.size_bytes as f64 / (1024.0 * 1024.0);
+            mib.powf(weights.size) * days.powf(weights.age)
+        });
+    }
+}
+
+/// Order `files` by score, highest first, then by size. Files without a
+/// score go last.
+pub fn sort(files: &mut [FileInfo]) {
+    files.sort_by(|a, b| {
+        let score = |file: &FileInfo| file.score.unwrap_or(-1.0);
+        score(b)
+            .total_cmp(&score(a))
+            .then_with(|| Reverse(a.size_bytes).cmp(&Reverse(b.size_bytes)))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const DAY: u64 = 86_400;
+    const MIB: u64 = 1024 * 1024;
+
+    fn file(path: &str, size_bytes: u64, modified: Option<u64>) -> FileInfo {
+        let mut file = FileInfo::new(path, size_bytes);
+        file.modified = modified;
+        file
+    }
+
+    #[test]
+    fn test_parse_weights() {
+        assert_eq!(
+            parse_weights("1, 2.5"),
+            Ok(Weights {
+                size: 1.0,
+                age: 2.5
+            })
+        );
+        assert!(parse_weights("1").is_err());
+        assert!(parse_weights("1,-1").is_err());
+        assert!(parse_weights("a,b").is_err());
+    }
+
+    #[test]
+    fn test_old_files_outrank_bigger_new_ones() {
+        let now = UNIX_EPOCH + Duration::from_secs(1000 * DAY);
+        let mut files = vec![
+            file("new.iso", 500 * MIB, Some(999 * DAY)),
+            file("old.tar", 100 * MIB, Some(0)),
+            file("unknown.bin", 900 * MIB, None),
+        ];
+        add_scores(&mut files, Weights::default(), now);
+        assert_eq!(files[0].score, Some(500.0));
+        assert_eq!(files[1].score, Some(100_000.0));
+        assert_eq!(files[2].score, None);
+        sort(&mut files);
+        let order: Vec<_> = files.iter().map(|f| f.path.to_str().unwrap()).collect();
+        assert_eq!(order, ["old.tar", "new.iso", "unknown.bin"]);
+    }
+
+    #[test]
+    fn test_weights_shift_the_ranking() {
+        let now = UNIX_EPOCH + Duration::from_secs(100 * DAY);
+        let mut files = vec![
+            file("big.iso", 1000 * MIB, Some(99 * DAY)),
+            file("old.log", 10 * MIB, Some(0)),
+        ];
+        let size_only = Weights {
+            size: 1.0,
+            age: 0.0,
+        };
+        add_scores(&mut files, size_only, now);
+        sort(&mut files);
+        assert_eq!(files[0].path.to_str(), Some("big.iso"));
+        add_scores(
+            &mut files,
+            Weights {
+                size: 1.0,
+                age: 2.0,
+            },
+            now,
+        );
+        sort(&mut files);
+        assert_eq!(files[0].path.to_str(), Some("old.log"));
+    }
+}
@@ -0,0 +1,146 @@
+// Import the metadata lookups the script's variables come from
+use crate::stat::{self, Owners};
+// Import FileInfo for the matches being filtered
+use list_big_files::FileInfo;
+// Import the Rhai interpreter
+use rhai::{Dynamic, Engine, Scope, AST};
+// Import fs for reading the script
+use std::fs;
+// Import Path types for where the script is loaded from
+use std::path::{Path, PathBuf};
+// Import time types for the script's `now`
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Operations a single run may take, so a script stuck in a loop cannot hang
+// the scan
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A Rhai script given with `--filter-script` that decides which matches
+/// are reported. It runs once per match with these variables and must
+/// evaluate to `true` to keep it:
+///
+/// - `path`: the match's path, as a string
+/// - `size`: its size in bytes
+/// - `mtime`: its modification time in seconds since the Unix epoch, or `()`
+///   when unknown
+/// - `owner`: the user owning it, or `()` when unknown
+/// - `now`: the current time in seconds since the Unix epoch
+pub struct FilterScript {
+    path: PathBuf,
+    engine: Engine,
+    ast: AST,
+    // Whether the script mentions `mtime` or `owner` at all, so files are
+    // not looked up for scripts that never ask
+    wants_mtime: bool,
+    wants_owner: bool,
+    owners: Owners,
+}
+
+impl FilterScript {
+    /// Read and compile the script at `path`.
+    pub fn load(path: &Path) -> Result<FilterScript, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // print and debug would otherwise land in the report on stdout
+        engine.on_print(|text| eprintln!("{}", text));
+        engine.on_debug(|text, _, position| eprintln!("{:?} {}", position, text));
+        let ast = engine
+            .compile(&source)
+            .map_err(|err| format!("cannot compile {}: {}", path.display(), err))?;
+        Ok(FilterScript {
+            path: path.to_path_buf(),
+            engine,
+            ast,
+            wants_mtime: source.contains("mtime"),
+            wants_owner: source.contains("owner"),
+            owners: Owners::default(),
+        })
+    }
+
+    /// Where the script was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the script keeps `file`, or why it could not say, e.g. because
+    /// it failed or did not evaluate to a boolean.
+    pub fn call(&self, file: &FileInfo) -> Result<bool, String> {
+        let mtime = file.modified.or_else(|| {
+            self.wants_mtime
+                .then(|| stat::modified(&file.path))
+                .flatten()
+        });
+        let owner = file.owner.clone().or_else(|| {
+            self.wants_owner
+                .then(|| self.owners.user(&file.path))
+                .flatten()
+        });
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+
+        let mut scope = Scope::new();
+        scope.push("path", file.path.to_string_lossy().into_owned());
+        scope.push("size", file.size_bytes as i64);
+        scope.push_dynamic(
+            "mtime",
+            mtime.map_or(Dynamic::UNIT, |mtime| (mtime as i64).into()),
+        );
+        scope.push_dynamic("owner", owner.map_or(Dynamic::UNIT, Dynamic::from));
+        scope.push("now", now as i64);
+        self.engine
+            .eval_ast_with_scope::<bool>(&mut scope, &self.ast)
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn load(source: &str) -> (tempfile::TempDir, FilterScript) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("filter.rhai");
+        fs::write(&path, source).unwrap();
+        let script = FilterScript::load(&path).unwrap();
+        (dir, script)
+    }
+
+    #[test]
+    fn test_script_sees_matches() {
+        let (_dir, script) = load(
+            r#"
+            let old = mtime != () && now - mtime > 30 * 86400;
+            size > 1000 && !path.ends_with(".keep") && (old || owner == "backup")
+            "#,
+        );
+        let mut file = FileInfo::new("/srv/a.iso", 5000);
+        file.modified = Some(0);
+        assert_eq!(script.call(&file), Ok(true));
+        file.path = "/srv/a.keep".into();
+        assert_eq!(script.call(&file), Ok(false));
+        file.path = "/srv/new.iso".into();
+        file.modified = Some(u64::MAX >> 2);
+        file.owner = Some("backup".to_string());
+        assert_eq!(script.call(&file), Ok(true));
+        file.owner = Some("alice".to_string());
+        assert_eq!(script.call(&file), Ok(false));
+    }
+
+    #[test]
+    fn test_script_failures() {
+        let (_dir, script) = load("size");
+        assert!(script.call(&FileInfo::new("/srv/a.iso", 1)).is_err());
+        let (_dir, script) = load("loop {}");
+        assert!(script.call(&FileInfo::new("/srv/a.iso", 1)).is_err());
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("broken.rhai");
+        fs::write(&path, "size >").unwrap();
+        let err = FilterScript::load(&path).err().unwrap();
+        assert!(err.starts_with("cannot compile"), "{}", err);
+    }
+}
@@ -0,0 +1,220 @@
+// Import the volume details included in responses
+use crate::disk;
+// Import the network mounts left out with --skip-network
+use crate::netfs;
+// Import the saved scan format used for responses
+use crate::report::{ScanInfo, ScanReport};
+// Import the scanner and size parser shared with the command line
+use list_big_files::{parse_size, Backend, ScanError, ScanStats, Scanner};
+// Import serde for request and error bodies
+use serde::{Deserialize, Serialize};
+// Import Reverse for listing the largest files first
+use std::cmp::Reverse;
+// Import PathBuf for the requested directory
+use std::path::PathBuf;
+// Import Arc and atomics for sharing the config and stopping on Ctrl+C
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+// Import thread so slow scans do not block other requests
+use std::thread;
+// Import time types for polling the stop flag and timing scans
+use std::time::{Duration, Instant, SystemTime};
+
+// How often the stop flag is checked while no requests arrive
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Defaults and scanner settings applied to every request.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub directory: PathBuf,
+    pub min_size_bytes: u64,
+    pub backend: Backend,
+    pub threads: Option<usize>,
+    pub ads: bool,
+    pub follow_links: bool,
+    pub bundle_as_file: bool,
+    pub skip_network: bool,
+}
+
+/// Body of `POST /scan`; omitted fields fall back to the command line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScanRequest {
+    dir: Option<PathBuf>,
+    min_size: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Answer scan requests on `addr` until `stop` is set.
+pub fn run(addr: &str, config: Config, stop: &AtomicBool) -> Result<(), String> {
+    let server = tiny_http::Server::http(addr)
+        .map_err(|err| format!("cannot listen on {}: {}", addr, err))?;
+    let config = Arc::new(config);
+
+    while !stop.load(Ordering::SeqCst) {
+        let mut request = match server.recv_timeout(POLL_INTERVAL) {
+            Ok(Some(request)) => request,
+            Ok(None) => continue,
+            Err(err) => return Err(format!("cannot accept requests: {}", err)),
+        };
+        let config = config.clone();
+        thread::spawn(move || {
+            let mut body = String::new();
+            let (status, body) = match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => handle(request.method().as_str(), request.url(), &body, &config),
+                Err(err) => error(400, format!("cannot read request body: {}", err)),
+            };
+            let content_type =
+                tiny_http::Header::from_bytes("Content-Type", "application/json").unwrap();
+            let response = tiny_http::Response::from_string(body)
+                .with_status_code(status)
+                .with_header(content_type);
+            let _ = request.respond(response);
+        });
+    }
+    Ok(())
+}
+
+// Route a request, returning the status code and JSON body
+fn handle(method: &str, url: &str, body: &str, config: &Config) -> (u16, String) {
+    match (method, url) {
+        ("POST", "/scan") => scan(body, config),
+        (_, "/scan") => error(405, "use POST /scan".to_string()),
+        _ => error(404, format!("no such endpoint: {}", url)),
+    }
+}
+
+// Run the scan described by a request body
+fn scan(body: &str, config: &Config) -> (u16, String) {
+    // An empty body scans the command line's directory with its threshold
+    let request: ScanRequest = if body.trim().is_empty() {
+        ScanRequest::default()
+    } else {
+        match serde_json::from_str(body) {
+            Ok(request) => request,
+            Err(err) => return error(400, format!("invalid request: {}", err)),
+        }
+    };
+
+    let directory = request.dir.unwrap_or_else(|| config.directory.clone());
+    let min_size_bytes = match &request.min_size {
+        Some(size) => match parse_size(size) {
+            Ok((size_bytes, _)) => size_bytes,
+            Err(err) => return error(400, format!("invalid min_size {:?}: {}", size, err)),
+        },
+        None => config.min_size_bytes,
+    };
+
+    let started = SystemTime::now();
+    let timer = Instant::now();
+    let stats = ScanStats::new();
+    let mut scanner = Scanner::new(&directory)
+        .min_size(min_size_bytes)
+        .stats(&stats)
+        .backend(config.backend)
+        .alternate_streams(config.ads)
+        .follow_links(config.follow_links)
+        .bundles_as_files(config.bundle_as_file);
+    if config.skip_network {
+        scanner = scanner.skip_dirs(netfs::mounts_below(&directory));
+    }
+    if let Some(threads) = config.threads {
+        scanner = scanner.threads(threads);
+    }
+    let (mut files, files_scanned) = match scanner.collect() {
+        Ok(result) => result,
+        Err(err @ (ScanError::NotFound(_) | ScanError::NotADirectory(_))) => {
+            return error(404, err.to_string())
+        }
+        Err(err) => return error(500, err.to_string()),
+    };
+    files.sort_by_key(|f| Reverse(f.size_bytes));
+
+    let volume = disk::volume(&directory).ok();
+    let report = ScanReport {
+        directory,
+        min_size_bytes,
+        files_scanned,
+        partial: false,
+        volume,
+        files,
+        dry_run: None,
+        scan: Some(ScanInfo::new(started, timer.elapsed(), stats.errors())),
+    };
+    (200, report.to_json())
+}
+
+// An error response with a JSON body
+fn error(status: u16, message: String) -> (u16, String) {
+    let body = serde_json::to_string(&ErrorBody { error: message }).unwrap();
+    (status, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn config(directory: PathBuf) -> Config {
+        Config {
+            directory,
+            min_size_bytes: 0,
+            backend: Backend::Std,
+            threads: None,
+            ads: false,
+            follow_links: false,
+            bundle_as_file: false,
+            skip_network: false,
+        }
+    }
+
+    #[test]
+    fn test_scan_request() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big.bin"), vec![0u8; 2 * 1024 * 1024]).unwrap();
+        fs::write(dir.path().join("small.bin"), [0u8; 10]).unwrap();
+
+        let body = format!(
+            r#"{{"dir": {:?}, "min_size": "1MB"}}"#,
+            dir.path().to_str().unwrap()
+        );
+        let (status, body) = handle("POST", "/scan", &body, &config(PathBuf::from(".")));
+        assert_eq!(status, 200);
+        let report: ScanReport = serde_json::from_str(&body).unwrap();
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.files_scanned, 2);
+        assert_eq!(report.min_size_bytes, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_empty_body_uses_defaults() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.bin"), [0u8; 10]).unwrap();
+        let (status, body) = handle("POST", "/scan", "", &config(dir.path().to_path_buf()));
+        assert_eq!(status, 200);
+        let report: ScanReport = serde_json::from_str(&body).unwrap();
+        assert_eq!(report.files.len(), 1);
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let config = config(PathBuf::from("."));
+        assert_eq!(handle("POST", "/scan", "{", &config).0, 400);
+        assert_eq!(
+            handle("POST", "/scan", r#"{"min_size": "10XB"}"#, &config).0,
+            400
+        );
+        assert_eq!(handle("POST", "/scan", r#"{"size": 1}"#, &config).0, 400);
+        assert_eq!(
+            handle("POST", "/scan", r#"{"dir": "/no/such/dir"}"#, &config).0,
+            404
+        );
+        assert_eq!(handle("GET", "/scan", "", &config).0, 405);
+        assert_eq!(handle("GET", "/", "", &config).0, 404);
+    }
+}
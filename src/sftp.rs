@@ -0,0 +1,129 @@
+// Import the directory walk shared by remote roots
+use crate::remote::{walk, Entry};
+// Import FileInfo for the files found
+use list_big_files::FileInfo;
+// Import ssh2 for the SSH session and its SFTP channel
+use ssh2::{CheckResult, KnownHostFileKind, Session, Sftp};
+// Import env for the user name and home directory
+use std::env;
+// Import TcpStream for the connection to the server
+use std::net::TcpStream;
+// Import Path for the files under ~/.ssh
+use std::path::{Path, PathBuf};
+
+// How long to wait on the server, in milliseconds
+const TIMEOUT_MS: u32 = 60_000;
+
+// Private keys tried in order when the SSH agent cannot log in
+const KEY_FILES: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// Walk the tree under `path` on `host`, passing each regular file to `visit`
+/// as a file whose path is its `sftp://` URL. Returns how many files were
+/// found. The server's key must already be in `~/.ssh/known_hosts`.
+pub fn list<F>(
+    user: Option<&str>,
+    host: &str,
+    port: u16,
+    path: &str,
+    mut visit: F,
+) -> Result<u64, String>
+where
+    F: FnMut(FileInfo),
+{
+    let user = match user {
+        Some(user) => user.to_string(),
+        None => env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .map_err(|_| format!("no user for {}; give one as sftp://USER@{}", host, host))?,
+    };
+    let session = connect(&user, host, port)?;
+    let sftp = session
+        .sftp()
+        .map_err(|err| format!("cannot start SFTP on {}: {}", host, err))?;
+    let prefix = match port {
+        22 => format!("sftp://{}@{}", user, host),
+        port => format!("sftp://{}@{}:{}", user, host, port),
+    };
+    walk(
+        path,
+        |dir| read_dir(&sftp, dir),
+        |path, entry| {
+            let mut file = FileInfo::new(format!("{}{}", prefix, path), entry.size_bytes);
+            file.modified = entry.modified;
+            visit(file);
+        },
+    )
+}
+
+// Log in to `host` as `user`, first checking its key against known_hosts
+fn connect(user: &str, host: &str, port: u16) -> Result<Session, String> {
+    let tcp = TcpStream::connect((host, port))
+        .map_err(|err| format!("cannot connect to {}:{}: {}", host, port, err))?;
+    let mut session = Session::new().map_err(|err| err.to_string())?;
+    session.set_timeout(TIMEOUT_MS);
+    session.set_tcp_stream(tcp);
+    session
+        .handshake()
+        .map_err(|err| format!("SSH handshake with {} failed: {}", host, err))?;
+
+    let ssh_dir = ssh_dir().ok_or("cannot find the home directory for ~/.ssh")?;
+    let (key, _) = session.host_key().ok_or("the server sent no host key")?;
+    let mut known_hosts = session.known_hosts().map_err(|err| err.to_string())?;
+    let _ = known_hosts.read_file(&ssh_dir.join("known_hosts"), KnownHostFileKind::OpenSSH);
+    match known_hosts.check_port(host, port, key) {
+        CheckResult::Match => {}
+        CheckResult::Mismatch => {
+            return Err(format!(
+                "the host key of {} does not match ~/.ssh/known_hosts",
+                host
+            ))
+        }
+        CheckResult::NotFound | CheckResult::Failure => {
+            return Err(format!(
+                "{} is not in ~/.ssh/known_hosts; connect once with ssh to trust it",
+                host
+            ))
+        }
+    }
+
+    if session.userauth_agent(user).is_err() {
+        for name in KEY_FILES {
+            let key = ssh_dir.join(name);
+            if key.exists() && session.userauth_pubkey_file(user, None, &key, None).is_ok() {
+                break;
+            }
+        }
+    }
+    if !session.authenticated() {
+        return Err(format!(
+            "cannot log in to {} as {} with the SSH agent or an unencrypted key in ~/.ssh",
+            host, user
+        ));
+    }
+    Ok(session)
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(Path::new(&home).join(".ssh"))
+}
+
+fn read_dir(sftp: &Sftp, dir: &str) -> Result<Vec<Entry>, String> {
+    let entries = sftp
+        .readdir(Path::new(dir))
+        .map_err(|err| format!("cannot read {}: {}", dir, err))?;
+    // Symlinks, devices and the like are neither followed nor counted
+    Ok(entries
+        .into_iter()
+        .filter(|(_, stat)| stat.is_dir() || stat.is_file())
+        .map(|(path, stat)| Entry {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            is_dir: stat.is_dir(),
+            size_bytes: stat.size.unwrap_or(0),
+            modified: stat.mtime,
+        })
+        .collect())
+}
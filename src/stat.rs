@@ -0,0 +1,554 @@
+// Import rayon to read the matches' metadata in parallel
+use rayon::prelude::*;
+// Import the ISO 8601 formatting shared with the daemon's reports
+use crate::daemon;
+// Import FileInfo for the matches that receive their metadata, and long_path
+// for files past MAX_PATH on Windows
+use list_big_files::{long_path, FileInfo};
+// Import HashMap and Mutex to resolve each owner's name once
+use std::collections::HashMap;
+use std::sync::Mutex;
+// Import fs for reading metadata
+use std::fs;
+// Import Path for the files whose owners and times are looked up
+use std::path::Path;
+// Import time types for modification times
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Set the modification time of every file in `files`. Files whose metadata
+/// cannot be read are left without one.
+pub fn add_modified(files: &mut [FileInfo]) {
+    files.par_iter_mut().for_each(|file| {
+        file.modified = modified(&file.path);
+    });
+}
+
+/// The modification time of the file at `path` in seconds since the Unix
+/// epoch, if its metadata can be read.
+pub fn modified(path: &Path) -> Option<u64> {
+    fs::metadata(long_path(path))
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_secs())
+}
+
+/// Set the permissions of every file in `files`: the mode bits on Unix,
+/// e.g. `-rw-r--r--`, and the read-only, hidden, system and archive
+/// attributes on Windows, e.g. `R--A`.
+pub fn add_permissions(files: &mut [FileInfo]) {
+    files.par_iter_mut().for_each(|file| {
+        file.permissions = fs::metadata(long_path(&file.path))
+            .ok()
+            .map(|meta| permissions(&meta));
+    });
+}
+
+// The mode of a file's metadata in symbolic form
+#[cfg(unix)]
+fn permissions(meta: &fs::Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    mode_string(meta.permissions().mode())
+}
+
+// The attribute flags of a file's metadata, with `-` for each that is unset
+#[cfg(windows)]
+fn permissions(meta: &fs::Metadata) -> String {
+    use std::os::windows::fs::MetadataExt;
+    const FLAGS: [(u32, char); 4] = [(0x1, 'R'), (0x2, 'H'), (0x4, 'S'), (0x20, 'A')];
+    let attributes = meta.file_attributes();
+    FLAGS
+        .iter()
+        .map(|&(bit, flag)| if attributes & bit != 0 { flag } else { '-' })
+        .collect()
+}
+
+// Elsewhere only whether the file is read-only is known
+#[cfg(not(any(unix, windows)))]
+fn permissions(meta: &fs::Metadata) -> String {
+    if meta.permissions().readonly() {
+        "read-only".to_string()
+    } else {
+        "writable".to_string()
+    }
+}
+
+// A Unix mode as `ls -l` writes it, including the file type and the setuid,
+// setgid and sticky bits
+#[cfg(unix)]
+fn mode_string(mode: u32) -> String {
+    let kind = match mode & 0o170_000 {
+        0o040_000 => 'd',
+        0o120_000 => 'l',
+        0o020_000 => 'c',
+        0o060_000 => 'b',
+        0o010_000 => 'p',
+        0o140_000 => 's',
+        _ => '-',
+    };
+    let mut text = String::from(kind);
+    // Each class's bits, with the special bit shown in place of execute
+    let classes = [
+        (0o700, 0o4000, 's'),
+        (0o070, 0o2000, 's'),
+        (0o007, 0o1000, 't'),
+    ];
+    for (shift, (bits, special, mark)) in [6, 3, 0].into_iter().zip(classes) {
+        let class = (mode & bits) >> shift;
+        text.push(if class & 4 != 0 { 'r' } else { '-' });
+        text.push(if class & 2 != 0 { 'w' } else { '-' });
+        text.push(match (class & 1 != 0, mode & special != 0) {
+            (true, true) => mark,
+            (false, true) => mark.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    text
+}
+
+/// Set the device and inode numbers of every file in `files`, so hardlinks
+/// and files seen twice through bind mounts can be told apart from copies.
+pub fn add_inodes(files: &mut [FileInfo]) {
+    files.par_iter_mut().for_each(|file| {
+        if let Some((device, inode)) = file_id(&file.path) {
+            file.device = Some(device);
+            file.inode = Some(inode);
+        }
+    });
+}
+
+/// Merge matches that are the same file reached through several paths, such
+/// as bind mounts or loop mounts of one directory under the scan root, or
+/// hardlinks. Each file is kept once, where the first of its paths was in
+/// `files`, under the path that sorts first so that reports of the same tree
+/// agree, with the others in order as its alternate paths. Files whose
+/// identity cannot be read are kept as they are.
+pub fn merge_same_files(files: &mut Vec<FileInfo>) {
+    let ids: Vec<Option<(u64, u64)>> = files.par_iter().map(|file| file_id(&file.path)).collect();
+    let mut kept: HashMap<(u64, u64), usize> = HashMap::new();
+    let mut merged: Vec<FileInfo> = Vec::with_capacity(files.len());
+    for (file, id) in files.drain(..).zip(ids) {
+        if let Some(&index) = id.as_ref().and_then(|id| kept.get(id)) {
+            merged[index].alternate_paths.push(file.path);
+            continue;
+        }
+        if let Some(id) = id {
+            kept.insert(id, merged.len());
+        }
+        merged.push(file);
+    }
+    for file in merged
+        .iter_mut()
+        .filter(|file| !file.alternate_paths.is_empty())
+    {
+        file.alternate_paths.push(file.path.clone());
+        file.alternate_paths.sort();
+        file.path = file.alternate_paths.remove(0);
+    }
+    *files = merged;
+}
+
+// The device and inode of `path`
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.dev(), meta.ino()))
+}
+
+// The volume serial number and file index of `path`
+#[cfg(windows)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS,
+    };
+
+    // Opening without read access works on files others hold locked
+    let file = fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(long_path(path))
+        .ok()?;
+    // SAFETY: the handle stays open while the zeroed record is filled in
+    let info = unsafe {
+        let mut info: BY_HANDLE_FILE_INFORMATION = std::mem::zeroed();
+        if GetFileInformationByHandle(file.as_raw_handle(), &mut info) == 0 {
+            return None;
+        }
+        info
+    };
+    let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+    Some((u64::from(info.dwVolumeSerialNumber), index))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn file_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Set the owning user and group of every file in `files`: names resolved
+/// from uid and gid on Unix, and from the owner and group SIDs on Windows.
+/// Ids without a name are shown as numbers or SID strings.
+pub fn add_owners(files: &mut [FileInfo]) {
+    let owners = Owners::default();
+    files.par_iter_mut().for_each(|file| {
+        if let Some((owner, group)) = owner_ids(&file.path) {
+            file.owner = Some(owners.resolve(owner));
+            file.group = Some(owners.resolve(group));
+        }
+    });
+}
+
+/// Names of the users and groups owning files, each looked up once per scan
+/// since many files share owners.
+#[derive(Default)]
+pub struct Owners {
+    names: Mutex<HashMap<OwnerId, String>>,
+}
+
+impl Owners {
+    /// The user owning the file at `path`, named as for `add_owners`.
+    pub fn user(&self, path: &Path) -> Option<String> {
+        owner_ids(path).map(|(owner, _)| self.resolve(owner))
+    }
+
+    // The name of `id`
+    fn resolve(&self, id: OwnerId) -> String {
+        if let Some(name) = self.names.lock().unwrap().get(&id) {
+            return name.clone();
+        }
+        let name = owner_name(&id);
+        self.names.lock().unwrap().insert(id, name.clone());
+        name
+    }
+}
+
+// A user or group that can own a file
+#[cfg(unix)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum OwnerId {
+    User(u32),
+    Group(u32),
+}
+
+// The uid and gid owning `path`
+#[cfg(unix)]
+fn owner_ids(path: &Path) -> Option<(OwnerId, OwnerId)> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = fs::metadata(path).ok()?;
+    Some((OwnerId::User(meta.uid()), OwnerId::Group(meta.gid())))
+}
+
+// The user or group name from the passwd and group databases, which may be
+// backed by LDAP or similar, or the number itself when there is none
+#[cfg(unix)]
+fn owner_name(id: &OwnerId) -> String {
+    use std::ffi::CStr;
+    use std::{mem, ptr};
+
+    let mut buf: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        // SAFETY: the record and buffer outlive each call, and the name,
+        // which points into the buffer, is copied out before either is dropped
+        let (status, name) = unsafe {
+            match *id {
+                OwnerId::User(uid) => {
+                    let mut pwd: libc::passwd = mem::zeroed();
+                    let mut result = ptr::null_mut();
+                    let status =
+                        libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result);
+                    let name = (status == 0 && !result.is_null())
+                        .then(|| CStr::from_ptr(pwd.pw_name).to_string_lossy().into_owned());
+                    (status, name)
+                }
+                OwnerId::Group(gid) => {
+                    let mut grp: libc::group = mem::zeroed();
+                    let mut result = ptr::null_mut();
+                    let status =
+                        libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result);
+                    let name = (status == 0 && !result.is_null())
+                        .then(|| CStr::from_ptr(grp.gr_name).to_string_lossy().into_owned());
+                    (status, name)
+                }
+            }
+        };
+        // Records larger than the buffer ask for a bigger one
+        if status == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        return name.unwrap_or_else(|| match *id {
+            OwnerId::User(id) | OwnerId::Group(id) => id.to_string(),
+        });
+    }
+}
+
+// A user or group that can own a file, by its SID
+#[cfg(windows)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct OwnerId(Vec<u8>);
+
+// The owner and primary group SIDs of `path`
+#[cfg(windows)]
+fn owner_ids(path: &Path) -> Option<(OwnerId, OwnerId)> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+    use windows_sys::Win32::Security::Authorization::{GetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows_sys::Win32::Security::{
+        GetLengthSid, GROUP_SECURITY_INFORMATION, OWNER_SECURITY_INFORMATION, PSID,
+    };
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut owner: PSID = ptr::null_mut();
+    let mut group: PSID = ptr::null_mut();
+    let mut descriptor = ptr::null_mut();
+    // SAFETY: the SIDs point into the descriptor, and are copied out before
+    // it is freed
+    unsafe {
+        let status = GetNamedSecurityInfoW(
+            wide.as_ptr(),
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION | GROUP_SECURITY_INFORMATION,
+            &mut owner,
+            &mut group,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut descriptor,
+        );
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+        let copy = |sid: PSID| {
+            let len = GetLengthSid(sid) as usize;
+            OwnerId(std::slice::from_raw_parts(sid as *const u8, len).to_vec())
+        };
+        let ids = (!owner.is_null() && !group.is_null()).then(|| (copy(owner), copy(group)));
+        LocalFree(descriptor);
+        ids
+    }
+}
+
+// The DOMAIN\name account of a SID, or the SID string when it has none
+#[cfg(windows)]
+fn owner_name(id: &OwnerId) -> String {
+    use std::ptr;
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Authorization::ConvertSidToStringSidW;
+    use windows_sys::Win32::Security::LookupAccountSidW;
+
+    let sid = id.0.as_ptr() as *mut std::ffi::c_void;
+    let mut name = [0u16; 256];
+    let mut domain = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain_len = domain.len() as u32;
+    let mut kind = 0;
+    // SAFETY: the SID bytes and both buffers outlive the calls, and the
+    // lengths passed are the buffers' own
+    unsafe {
+        if LookupAccountSidW(
+            ptr::null(),
+            sid,
+            name.as_mut_ptr(),
+            &mut name_len,
+            domain.as_mut_ptr(),
+            &mut domain_len,
+            &mut kind,
+        ) != 0
+        {
+            let name = String::from_utf16_lossy(&name[..name_len as usize]);
+            if domain_len == 0 {
+                return name;
+            }
+            let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+            return format!("{}\\{}", domain, name);
+        }
+        let mut string = ptr::null_mut();
+        if ConvertSidToStringSidW(sid, &mut string) == 0 {
+            return "?".to_string();
+        }
+        let len = (0..).take_while(|&i| *string.add(i) != 0).count();
+        let text = String::from_utf16_lossy(std::slice::from_raw_parts(string, len));
+        LocalFree(string.cast());
+        text
+    }
+}
+
+// Other platforms have no owners to show
+#[cfg(not(any(unix, windows)))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum OwnerId {}
+
+#[cfg(not(any(unix, windows)))]
+fn owner_ids(_path: &Path) -> Option<(OwnerId, OwnerId)> {
+    None
+}
+
+#[cfg(not(any(unix, windows)))]
+fn owner_name(id: &OwnerId) -> String {
+    match *id {}
+}
+
+/// A modification time as UTC ISO 8601, e.g. `2024-05-01T12:00:00Z`.
+pub fn iso(modified: u64) -> String {
+    daemon::timestamp(UNIX_EPOCH + Duration::from_secs(modified))
+}
+
+/// How long before `now` a modification time was, e.g. `3 months ago`.
+pub fn ago(modified: u64, now: SystemTime) -> String {
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let Some(secs) = now.checked_sub(modified) else {
+        return "in the future".to_string();
+    };
+    const UNITS: [(&str, u64); 6] = [
+        ("year", 365 * 86_400),
+        ("month", 30 * 86_400),
+        ("week", 7 * 86_400),
+        ("day", 86_400),
+        ("hour", 3600),
+        ("minute", 60),
+    ];
+    match UNITS.iter().find(|(_, length)| secs >= *length) {
+        Some((unit, length)) => {
+            let count = secs / length;
+            let plural = if count == 1 { "" } else { "s" };
+            format!("{} {}{} ago", count, unit, plural)
+        }
+        None => "just now".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_add_modified() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        fs::write(&path, [0; 16]).unwrap();
+        let mut files = vec![
+            FileInfo::new(&path, 16),
+            FileInfo::new(dir.path().join("gone"), 16),
+        ];
+        add_modified(&mut files);
+        let expected = fs::metadata(&path)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert_eq!(files[0].modified, Some(expected));
+        assert_eq!(files[1].modified, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_add_owners() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        fs::write(&path, [0; 16]).unwrap();
+        let mut files = vec![
+            FileInfo::new(path, 16),
+            FileInfo::new(dir.path().join("gone"), 16),
+        ];
+        add_owners(&mut files);
+        assert!(files[0].owner.is_some());
+        assert!(files[0].group.is_some());
+        assert_eq!(files[1].owner, None);
+        assert_eq!(files[1].group, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mode_string() {
+        assert_eq!(mode_string(0o100_644), "-rw-r--r--");
+        assert_eq!(mode_string(0o100_755), "-rwxr-xr-x");
+        assert_eq!(mode_string(0o104_755), "-rwsr-xr-x");
+        assert_eq!(mode_string(0o102_640), "-rw-r-S---");
+        assert_eq!(mode_string(0o041_777), "drwxrwxrwt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_add_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        fs::write(&path, [0; 16]).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        let mut files = vec![
+            FileInfo::new(path, 16),
+            FileInfo::new(dir.path().join("gone"), 16),
+        ];
+        add_permissions(&mut files);
+        assert_eq!(files[0].permissions.as_deref(), Some("-rw-r-----"));
+        assert_eq!(files[1].permissions, None);
+    }
+
+    #[test]
+    fn test_merge_same_files() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        let link = dir.path().join("link.bin");
+        let copy = dir.path().join("copy.bin");
+        fs::write(&path, [0; 16]).unwrap();
+        fs::hard_link(&path, &link).unwrap();
+        fs::copy(&path, &copy).unwrap();
+        let gone = dir.path().join("gone");
+        let extra = dir.path().join("b.bin");
+        fs::hard_link(&path, &extra).unwrap();
+        let mut files: Vec<FileInfo> = [&link, &gone, &copy, &extra, &path, &gone]
+            .into_iter()
+            .map(|path| FileInfo::new(path, 16))
+            .collect();
+        merge_same_files(&mut files);
+        // The path that sorts first is listed, whichever was found first
+        let paths: Vec<&Path> = files.iter().map(|file| file.path.as_path()).collect();
+        assert_eq!(paths, [&path, &gone, &copy, &gone]);
+        assert_eq!(files[0].alternate_paths, [extra, link]);
+        assert!(files[2].alternate_paths.is_empty());
+    }
+
+    #[test]
+    fn test_add_inodes_finds_hardlinks() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.bin");
+        let link = dir.path().join("link.bin");
+        let copy = dir.path().join("copy.bin");
+        fs::write(&path, [0; 16]).unwrap();
+        fs::hard_link(&path, &link).unwrap();
+        fs::copy(&path, &copy).unwrap();
+        let mut files: Vec<FileInfo> = [&path, &link, &copy]
+            .iter()
+            .map(|path| FileInfo::new(path, 16))
+            .collect();
+        add_inodes(&mut files);
+        assert!(files[0].inode.is_some());
+        assert_eq!(
+            (files[0].device, files[0].inode),
+            (files[1].device, files[1].inode)
+        );
+        assert_ne!(files[0].inode, files[2].inode);
+    }
+
+    #[test]
+    fn test_iso() {
+        assert_eq!(iso(1_714_564_800), "2024-05-01T12:00:00Z");
+    }
+
+    #[test]
+    fn test_ago() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        assert_eq!(ago(1_000_000_000 - 30, now), "just now");
+        assert_eq!(ago(1_000_000_000 - 3600, now), "1 hour ago");
+        assert_eq!(ago(1_000_000_000 - 95 * 86_400, now), "3 months ago");
+        assert_eq!(ago(1_000_000_000 - 800 * 86_400, now), "2 years ago");
+        assert_eq!(ago(1_000_000_000 + 10, now), "in the future");
+    }
+}
@@ -0,0 +1,138 @@
+// Import env for locating the user's cache and state directories
+use std::env;
+// Import Path and PathBuf for building cache paths
+use std::path::{Component, Path, PathBuf};
+
+/// Directory for data that can be regenerated, such as totals from
+/// previous scans: `cache` below `state_dir` when `--state-dir` is given,
+/// and otherwise the platform's cache directory. `None` when no home
+/// directory can be determined.
+pub fn cache_dir(state_dir: Option<&Path>) -> Option<PathBuf> {
+    if let Some(dir) = state_dir {
+        return Some(dir.join("cache"));
+    }
+    let base = if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("XDG_CACHE_HOME").or_else(|| home_dir("Library/Caches"))
+    } else {
+        env_dir("XDG_CACHE_HOME").or_else(|| home_dir(".cache"))
+    };
+
+    base.map(|dir| dir.join("list-big-files"))
+}
+
+/// Directory holding snapshots saved by name: `snapshots` below `state_dir`
+/// when `--state-dir` is given, and otherwise one in the platform's
+/// directory for application state.
+pub fn snapshot_dir(state_dir: Option<&Path>) -> Option<PathBuf> {
+    if let Some(dir) = state_dir {
+        return Some(dir.join("snapshots"));
+    }
+    let base = if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("XDG_STATE_HOME").or_else(|| home_dir("Library/Application Support"))
+    } else {
+        env_dir("XDG_STATE_HOME").or_else(|| home_dir(".local/state"))
+    };
+
+    base.map(|dir| dir.join("list-big-files").join("snapshots"))
+}
+
+/// The file a snapshot argument refers to. A bare name such as `home`, with
+/// no directory and no extension, is a snapshot kept in
+/// [`snapshot_dir`]; anything else is a path.
+pub fn snapshot_file(path: &Path, state_dir: Option<&Path>) -> Result<PathBuf, String> {
+    let bare = matches!(
+        path.components().collect::<Vec<_>>().as_slice(),
+        [Component::Normal(_)]
+    ) && path.extension().is_none();
+    if !bare {
+        return Ok(path.to_path_buf());
+    }
+    snapshot_dir(state_dir)
+        .map(|dir| dir.join(path).with_extension("lbf"))
+        .ok_or_else(|| {
+            format!(
+                "no home directory to keep snapshot {:?} in; pass --state-dir or a path",
+                path
+            )
+        })
+}
+
+// A directory named by an environment variable, if it is set
+fn env_dir(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+// `path` below the user's home directory
+fn home_dir(path: &str) -> Option<PathBuf> {
+    env_dir("HOME").map(|home| home.join(path))
+}
+
+/// Location of the incremental scan index for `root`, one file per
+/// canonical root directory.
+pub fn index_file(root: &Path, state_dir: Option<&Path>) -> Option<PathBuf> {
+    let root = root.canonicalize().ok()?;
+    let name = format!("{:016x}.bin", fnv1a(root.as_os_str().as_encoded_bytes()));
+    cache_dir(state_dir).map(|dir| dir.join("index").join(name))
+}
+
+// 64-bit FNV-1a, stable across builds unlike the standard library's hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fnv1a_known_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_index_file_is_per_root() {
+        let a = tempdir().unwrap();
+        let b = tempdir().unwrap();
+        if cache_dir(None).is_none() {
+            return;
+        }
+        let index_a = index_file(a.path(), None).unwrap();
+        assert_eq!(index_file(&a.path().join("."), None), Some(index_a.clone()));
+        assert_ne!(index_file(b.path(), None), Some(index_a));
+    }
+
+    #[test]
+    fn test_state_dir_holds_everything() {
+        let state = Path::new("/var/lib/lbf");
+        assert_eq!(cache_dir(Some(state)), Some(state.join("cache")));
+        let root = tempdir().unwrap();
+        assert!(index_file(root.path(), Some(state))
+            .unwrap()
+            .starts_with("/var/lib/lbf/cache/index"));
+        assert_eq!(
+            snapshot_file(Path::new("weekly"), Some(state)),
+            Ok(state.join("snapshots").join("weekly.lbf"))
+        );
+    }
+
+    #[test]
+    fn test_snapshot_paths_are_kept() {
+        let state = Some(Path::new("/var/lib/lbf"));
+        for path in ["home.lbf", "old.json", "reports/home", "/tmp/home", "."] {
+            assert_eq!(
+                snapshot_file(Path::new(path), state),
+                Ok(PathBuf::from(path))
+            );
+        }
+    }
+}
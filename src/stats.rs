@@ -0,0 +1,178 @@
+// Import Serialize so the skip counts can be written with the rest of a report
+use serde::Serialize;
+// Import atomics so every walker thread can count without locking
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+// Import Mutex for the time spent on each thread
+use std::sync::Mutex;
+// Import time types for timing the work of each thread
+use std::time::{Duration, Instant};
+
+/// Counters a [`crate::Scanner`] updates as it walks, to tell afterwards how
+/// the scan went. Attach them with [`crate::Scanner::stats`].
+#[derive(Debug, Default)]
+pub struct ScanStats {
+    errors: AtomicUsize,
+    dirs_visited: AtomicUsize,
+    files_statted: AtomicUsize,
+    bytes_considered: AtomicU64,
+    links: AtomicUsize,
+    special: AtomicUsize,
+    excluded: AtomicUsize,
+    revisited: AtomicUsize,
+    // Time spent walking on each rayon worker, by its index in the pool
+    thread_times: Mutex<Vec<Duration>>,
+}
+
+/// Entries a scan passed over without reporting, by why it did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Skipped {
+    /// Symbolic links, junctions and mount points that were not followed.
+    pub links: usize,
+    /// Sockets, FIFOs and device files.
+    pub special: usize,
+    /// Directories left out with [`crate::Scanner::skip_dirs`].
+    pub excluded: usize,
+    /// Directories reached again through a followed link.
+    pub revisited: usize,
+    /// Directories and entries that could not be read.
+    pub unreadable: usize,
+}
+
+impl ScanStats {
+    /// Counters that start at zero.
+    pub fn new() -> Self {
+        ScanStats::default()
+    }
+
+    /// Entries that could not be read and were left out: directories that
+    /// could not be listed, and entries whose type or size could not be read.
+    pub fn errors(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Directories listed, or replayed from a [`crate::ScanCache`].
+    pub fn dirs_visited(&self) -> usize {
+        self.dirs_visited.load(Ordering::Relaxed)
+    }
+
+    /// Files whose size was read from the file system.
+    pub fn files_statted(&self) -> usize {
+        self.files_statted.load(Ordering::Relaxed)
+    }
+
+    /// Combined size of every file and stream compared with the threshold,
+    /// matching or not.
+    pub fn bytes_considered(&self) -> u64 {
+        self.bytes_considered.load(Ordering::Relaxed)
+    }
+
+    /// Entries passed over, by reason.
+    pub fn skipped(&self) -> Skipped {
+        Skipped {
+            links: self.links.load(Ordering::Relaxed),
+            special: self.special.load(Ordering::Relaxed),
+            excluded: self.excluded.load(Ordering::Relaxed),
+            revisited: self.revisited.load(Ordering::Relaxed),
+            unreadable: self.errors(),
+        }
+    }
+
+    /// Time each thread of the scan's pool spent walking, by its index in
+    /// the pool. Threads that found no work show no time, or are left off
+    /// the end.
+    pub fn thread_times(&self) -> Vec<Duration> {
+        self.thread_times.lock().unwrap().clone()
+    }
+
+    pub(crate) fn add_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_dir(&self) {
+        self.dirs_visited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_stat(&self) {
+        self.files_statted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_bytes(&self, size_bytes: u64) {
+        self.bytes_considered
+            .fetch_add(size_bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_link(&self) {
+        self.links.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_special(&self) {
+        self.special.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_excluded(&self) {
+        self.excluded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_revisited(&self) {
+        self.revisited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Time a task until the returned timer is dropped, counting it towards
+    // the current pool thread
+    pub(crate) fn time_task(&self) -> TaskTimer<'_> {
+        TaskTimer {
+            stats: self,
+            started: Instant::now(),
+        }
+    }
+}
+
+// Adds the time since it was made to its thread's total when dropped, so
+// every return from a task is counted
+pub(crate) struct TaskTimer<'a> {
+    stats: &'a ScanStats,
+    started: Instant,
+}
+
+impl Drop for TaskTimer<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        let index = rayon::current_thread_index().unwrap_or(0);
+        let mut times = self.stats.thread_times.lock().unwrap();
+        if times.len() <= index {
+            times.resize(index + 1, Duration::ZERO);
+        }
+        times[index] += elapsed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skipped_includes_errors() {
+        let stats = ScanStats::new();
+        stats.add_error();
+        stats.add_link();
+        stats.add_link();
+        assert_eq!(
+            stats.skipped(),
+            Skipped {
+                links: 2,
+                unreadable: 1,
+                ..Skipped::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_task_times_add_up_per_thread() {
+        let stats = ScanStats::new();
+        drop(stats.time_task());
+        drop(stats.time_task());
+        let times = stats.thread_times();
+        assert_eq!(times.len(), 1);
+        assert!(times[0] > Duration::ZERO);
+    }
+}
@@ -0,0 +1,199 @@
+// Import FileInfo for the objects listed
+use list_big_files::FileInfo;
+// Import Duration for request timeouts
+use std::time::Duration;
+
+/// How long to wait on each page of a listing.
+pub const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// One object of a listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub key: String,
+    pub size_bytes: u64,
+    pub modified: Option<u64>,
+}
+
+/// A page of a listing, with the token for the next one if there is more.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub objects: Vec<Object>,
+    pub next: Option<String>,
+}
+
+/// A bucket or container of an object store, listed a page at a time.
+pub trait Store {
+    /// The URL keys are shown under, e.g. `s3://backups`.
+    fn url(&self) -> String;
+
+    /// The page of objects that `token` points at, or the first page.
+    fn page(&self, token: Option<String>) -> Result<Page, String>;
+}
+
+/// List every object in `store`, passing each to `visit` as a file whose path
+/// is its URL. Returns how many objects were listed.
+pub fn list<S, F>(store: &S, mut visit: F) -> Result<u64, String>
+where
+    S: Store,
+    F: FnMut(FileInfo),
+{
+    let url = store.url();
+    let mut listed = 0;
+    let mut token = None;
+    loop {
+        let page = store.page(token.take())?;
+        for object in page.objects {
+            // Zero-byte keys ending in a slash only stand for folders
+            if object.key.ends_with('/') {
+                continue;
+            }
+            let mut file = FileInfo::new(format!("{}/{}", url, object.key), object.size_bytes);
+            file.modified = object.modified;
+            visit(file);
+            listed += 1;
+        }
+        match page.next {
+            Some(next) => token = Some(next),
+            None => return Ok(listed),
+        }
+    }
+}
+
+/// Send `request` for a page of the listing of `url` and return the body it
+/// answers with. Errors quote what `message` finds in the response, or else
+/// its status.
+pub fn call(
+    request: ureq::Request,
+    url: &str,
+    message: fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    match request.timeout(TIMEOUT).call() {
+        Ok(response) => response
+            .into_string()
+            .map_err(|err| format!("cannot read the listing of {}: {}", url, err)),
+        Err(ureq::Error::Status(status, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(format!(
+                "cannot list {}: {}",
+                url,
+                message(&body).unwrap_or_else(|| format!("HTTP {}", status))
+            ))
+        }
+        Err(err) => Err(format!("cannot list {}: {}", url, err)),
+    }
+}
+
+/// A setting from the environment, treating an empty variable as unset.
+pub fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Percent-encode everything but the characters RFC 3986 leaves unreserved.
+pub fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// The message of an S3 or Azure XML error response, without the request
+/// details Azure appends on later lines.
+#[cfg(any(feature = "s3", feature = "azure"))]
+pub fn xml_error(xml: &str) -> Option<String> {
+    let document = roxmltree::Document::parse(xml).ok()?;
+    let message = |name: &str| {
+        document
+            .descendants()
+            .find(|node| node.has_tag_name(name))
+            .and_then(|node| node.text())
+            .and_then(|text| text.lines().next())
+            .map(str::to_string)
+    };
+    message("Message").or_else(|| message("Code"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::path::Path;
+
+    // A store serving canned pages and recording the tokens it was asked for
+    struct Canned {
+        pages: Vec<Page>,
+        tokens: RefCell<Vec<Option<String>>>,
+    }
+
+    impl Store for Canned {
+        fn url(&self) -> String {
+            "gs://media".to_string()
+        }
+
+        fn page(&self, token: Option<String>) -> Result<Page, String> {
+            let mut tokens = self.tokens.borrow_mut();
+            tokens.push(token);
+            Ok(self.pages[tokens.len() - 1].clone())
+        }
+    }
+
+    fn object(key: &str, size_bytes: u64) -> Object {
+        Object {
+            key: key.to_string(),
+            size_bytes,
+            modified: Some(1_714_564_800),
+        }
+    }
+
+    #[test]
+    fn test_list_follows_pages() {
+        let store = Canned {
+            pages: vec![
+                Page {
+                    objects: vec![object("video/", 0), object("video/a.mp4", 4096)],
+                    next: Some("page-2".to_string()),
+                },
+                Page {
+                    objects: vec![object("video/b.mp4", 10)],
+                    next: None,
+                },
+            ],
+            tokens: RefCell::new(Vec::new()),
+        };
+        let mut files = Vec::new();
+        let listed = list(&store, |file| files.push(file)).unwrap();
+        assert_eq!(listed, 2);
+        assert_eq!(files[0].path, Path::new("gs://media/video/a.mp4"));
+        assert_eq!(files[0].modified, Some(1_714_564_800));
+        assert_eq!(files[1].size_bytes, 10);
+        assert_eq!(
+            store.tokens.into_inner(),
+            vec![None, Some("page-2".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("logs/2024 05~x"), "logs%2F2024%2005~x");
+    }
+
+    #[cfg(any(feature = "s3", feature = "azure"))]
+    #[test]
+    fn test_xml_error() {
+        let body = "<Error><Code>ContainerNotFound</Code>\
+                    <Message>The specified container does not exist.\n\
+                    RequestId:1234</Message></Error>";
+        assert_eq!(
+            xml_error(body).as_deref(),
+            Some("The specified container does not exist.")
+        );
+        assert_eq!(
+            xml_error("<Error><Code>AccessDenied</Code></Error>").as_deref(),
+            Some("AccessDenied")
+        );
+    }
+}
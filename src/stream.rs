@@ -0,0 +1,97 @@
+// Import the blocking scanner that feeds the stream
+use crate::{check_root, FileInfo, ScanError, Scanner};
+// Import PathBuf so the scan can own its root on the blocking pool
+use std::path::PathBuf;
+// Import the flag that stops the scan once the stream is dropped
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+// Import tokio's bounded channel to hand matches across to async code
+use tokio::sync::mpsc;
+// Import the stream adapter and trait exposed to callers
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+
+// Number of matches buffered before the scan waits for the consumer
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Scan `directory` on tokio's blocking pool and yield each file of at least
+/// `min_size_bytes` as it is found.
+///
+/// Must be called from within a Tokio runtime. Matches arrive unsorted. The
+/// root is validated up front so a bad directory is reported immediately; a
+/// scan that fails later ends the stream with the error, so it can be told
+/// apart from one that found nothing. Dropping the stream stops the scan.
+pub fn scan_async(
+    directory: impl Into<PathBuf>,
+    min_size_bytes: u64,
+) -> Result<impl Stream<Item = Result<FileInfo, ScanError>>, ScanError> {
+    let directory = directory.into();
+    check_root(&directory)?;
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+
+    tokio::task::spawn_blocking(move || {
+        let stop = Arc::new(AtomicBool::new(false));
+        let result = Scanner::new(&directory)
+            .min_size(min_size_bytes)
+            .cancel_on(stop.clone())
+            .scan_with(|file| {
+                // Sending only fails once the consumer dropped the stream
+                if tx.blocking_send(Ok(file)).is_err() {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            });
+        if let Err(err) = result {
+            let _ = tx.blocking_send(Err(err));
+        }
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+    use tokio_stream::StreamExt;
+
+    #[test]
+    fn test_scan_async_yields_matches() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("small.txt"))
+            .unwrap()
+            .write_all(&[0u8; 10])
+            .unwrap();
+        File::create(dir.path().join("big.txt"))
+            .unwrap()
+            .write_all(&vec![0u8; 4096])
+            .unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let files: Vec<FileInfo> = runtime
+            .block_on(async {
+                scan_async(dir.path().to_path_buf(), 1024)
+                    .unwrap()
+                    .collect::<Result<_, _>>()
+                    .await
+            })
+            .unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert!(files[0].path.ends_with("big.txt"));
+    }
+
+    #[test]
+    fn test_scan_async_rejects_missing_directory() {
+        let dir = tempdir().unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let result =
+            runtime.block_on(async { scan_async(dir.path().join("missing"), 0).map(|_| ()) });
+
+        assert!(matches!(result, Err(ScanError::NotFound(_))));
+    }
+}
@@ -0,0 +1,161 @@
+// Import the saved scan format being logged
+use crate::report::ScanReport;
+// Import io for socket errors
+use std::io;
+// Import Path for the log socket location
+use std::path::Path;
+// Import process for the PID included in each entry
+use std::process;
+
+// Sockets the local syslog daemon listens on: Linux (also journald), macOS
+// and the BSDs
+const SOCKETS: &[&str] = &["/dev/log", "/var/run/syslog", "/var/run/log"];
+
+// Facility "user" (1) times 8 plus severity "informational" (6) or "notice" (5)
+const PRI_MATCH: u8 = 14;
+const PRI_SUMMARY: u8 = 13;
+
+/// Log one entry per match and a summary entry for `report` to the local
+/// syslog daemon.
+pub fn log_report(report: &ScanReport) -> io::Result<()> {
+    let socket = SOCKETS
+        .iter()
+        .map(Path::new)
+        .find(|path| path.exists())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no syslog socket found"))?;
+    log_report_to(socket, report)
+}
+
+// Send the entries for `report` to the datagram socket at `socket`
+#[cfg(unix)]
+fn log_report_to(socket: &Path, report: &ScanReport) -> io::Result<()> {
+    let sender = std::os::unix::net::UnixDatagram::unbound()?;
+    for line in entries(report) {
+        sender.send_to(line.as_bytes(), socket)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn log_report_to(_socket: &Path, _report: &ScanReport) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "syslog is only available on Unix",
+    ))
+}
+
+// Syslog lines for `report`: one per match, then the summary. Entries use the
+// traditional `<PRI>TAG[PID]: MESSAGE` form understood by rsyslog, syslog-ng
+// and journald, with the details as key=value pairs in the message
+fn entries(report: &ScanReport) -> Vec<String> {
+    let root = quote(&report.directory.display().to_string());
+    let mut lines: Vec<String> = report
+        .files
+        .iter()
+        .map(|file| {
+            entry(
+                PRI_MATCH,
+                &format!(
+                    "match root={} path={} size_bytes={}",
+                    root,
+                    quote(&file.path.to_string_lossy()),
+                    file.size_bytes
+                ),
+            )
+        })
+        .collect();
+
+    let total_bytes: u64 = report.files.iter().map(|file| file.size_bytes).sum();
+    lines.push(entry(
+        PRI_SUMMARY,
+        &format!(
+            "summary root={} min_size_bytes={} matches={} total_bytes={} files_scanned={} partial={}",
+            root,
+            report.min_size_bytes,
+            report.files.len(),
+            total_bytes,
+            report.files_scanned,
+            report.partial
+        ),
+    ));
+    lines
+}
+
+// Format one syslog entry
+fn entry(pri: u8, message: &str) -> String {
+    format!("<{}>list-big-files[{}]: {}", pri, process::id(), message)
+}
+
+// Quote a value so paths with spaces or quotes stay a single field
+fn quote(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use list_big_files::FileInfo;
+    use std::path::PathBuf;
+
+    fn report() -> ScanReport {
+        ScanReport {
+            directory: PathBuf::from("/srv"),
+            min_size_bytes: 100,
+            files_scanned: 7,
+            partial: false,
+            volume: None,
+            files: vec![
+                FileInfo::new("/srv/a b.iso".to_string(), 300),
+                FileInfo::new("/srv/\"q\".bin".to_string(), 200),
+            ],
+            dry_run: None,
+            scan: None,
+        }
+    }
+
+    #[test]
+    fn test_entries() {
+        let lines = entries(&report());
+        let prefix = format!("list-big-files[{}]: ", process::id());
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            format!(
+                "<14>{}match root=\"/srv\" path=\"/srv/a b.iso\" size_bytes=300",
+                prefix
+            )
+        );
+        assert!(lines[1].contains(r#"path="/srv/\"q\".bin""#));
+        assert_eq!(
+            lines[2],
+            format!(
+                "<13>{}summary root=\"/srv\" min_size_bytes=100 matches=2 total_bytes=500 files_scanned=7 partial=false",
+                prefix
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_log_report_to_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log");
+        let receiver = std::os::unix::net::UnixDatagram::bind(&path).unwrap();
+
+        log_report_to(&path, &report()).unwrap();
+        let mut buf = [0u8; 512];
+        let received: Vec<String> = (0..3)
+            .map(|_| {
+                let len = receiver.recv(&mut buf).unwrap();
+                String::from_utf8_lossy(&buf[..len]).into_owned()
+            })
+            .collect();
+        assert_eq!(received, entries(&report()));
+    }
+}
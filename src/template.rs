@@ -0,0 +1,207 @@
+// Import the detail fields a template can show
+use crate::columns::Column;
+// Import the size helpers for the size fields
+use list_big_files::{display_number, FileInfo, SizeUnit};
+// Import Path for the name and directory of each match
+use std::path::Path;
+
+/// A line format given to `--template`, e.g. `{size_gb}\t{path}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+// Literal text or a field filled in for each match
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+// What a placeholder stands for
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    // The size in the display unit, without a label
+    Size,
+    // The size in a fixed unit, such as {size_gb}
+    SizeIn(SizeUnit),
+    Name,
+    Dir,
+    Column(Column),
+}
+
+// Placeholders for sizes in a fixed unit, binary unless --si is given
+const SIZE_FIELDS: [(&str, SizeUnit); 6] = [
+    ("size_bytes", SizeUnit::B),
+    ("size_kb", SizeUnit::KiB),
+    ("size_mb", SizeUnit::MiB),
+    ("size_gb", SizeUnit::GiB),
+    ("size_tb", SizeUnit::TiB),
+    ("size_pb", SizeUnit::PiB),
+];
+
+// Placeholders for the details --columns can also show
+const COLUMN_FIELDS: [(&str, Column); 16] = [
+    ("path", Column::Path),
+    ("mtime", Column::Modified),
+    ("age", Column::Age),
+    ("score", Column::Score),
+    ("type", Column::Type),
+    ("mime", Column::Mime),
+    ("ratio", Column::Ratio),
+    ("disk", Column::Disk),
+    ("hash", Column::Hash),
+    ("perms", Column::Perms),
+    ("owner", Column::Owner),
+    ("group", Column::Group),
+    ("device", Column::Device),
+    ("inode", Column::Inode),
+    ("cloud", Column::Cloud),
+    ("tier", Column::Tier),
+];
+
+impl Template {
+    /// Parse a template, reading `\t`, `\n` and `\\` as escapes and `{{` and
+    /// `}}` as literal braces. With `si`, `{size_gb}` and the like are decimal.
+    pub fn parse(template: &str, si: bool) -> Result<Template, String> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| format!("unclosed {{ in template {:?}", template))?;
+                    let name = &rest[..end];
+                    chars = rest[end + 1..].chars();
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Field(field(name, si)?));
+                }
+                '}' => return Err(format!("unmatched }} in template {:?}", template)),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Template { pieces })
+    }
+
+    /// Whether the template shows `column`, so its detail must be gathered.
+    pub fn uses(&self, column: Column) -> bool {
+        self.pieces.contains(&Piece::Field(Field::Column(column)))
+    }
+
+    /// The line for `file`, with sizes in `unit` for `{size}`. Details that
+    /// were not gathered are shown as `-`.
+    pub fn render(&self, file: &FileInfo, unit: SizeUnit) -> String {
+        let mut line = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => line.push_str(text),
+                Piece::Field(Field::Size) => {
+                    line.push_str(&crate::size_cell(file.size_bytes, unit))
+                }
+                Piece::Field(Field::SizeIn(unit)) => {
+                    line.push_str(&display_number(file.size_bytes, *unit))
+                }
+                Piece::Field(Field::Name) => {
+                    let path = &file.path;
+                    let name = path.file_name().unwrap_or(path.as_os_str());
+                    line.push_str(&name.to_string_lossy());
+                }
+                Piece::Field(Field::Dir) => {
+                    let dir = file.path.parent().unwrap_or(Path::new(""));
+                    line.push_str(&dir.to_string_lossy());
+                }
+                Piece::Field(Field::Column(column)) => {
+                    line.push_str(column.cell(file, unit).as_deref().unwrap_or("-"))
+                }
+            }
+        }
+        line
+    }
+}
+
+// The field a placeholder names
+fn field(name: &str, si: bool) -> Result<Field, String> {
+    let name = name.trim();
+    let unit = |unit: SizeUnit| if si { unit.si() } else { unit };
+    match name {
+        "size" => Ok(Field::Size),
+        "name" => Ok(Field::Name),
+        "dir" => Ok(Field::Dir),
+        _ => SIZE_FIELDS
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|&(_, size_unit)| Field::SizeIn(unit(size_unit)))
+            .or_else(|| {
+                COLUMN_FIELDS
+                    .iter()
+                    .find(|(field, _)| *field == name)
+                    .map(|&(_, column)| Field::Column(column))
+            })
+            .ok_or_else(|| format!("unknown template field {{{}}}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut file = FileInfo::new("/data/logs/app.log", 3 * 1024 * 1024 * 1024);
+        file.hash = Some("abc".to_string());
+        let template = Template::parse("{size_gb}\\t{name} in {dir} {{{hash}}}", false).unwrap();
+        assert_eq!(
+            template.render(&file, SizeUnit::MiB),
+            "3.00\tapp.log in /data/logs {abc}"
+        );
+        let template = Template::parse("{size} {size_bytes} {type}", false).unwrap();
+        assert_eq!(template.render(&file, SizeUnit::GiB), "3.00 3221225472 -");
+    }
+
+    #[test]
+    fn test_si_sizes() {
+        let file = FileInfo::new("/data/a.bin", 1_500_000_000);
+        let template = Template::parse("{size_gb}", true).unwrap();
+        assert_eq!(template.render(&file, SizeUnit::MB), "1.50");
+    }
+
+    #[test]
+    fn test_uses() {
+        let template = Template::parse("{type} {path}", false).unwrap();
+        assert!(template.uses(Column::Type));
+        assert!(!template.uses(Column::Hash));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{size", false).is_err());
+        assert!(Template::parse("size}", false).is_err());
+        assert!(Template::parse("{uid}", false).is_err());
+    }
+}
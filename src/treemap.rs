@@ -0,0 +1,121 @@
+// Import FileInfo for the matches placed in the tree
+use list_big_files::FileInfo;
+// Import serde for the JSON layout
+use serde::Serialize;
+// Import BTreeMap so children come out in a stable order
+use std::collections::BTreeMap;
+// Import Path for splitting match paths into components
+use std::path::{Component, Path};
+
+/// A directory or file in the layout expected by d3's `hierarchy` and most
+/// treemap and sunburst viewers: directories have `children`, files a `value`.
+/// The root also carries the combined size of all matches in `total_bytes`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Node {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_bytes: Option<u64>,
+}
+
+// Directory under construction, with children keyed by name
+#[derive(Default)]
+struct Dir {
+    dirs: BTreeMap<String, Dir>,
+    files: BTreeMap<String, u64>,
+}
+
+impl Dir {
+    fn into_node(self, name: String) -> Node {
+        let mut children: Vec<Node> = self
+            .dirs
+            .into_iter()
+            .map(|(name, dir)| dir.into_node(name))
+            .collect();
+        children.extend(self.files.into_iter().map(|(name, size)| Node {
+            name,
+            value: Some(size),
+            children: Vec::new(),
+            total_bytes: None,
+        }));
+        Node {
+            name,
+            value: None,
+            children,
+            total_bytes: None,
+        }
+    }
+}
+
+/// Arrange `files` into a tree rooted at `root`. Files outside `root` are
+/// placed by their full path below it.
+pub fn build(root: &Path, files: &[FileInfo]) -> Node {
+    let mut tree = Dir::default();
+    for file in files {
+        let path = &file.path;
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let mut names: Vec<String> = relative
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let Some(file_name) = names.pop() else {
+            continue;
+        };
+
+        let mut dir = &mut tree;
+        for name in names {
+            dir = dir.dirs.entry(name).or_default();
+        }
+        dir.files.insert(file_name, file.size_bytes);
+    }
+    let mut node = tree.into_node(root.display().to_string());
+    node.total_bytes = Some(files.iter().map(|file| file.size_bytes).sum());
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size_bytes: u64) -> FileInfo {
+        FileInfo::new(path.to_string(), size_bytes)
+    }
+
+    #[test]
+    fn test_build_nests_directories() {
+        let files = [
+            file("/srv/a/b/big.iso", 300),
+            file("/srv/a/c.bin", 200),
+            file("/srv/top.bin", 100),
+        ];
+        let tree = build(Path::new("/srv"), &files);
+        let json = serde_json::to_value(&tree).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "name": "/srv",
+                "children": [
+                    {"name": "a", "children": [
+                        {"name": "b", "children": [{"name": "big.iso", "value": 300}]},
+                        {"name": "c.bin", "value": 200}
+                    ]},
+                    {"name": "top.bin", "value": 100}
+                ],
+                "total_bytes": 600
+            })
+        );
+    }
+
+    #[test]
+    fn test_build_empty() {
+        let tree = build(Path::new("."), &[]);
+        assert_eq!(
+            serde_json::to_string(&tree).unwrap(),
+            r#"{"name":".","total_bytes":0}"#
+        );
+    }
+}
@@ -0,0 +1,519 @@
+// Import the removals offered for the selected file and their running total
+use crate::actions::{Removal, Summary};
+// Import the size helpers used for every row
+use list_big_files::{display_size, FileInfo, SizeUnit};
+// Import crossterm events for keyboard input
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+// Import the layout, styling and widgets the browser is drawn with
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+// Import HashMap for directory subtotals
+use std::collections::HashMap;
+// Import OsString for the argument naming the file to select in Explorer
+use std::ffi::OsString;
+// Import io for terminal errors
+use std::io;
+// Import Path for splitting paths into directory and name
+use std::path::Path;
+// Import Command for opening a file's location
+use std::process::Command;
+
+// Rows moved by Page Up and Page Down
+const PAGE: isize = 10;
+
+// One line of the result list
+#[derive(Debug, PartialEq)]
+enum Row {
+    Dir {
+        path: String,
+        size_bytes: u64,
+        files: usize,
+    },
+    File(usize),
+}
+
+// What keys currently do
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Browse,
+    Filter,
+    Confirm(Removal),
+}
+
+// State of the result browser, kept apart from drawing so it can be tested
+struct App {
+    files: Vec<FileInfo>,
+    unit: SizeUnit,
+    // Files ignored or deleted during the session
+    hidden: Vec<bool>,
+    filter: String,
+    grouped: bool,
+    mode: Mode,
+    rows: Vec<Row>,
+    // Index into rows, always of a file row
+    selected: Option<usize>,
+    status: String,
+    summary: Summary,
+    quit: bool,
+}
+
+impl App {
+    fn new(files: Vec<FileInfo>, unit: SizeUnit) -> Self {
+        let mut app = App {
+            hidden: vec![false; files.len()],
+            files,
+            unit,
+            filter: String::new(),
+            grouped: false,
+            mode: Mode::Browse,
+            rows: Vec::new(),
+            selected: None,
+            status: String::new(),
+            summary: Summary::default(),
+            quit: false,
+        };
+        app.refresh();
+        app
+    }
+
+    // Rebuild the rows from the visible files, keeping the selected file
+    // selected when it is still shown
+    fn refresh(&mut self) {
+        let current = self.selected_index();
+        let filter = self.filter.to_lowercase();
+        let visible: Vec<usize> = (0..self.files.len())
+            .filter(|&i| {
+                !self.hidden[i]
+                    && self.files[i]
+                        .path
+                        .to_string_lossy()
+                        .to_lowercase()
+                        .contains(&filter)
+            })
+            .collect();
+
+        self.rows = if self.grouped {
+            let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+            let mut positions: HashMap<String, usize> = HashMap::new();
+            for i in visible {
+                let dir = parent(&self.files[i].path);
+                let position = *positions.entry(dir.clone()).or_insert_with(|| {
+                    groups.push((dir, Vec::new()));
+                    groups.len() - 1
+                });
+                groups[position].1.push(i);
+            }
+            let total = |members: &[usize]| -> u64 {
+                members.iter().map(|&i| self.files[i].size_bytes).sum()
+            };
+            groups.sort_by_key(|(_, members)| std::cmp::Reverse(total(members)));
+
+            let mut rows = Vec::new();
+            for (path, members) in groups {
+                rows.push(Row::Dir {
+                    path,
+                    size_bytes: total(&members),
+                    files: members.len(),
+                });
+                rows.extend(members.into_iter().map(Row::File));
+            }
+            rows
+        } else {
+            visible.into_iter().map(Row::File).collect()
+        };
+
+        self.selected = current
+            .and_then(|index| self.rows.iter().position(|row| *row == Row::File(index)))
+            .or_else(|| self.file_rows().next());
+    }
+
+    // Positions of the rows that hold files
+    fn file_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, Row::File(_)))
+            .map(|(position, _)| position)
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        match self.rows.get(self.selected?)? {
+            Row::File(index) => Some(*index),
+            Row::Dir { .. } => None,
+        }
+    }
+
+    // Move the selection by `delta` files, stopping at either end
+    fn move_by(&mut self, delta: isize) {
+        let positions: Vec<usize> = self.file_rows().collect();
+        let Some(current) = self
+            .selected
+            .and_then(|selected| positions.iter().position(|&p| p == selected))
+        else {
+            return;
+        };
+        let target = (current as isize + delta).clamp(0, positions.len() as isize - 1);
+        self.selected = Some(positions[target as usize]);
+    }
+
+    fn handle_key(&mut self, key: KeyCode) {
+        match self.mode {
+            Mode::Filter => match key {
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.refresh();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.refresh();
+                }
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Browse;
+                    self.refresh();
+                }
+                _ => {}
+            },
+            Mode::Confirm(removal) => {
+                self.mode = Mode::Browse;
+                if key == KeyCode::Char('y') {
+                    self.remove(removal);
+                } else {
+                    self.status = "Cancelled".to_string();
+                }
+            }
+            Mode::Browse => {
+                self.status.clear();
+                match key {
+                    KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                    KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                    KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                    KeyCode::PageDown => self.move_by(PAGE),
+                    KeyCode::PageUp => self.move_by(-PAGE),
+                    KeyCode::Home => self.move_by(isize::MIN / 2),
+                    KeyCode::End => self.move_by(isize::MAX / 2),
+                    KeyCode::Char('/') => self.mode = Mode::Filter,
+                    KeyCode::Char('g') => {
+                        self.grouped = !self.grouped;
+                        self.refresh();
+                    }
+                    KeyCode::Char('i') => {
+                        if let Some(index) = self.selected_index() {
+                            self.hidden[index] = true;
+                            self.status = format!("Ignored {}", self.files[index].path.display());
+                            self.refresh();
+                        }
+                    }
+                    KeyCode::Char('d') if self.selected_index().is_some() => {
+                        self.mode = Mode::Confirm(Removal::Delete);
+                    }
+                    KeyCode::Char('t') if self.selected_index().is_some() => {
+                        self.mode = Mode::Confirm(Removal::Trash);
+                    }
+                    KeyCode::Char('o') => self.open_location(),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, removal: Removal) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        let file = &self.files[index];
+        match removal.apply(&file.path) {
+            Ok(()) => {
+                self.status = format!(
+                    "{} {} ({})",
+                    removal.done(),
+                    file.path.display(),
+                    display_size(file.size_bytes, self.unit)
+                );
+                self.summary.add(file.size_bytes);
+                self.hidden[index] = true;
+                self.refresh();
+            }
+            Err(err) => self.status = format!("cannot remove {}: {}", file.path.display(), err),
+        }
+    }
+
+    // Show the selected file's directory in the platform's file manager
+    fn open_location(&mut self) {
+        let Some(index) = self.selected_index() else {
+            return;
+        };
+        let path = &self.files[index].path;
+        let mut command = if cfg!(target_os = "macos") {
+            let mut command = Command::new("open");
+            command.arg("-R").arg(path);
+            command
+        } else if cfg!(windows) {
+            let mut command = Command::new("explorer");
+            let mut select = OsString::from("/select,");
+            select.push(path);
+            command.arg(select);
+            command
+        } else {
+            let mut command = Command::new("xdg-open");
+            command.arg(path.parent().unwrap_or(path));
+            command
+        };
+        self.status = match command.spawn() {
+            Ok(_) => format!("Opened the location of {}", path.display()),
+            Err(err) => format!("cannot open the location of {}: {}", path.display(), err),
+        };
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let shown: Vec<&FileInfo> = self
+            .rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::File(index) => Some(&self.files[*index]),
+                Row::Dir { .. } => None,
+            })
+            .collect();
+        let shown_bytes: u64 = shown.iter().map(|file| file.size_bytes).sum();
+        let mut title = format!(
+            "{} files, {} shown",
+            shown.len(),
+            display_size(shown_bytes, self.unit)
+        );
+        if self.summary.removed > 0 {
+            title.push_str(&format!(
+                "  |  freed {} ({} removed)",
+                display_size(self.summary.freed_bytes, self.unit),
+                self.summary.removed
+            ));
+        }
+        frame.render_widget(
+            Paragraph::new(title).style(Style::default().add_modifier(Modifier::BOLD)),
+            header,
+        );
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Dir {
+                    path,
+                    size_bytes,
+                    files,
+                } => ListItem::new(format!(
+                    "{:>14}  {}/ ({} files)",
+                    crate::size_cell(*size_bytes, self.unit),
+                    path,
+                    files
+                ))
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+                Row::File(index) => {
+                    let file = &self.files[*index];
+                    let name = if self.grouped {
+                        format!("    {}", file_name(&file.path))
+                    } else {
+                        file.path.display().to_string()
+                    };
+                    ListItem::new(format!(
+                        "{:>14}  {}",
+                        crate::size_cell(file.size_bytes, self.unit),
+                        name
+                    ))
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(self.selected);
+        frame.render_stateful_widget(list, body, &mut state);
+
+        let line = match self.mode {
+            Mode::Filter => format!("Filter: {}_  (Enter to keep, Esc to clear)", self.filter),
+            Mode::Confirm(removal) => match self.selected_index() {
+                Some(index) => format!(
+                    "{} {}? (y/n)",
+                    match removal {
+                        Removal::Delete => "Delete",
+                        Removal::Trash => "Move to trash",
+                    },
+                    self.files[index].path.display()
+                ),
+                None => String::new(),
+            },
+            Mode::Browse if !self.status.is_empty() => self.status.clone(),
+            Mode::Browse => {
+                "↑↓ move  / filter  g group by directory  o open location  d delete  t trash  i ignore  q quit"
+                    .to_string()
+            }
+        };
+        frame.render_widget(Paragraph::new(line), footer);
+    }
+}
+
+/// Browse `files` interactively until the user quits, returning what was
+/// deleted along the way.
+pub fn run(files: Vec<FileInfo>, unit: SizeUnit) -> io::Result<Summary> {
+    let mut app = App::new(files, unit);
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result.map(|()| app.summary)
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(key.code);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Directory part of a reported path
+fn parent(path: &Path) -> String {
+    path.parent()
+        .map(|parent| parent.display().to_string())
+        .unwrap_or_default()
+}
+
+// Name part of a reported path
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn app() -> App {
+        App::new(
+            vec![
+                FileInfo::new("/srv/a/big.iso", 500),
+                FileInfo::new("/srv/b/movie.mkv", 400),
+                FileInfo::new("/srv/a/small.iso", 300),
+            ],
+            SizeUnit::MiB,
+        )
+    }
+
+    fn type_keys(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            app.handle_key(KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_navigation_stops_at_the_ends() {
+        let mut app = app();
+        assert_eq!(app.selected_index(), Some(0));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.selected_index(), Some(2));
+        app.handle_key(KeyCode::Home);
+        assert_eq!(app.selected_index(), Some(0));
+    }
+
+    #[test]
+    fn test_filter_narrows_rows() {
+        let mut app = app();
+        type_keys(&mut app, "/ISO");
+        assert_eq!(app.rows, vec![Row::File(0), Row::File(2)]);
+        app.handle_key(KeyCode::Esc);
+        assert_eq!(app.rows.len(), 3);
+        assert_eq!(app.mode, Mode::Browse);
+    }
+
+    #[test]
+    fn test_grouping_orders_directories_by_size() {
+        let mut app = app();
+        app.handle_key(KeyCode::Char('g'));
+        assert_eq!(
+            app.rows,
+            vec![
+                Row::Dir {
+                    path: "/srv/a".to_string(),
+                    size_bytes: 800,
+                    files: 2
+                },
+                Row::File(0),
+                Row::File(2),
+                Row::Dir {
+                    path: "/srv/b".to_string(),
+                    size_bytes: 400,
+                    files: 1
+                },
+                Row::File(1),
+            ]
+        );
+        // Directory headers are skipped when moving
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn test_ignore_hides_the_selected_file() {
+        let mut app = app();
+        app.handle_key(KeyCode::Char('i'));
+        assert_eq!(app.rows, vec![Row::File(1), Row::File(2)]);
+        assert_eq!(app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn test_delete_requires_confirmation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("big.iso");
+        fs::write(&path, vec![0; 2048]).unwrap();
+        let mut app = App::new(vec![FileInfo::new(&path, 2048)], SizeUnit::MiB);
+
+        type_keys(&mut app, "dn");
+        assert!(path.exists());
+        type_keys(&mut app, "dy");
+        assert!(!path.exists());
+        assert!(app.rows.is_empty());
+        assert_eq!(
+            app.summary,
+            Summary {
+                removed: 1,
+                freed_bytes: 2048
+            }
+        );
+    }
+
+    #[test]
+    fn test_draw_shows_totals_and_rows() {
+        let app = app();
+        let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("3 files, 0.00 MiB shown"));
+        assert!(screen.contains("/srv/b/movie.mkv"));
+    }
+}
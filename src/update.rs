@@ -0,0 +1,240 @@
+// Import serde for the GitHub release listing
+use serde::Deserialize;
+// Import SHA-256 for checking downloads against their published checksums
+use sha2::{Digest, Sha256};
+// Import env for the running binary and the platform it was built for
+use std::env;
+// Import fs and io for writing the new binary next to the old one
+use std::fs;
+use std::io::{Read, Write};
+// Import Path types for the binary being replaced
+use std::path::{Path, PathBuf};
+// Import Duration for request timeouts
+use std::time::Duration;
+
+// Where releases are published
+const LATEST_RELEASE: &str =
+    "https://api.github.com/repos/franzramadhan/list-big-files/releases/latest";
+
+// How long a request may stall before the update gives up
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A published release, as the GitHub API describes it.
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<Asset>,
+}
+
+/// A file attached to a release.
+#[derive(Debug, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// The release's version without the leading `v` of its tag.
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    // The attached file called `name`
+    fn asset(&self, name: &str) -> Result<&Asset, String> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| format!("release {} has no {}", self.tag_name, name))
+    }
+}
+
+/// The newest published release.
+pub fn latest() -> Result<Release, String> {
+    let body = get(LATEST_RELEASE)?;
+    serde_json::from_slice(&body).map_err(|err| format!("unexpected release listing: {}", err))
+}
+
+/// Whether `version` is newer than this binary.
+pub fn is_newer(version: &str) -> bool {
+    match (
+        parse_version(version),
+        parse_version(env!("CARGO_PKG_VERSION")),
+    ) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// Download this platform's binary from `release`, check it against the
+/// SHA-256 checksum published beside it, and put it in place of the running
+/// binary. Returns the path that was replaced.
+pub fn install(release: &Release) -> Result<PathBuf, String> {
+    let name = asset_name(env::consts::OS, env::consts::ARCH).ok_or_else(|| {
+        format!(
+            "no release binary is published for {}-{}; build from source instead",
+            env::consts::OS,
+            env::consts::ARCH
+        )
+    })?;
+    let binary = get(&release.asset(name)?.browser_download_url)?;
+    let checksums = get(&release
+        .asset(&format!("{}.sha256", name))?
+        .browser_download_url)?;
+    let expected = expected_checksum(&String::from_utf8_lossy(&checksums), name)
+        .ok_or_else(|| format!("{}.sha256 does not list {}", name, name))?;
+    let actual = hex(&Sha256::digest(&binary));
+    if actual != expected {
+        return Err(format!(
+            "checksum mismatch for {} (expected {}, got {}); nothing was changed",
+            name, expected, actual
+        ));
+    }
+
+    let current = env::current_exe()
+        .and_then(fs::canonicalize)
+        .map_err(|err| format!("cannot find the running binary: {}", err))?;
+    replace(&current, &binary)
+        .map_err(|err| format!("cannot replace {}: {}", current.display(), err))?;
+    Ok(current)
+}
+
+// The name of the release binary built for `os` on `arch`
+fn asset_name(os: &str, arch: &str) -> Option<&'static str> {
+    match (os, arch) {
+        ("linux", "x86_64") => Some("list-big-files-linux-x64"),
+        ("macos", "aarch64") => Some("list-big-files-darwin-arm64"),
+        ("windows", "x86_64") => Some("list-big-files-windows-x64.exe"),
+        _ => None,
+    }
+}
+
+// The numeric parts of a version such as `1.2.3` or `v1.2.3`, ignoring any
+// pre-release suffix
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches('v');
+    let release = version.split(['-', '+']).next()?;
+    release.split('.').map(|part| part.parse().ok()).collect()
+}
+
+// The checksum listed for `name` in a `sha256sum` style file, whose lines read
+// `HASH  NAME` or `HASH *NAME`; a file holding only a hash applies to `name`
+fn expected_checksum(checksums: &str, name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let hash = fields.next()?;
+        match fields.next() {
+            Some(file) if file.trim_start_matches('*') != name => None,
+            _ => Some(hash.to_ascii_lowercase()),
+        }
+    })
+}
+
+// Lowercase hexadecimal for `bytes`
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// The body of a GET request to `url`
+fn get(url: &str) -> Result<Vec<u8>, String> {
+    let response = ureq::get(url)
+        .timeout(TIMEOUT)
+        .set(
+            "User-Agent",
+            concat!("list-big-files/", env!("CARGO_PKG_VERSION")),
+        )
+        .call()
+        .map_err(|err| format!("cannot download {}: {}", url, err))?;
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|err| format!("cannot download {}: {}", url, err))?;
+    Ok(body)
+}
+
+// Write `contents` beside `binary` and rename it over `binary`. Windows cannot
+// replace a running binary, but it can rename it out of the way first
+fn replace(binary: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let staged = binary.with_extension("new");
+    let mut file = fs::File::create(&staged)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(windows)]
+    {
+        let old = binary.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(binary, &old)?;
+    }
+    fs::rename(&staged, binary).inspect_err(|_| {
+        let _ = fs::remove_file(&staged);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_name() {
+        assert_eq!(
+            asset_name("linux", "x86_64"),
+            Some("list-big-files-linux-x64")
+        );
+        assert_eq!(
+            asset_name("windows", "x86_64"),
+            Some("list-big-files-windows-x64.exe")
+        );
+        assert_eq!(asset_name("freebsd", "x86_64"), None);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v1.2.3"), Some(vec![1, 2, 3]));
+        assert_eq!(parse_version("0.10.0-rc.1"), Some(vec![0, 10, 0]));
+        assert_eq!(parse_version("latest"), None);
+        assert!(parse_version("0.10.0") > parse_version("0.9.9"));
+    }
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v999.0.0"));
+        assert!(!is_newer(env!("CARGO_PKG_VERSION")));
+        assert!(!is_newer("v0.0.1"));
+        assert!(!is_newer("nightly"));
+    }
+
+    #[test]
+    fn test_expected_checksum() {
+        let hash = "AB".repeat(32);
+        let sums = format!(
+            "{}  other\n{} *list-big-files-linux-x64\n",
+            "0".repeat(64),
+            hash
+        );
+        assert_eq!(
+            expected_checksum(&sums, "list-big-files-linux-x64"),
+            Some(hash.to_ascii_lowercase())
+        );
+        assert_eq!(expected_checksum(&sums, "missing"), None);
+        assert_eq!(
+            expected_checksum("abc123\n", "anything"),
+            Some("abc123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("list-big-files");
+        fs::write(&binary, "old").unwrap();
+        replace(&binary, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&binary).unwrap(), "new");
+        assert!(!binary.with_extension("new").exists());
+    }
+}
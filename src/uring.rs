@@ -0,0 +1,136 @@
+// Import io_uring for submitting batches of statx requests
+use io_uring::{opcode, types, IoUring};
+// Import RefCell for the per-thread ring
+use std::cell::RefCell;
+// Import C and OS string types for passing file names to the kernel
+use std::ffi::{CString, OsString};
+// Import File to hold the directory open while its entries are statted
+use std::fs::File;
+// Import io for error reporting
+use std::io;
+// Import mem for zeroed statx buffers
+use std::mem;
+// Import Unix extensions for raw names and file descriptors
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::AsRawFd;
+// Import Path for the directory being statted
+use std::path::Path;
+
+// Submission queue size, and so the largest batch sent to the kernel at once
+const RING_ENTRIES: u32 = 256;
+
+thread_local! {
+    // One ring per rayon worker, created on first use
+    static RING: RefCell<Option<IoUring>> = RefCell::new(IoUring::new(RING_ENTRIES).ok());
+}
+
+/// Whether the running kernel lets this process create an io_uring; it may be
+/// too old, or disabled by sysctl or a seccomp policy.
+pub fn is_available() -> bool {
+    IoUring::new(2).is_ok()
+}
+
+/// Sizes of the files `names` inside `dir`, in the same order, with `None`
+/// for entries that could not be statted. Symlinks are not followed.
+pub fn stat_sizes(dir: &Path, names: &[OsString]) -> io::Result<Vec<Option<u64>>> {
+    let dir_file = File::open(dir)?;
+    let dirfd = types::Fd(dir_file.as_raw_fd());
+    let c_names = names
+        .iter()
+        .map(|name| CString::new(name.as_bytes()).map_err(io::Error::other))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    RING.with(|ring| {
+        let mut ring = ring.borrow_mut();
+        let ring = ring
+            .as_mut()
+            .ok_or_else(|| io::Error::other("io_uring is not available"))?;
+
+        let mut sizes = vec![None; names.len()];
+        for start in (0..names.len()).step_by(RING_ENTRIES as usize) {
+            let end = (start + RING_ENTRIES as usize).min(names.len());
+            stat_chunk(ring, dirfd, &c_names[start..end], &mut sizes[start..end])?;
+        }
+        Ok(sizes)
+    })
+}
+
+// Stat one batch that fits in the submission queue
+fn stat_chunk(
+    ring: &mut IoUring,
+    dirfd: types::Fd,
+    names: &[CString],
+    sizes: &mut [Option<u64>],
+) -> io::Result<()> {
+    // SAFETY: statx is a plain C struct for which all zeroes is valid
+    let mut buffers: Vec<libc::statx> = vec![unsafe { mem::zeroed() }; names.len()];
+
+    for (index, name) in names.iter().enumerate() {
+        let entry = opcode::Statx::new(
+            dirfd,
+            name.as_ptr(),
+            buffers[index..].as_mut_ptr().cast::<types::statx>(),
+        )
+        .flags(libc::AT_SYMLINK_NOFOLLOW)
+        .mask(libc::STATX_SIZE)
+        .build()
+        .user_data(index as u64);
+
+        // SAFETY: the kernel copies the name on submission, and the buffer
+        // outlives the request: it completes before this function returns,
+        // or the buffers are leaked below
+        unsafe { ring.submission().push(&entry) }
+            .expect("submission queue has room for a full chunk");
+    }
+
+    let mut completed = 0;
+    while completed < names.len() {
+        if let Err(err) = ring.submit_and_wait(names.len() - completed) {
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            // Requests may still be in flight and write into the buffers, so
+            // they must never be freed
+            mem::forget(buffers);
+            return Err(err);
+        }
+        for cqe in ring.completion() {
+            let index = cqe.user_data() as usize;
+            if cqe.result() >= 0 {
+                sizes[index] = Some(buffers[index].stx_size);
+            }
+            completed += 1;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_stat_sizes_matches_std() {
+        if !is_available() {
+            return;
+        }
+        let dir = tempdir().unwrap();
+        let mut names = Vec::new();
+        // More files than fit in one submission batch
+        for i in 0..RING_ENTRIES as usize + 10 {
+            let name = format!("{}.bin", i);
+            fs::write(dir.path().join(&name), vec![0u8; i]).unwrap();
+            names.push(OsString::from(name));
+        }
+        names.push(OsString::from("missing.bin"));
+
+        let sizes = stat_sizes(dir.path(), &names).unwrap();
+        for (i, size) in sizes.iter().enumerate().take(names.len() - 1) {
+            assert_eq!(*size, Some(i as u64));
+        }
+        assert_eq!(sizes.last(), Some(&None));
+    }
+}
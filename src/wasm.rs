@@ -0,0 +1,199 @@
+// Import FileInfo and the filter trait the modules implement
+use crate::{FileFilter, FileInfo};
+// Import Path types for where modules are loaded from
+use std::path::{Path, PathBuf};
+// Import Mutex since a module instance runs one call at a time
+use std::sync::Mutex;
+// Import the interpreter that runs the modules
+use wasmi::{Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+// The interface version this build speaks, as for shared library plugins
+const ABI_VERSION: i32 = 1;
+
+// Instructions a single call may run before it is stopped
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+// Linear memory a module may grow to
+const MEMORY_LIMIT: usize = 64 << 20;
+
+/// A filter compiled to WebAssembly and run in a sandbox: the module gets no
+/// imports, so it cannot touch files, the network or the clock, each call
+/// is stopped after a fixed number of instructions, and its memory is
+/// capped. The module exports:
+///
+/// - `memory`
+/// - `lbf_plugin_abi_version() -> i32`, returning 1
+/// - `lbf_alloc(len: i32) -> i32`, returning where `len` bytes of path can be
+///   written
+/// - `lbf_filter(path: i32, len: i32, size_bytes: i64) -> i32`, returning
+///   nonzero to keep the match
+///
+/// Paths are written as for shared library plugins: as the OS gave them on
+/// Unix, and as UTF-8 on Windows. One instance serves the whole scan and runs
+/// one call at a time, so a module may keep state between calls.
+pub struct WasmFilter {
+    path: PathBuf,
+    instance: Mutex<Instance>,
+}
+
+// A running module and the exports the filter calls
+struct Instance {
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    filter: TypedFunc<(i32, i32, i64), i32>,
+}
+
+impl WasmFilter {
+    /// Compile and start the module at `path`, checking that it speaks this
+    /// interface.
+    pub fn load(path: &Path) -> Result<WasmFilter, String> {
+        let wasm = std::fs::read(path)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let failed = |err: wasmi::Error| format!("cannot load {}: {}", path.display(), err);
+
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm).map_err(failed)?;
+        let limits = StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build();
+        let mut store = Store::new(&engine, limits);
+        store.limiter(|limits| limits);
+        store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|err| failed(err.into()))?;
+        // Nothing is linked in, so modules that import anything fail here
+        let instance = Linker::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(failed)?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&store, "lbf_plugin_abi_version")
+            .and_then(|version| version.call(&mut store, ()))
+            .map_err(failed)?;
+        if version != ABI_VERSION {
+            return Err(format!(
+                "{} is built for plugin interface {}, but this build speaks {}",
+                path.display(),
+                version,
+                ABI_VERSION
+            ));
+        }
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| format!("{} does not export its memory", path.display()))?;
+        let alloc = instance
+            .get_typed_func(&store, "lbf_alloc")
+            .map_err(failed)?;
+        let filter = instance
+            .get_typed_func(&store, "lbf_filter")
+            .map_err(failed)?;
+        Ok(WasmFilter {
+            path: path.to_path_buf(),
+            instance: Mutex::new(Instance {
+                store,
+                memory,
+                alloc,
+                filter,
+            }),
+        })
+    }
+
+    /// Where the module was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the module keeps `file`, or why it could not say, e.g. because
+    /// it trapped or ran out of fuel.
+    pub fn call(&self, file: &FileInfo) -> Result<bool, String> {
+        let path = file.path.as_os_str().as_encoded_bytes();
+        let len = i32::try_from(path.len()).map_err(|_| "path too long".to_string())?;
+        let mut guard = self.instance.lock().unwrap_or_else(|err| err.into_inner());
+        let instance = &mut *guard;
+        let store = &mut instance.store;
+        store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|err| err.to_string())?;
+        let ptr = instance
+            .alloc
+            .call(&mut *store, len)
+            .map_err(|err| err.to_string())?;
+        let offset = usize::try_from(ptr).map_err(|_| format!("lbf_alloc returned {}", ptr))?;
+        instance
+            .memory
+            .write(&mut *store, offset, path)
+            .map_err(|err| err.to_string())?;
+        let keep = instance
+            .filter
+            .call(&mut *store, (ptr, len, file.size_bytes as i64))
+            .map_err(|err| err.to_string())?;
+        Ok(keep != 0)
+    }
+}
+
+/// Matches the module fails on are kept, so a broken filter cannot hide
+/// files; use [`WasmFilter::call`] to see the failures.
+impl FileFilter for WasmFilter {
+    fn keep(&self, file: &FileInfo) -> bool {
+        self.call(file).unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    // Keeps files of under 100 bytes whose path does not start with "/hold",
+    // and loops forever on files of exactly 7 bytes
+    const FILTER: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "lbf_plugin_abi_version") (result i32) i32.const 1)
+          (func (export "lbf_alloc") (param i32) (result i32) i32.const 1024)
+          (func (export "lbf_filter") (param $ptr i32) (param $len i32) (param $size i64) (result i32)
+            (if (i64.eq (local.get $size) (i64.const 7))
+              (then (loop $forever (br $forever))))
+            (if (i32.ge_u (local.get $len) (i32.const 5))
+              (then
+                (if (i32.eq (i32.load (local.get $ptr)) (i32.const 0x6c6f682f))
+                  (then (return (i32.const 0))))))
+            (i64.lt_u (local.get $size) (i64.const 100))))
+    "#;
+
+    fn module(wat: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("filter.wasm");
+        std::fs::write(&path, wat::parse_str(wat).unwrap()).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_filter_runs_module() {
+        let (_dir, path) = module(FILTER);
+        let filter = WasmFilter::load(&path).unwrap();
+        assert!(filter.keep(&FileInfo::new("/srv/a.bin", 50)));
+        assert!(!filter.keep(&FileInfo::new("/srv/b.bin", 500)));
+        assert!(!filter.keep(&FileInfo::new("/hold/c.bin", 50)));
+    }
+
+    #[test]
+    fn test_runaway_calls_are_stopped() {
+        let (_dir, path) = module(FILTER);
+        let filter = WasmFilter::load(&path).unwrap();
+        assert!(filter.call(&FileInfo::new("/srv/loop", 7)).is_err());
+        assert!(filter.keep(&FileInfo::new("/srv/loop", 7)));
+        assert_eq!(filter.call(&FileInfo::new("/srv/a.bin", 50)), Ok(true));
+    }
+
+    #[test]
+    fn test_imports_are_refused() {
+        let (_dir, path) = module(
+            r#"(module (import "wasi_snapshot_preview1" "fd_write"
+                 (func (param i32 i32 i32 i32) (result i32))))"#,
+        );
+        assert!(WasmFilter::load(&path).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+// Import the scanner for directories that appear while watching
+use list_big_files::{FileInfo, Scanner};
+// Import notify for filesystem change events
+use notify::{Event, RecursiveMode, Watcher};
+// Import HashSet for the files currently above the threshold
+use std::collections::HashSet;
+// Import fs for checking the size of changed paths
+use std::fs;
+// Import Path types for event paths and the files tracked
+use std::path::{Path, PathBuf};
+// Import atomics for stopping on Ctrl+C
+use std::sync::atomic::{AtomicBool, Ordering};
+// Import mpsc for receiving events from the watcher thread
+use std::sync::mpsc;
+// Import Duration for polling the stop flag between events
+use std::time::Duration;
+
+// How often the stop flag is checked while no events arrive
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A file crossing the size threshold in either direction.
+#[derive(Debug, PartialEq)]
+pub enum Crossing {
+    Above(FileInfo),
+    Below(FileInfo),
+    Removed(PathBuf),
+}
+
+/// Remembers which files are at or above the threshold so that only changes
+/// across it are reported.
+pub struct Tracker {
+    min_size_bytes: u64,
+    known: HashSet<PathBuf>,
+}
+
+impl Tracker {
+    /// Start from the matches of the initial scan.
+    pub fn new(min_size_bytes: u64, matches: &[FileInfo]) -> Self {
+        Tracker {
+            min_size_bytes,
+            known: matches.iter().map(|file| file.path.clone()).collect(),
+        }
+    }
+
+    /// Re-check `path` after a change event, returning the crossings it caused.
+    pub fn update(&mut self, path: &Path) -> Vec<Crossing> {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            // A vanished directory takes every known file below it with it
+            Err(_) => return self.forget(path),
+        };
+
+        if metadata.is_dir() {
+            return self.scan_dir(path);
+        }
+        if !metadata.is_file() {
+            return Vec::new();
+        }
+
+        let file = FileInfo::new(path, metadata.len());
+        let above = file.size_bytes >= self.min_size_bytes;
+        match (above, self.known.contains(&file.path)) {
+            (true, false) => {
+                self.known.insert(file.path.clone());
+                vec![Crossing::Above(file)]
+            }
+            (false, true) => {
+                self.known.remove(&file.path);
+                vec![Crossing::Below(file)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // Drop `path` and anything below it
+    fn forget(&mut self, path: &Path) -> Vec<Crossing> {
+        let mut gone: Vec<PathBuf> = self
+            .known
+            .iter()
+            .filter(|known| known.starts_with(path))
+            .cloned()
+            .collect();
+        gone.sort();
+        for known in &gone {
+            self.known.remove(known);
+        }
+        gone.into_iter().map(Crossing::Removed).collect()
+    }
+
+    // Report big files in a directory that was created or moved into the tree
+    fn scan_dir(&mut self, dir: &Path) -> Vec<Crossing> {
+        let Ok((mut files, _)) = Scanner::new(dir).min_size(self.min_size_bytes).collect() else {
+            return Vec::new();
+        };
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+            .into_iter()
+            .filter(|file| self.known.insert(file.path.clone()))
+            .map(Crossing::Above)
+            .collect()
+    }
+}
+
+/// Watch `root` until `stop` is set, passing each crossing to `report`.
+pub fn run<F>(
+    root: &Path,
+    mut tracker: Tracker,
+    stop: &AtomicBool,
+    mut report: F,
+) -> notify::Result<()>
+where
+    F: FnMut(Crossing),
+{
+    // Events name absolute paths; they are mapped back onto `root` as given so
+    // they match the paths reported by the initial scan
+    let watched = root.canonicalize()?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&watched, RecursiveMode::Recursive)?;
+
+    while !stop.load(Ordering::SeqCst) {
+        let event: Event = match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(event) => event?,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        if event.kind.is_access() {
+            continue;
+        }
+        for path in &event.paths {
+            let path = match path.strip_prefix(&watched) {
+                Ok(relative) => root.join(relative),
+                Err(_) => path.clone(),
+            };
+            tracker.update(&path).into_iter().for_each(&mut report);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tracker_reports_growth_past_threshold() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("app.log");
+        fs::write(&log, [0u8; 10]).unwrap();
+        let mut tracker = Tracker::new(100, &[]);
+        assert!(tracker.update(&log).is_empty());
+
+        fs::write(&log, [0u8; 150]).unwrap();
+        let crossings = tracker.update(&log);
+        assert!(matches!(&crossings[..], [Crossing::Above(file)] if file.size_bytes == 150));
+
+        // Growing further is not a new crossing
+        fs::write(&log, [0u8; 200]).unwrap();
+        assert!(tracker.update(&log).is_empty());
+    }
+
+    #[test]
+    fn test_tracker_reports_shrink_and_removal() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("app.log");
+        fs::write(&log, [0u8; 10]).unwrap();
+        let known = FileInfo::new(&log, 200);
+        let mut tracker = Tracker::new(100, &[known]);
+        assert!(matches!(&tracker.update(&log)[..], [Crossing::Below(_)]));
+
+        fs::write(&log, [0u8; 200]).unwrap();
+        tracker.update(&log);
+        fs::remove_file(&log).unwrap();
+        assert_eq!(tracker.update(&log), vec![Crossing::Removed(log)]);
+    }
+
+    #[test]
+    fn test_tracker_handles_directories() {
+        let dir = tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join("big.bin"), [0u8; 200]).unwrap();
+        fs::write(sub.join("small.bin"), [0u8; 10]).unwrap();
+
+        let mut tracker = Tracker::new(100, &[]);
+        let crossings = tracker.update(&sub);
+        assert!(
+            matches!(&crossings[..], [Crossing::Above(file)] if file.path.ends_with("big.bin"))
+        );
+
+        fs::remove_dir_all(&sub).unwrap();
+        assert!(matches!(&tracker.update(&sub)[..], [Crossing::Removed(_)]));
+    }
+}
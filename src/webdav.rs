@@ -0,0 +1,289 @@
+// Import the HTTP date parser for Last-Modified dates
+use crate::daemon::parse_http_date;
+// Import the directory walk shared by remote roots
+use crate::remote::{walk, Entry};
+// Import base64 for Basic authentication
+use base64::{engine::general_purpose::STANDARD, Engine};
+// Import FileInfo for the files found
+use list_big_files::FileInfo;
+// Import Duration for request timeouts
+use std::time::Duration;
+
+// How long to wait on each directory listing
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+// Ask only for what the walk needs
+const PROPFIND: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getcontentlength/><d:getlastmodified/></d:prop></d:propfind>"#;
+
+/// A WebDAV share and how to log in to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Share {
+    /// `http://host[:port]` or `https://host[:port]`.
+    pub base: String,
+    /// How paths are shown: `dav://host` or `davs://host`.
+    pub shown: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Walk the tree under `path` on `share` with PROPFIND requests, passing each
+/// file to `visit` as a file whose path is its `dav://` or `davs://` URL.
+/// Returns how many files were found.
+pub fn list<F>(share: &Share, path: &str, mut visit: F) -> Result<u64, String>
+where
+    F: FnMut(FileInfo),
+{
+    let root = encode_path(&decode(path));
+    walk(
+        &root,
+        |dir| read_dir(share, dir),
+        |path, entry| {
+            let url = format!("{}{}", share.shown, decode(&path));
+            let mut file = FileInfo::new(url, entry.size_bytes);
+            file.modified = entry.modified;
+            visit(file);
+        },
+    )
+}
+
+// List one collection, whose path is percent-encoded
+fn read_dir(share: &Share, dir: &str) -> Result<Vec<Entry>, String> {
+    // Servers answer for the collection itself too, under its canonical path
+    let dir = format!("{}/", dir.trim_end_matches('/'));
+    let mut request = ureq::request("PROPFIND", &format!("{}{}", share.base, dir))
+        .timeout(TIMEOUT)
+        .set("Depth", "1")
+        .set("Content-Type", "application/xml; charset=utf-8");
+    if let Some(user) = &share.user {
+        let login = format!("{}:{}", user, share.password.as_deref().unwrap_or(""));
+        request = request.set(
+            "Authorization",
+            &format!("Basic {}", STANDARD.encode(login)),
+        );
+    }
+    let shown = format!("{}{}", share.shown, decode(&dir));
+    let body = match request.send_string(PROPFIND) {
+        Ok(response) => response
+            .into_string()
+            .map_err(|err| format!("cannot read the listing of {}: {}", shown, err))?,
+        Err(ureq::Error::Status(status, _)) => {
+            return Err(format!("cannot list {}: HTTP {}", shown, status))
+        }
+        Err(err) => return Err(format!("cannot list {}: {}", shown, err)),
+    };
+    parse_multistatus(&body, &dir)
+}
+
+// The entries of a PROPFIND multistatus response for `dir`, leaving out the
+// collection itself. Names stay percent-encoded, as in the hrefs.
+fn parse_multistatus(xml: &str, dir: &str) -> Result<Vec<Entry>, String> {
+    let document =
+        roxmltree::Document::parse(xml).map_err(|err| format!("bad WebDAV listing: {}", err))?;
+    let dav = |node: &roxmltree::Node, name: &str| {
+        node.tag_name().name() == name && node.tag_name().namespace() == Some("DAV:")
+    };
+    let mut entries = Vec::new();
+    for response in document.descendants().filter(|node| dav(node, "response")) {
+        let Some(href) = response
+            .children()
+            .find(|node| dav(node, "href"))
+            .and_then(|node| node.text())
+        else {
+            continue;
+        };
+        // Hrefs may be full URLs or absolute paths
+        let path = match href.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("/", |slash| &rest[slash..]),
+            None => href,
+        };
+        let path = path.trim_end_matches('/');
+        if path == dir.trim_end_matches('/') || path.is_empty() {
+            continue;
+        }
+        let name = path.rsplit('/').next().unwrap_or(path).to_string();
+        // Properties the server could not return come in a propstat of their own
+        let prop = |name: &str| {
+            response
+                .descendants()
+                .find(|node| dav(node, name))
+                .filter(|node| {
+                    node.ancestors()
+                        .find(|node| dav(node, "propstat"))
+                        .and_then(|propstat| propstat.children().find(|node| dav(node, "status")))
+                        .and_then(|status| status.text())
+                        .is_none_or(|status| status.contains(" 200 "))
+                })
+        };
+        let collection = prop("resourcetype")
+            .is_some_and(|node| node.children().any(|node| dav(&node, "collection")));
+        entries.push(Entry {
+            name,
+            is_dir: collection,
+            size_bytes: prop("getcontentlength")
+                .and_then(|node| node.text())
+                .and_then(|size| size.trim().parse().ok())
+                .unwrap_or(0),
+            modified: prop("getlastmodified")
+                .and_then(|node| node.text())
+                .and_then(parse_http_date),
+        });
+    }
+    Ok(entries)
+}
+
+// Percent-encode each segment of a path, keeping the slashes
+fn encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// Undo percent-encoding, leaving malformed escapes as they are
+fn decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const ROOT: &str = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response><d:href>/files/alice/</d:href>
+    <d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop>
+    <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>
+  <d:response><d:href>/files/alice/My%20Photos/</d:href>
+    <d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop>
+    <d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+    <d:propstat><d:prop><d:getcontentlength/></d:prop>
+    <d:status>HTTP/1.1 404 Not Found</d:status></d:propstat></d:response>
+  <d:response><d:href>https://cloud.example.com/files/alice/backup.tar</d:href>
+    <d:propstat><d:prop><d:resourcetype/><d:getcontentlength>5000</d:getcontentlength>
+    <d:getlastmodified>Wed, 01 May 2024 12:00:00 GMT</d:getlastmodified></d:prop>
+    <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>
+</d:multistatus>"#;
+
+    const PHOTOS: &str = r#"<?xml version="1.0"?>
+<multistatus xmlns="DAV:">
+  <response><href>/files/alice/My%20Photos/</href>
+    <propstat><prop><resourcetype><collection/></resourcetype></prop>
+    <status>HTTP/1.1 200 OK</status></propstat></response>
+  <response><href>/files/alice/My%20Photos/beach.jpg</href>
+    <propstat><prop><resourcetype/><getcontentlength>300</getcontentlength></prop>
+    <status>HTTP/1.1 200 OK</status></propstat></response>
+</multistatus>"#;
+
+    #[test]
+    fn test_parse_multistatus() {
+        let entries = parse_multistatus(ROOT, "/files/alice/").unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    name: "My%20Photos".to_string(),
+                    is_dir: true,
+                    size_bytes: 0,
+                    modified: None,
+                },
+                Entry {
+                    name: "backup.tar".to_string(),
+                    is_dir: false,
+                    size_bytes: 5000,
+                    modified: Some(1_714_564_800),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_and_encode() {
+        assert_eq!(decode("/My%20Photos/caf%C3%A9"), "/My Photos/café");
+        assert_eq!(decode("/100%/x"), "/100%/x");
+        assert_eq!(encode_path("/My Photos/café"), "/My%20Photos/caf%C3%A9");
+    }
+
+    #[test]
+    fn test_list_walks_collections() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for _ in 0..2 {
+                let request = server.recv().unwrap();
+                let body = if request.url().contains("Photos") {
+                    PHOTOS
+                } else {
+                    ROOT
+                };
+                requests.push((
+                    request.method().to_string(),
+                    request.url().to_string(),
+                    request
+                        .headers()
+                        .iter()
+                        .any(|header| header.field.equiv("Depth") && header.value == "1"),
+                ));
+                let response = tiny_http::Response::from_string(body).with_status_code(207);
+                request.respond(response).unwrap();
+            }
+            requests
+        });
+        let share = Share {
+            base,
+            shown: "davs://cloud.example.com".to_string(),
+            user: Some("alice".to_string()),
+            password: Some("secret".to_string()),
+        };
+        let mut files = Vec::new();
+        let found = list(&share, "/files/alice", |file| files.push(file)).unwrap();
+        assert_eq!(found, 2);
+        let paths: Vec<&str> = files
+            .iter()
+            .map(|file| file.path.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "davs://cloud.example.com/files/alice/backup.tar",
+                "davs://cloud.example.com/files/alice/My Photos/beach.jpg",
+            ]
+        );
+        assert_eq!(
+            handle.join().unwrap(),
+            vec![
+                ("PROPFIND".to_string(), "/files/alice/".to_string(), true),
+                (
+                    "PROPFIND".to_string(),
+                    "/files/alice/My%20Photos/".to_string(),
+                    true
+                ),
+            ]
+        );
+    }
+}